@@ -0,0 +1,542 @@
+use crate::checkout;
+use crate::commit;
+use crate::configuration::LOGGER_COMMANDS_FILE;
+use crate::logger::Logger;
+use crate::patch;
+use crate::tree_handler;
+use crate::utils::{self, get_current_time};
+use chrono::DateTime;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Directory of the Git directory where `am` keeps the patches it is applying.
+const AM_DIR: &str = "rebase-apply";
+const AM_USAGE: &str = "usage: git am [--continue | --skip | --abort] [<mbox>...]";
+
+/// Logs the 'git am' command with the specified action.
+///
+/// This function logs the 'git am' command with the provided action (the mailboxes given, or
+/// `--continue`, `--skip` or `--abort`) to a file named 'logger_commands.txt'.
+///
+/// # Arguments
+///
+/// * `action` - What the command was asked to do.
+///
+/// # Errors
+///
+/// Returns an `io::Result` indicating whether the operation was successful.
+///
+fn log_am(action: &str) -> io::Result<()> {
+    let log_file_path = LOGGER_COMMANDS_FILE;
+    let mut logger = Logger::new(log_file_path)?;
+
+    let full_message = format!(
+        "Command 'git am': Action '{}', {}",
+        action,
+        get_current_time()
+    );
+    logger.write_all(full_message.as_bytes())?;
+    logger.flush()?;
+    Ok(())
+}
+
+/// A commit received by email.
+#[derive(Debug, PartialEq, Eq)]
+struct PatchEmail {
+    /// The author, as stored in commits: `<name> <email>`.
+    author: String,
+    /// The author date, as stored in commits: `<timestamp> <offset>`.
+    date: String,
+    /// The commit message: the subject, without the `[PATCH]` prefix, and the body.
+    message: String,
+    /// The part of the email after the message, with the diffstat and the patch.
+    patch: String,
+}
+
+/// Splits a mailbox into its emails, each one starting with a `From ` line.
+fn split_mailbox(content: &str) -> Vec<String> {
+    let mut emails: Vec<String> = Vec::new();
+    let mut previous_blank = true;
+    for line in content.split_inclusive('\n') {
+        if line.starts_with("From ") && previous_blank || emails.is_empty() {
+            emails.push(String::new());
+        }
+        if let Some(email) = emails.last_mut() {
+            email.push_str(line);
+        }
+        previous_blank = line.trim().is_empty();
+    }
+    emails.retain(|email| !email.trim().is_empty());
+    emails
+}
+
+/// Converts an RFC 2822 date, as found in the `Date` header, into `<timestamp> <offset>`.
+fn parse_email_date(value: &str) -> Option<String> {
+    let date = DateTime::parse_from_rfc2822(value.trim()).ok()?;
+    let offset = date.offset().local_minus_utc();
+    let sign = if offset < 0 { '-' } else { '+' };
+    let minutes = offset.abs() / 60;
+    Some(format!(
+        "{} {}{:02}{:02}",
+        date.timestamp(),
+        sign,
+        minutes / 60,
+        minutes % 60
+    ))
+}
+
+/// Parses an email written by `format-patch` into the commit it carries.
+///
+/// # Errors
+///
+/// Returns an `InvalidData` error if the email has no author or subject.
+///
+fn parse_email(email: &str) -> io::Result<PatchEmail> {
+    let (headers, rest) = match email.split_once("\n\n") {
+        Some((headers, rest)) => (headers, rest),
+        None => (email, ""),
+    };
+    let mut fields: Vec<(String, String)> = Vec::new();
+    for line in headers.lines() {
+        if line.starts_with([' ', '\t']) {
+            if let Some((_, value)) = fields.last_mut() {
+                value.push(' ');
+                value.push_str(line.trim());
+            }
+        } else if let Some((name, value)) = line.split_once(':') {
+            fields.push((name.to_lowercase(), value.trim().to_string()));
+        }
+    }
+    let header = |name: &str| {
+        fields
+            .iter()
+            .find(|(field, _)| field == name)
+            .map(|(_, value)| value.as_str())
+    };
+
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+    let from =
+        header("from").ok_or_else(|| invalid("Patch does not have a valid e-mail address."))?;
+    let author = match from.split_once('<') {
+        Some((name, email)) => format!("{} {}", name.trim(), email.trim_end_matches('>').trim()),
+        None => format!("{} {}", from, from),
+    };
+    let date = match header("date").and_then(parse_email_date) {
+        Some(date) => date,
+        None => {
+            let (timestamp, offset) = utils::get_timestamp()?;
+            format!("{} {}", timestamp, offset)
+        }
+    };
+    let subject = header("subject").ok_or_else(|| invalid("Patch does not have a subject."))?;
+    let subject = match subject.strip_prefix('[') {
+        Some(tagged) => tagged
+            .split_once(']')
+            .map_or(subject, |(_, subject)| subject.trim_start()),
+        None => subject,
+    };
+
+    let separator = rest
+        .find("\n---\n")
+        .map(|position| (position + 1, position + 5))
+        .or_else(|| rest.starts_with("---\n").then_some((0, 4)))
+        .or_else(|| {
+            rest.find("diff --git ")
+                .map(|position| (position, position))
+        });
+    let (body, patch) = match separator {
+        Some((body_end, patch_start)) => (&rest[..body_end], &rest[patch_start..]),
+        None => (rest, ""),
+    };
+    let body = body.trim();
+    let message = if body.is_empty() {
+        subject.to_string()
+    } else {
+        format!("{}\n\n{}", subject, body)
+    };
+    Ok(PatchEmail {
+        author,
+        date,
+        message,
+        patch: patch.to_string(),
+    })
+}
+
+/// Returns the path of a file of the `am` state directory.
+fn state_path(git_dir: &str, name: &str) -> String {
+    format!("{}/{}/{}", git_dir, AM_DIR, name)
+}
+
+/// Reads a number stored in a file of the `am` state directory.
+fn read_state_number(git_dir: &str, name: &str) -> io::Result<usize> {
+    fs::read_to_string(state_path(git_dir, name))?
+        .trim()
+        .parse()
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, format!("invalid {}", name)))
+}
+
+/// Returns the hash of the commit HEAD points to, or `None` in a repository without commits.
+fn read_head(git_dir: &str) -> Option<String> {
+    utils::resolve_revision(git_dir, "HEAD").ok()
+}
+
+/// Moves HEAD, or the branch it points to, to the given commit.
+fn update_head(git_dir: &str, commit_hash: &str) -> io::Result<()> {
+    let head = fs::read_to_string(format!("{}/HEAD", git_dir))?;
+    match head.trim().strip_prefix("ref: ") {
+        Some(reference) => fs::write(utils::git_path(git_dir, reference), commit_hash),
+        None => fs::write(
+            format!("{}/HEAD", git_dir),
+            format!("{} (commit)\n", commit_hash),
+        ),
+    }
+}
+
+/// Commits the index with the author, date and message of an email, on top of HEAD.
+fn commit_email(git_dir: &str, root_dir: &str, email: &PatchEmail) -> io::Result<String> {
+    let index = checkout::load_index(git_dir, root_dir)?;
+    let tree = tree_handler::build_tree_from_entries(index.iter())?;
+    let (tree_hash, _) = tree_handler::write_tree(&tree, git_dir)?;
+    let parents: Vec<String> = read_head(git_dir).into_iter().collect();
+    let author = format!("{} {}", email.author, email.date);
+    let commit_hash =
+        commit::new_commit_with_author(git_dir, &tree_hash, &parents, &author, &email.message)?;
+    update_head(git_dir, &commit_hash)?;
+    Ok(commit_hash)
+}
+
+/// Returns the subject of a commit message.
+fn subject_of(message: &str) -> &str {
+    message.lines().next().unwrap_or_default()
+}
+
+/// Applies the pending emails of the `am` state directory, one commit each.
+///
+/// When an email does not apply, the state is kept so the user can fix the conflict and run
+/// `git am --continue`, skip the email with `--skip` or go back with `--abort`.
+fn apply_pending(git_dir: &str, root_dir: &str, output: &mut impl Write) -> io::Result<()> {
+    let last = read_state_number(git_dir, "last")?;
+    let mut next = read_state_number(git_dir, "next")?;
+    while next <= last {
+        let content = fs::read_to_string(state_path(git_dir, &format!("{:04}", next)))?;
+        let email = parse_email(&content)?;
+        let subject = subject_of(&email.message);
+        writeln!(output, "Applying: {}", subject)?;
+        let result = patch::parse_patch(&email.patch).and_then(|patches| {
+            if patches.is_empty() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "error: patch is empty",
+                ));
+            }
+            patch::apply_to_index_and_working_tree(git_dir, root_dir, &patches)
+        });
+        if let Err(error) = result {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "{}\nPatch failed at {:04} {}\n\
+                     When you have resolved this problem, run \"git am --continue\".\n\
+                     If you prefer to skip this patch, run \"git am --skip\" instead.\n\
+                     To restore the original branch and stop patching, run \"git am --abort\".",
+                    error, next, subject
+                ),
+            ));
+        }
+        commit_email(git_dir, root_dir, &email)?;
+        next += 1;
+        fs::write(state_path(git_dir, "next"), next.to_string())?;
+    }
+    fs::remove_dir_all(format!("{}/{}", git_dir, AM_DIR))
+}
+
+/// Starts applying the emails of the given mailboxes.
+fn am_start(
+    git_dir: &str,
+    root_dir: &str,
+    mailboxes: &[String],
+    output: &mut impl Write,
+) -> io::Result<()> {
+    if Path::new(&format!("{}/{}", git_dir, AM_DIR)).exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            "fatal: previous am still in progress; use --continue, --skip or --abort",
+        ));
+    }
+    let mut emails = Vec::new();
+    for mailbox in mailboxes {
+        emails.extend(split_mailbox(&fs::read_to_string(mailbox)?));
+    }
+    if emails.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Patch format detection failed.",
+        ));
+    }
+    for email in &emails {
+        parse_email(email)?;
+    }
+
+    fs::create_dir_all(format!("{}/{}", git_dir, AM_DIR))?;
+    for (position, email) in emails.iter().enumerate() {
+        fs::write(state_path(git_dir, &format!("{:04}", position + 1)), email)?;
+    }
+    fs::write(state_path(git_dir, "last"), emails.len().to_string())?;
+    fs::write(state_path(git_dir, "next"), "1")?;
+    fs::write(
+        state_path(git_dir, "orig-head"),
+        read_head(git_dir).unwrap_or_default(),
+    )?;
+    apply_pending(git_dir, root_dir, output)
+}
+
+/// Applies a series of patches from a mailbox, creating a commit for each one.
+///
+/// Supports the following forms:
+/// - `git am <mbox>...`: applies the emails of the mailboxes, as written by `format-patch`,
+///   keeping the author, date and message of every commit.
+/// - `git am --continue`: commits the staged resolution of the patch that failed and applies
+///   the rest.
+/// - `git am --skip`: drops the patch that failed and applies the rest.
+/// - `git am --abort`: goes back to the commit HEAD pointed to before `am` started.
+///
+/// The emails being applied are kept in the `rebase-apply` directory of the Git directory until
+/// every one of them was applied or the command is aborted.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the Git directory.
+/// * `root_dir` - The root of the working directory. Paths in the index are relative to it.
+/// * `line` - The command line, starting with `git am`.
+/// * `output` - Where the subjects of the applied patches are written.
+///
+/// # Errors
+///
+/// Returns an error if the arguments are invalid, a mailbox cannot be parsed or a patch does not
+/// apply.
+///
+pub fn git_am(
+    git_dir: &str,
+    root_dir: &str,
+    line: Vec<String>,
+    output: &mut impl Write,
+) -> io::Result<()> {
+    let args = &line[2.min(line.len())..];
+    let action = match args.first().map(|arg| arg.as_str()) {
+        Some(option @ ("--continue" | "--skip" | "--abort")) => option.to_string(),
+        Some(_) => args.join(" "),
+        None => return Err(io::Error::new(io::ErrorKind::InvalidInput, AM_USAGE)),
+    };
+    log_am(&action)?;
+    if !action.starts_with("--") {
+        return am_start(git_dir, root_dir, args, output);
+    }
+
+    if !Path::new(&format!("{}/{}", git_dir, AM_DIR)).exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "fatal: Resolve operation not in progress, we are not resuming.",
+        ));
+    }
+    let next = read_state_number(git_dir, "next")?;
+    match action.as_str() {
+        "--continue" => {
+            let content = fs::read_to_string(state_path(git_dir, &format!("{:04}", next)))?;
+            let email = parse_email(&content)?;
+            let index = checkout::load_index(git_dir, root_dir)?;
+            let tree = tree_handler::build_tree_from_entries(index.iter())?;
+            let (tree_hash, _) = tree_handler::write_tree(&tree, git_dir)?;
+            let unchanged = match read_head(git_dir) {
+                Some(head) => tree_handler::load_tree_from_commit(&head, git_dir)
+                    .and_then(|head_tree| tree_handler::write_tree(&head_tree, git_dir))
+                    .is_ok_and(|(head_tree_hash, _)| head_tree_hash == tree_hash),
+                None => false,
+            };
+            if unchanged {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "No changes - did you forget to use 'git add'?",
+                ));
+            }
+            writeln!(output, "Applying: {}", subject_of(&email.message))?;
+            commit_email(git_dir, root_dir, &email)?;
+        }
+        "--skip" => {
+            if let Some(head) = read_head(git_dir) {
+                checkout::reset_working_tree(git_dir, root_dir, &head)?;
+            }
+        }
+        _ => {
+            let orig_head = fs::read_to_string(state_path(git_dir, "orig-head"))?;
+            if !orig_head.is_empty() {
+                update_head(git_dir, &orig_head)?;
+                checkout::reset_working_tree(git_dir, root_dir, &orig_head)?;
+            }
+            return fs::remove_dir_all(format!("{}/{}", git_dir, AM_DIR));
+        }
+    }
+    fs::write(state_path(git_dir, "next"), (next + 1).to_string())?;
+    apply_pending(git_dir, root_dir, output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format_patch;
+    use crate::test_utils;
+
+    /// Creates a repository in `root_dir` with a commit containing `file.txt`.
+    fn create_repo(root_dir: &str, content: &str) -> String {
+        let git_dir = test_utils::create_repo(root_dir);
+        test_utils::commit_files(&git_dir, root_dir, &[("file.txt", content)], "first commit");
+        git_dir
+    }
+
+    fn run_am(git_dir: &str, root_dir: &str, args: &[&str]) -> io::Result<String> {
+        let mut line = vec!["git".to_string(), "am".to_string()];
+        line.extend(args.iter().map(|arg| arg.to_string()));
+        let mut output = Vec::new();
+        git_am(git_dir, root_dir, line, &mut output)?;
+        Ok(String::from_utf8(output).unwrap())
+    }
+
+    /// Exports the commits after `since` of the repository into a mailbox.
+    fn export(git_dir: &str, since: &str, mailbox: &str) {
+        let line = ["git", "format-patch", "--stdout", since]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        let mut output = Vec::new();
+        format_patch::git_format_patch(git_dir, line, &mut output).unwrap();
+        fs::write(mailbox, output).unwrap();
+    }
+
+    #[test]
+    fn test_parse_email_keeps_author_date_and_message() {
+        let email = "From abc Mon Sep 17 00:00:00 2001\nFrom: Ada Lovelace <ada@mail.com>\nDate: Tue, 14 Nov 2023 19:13:20 -0300\nSubject: [PATCH 2/3] Fix the\n parser\n\nLonger explanation.\n---\n a | 1 +\n\ndiff --git a/a b/a\n";
+        let parsed = parse_email(email).unwrap();
+        assert_eq!(parsed.author, "Ada Lovelace ada@mail.com");
+        assert_eq!(parsed.date, "1700000000 -0300");
+        assert_eq!(parsed.message, "Fix the parser\n\nLonger explanation.");
+        assert!(parsed.patch.starts_with(" a | 1 +"));
+        assert_eq!(split_mailbox(&format!("{}\n{}", email, email)).len(), 2);
+    }
+
+    #[test]
+    fn test_am_applies_a_series_exported_by_format_patch() {
+        let source_dir = "tests/am/source";
+        let target_dir = "tests/am/target";
+        let source = create_repo(source_dir, "one\ntwo\nthree\n");
+        let target = create_repo(target_dir, "one\ntwo\nthree\n");
+        let base = read_head(&source).unwrap();
+        test_utils::commit_files(
+            &source,
+            source_dir,
+            &[("file.txt", "one\n2\nthree\n")],
+            "Use digits",
+        );
+        test_utils::commit_files(
+            &source,
+            source_dir,
+            &[("new.txt", "hello\n")],
+            "Add new file\n\nWith a body.",
+        );
+        let mailbox = "tests/am/series.mbox";
+        export(&source, &base, mailbox);
+
+        let output = run_am(&target, target_dir, &[mailbox]).unwrap();
+        assert_eq!(output, "Applying: Use digits\nApplying: Add new file\n");
+        assert_eq!(
+            fs::read_to_string(format!("{}/file.txt", target_dir)).unwrap(),
+            "one\n2\nthree\n"
+        );
+        assert_eq!(
+            fs::read_to_string(format!("{}/new.txt", target_dir)).unwrap(),
+            "hello\n"
+        );
+        let head = read_head(&target).unwrap();
+        let source_head = read_head(&source).unwrap();
+        let tree_of = |git_dir: &str, hash: &str| {
+            let tree = tree_handler::load_tree_from_commit(hash, git_dir).unwrap();
+            tree_handler::write_tree(&tree, git_dir).unwrap().0
+        };
+        assert_eq!(tree_of(&target, &head), tree_of(&source, &source_head));
+        let log = crate::log::Log::load(Some(&head), &target).unwrap();
+        let source_log = crate::log::Log::load(Some(&source_head), &source).unwrap();
+        assert_eq!(
+            log.expand_format("%an|%ae|%at|%B"),
+            source_log.expand_format("%an|%ae|%at|%B")
+        );
+        assert!(!Path::new(&format!("{}/{}", target, AM_DIR)).exists());
+
+        fs::remove_dir_all(source_dir).unwrap();
+        fs::remove_dir_all(target_dir).unwrap();
+        fs::remove_file(mailbox).unwrap();
+    }
+
+    #[test]
+    fn test_am_stops_on_failure_and_resumes() {
+        let source_dir = "tests/am/conflict_source";
+        let target_dir = "tests/am/conflict_target";
+        let source = create_repo(source_dir, "one\ntwo\nthree\n");
+        let target = create_repo(target_dir, "uno\ndos\ntres\n");
+        let base = read_head(&source).unwrap();
+        test_utils::commit_files(
+            &source,
+            source_dir,
+            &[("file.txt", "one\n2\nthree\n")],
+            "Use digits",
+        );
+        test_utils::commit_files(
+            &source,
+            source_dir,
+            &[("new.txt", "hello\n")],
+            "Add new file",
+        );
+        let mailbox = "tests/am/conflict.mbox";
+        export(&source, &base, mailbox);
+        let orig_head = read_head(&target).unwrap();
+
+        let error = run_am(&target, target_dir, &[mailbox]).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("Patch failed at 0001 Use digits"));
+        assert!(run_am(&target, target_dir, &[mailbox]).is_err());
+        assert!(run_am(&target, target_dir, &["--continue"]).is_err());
+
+        test_utils::commit_files(
+            &target,
+            target_dir,
+            &[("file.txt", "uno\n2\ntres\n")],
+            "unused",
+        );
+        let resolved = read_head(&target).unwrap();
+        update_head(&target, &orig_head).unwrap();
+        let index_only = checkout::load_index(&target, target_dir).unwrap();
+        assert!(index_only.contains("file.txt"));
+        let output = run_am(&target, target_dir, &["--continue"]).unwrap();
+        assert_eq!(output, "Applying: Use digits\nApplying: Add new file\n");
+        let head = read_head(&target).unwrap();
+        assert_ne!(head, resolved);
+        let parent = commit::get_commit_parents(&head, &target).unwrap()[0].clone();
+        assert_eq!(
+            commit::get_commit_parents(&parent, &target).unwrap(),
+            std::slice::from_ref(&orig_head)
+        );
+
+        test_utils::commit_files(&target, target_dir, &[("file.txt", "other\n")], "Diverge");
+        let before = read_head(&target).unwrap();
+        assert!(run_am(&target, target_dir, &[mailbox]).is_err());
+        run_am(&target, target_dir, &["--abort"]).unwrap();
+        assert_eq!(read_head(&target).unwrap(), before);
+        assert_eq!(
+            fs::read_to_string(format!("{}/file.txt", target_dir)).unwrap(),
+            "other\n"
+        );
+
+        fs::remove_dir_all(source_dir).unwrap();
+        fs::remove_dir_all(target_dir).unwrap();
+        fs::remove_file(mailbox).unwrap();
+    }
+}
@@ -0,0 +1,211 @@
+use crate::configuration::LOGGER_COMMANDS_FILE;
+use crate::logger::Logger;
+use crate::patch::{self, ApplyTarget, FilePatch};
+use crate::utils::get_current_time;
+use std::fs;
+use std::io::{self, Write};
+
+const APPLY_USAGE: &str = "usage: git apply [--cached] [--check] [-R | --reverse] <patch>...";
+
+/// Logs the 'git apply' command with the specified patches.
+///
+/// This function logs the 'git apply' command with the provided patch files to a file named
+/// 'logger_commands.txt'.
+///
+/// # Arguments
+///
+/// * `patches` - The patch files given to the command.
+///
+/// # Errors
+///
+/// Returns an `io::Result` indicating whether the operation was successful.
+///
+fn log_apply(patches: &str) -> io::Result<()> {
+    let log_file_path = LOGGER_COMMANDS_FILE;
+    let mut logger = Logger::new(log_file_path)?;
+
+    let full_message = format!(
+        "Command 'git apply': Patches '{}', {}",
+        patches,
+        get_current_time()
+    );
+    logger.write_all(full_message.as_bytes())?;
+    logger.flush()?;
+    Ok(())
+}
+
+/// Applies unified diffs to the working tree or the index.
+///
+/// The command line is `git apply [--cached] [--check] [-R | --reverse] <patch>...`:
+///
+/// - Without options the patched files are read from and written to the working tree.
+/// - `--cached` reads and writes the files staged in the index instead, leaving the working
+///   tree as it is.
+/// - `--check` only tells whether the patches apply, without changing anything.
+/// - `-R` or `--reverse` undoes the patches instead of applying them.
+///
+/// The patches may change several files, create, delete or rename them, and change their mode,
+/// either as written by `git diff` or by `diff -u`. A hunk whose lines moved is applied where it
+/// is found, and up to two of the context lines at its ends may differ. Either every patch
+/// applies or nothing is changed.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the Git directory.
+/// * `root_dir` - The root of the working directory. Paths in the patches are relative to it.
+/// * `line` - The command line, starting with `git apply`.
+///
+/// # Errors
+///
+/// Returns an error if the arguments are invalid, a patch cannot be read or parsed, or does not
+/// apply. In the last case the error lists the rejected hunks of every file.
+///
+pub fn git_apply(git_dir: &str, root_dir: &str, line: Vec<String>) -> io::Result<()> {
+    let mut target = ApplyTarget::WorkingTree;
+    let mut check = false;
+    let mut reverse = false;
+    let mut files: Vec<String> = Vec::new();
+    for arg in line.iter().skip(2) {
+        match arg.as_str() {
+            "--cached" => target = ApplyTarget::Index,
+            "--check" => check = true,
+            "-R" | "--reverse" => reverse = true,
+            _ if arg.starts_with('-') => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("error: unknown option '{}'\n{}", arg, APPLY_USAGE),
+                ))
+            }
+            _ => files.push(arg.to_string()),
+        }
+    }
+    if files.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, APPLY_USAGE));
+    }
+    log_apply(&files.join(" "))?;
+
+    let mut patches: Vec<FilePatch> = Vec::new();
+    for file in &files {
+        let text = fs::read_to_string(file).map_err(|error| {
+            io::Error::new(
+                error.kind(),
+                format!("error: can't open patch '{}': {}", file, error),
+            )
+        })?;
+        patches.extend(patch::parse_patch(&text)?);
+    }
+    if patches.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "error: No valid patches in input",
+        ));
+    }
+    if reverse {
+        patches = patches.iter().map(FilePatch::reversed).collect();
+    }
+    patch::apply_patches(git_dir, root_dir, &patches, target, check)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+    use crate::{checkout, diff};
+
+    /// Creates a repository in `root_dir` with a commit containing the given files.
+    fn create_repo(root_dir: &str, files: &[(&str, &str)]) -> String {
+        let git_dir = test_utils::create_repo(root_dir);
+        test_utils::commit_files(&git_dir, root_dir, files, "first commit");
+        git_dir
+    }
+
+    fn run_apply(git_dir: &str, root_dir: &str, args: &[&str]) -> io::Result<()> {
+        let mut line = vec!["git".to_string(), "apply".to_string()];
+        line.extend(args.iter().map(|arg| arg.to_string()));
+        git_apply(git_dir, root_dir, line)
+    }
+
+    fn read(root_dir: &str, path: &str) -> Option<String> {
+        fs::read_to_string(checkout::working_path(root_dir, path)).ok()
+    }
+
+    fn staged(git_dir: &str, root_dir: &str, path: &str) -> Option<String> {
+        let index = checkout::load_index(git_dir, root_dir).unwrap();
+        index.get_hash(path).cloned()
+    }
+
+    /// Writes a patch that edits `a.txt`, deletes `b.txt` and creates `dir/c.txt`.
+    fn write_patch(path: &str) {
+        let mut text =
+            diff::file_patch("a.txt", Some("one\ntwo\nthree\n"), Some("one\n2\nthree\n"));
+        text.push_str(&diff::file_patch("b.txt", Some("bye\n"), None));
+        text.push_str(&diff::file_patch("dir/c.txt", None, Some("new\n")));
+        fs::write(path, text).unwrap();
+    }
+
+    #[test]
+    fn test_apply_patches_the_working_tree_and_reverses() {
+        let root_dir = "tests/apply/working_tree";
+        let git_dir = create_repo(
+            root_dir,
+            &[("a.txt", "one\ntwo\nthree\n"), ("b.txt", "bye\n")],
+        );
+        let patch_path = "tests/apply/working_tree.patch";
+        write_patch(patch_path);
+        let staged_a = staged(&git_dir, root_dir, "a.txt");
+
+        run_apply(&git_dir, root_dir, &["--check", patch_path]).unwrap();
+        assert_eq!(read(root_dir, "a.txt").unwrap(), "one\ntwo\nthree\n");
+
+        run_apply(&git_dir, root_dir, &[patch_path]).unwrap();
+        assert_eq!(read(root_dir, "a.txt").unwrap(), "one\n2\nthree\n");
+        assert_eq!(read(root_dir, "b.txt"), None);
+        assert_eq!(read(root_dir, "dir/c.txt").unwrap(), "new\n");
+        assert_eq!(staged(&git_dir, root_dir, "a.txt"), staged_a);
+        assert!(staged(&git_dir, root_dir, "dir/c.txt").is_none());
+        assert!(run_apply(&git_dir, root_dir, &[patch_path]).is_err());
+
+        run_apply(&git_dir, root_dir, &["-R", patch_path]).unwrap();
+        assert_eq!(read(root_dir, "a.txt").unwrap(), "one\ntwo\nthree\n");
+        assert_eq!(read(root_dir, "b.txt").unwrap(), "bye\n");
+        assert_eq!(read(root_dir, "dir/c.txt"), None);
+
+        fs::remove_dir_all(root_dir).unwrap();
+        fs::remove_file(patch_path).unwrap();
+    }
+
+    #[test]
+    fn test_apply_cached_and_rejects() {
+        let root_dir = "tests/apply/cached";
+        let git_dir = create_repo(
+            root_dir,
+            &[("a.txt", "one\ntwo\nthree\n"), ("b.txt", "bye\n")],
+        );
+        let patch_path = "tests/apply/cached.patch";
+        write_patch(patch_path);
+
+        run_apply(&git_dir, root_dir, &["--cached", patch_path]).unwrap();
+        assert_eq!(read(root_dir, "a.txt").unwrap(), "one\ntwo\nthree\n");
+        assert_eq!(read(root_dir, "dir/c.txt"), None);
+        assert!(staged(&git_dir, root_dir, "b.txt").is_none());
+        assert!(staged(&git_dir, root_dir, "dir/c.txt").is_some());
+
+        fs::write(
+            checkout::working_path(root_dir, "a.txt"),
+            "uno\ndos\ntres\n",
+        )
+        .unwrap();
+        fs::write(checkout::working_path(root_dir, "b.txt"), "changed\n").unwrap();
+        let error = run_apply(&git_dir, root_dir, &[patch_path]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "error: patch failed: a.txt:1\nerror: a.txt: patch does not apply\n\
+             error: patch failed: b.txt:1\nerror: b.txt: patch does not apply"
+        );
+        assert_eq!(read(root_dir, "dir/c.txt"), None);
+        assert_eq!(read(root_dir, "b.txt").unwrap(), "changed\n");
+
+        fs::remove_dir_all(root_dir).unwrap();
+        fs::remove_file(patch_path).unwrap();
+    }
+}
@@ -0,0 +1,1038 @@
+use crate::attributes::{self, AttributeRule, AttributeValue, ATTRIBUTES_FILE};
+use crate::cat_file;
+use crate::configuration::LOGGER_COMMANDS_FILE;
+use crate::ignorer::is_subpath;
+use crate::log::Log;
+use crate::logger::Logger;
+use crate::tree_handler::{self, Tree};
+use crate::utils::{self, get_current_time};
+use chrono::{DateTime, Datelike, Local, Timelike};
+use flate2::write::{DeflateEncoder, GzEncoder};
+use flate2::{Compression, Crc};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, Write};
+
+const ARCHIVE_USAGE: &str =
+    "usage: git archive [--format=<fmt>] [--prefix=<prefix>/] [-o <file>] <tree-ish> [<path>...]";
+
+/// Permissions of the files of an archive, the ones git gives them with its default `tar.umask`.
+const FILE_MODE: u32 = 0o664;
+/// Permissions of the executable files of an archive.
+const EXECUTABLE_MODE: u32 = 0o775;
+/// Permissions of the symbolic links of an archive, which the umask does not apply to.
+const SYMLINK_MODE: u32 = 0o777;
+/// Permissions of the directories of an archive.
+const DIRECTORY_MODE: u32 = 0o775;
+/// Type bits of the mode of a regular file, as stored in trees and in the attributes of a zip
+/// entry.
+const REGULAR_FILE_TYPE: u32 = 0o100000;
+/// Type bits of the mode of a symbolic link.
+const SYMLINK_TYPE: u32 = 0o120000;
+/// Mask of the type bits of a mode.
+const TYPE_MASK: u32 = 0o170000;
+/// Mode of the subtrees listed in a tree.
+const TREE_MODE: u32 = 0o040000;
+/// Type bits of the mode of a directory, as stored in the attributes of a zip entry.
+const DIRECTORY_TYPE: u32 = 0o040000;
+
+/// Size of the blocks of a tar archive.
+const TAR_BLOCK_SIZE: usize = 512;
+/// Tar archives are padded to a multiple of this size, as tar itself does.
+const TAR_RECORD_SIZE: usize = 20 * TAR_BLOCK_SIZE;
+/// Longest name, and longest prefix, that fit in the header of a tar entry.
+const TAR_NAME_SIZE: usize = 100;
+const TAR_PREFIX_SIZE: usize = 155;
+
+/// Signatures of the records of a zip archive.
+const ZIP_LOCAL_HEADER: u32 = 0x04034b50;
+const ZIP_CENTRAL_HEADER: u32 = 0x02014b50;
+const ZIP_END_OF_CENTRAL_DIRECTORY: u32 = 0x06054b50;
+/// Version of the zip format needed to extract the entries: 2.0, which added deflate.
+const ZIP_VERSION: u16 = 20;
+/// Version made by a Unix system, so the attributes of the entries are read as Unix modes.
+const ZIP_VERSION_MADE_BY: u16 = (3 << 8) | ZIP_VERSION;
+/// Flag telling that the names of the entries are UTF-8.
+const ZIP_UTF8_FLAG: u16 = 0x0800;
+const ZIP_STORED: u16 = 0;
+const ZIP_DEFLATED: u16 = 8;
+/// MS-DOS attribute of the directories.
+const ZIP_DIRECTORY_ATTRIBUTE: u32 = 0x10;
+
+/// Attribute that leaves a path out of the archives.
+const EXPORT_IGNORE: &str = "export-ignore";
+
+/// Logs the 'git archive' command with the specified tree-ish.
+///
+/// This function logs the 'git archive' command with the provided tree-ish to a file named
+/// 'logger_commands.txt'.
+///
+/// # Arguments
+///
+/// * `tree_ish` - The commit or tree being archived.
+///
+/// # Errors
+///
+/// Returns an `io::Result` indicating whether the operation was successful.
+///
+fn log_archive(tree_ish: &str) -> io::Result<()> {
+    let log_file_path = LOGGER_COMMANDS_FILE;
+    let mut logger = Logger::new(log_file_path)?;
+
+    let full_message = format!(
+        "Command 'git archive': Tree-ish '{}', {}",
+        tree_ish,
+        get_current_time()
+    );
+    logger.write_all(full_message.as_bytes())?;
+    logger.flush()?;
+    Ok(())
+}
+
+/// The formats an archive can be written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    Tar,
+    TarGz,
+    Zip,
+}
+
+impl ArchiveFormat {
+    /// Returns the format with the given name, as accepted by `--format`.
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "tar" => Some(ArchiveFormat::Tar),
+            "tgz" | "tar.gz" => Some(ArchiveFormat::TarGz),
+            "zip" => Some(ArchiveFormat::Zip),
+            _ => None,
+        }
+    }
+
+    /// Guesses the format from the extension of the file the archive is written to.
+    fn from_file_name(path: &str) -> Option<Self> {
+        if path.ends_with(".zip") {
+            Some(ArchiveFormat::Zip)
+        } else if path.ends_with(".tar.gz") || path.ends_with(".tgz") {
+            Some(ArchiveFormat::TarGz)
+        } else if path.ends_with(".tar") {
+            Some(ArchiveFormat::Tar)
+        } else {
+            None
+        }
+    }
+}
+
+/// A file or directory of an archive.
+struct ArchiveEntry {
+    /// The path inside the archive, with the prefix. The paths of directories end with `/`.
+    path: String,
+    /// The hash of the blob with the content of a file, or `None` for a directory.
+    blob: Option<String>,
+    /// The mode of a file in its tree, such as `0o100755` for an executable.
+    mode: u32,
+}
+
+impl ArchiveEntry {
+    fn is_symlink(&self) -> bool {
+        self.mode & TYPE_MASK == SYMLINK_TYPE
+    }
+
+    /// Returns the permissions the entry is archived with.
+    fn permissions(&self) -> u32 {
+        if self.blob.is_none() {
+            DIRECTORY_MODE
+        } else if self.is_symlink() {
+            SYMLINK_MODE
+        } else if self.mode & 0o100 != 0 {
+            EXECUTABLE_MODE
+        } else {
+            FILE_MODE
+        }
+    }
+}
+
+/// Reads the modes of the files of a tree and its subtrees, by their path.
+///
+/// The modes are read from the tree objects, since `Tree` does not keep them.
+fn read_file_modes(
+    git_dir: &str,
+    tree_hash: &str,
+    directory: &str,
+    modes: &mut HashMap<String, u32>,
+) -> io::Result<()> {
+    for (mode, name, hash) in cat_file::cat_tree(tree_hash, git_dir)? {
+        let mode = u32::from_str_radix(mode.trim(), 8).unwrap_or(REGULAR_FILE_TYPE | 0o644);
+        let path = if directory.is_empty() {
+            name
+        } else {
+            format!("{}/{}", directory, name)
+        };
+        if mode == TREE_MODE {
+            read_file_modes(git_dir, &hash, &path, modes)?;
+        } else {
+            modes.insert(path, mode);
+        }
+    }
+    Ok(())
+}
+
+/// Reads the rules of the `.gitattributes` file of a directory, if it has one.
+fn read_attribute_rules(
+    git_dir: &str,
+    tree: &Tree,
+    directory: &str,
+    rules: &mut Vec<AttributeRule>,
+) -> io::Result<()> {
+    let Some((_, hash)) = tree.files.iter().find(|(name, _)| name == ATTRIBUTES_FILE) else {
+        return Ok(());
+    };
+    let content = cat_file::cat_file_return_content(hash, git_dir)?;
+    rules.extend(attributes::parse_rules(&content, directory));
+    Ok(())
+}
+
+/// Checks whether the last rule that mentions `export-ignore` for a path sets it.
+fn is_export_ignored(rules: &[AttributeRule], path: &str, is_directory: bool) -> bool {
+    attributes::lookup(rules, path, is_directory, EXPORT_IGNORE) == Some(&AttributeValue::Set)
+}
+
+/// Lists the entries of a tree that go into an archive, parents before their children.
+///
+/// Paths with the `export-ignore` attribute are left out, and when paths are given, only the
+/// files below them and their parent directories are listed.
+fn collect_entries(
+    git_dir: &str,
+    tree: &Tree,
+    directory: &str,
+    prefix: &str,
+    paths: &[String],
+    rules: &mut Vec<AttributeRule>,
+    entries: &mut Vec<ArchiveEntry>,
+) -> io::Result<()> {
+    let inherited_rules = rules.len();
+    read_attribute_rules(git_dir, tree, directory, rules)?;
+    let join = |name: &str| {
+        if directory.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}/{}", directory, name)
+        }
+    };
+
+    let mut children: Vec<(String, Option<&String>, Option<&Tree>)> = Vec::new();
+    for (name, hash) in &tree.files {
+        children.push((name.to_string(), Some(hash), None));
+    }
+    for subtree in &tree.directories {
+        children.push((format!("{}/", subtree.name), None, Some(subtree)));
+    }
+    children.sort_by(|a, b| a.0.cmp(&b.0));
+
+    for (name, hash, subtree) in children {
+        let path = join(name.trim_end_matches('/'));
+        if let Some(hash) = hash {
+            let selected = paths.is_empty() || paths.iter().any(|p| is_subpath(&path, p));
+            if selected && !is_export_ignored(rules, &path, false) {
+                entries.push(ArchiveEntry {
+                    path: format!("{}{}", prefix, path),
+                    blob: Some(hash.to_string()),
+                    mode: REGULAR_FILE_TYPE | 0o644,
+                });
+            }
+        } else if let Some(subtree) = subtree {
+            let selected = paths.is_empty()
+                || paths
+                    .iter()
+                    .any(|p| is_subpath(&path, p) || is_subpath(p, &path));
+            if selected && !is_export_ignored(rules, &path, true) {
+                entries.push(ArchiveEntry {
+                    path: format!("{}{}/", prefix, path),
+                    blob: None,
+                    mode: TREE_MODE,
+                });
+                collect_entries(git_dir, subtree, &path, prefix, paths, rules, entries)?;
+            }
+        }
+    }
+    rules.truncate(inherited_rules);
+    Ok(())
+}
+
+/// Copies a field into a tar header, truncating it if it does not fit.
+fn put_field(field: &mut [u8], value: &[u8]) {
+    let length = value.len().min(field.len());
+    field[..length].copy_from_slice(&value[..length]);
+}
+
+/// The fields of the header of a tar entry.
+struct TarHeader<'a> {
+    prefix: &'a str,
+    name: &'a str,
+    kind: u8,
+    mode: u32,
+    link: &'a str,
+    size: usize,
+    mtime: i64,
+}
+
+impl TarHeader<'_> {
+    /// Writes the header, in the ustar format.
+    fn write(&self, output: &mut impl Write) -> io::Result<()> {
+        let mut header = [0u8; TAR_BLOCK_SIZE];
+        put_field(&mut header[0..100], self.name.as_bytes());
+        put_field(
+            &mut header[100..108],
+            format!("{:07o}\0", self.mode).as_bytes(),
+        );
+        put_field(&mut header[108..116], b"0000000\0");
+        put_field(&mut header[116..124], b"0000000\0");
+        put_field(
+            &mut header[124..136],
+            format!("{:011o}\0", self.size).as_bytes(),
+        );
+        put_field(
+            &mut header[136..148],
+            format!("{:011o}\0", self.mtime.max(0)).as_bytes(),
+        );
+        header[148..156].fill(b' ');
+        header[156] = self.kind;
+        put_field(&mut header[157..257], self.link.as_bytes());
+        put_field(&mut header[257..265], b"ustar\x0000");
+        put_field(&mut header[265..297], b"root");
+        put_field(&mut header[297..329], b"root");
+        put_field(&mut header[329..337], b"0000000\0");
+        put_field(&mut header[337..345], b"0000000\0");
+        put_field(&mut header[345..500], self.prefix.as_bytes());
+        let checksum: u32 = header.iter().map(|&byte| u32::from(byte)).sum();
+        put_field(
+            &mut header[148..156],
+            format!("{:06o}\0 ", checksum).as_bytes(),
+        );
+        output.write_all(&header)
+    }
+}
+
+/// Splits a path into the prefix and name fields of a ustar header, if it fits in them.
+fn split_tar_path(path: &str) -> Option<(&str, &str)> {
+    if path.len() <= TAR_NAME_SIZE {
+        return Some(("", path));
+    }
+    path.char_indices()
+        .filter(|&(_, c)| c == '/')
+        .map(|(position, _)| (&path[..position], &path[position + 1..]))
+        .find(|(prefix, name)| {
+            prefix.len() <= TAR_PREFIX_SIZE && name.len() <= TAR_NAME_SIZE && !name.is_empty()
+        })
+}
+
+/// Builds a record of a pax extended header: `<length> <key>=<value>\n`, where the length
+/// counts the whole record, its own digits included.
+fn pax_record(key: &str, value: &str) -> String {
+    let base = key.len() + value.len() + 3;
+    let mut length = base + 1;
+    while base + length.to_string().len() != length {
+        length = base + length.to_string().len();
+    }
+    format!("{} {}={}\n", length, key, value)
+}
+
+/// Writes a tar entry and its content, padded to a whole number of blocks.
+///
+/// Paths and link targets that do not fit in a ustar header are stored in a pax extended header
+/// before it.
+///
+/// # Returns
+///
+/// The number of bytes written.
+///
+fn write_tar_entry(
+    output: &mut impl Write,
+    path: &str,
+    kind: u8,
+    mode: u32,
+    link: &str,
+    content: &[u8],
+    mtime: i64,
+) -> io::Result<usize> {
+    let mut written = 0;
+    let mut records = String::new();
+    let (prefix, name) = match split_tar_path(path) {
+        Some(parts) => parts,
+        None => {
+            records.push_str(&pax_record("path", path));
+            let mut end = TAR_NAME_SIZE;
+            while !path.is_char_boundary(end) {
+                end -= 1;
+            }
+            ("", &path[..end])
+        }
+    };
+    if link.len() > TAR_NAME_SIZE {
+        records.push_str(&pax_record("linkpath", link));
+    }
+    if !records.is_empty() {
+        written += write_tar_entry(
+            output,
+            "PaxHeader",
+            b'x',
+            0o644,
+            "",
+            records.as_bytes(),
+            mtime,
+        )?;
+    }
+    let header = TarHeader {
+        prefix,
+        name,
+        kind,
+        mode,
+        link,
+        size: content.len(),
+        mtime,
+    };
+    header.write(output)?;
+    output.write_all(content)?;
+    let padding = (TAR_BLOCK_SIZE - content.len() % TAR_BLOCK_SIZE) % TAR_BLOCK_SIZE;
+    output.write_all(&vec![0; padding])?;
+    Ok(written + TAR_BLOCK_SIZE + content.len() + padding)
+}
+
+/// Writes the entries as a tar archive.
+///
+/// When a commit is archived, its hash is stored in a pax global header, where
+/// `git get-tar-commit-id` looks for it.
+fn write_tar(
+    git_dir: &str,
+    entries: &[ArchiveEntry],
+    mtime: i64,
+    commit: Option<&str>,
+    output: &mut impl Write,
+) -> io::Result<()> {
+    let mut written = 0;
+    if let Some(commit) = commit {
+        let record = pax_record("comment", commit);
+        written += write_tar_entry(
+            output,
+            "pax_global_header",
+            b'g',
+            0o666,
+            "",
+            record.as_bytes(),
+            mtime,
+        )?;
+    }
+    for entry in entries {
+        let mode = entry.permissions();
+        written += match &entry.blob {
+            Some(hash) if entry.is_symlink() => {
+                let target = cat_file::cat_file_return_bytes(hash, git_dir)?;
+                let target = String::from_utf8_lossy(&target);
+                write_tar_entry(output, &entry.path, b'2', mode, &target, &[], mtime)?
+            }
+            Some(hash) => {
+                let content = cat_file::cat_file_return_bytes(hash, git_dir)?;
+                write_tar_entry(output, &entry.path, b'0', mode, "", &content, mtime)?
+            }
+            None => write_tar_entry(output, &entry.path, b'5', mode, "", &[], mtime)?,
+        };
+    }
+    written += 2 * TAR_BLOCK_SIZE;
+    let padding = (TAR_RECORD_SIZE - written % TAR_RECORD_SIZE) % TAR_RECORD_SIZE;
+    output.write_all(&vec![0; 2 * TAR_BLOCK_SIZE + padding])
+}
+
+/// Converts a timestamp to the MS-DOS time and date stored in zip entries, in local time.
+///
+/// Dates before 1980, which the format cannot represent, become the first of January of 1980.
+fn dos_time_and_date(timestamp: i64) -> (u16, u16) {
+    let date = match DateTime::from_timestamp(timestamp, 0) {
+        Some(date) => date.with_timezone(&Local),
+        None => return (0, (1 << 5) | 1),
+    };
+    if date.year() < 1980 {
+        return (0, (1 << 5) | 1);
+    }
+    let time = (date.hour() << 11) | (date.minute() << 5) | (date.second() / 2);
+    let day = ((date.year() as u32 - 1980) << 9) | (date.month() << 5) | date.day();
+    (time as u16, day as u16)
+}
+
+/// Writes the entries as a zip archive, deflating the files that get smaller that way.
+fn write_zip(
+    git_dir: &str,
+    entries: &[ArchiveEntry],
+    mtime: i64,
+    commit: Option<&str>,
+    output: &mut impl Write,
+) -> io::Result<()> {
+    let (time, date) = dos_time_and_date(mtime);
+    let mut central_directory: Vec<u8> = Vec::new();
+    let mut offset: usize = 0;
+    for entry in entries {
+        let file_type = if entry.is_symlink() {
+            SYMLINK_TYPE
+        } else {
+            REGULAR_FILE_TYPE
+        };
+        let (content, mode, attributes) = match &entry.blob {
+            Some(hash) => (
+                cat_file::cat_file_return_bytes(hash, git_dir)?,
+                file_type | entry.permissions(),
+                0,
+            ),
+            None => (
+                Vec::new(),
+                DIRECTORY_TYPE | entry.permissions(),
+                ZIP_DIRECTORY_ATTRIBUTE,
+            ),
+        };
+        let mut crc = Crc::new();
+        crc.update(&content);
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&content)?;
+        let deflated = encoder.finish()?;
+        let (method, data) = if deflated.len() < content.len() {
+            (ZIP_DEFLATED, deflated)
+        } else {
+            (ZIP_STORED, content.clone())
+        };
+
+        let name = entry.path.as_bytes();
+        let mut header: Vec<u8> = Vec::new();
+        header.extend(ZIP_VERSION.to_le_bytes());
+        header.extend(ZIP_UTF8_FLAG.to_le_bytes());
+        header.extend(method.to_le_bytes());
+        header.extend(time.to_le_bytes());
+        header.extend(date.to_le_bytes());
+        header.extend(crc.sum().to_le_bytes());
+        header.extend((data.len() as u32).to_le_bytes());
+        header.extend((content.len() as u32).to_le_bytes());
+        header.extend((name.len() as u16).to_le_bytes());
+        header.extend(0u16.to_le_bytes());
+
+        output.write_all(&ZIP_LOCAL_HEADER.to_le_bytes())?;
+        output.write_all(&header)?;
+        output.write_all(name)?;
+        output.write_all(&data)?;
+
+        central_directory.extend(ZIP_CENTRAL_HEADER.to_le_bytes());
+        central_directory.extend(ZIP_VERSION_MADE_BY.to_le_bytes());
+        central_directory.extend(&header);
+        central_directory.extend(0u16.to_le_bytes());
+        central_directory.extend(0u16.to_le_bytes());
+        central_directory.extend(0u16.to_le_bytes());
+        central_directory.extend(((mode << 16) | attributes).to_le_bytes());
+        central_directory.extend((offset as u32).to_le_bytes());
+        central_directory.extend(name);
+        offset += 4 + header.len() + name.len() + data.len();
+    }
+
+    let comment = commit.unwrap_or_default().as_bytes();
+    output.write_all(&central_directory)?;
+    output.write_all(&ZIP_END_OF_CENTRAL_DIRECTORY.to_le_bytes())?;
+    output.write_all(&0u16.to_le_bytes())?;
+    output.write_all(&0u16.to_le_bytes())?;
+    output.write_all(&(entries.len() as u16).to_le_bytes())?;
+    output.write_all(&(entries.len() as u16).to_le_bytes())?;
+    output.write_all(&(central_directory.len() as u32).to_le_bytes())?;
+    output.write_all(&(offset as u32).to_le_bytes())?;
+    output.write_all(&(comment.len() as u16).to_le_bytes())?;
+    output.write_all(comment)
+}
+
+/// Writes an archive with the files of a commit or tree.
+///
+/// The entries have the permissions git gives them (`0664` for files, `0775` for executables
+/// and directories, and `0777` for symbolic links, which hold their target) and, when a commit
+/// is archived, its commit date as their modification time;
+/// a tree is archived with the current time. Paths with the `export-ignore` attribute in the
+/// `.gitattributes` files of the archived tree are left out.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the Git directory.
+/// * `tree_ish` - The commit, tag or tree to archive.
+/// * `format` - The format of the archive.
+/// * `prefix` - Prepended to the path of every entry. It usually ends with `/`, so that the
+///   archive extracts into a directory.
+/// * `paths` - When not empty, only the files below these paths are archived.
+/// * `output` - Where the archive is written.
+///
+/// # Errors
+///
+/// Returns an error if the tree-ish cannot be resolved, does not name a tree, a path does not
+/// match any file, or the archive cannot be written.
+///
+pub fn archive(
+    git_dir: &str,
+    tree_ish: &str,
+    format: ArchiveFormat,
+    prefix: &str,
+    paths: &[String],
+    output: &mut impl Write,
+) -> io::Result<()> {
+    let hash = utils::resolve_revision(git_dir, tree_ish)?;
+    let (tree_hash, mtime, commit) = match cat_file::get_object_type(&hash, git_dir)?.as_str() {
+        "commit" => {
+            let log = Log::load(Some(&hash), git_dir)?;
+            let mtime = log.expand_format("%ct").parse().unwrap_or_default();
+            (log.expand_format("%T"), mtime, Some(hash.as_str()))
+        }
+        "tree" => (hash.clone(), utils::get_timestamp()?.0, None),
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("fatal: not a tree object: {}", hash),
+            ))
+        }
+    };
+
+    let paths: Vec<String> = paths
+        .iter()
+        .map(|path| path.trim_end_matches('/').to_string())
+        .collect();
+    let tree = tree_handler::load_tree_from_file(&tree_hash, git_dir)?;
+    let mut entries: Vec<ArchiveEntry> = Vec::new();
+    if prefix.ends_with('/') {
+        entries.push(ArchiveEntry {
+            path: prefix.to_string(),
+            blob: None,
+            mode: TREE_MODE,
+        });
+    }
+    collect_entries(
+        git_dir,
+        &tree,
+        "",
+        prefix,
+        &paths,
+        &mut Vec::new(),
+        &mut entries,
+    )?;
+    let mut modes = HashMap::new();
+    read_file_modes(git_dir, &tree_hash, "", &mut modes)?;
+    for entry in entries.iter_mut().filter(|entry| entry.blob.is_some()) {
+        let path = entry.path.strip_prefix(prefix).unwrap_or(&entry.path);
+        if let Some(&mode) = modes.get(path) {
+            entry.mode = mode;
+        }
+    }
+    for path in &paths {
+        let matched = entries.iter().any(|entry| {
+            entry.blob.is_some()
+                && is_subpath(entry.path.strip_prefix(prefix).unwrap_or(&entry.path), path)
+        });
+        if !matched {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("fatal: pathspec '{}' did not match any files", path),
+            ));
+        }
+    }
+
+    match format {
+        ArchiveFormat::Tar => write_tar(git_dir, &entries, mtime, commit, output),
+        ArchiveFormat::TarGz => {
+            let mut encoder = GzEncoder::new(output, Compression::default());
+            write_tar(git_dir, &entries, mtime, commit, &mut encoder)?;
+            encoder.finish()?;
+            Ok(())
+        }
+        ArchiveFormat::Zip => write_zip(git_dir, &entries, mtime, commit, output),
+    }
+}
+
+/// Returns the value of an option given either as `<option>=<value>` or as two arguments.
+fn option_value<'a>(
+    arg: &'a str,
+    names: &[&str],
+    args: &mut impl Iterator<Item = &'a String>,
+) -> io::Result<Option<String>> {
+    for name in names {
+        if arg == *name {
+            return match args.next() {
+                Some(value) => Ok(Some(value.to_string())),
+                None => Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("error: option '{}' requires a value", name),
+                )),
+            };
+        }
+        if let Some(value) = arg
+            .strip_prefix(name)
+            .and_then(|rest| rest.strip_prefix('='))
+        {
+            return Ok(Some(value.to_string()));
+        }
+    }
+    Ok(None)
+}
+
+/// Creates an archive of the files of a commit or tree.
+///
+/// The command line is
+/// `git archive [--format=<fmt>] [--prefix=<prefix>/] [-o <file>] <tree-ish> [<path>...]`:
+///
+/// - `--format` is one of `tar`, `tgz` (or `tar.gz`) and `zip`. When it is not given, it is
+///   guessed from the extension of the output file, and otherwise it is `tar`.
+/// - `--prefix` is prepended to the path of every file in the archive.
+/// - `-o` or `--output` writes the archive to a file instead of the output.
+/// - `-l` or `--list` lists the available formats.
+///
+/// The archive is written as explained in [`archive`].
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the Git directory.
+/// * `line` - The command line, starting with `git archive`.
+/// * `output` - Where the archive, or the list of formats, is written.
+///
+/// # Errors
+///
+/// Returns an error if the arguments are invalid or the archive cannot be created.
+///
+pub fn git_archive(git_dir: &str, line: Vec<String>, output: &mut impl Write) -> io::Result<()> {
+    let mut format: Option<ArchiveFormat> = None;
+    let mut prefix = String::new();
+    let mut output_file: Option<String> = None;
+    let mut positional: Vec<String> = Vec::new();
+    let mut args = line.iter().skip(2);
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            positional.extend(args.by_ref().cloned());
+        } else if arg == "-l" || arg == "--list" {
+            return writeln!(output, "tar\ntgz\ntar.gz\nzip");
+        } else if let Some(name) = option_value(arg, &["--format"], &mut args)? {
+            format = Some(ArchiveFormat::from_name(&name).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("fatal: Unknown archive format '{}'", name),
+                )
+            })?);
+        } else if let Some(value) = option_value(arg, &["--prefix"], &mut args)? {
+            prefix = value;
+        } else if let Some(path) = option_value(arg, &["-o", "--output"], &mut args)? {
+            output_file = Some(path);
+        } else if arg.starts_with('-') {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("error: unknown option '{}'\n{}", arg, ARCHIVE_USAGE),
+            ));
+        } else {
+            positional.push(arg.to_string());
+        }
+    }
+    let Some((tree_ish, paths)) = positional.split_first() else {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, ARCHIVE_USAGE));
+    };
+    log_archive(tree_ish)?;
+
+    let format = format
+        .or_else(|| {
+            output_file
+                .as_deref()
+                .and_then(ArchiveFormat::from_file_name)
+        })
+        .unwrap_or(ArchiveFormat::Tar);
+    match output_file {
+        Some(path) => {
+            let mut file = File::create(path)?;
+            archive(git_dir, tree_ish, format, &prefix, paths, &mut file)
+        }
+        None => archive(git_dir, tree_ish, format, &prefix, paths, output),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+    use flate2::read::{DeflateDecoder, GzDecoder};
+    use std::fs;
+    use std::io::Read;
+
+    /// An entry read back from a tar archive: its path, type, mode, modification time, content
+    /// and link target.
+    type TarEntry = (String, u8, u32, i64, Vec<u8>, String);
+
+    fn run_archive(git_dir: &str, args: &[&str]) -> io::Result<Vec<u8>> {
+        let mut line = vec!["git".to_string(), "archive".to_string()];
+        line.extend(args.iter().map(|arg| arg.to_string()));
+        let mut output = Vec::new();
+        git_archive(git_dir, line, &mut output)?;
+        Ok(output)
+    }
+
+    fn octal(field: &[u8]) -> u64 {
+        let text = String::from_utf8_lossy(field);
+        u64::from_str_radix(text.trim_matches(|c: char| c == '\0' || c == ' '), 8).unwrap()
+    }
+
+    /// Reads back the entries of a tar archive, applying the paths of pax headers.
+    fn read_tar(archive: &[u8]) -> Vec<TarEntry> {
+        assert_eq!(archive.len() % TAR_RECORD_SIZE, 0);
+        let mut entries = Vec::new();
+        let mut long_path: Option<String> = None;
+        let mut position = 0;
+        while archive[position..position + TAR_BLOCK_SIZE]
+            .iter()
+            .any(|&byte| byte != 0)
+        {
+            let header = &archive[position..position + TAR_BLOCK_SIZE];
+            let checksum: u64 = header
+                .iter()
+                .enumerate()
+                .map(|(i, &byte)| {
+                    if (148..156).contains(&i) {
+                        32
+                    } else {
+                        byte as u64
+                    }
+                })
+                .sum();
+            assert_eq!(octal(&header[148..155]), checksum);
+            let text = |range: std::ops::Range<usize>| {
+                String::from_utf8_lossy(&header[range])
+                    .trim_end_matches('\0')
+                    .to_string()
+            };
+            let (name, prefix) = (text(0..100), text(345..500));
+            let size = octal(&header[124..136]) as usize;
+            let content =
+                archive[position + TAR_BLOCK_SIZE..position + TAR_BLOCK_SIZE + size].to_vec();
+            position += TAR_BLOCK_SIZE + size.div_ceil(TAR_BLOCK_SIZE) * TAR_BLOCK_SIZE;
+            let path = match long_path.take() {
+                Some(path) => path,
+                None if prefix.is_empty() => name,
+                None => format!("{}/{}", prefix, name),
+            };
+            if header[156] == b'x' {
+                let record = String::from_utf8(content).unwrap();
+                long_path = Some(record.split_once("path=").unwrap().1.trim_end().to_string());
+                continue;
+            }
+            entries.push((
+                path,
+                header[156],
+                octal(&header[100..108]) as u32,
+                octal(&header[136..148]) as i64,
+                content,
+                text(157..257),
+            ));
+        }
+        entries
+    }
+
+    #[test]
+    fn test_archive_tar_with_prefix_paths_and_export_ignore() {
+        let root_dir = "tests/archive/tar";
+        let long_name = format!("dir/{}.txt", "n".repeat(110));
+        let git_dir = test_utils::create_repo(root_dir);
+        test_utils::commit_files(
+            &git_dir,
+            root_dir,
+            &[
+                (
+                    ".gitattributes",
+                    "*.log export-ignore\nsecret/ export-ignore\n",
+                ),
+                ("a.txt", "hello\n"),
+                ("dir/b.txt", "bee\n"),
+                ("dir/debug.log", "noise\n"),
+                ("dir/keep.log", "kept\n"),
+                ("dir/.gitattributes", "keep.log -export-ignore\n"),
+                ("secret/key.txt", "key\n"),
+                (&long_name, "long\n"),
+            ],
+            "first commit",
+        );
+        let head = utils::resolve_revision(&git_dir, "HEAD").unwrap();
+        let commit_time: i64 = Log::load(Some(&head), &git_dir)
+            .unwrap()
+            .expand_format("%ct")
+            .parse()
+            .unwrap();
+
+        let archive = run_archive(&git_dir, &["--format=tar", "--prefix=proj/", "HEAD"]).unwrap();
+        let entries = read_tar(&archive);
+        let paths: Vec<&str> = entries.iter().map(|entry| entry.0.as_str()).collect();
+        let expected_long = format!("proj/{}", long_name);
+        assert_eq!(
+            paths,
+            [
+                "pax_global_header",
+                "proj/",
+                "proj/.gitattributes",
+                "proj/a.txt",
+                "proj/dir/",
+                "proj/dir/.gitattributes",
+                "proj/dir/b.txt",
+                "proj/dir/keep.log",
+                &expected_long,
+            ]
+        );
+        assert_eq!(entries[0].4, format!("52 comment={}\n", head).into_bytes());
+        assert_eq!(entries[1].1, b'5');
+        assert_eq!(entries[1].2, DIRECTORY_MODE);
+        assert_eq!(entries[3].1, b'0');
+        assert_eq!(entries[3].2, FILE_MODE);
+        assert_eq!(entries[3].4, b"hello\n");
+        assert!(entries.iter().all(|entry| entry.3 == commit_time));
+
+        let archive = run_archive(&git_dir, &["HEAD", "dir/b.txt"]).unwrap();
+        let paths: Vec<String> = read_tar(&archive)
+            .into_iter()
+            .map(|entry| entry.0)
+            .collect();
+        assert_eq!(paths, ["pax_global_header", "dir/", "dir/b.txt"]);
+        assert!(run_archive(&git_dir, &["HEAD", "missing.txt"]).is_err());
+
+        let plain = run_archive(&git_dir, &["HEAD"]).unwrap();
+        let compressed = run_archive(&git_dir, &["--format=tgz", "HEAD"]).unwrap();
+        let mut decompressed = Vec::new();
+        GzDecoder::new(&compressed[..])
+            .read_to_end(&mut decompressed)
+            .unwrap();
+        assert_eq!(decompressed, plain);
+
+        fs::remove_dir_all(root_dir).unwrap();
+    }
+
+    /// Stores a tree with the given entries, as `(mode, name, content)`, and returns its hash.
+    fn store_tree_with_modes(git_dir: &str, entries: &[(&str, &str, &str)]) -> String {
+        let blobs = entries
+            .iter()
+            .map(|(mode, name, content)| {
+                let hash =
+                    crate::hash_object::store_string_to_file(content, git_dir, "blob").unwrap();
+                let bytes = (0..hash.len())
+                    .step_by(2)
+                    .map(|i| u8::from_str_radix(&hash[i..i + 2], 16).unwrap())
+                    .collect();
+                (mode.to_string(), name.to_string(), bytes)
+            })
+            .collect();
+        crate::hash_object::store_tree_to_file(blobs, vec![], git_dir).unwrap()
+    }
+
+    #[test]
+    fn test_archive_keeps_executables_and_symlinks() {
+        let root_dir = "tests/archive/modes";
+        let git_dir = test_utils::create_repo(root_dir);
+        test_utils::commit_files(&git_dir, root_dir, &[("a.txt", "a\n")], "first commit");
+        let tree = store_tree_with_modes(
+            &git_dir,
+            &[
+                ("100644", "a.txt", "a\n"),
+                ("100755", "run.sh", "echo hi\n"),
+                ("120000", "link", "a.txt"),
+            ],
+        );
+
+        let entries = read_tar(&run_archive(&git_dir, &[&tree]).unwrap());
+        let modes: Vec<(&str, u8, u32, &str)> = entries
+            .iter()
+            .map(|entry| (entry.0.as_str(), entry.1, entry.2, entry.5.as_str()))
+            .collect();
+        assert_eq!(
+            modes,
+            [
+                ("a.txt", b'0', FILE_MODE, ""),
+                ("link", b'2', SYMLINK_MODE, "a.txt"),
+                ("run.sh", b'0', EXECUTABLE_MODE, ""),
+            ]
+        );
+        assert!(entries[1].4.is_empty());
+
+        let archive = run_archive(&git_dir, &["--format=zip", &tree]).unwrap();
+        let u16_at = |at: usize| u16::from_le_bytes([archive[at], archive[at + 1]]) as usize;
+        let u32_at = |at: usize| {
+            u32::from_le_bytes([
+                archive[at],
+                archive[at + 1],
+                archive[at + 2],
+                archive[at + 3],
+            ])
+        };
+        let end = archive.len() - 22;
+        let mut position = u32_at(end + 16) as usize;
+        let mut modes = Vec::new();
+        for _ in 0..u16_at(end + 10) {
+            let name_length = u16_at(position + 28);
+            let name =
+                String::from_utf8(archive[position + 46..position + 46 + name_length].to_vec())
+                    .unwrap();
+            modes.push((name, u32_at(position + 38) >> 16));
+            position += 46 + name_length;
+        }
+        assert_eq!(
+            modes,
+            [
+                ("a.txt".to_string(), REGULAR_FILE_TYPE | FILE_MODE),
+                ("link".to_string(), SYMLINK_TYPE | SYMLINK_MODE),
+                ("run.sh".to_string(), REGULAR_FILE_TYPE | EXECUTABLE_MODE),
+            ]
+        );
+
+        fs::remove_dir_all(root_dir).unwrap();
+    }
+
+    #[test]
+    fn test_archive_zip() {
+        let root_dir = "tests/archive/zip";
+        let content = "zip me\n".repeat(20);
+        let git_dir = test_utils::create_repo(root_dir);
+        let files = [("a.txt", content.as_str()), ("dir/b.txt", "b\n")];
+        test_utils::commit_files(&git_dir, root_dir, &files, "first commit");
+        let output_file = format!("{}/out.zip", root_dir);
+        run_archive(&git_dir, &["-o", &output_file, "--prefix=p/", "HEAD"]).unwrap();
+        let archive = fs::read(&output_file).unwrap();
+        let u16_at = |at: usize| u16::from_le_bytes([archive[at], archive[at + 1]]) as usize;
+        let u32_at = |at: usize| {
+            u32::from_le_bytes([
+                archive[at],
+                archive[at + 1],
+                archive[at + 2],
+                archive[at + 3],
+            ]) as usize
+        };
+
+        let head = utils::resolve_revision(&git_dir, "HEAD").unwrap();
+        let end = archive.len() - 22 - head.len();
+        assert_eq!(u32_at(end), ZIP_END_OF_CENTRAL_DIRECTORY as usize);
+        assert_eq!(&archive[end + 22..], head.as_bytes());
+        assert_eq!(u16_at(end + 10), 4);
+
+        let mut names = Vec::new();
+        let mut position = u32_at(end + 16);
+        for _ in 0..u16_at(end + 10) {
+            assert_eq!(u32_at(position), ZIP_CENTRAL_HEADER as usize);
+            let name_length = u16_at(position + 28);
+            let name =
+                String::from_utf8(archive[position + 46..position + 46 + name_length].to_vec())
+                    .unwrap();
+            let mode = u32_at(position + 38) >> 16;
+            let local = u32_at(position + 42);
+            assert_eq!(u32_at(local), ZIP_LOCAL_HEADER as usize);
+            if name == "p/a.txt" {
+                assert_eq!(mode as u32, REGULAR_FILE_TYPE | FILE_MODE);
+                assert_eq!(u16_at(local + 8), ZIP_DEFLATED as usize);
+                let compressed_size = u32_at(local + 18);
+                let data_start = local + 30 + u16_at(local + 26);
+                let mut data = Vec::new();
+                DeflateDecoder::new(&archive[data_start..data_start + compressed_size])
+                    .read_to_end(&mut data)
+                    .unwrap();
+                assert_eq!(data, content.as_bytes());
+                let mut crc = Crc::new();
+                crc.update(&data);
+                assert_eq!(u32_at(local + 14), crc.sum() as usize);
+            }
+            names.push(name);
+            position += 46 + name_length;
+        }
+        assert_eq!(names, ["p/", "p/a.txt", "p/dir/", "p/dir/b.txt"]);
+
+        fs::remove_dir_all(root_dir).unwrap();
+    }
+}
@@ -0,0 +1,513 @@
+use crate::utils;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Name of the files holding the attributes of the paths of a directory.
+pub const ATTRIBUTES_FILE: &str = ".gitattributes";
+
+/// Normalizes the line endings of a file when it is stored.
+pub const TEXT: &str = "text";
+/// Selects the line endings used when a file is written into the working tree.
+pub const EOL: &str = "eol";
+/// Unset for files whose changes cannot be shown as text.
+pub const DIFF: &str = "diff";
+/// Unset, or set to `binary`, for files that cannot be merged line by line.
+pub const MERGE: &str = "merge";
+/// Names the filter driver that converts a file when it is stored and checked out.
+pub const FILTER: &str = "filter";
+/// Macro attribute standing for `-diff -merge -text`.
+const BINARY: &str = "binary";
+
+/// Amount of bytes looked at when guessing whether a content is binary, as git does.
+const BINARY_CHECK_SIZE: usize = 8000;
+
+/// The state of an attribute in a line of a `.gitattributes` file.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AttributeValue {
+    /// The attribute is listed alone, like `text`.
+    Set,
+    /// The attribute is listed with a `-` in front, like `-text`.
+    Unset,
+    /// The attribute is given a value, like `eol=lf`.
+    Value(String),
+    /// The attribute is listed with a `!` in front, which drops what earlier lines said about it.
+    Unspecified,
+}
+
+impl AttributeValue {
+    /// Parses an attribute of a `.gitattributes` line, returning its name and state.
+    fn parse(attribute: &str) -> (&str, Self) {
+        if let Some(name) = attribute.strip_prefix('-') {
+            (name, AttributeValue::Unset)
+        } else if let Some(name) = attribute.strip_prefix('!') {
+            (name, AttributeValue::Unspecified)
+        } else if let Some((name, value)) = attribute.split_once('=') {
+            (name, AttributeValue::Value(value.to_string()))
+        } else {
+            (attribute, AttributeValue::Set)
+        }
+    }
+}
+
+/// A line of a `.gitattributes` file: a pattern and the attributes of the paths it matches.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttributeRule {
+    /// The directory of the `.gitattributes` file, relative to the root of the tree.
+    directory: String,
+    pattern: String,
+    /// Whether the pattern ended with `/`, so it only matches directories.
+    only_directories: bool,
+    attributes: Vec<(String, AttributeValue)>,
+}
+
+impl AttributeRule {
+    /// Checks whether the rule applies to a path, relative to the root of the tree.
+    ///
+    /// Like in `.gitignore`, a pattern without a `/` matches the name of a file at any depth
+    /// below the directory of the rule, and otherwise it matches the path relative to it.
+    pub fn matches(&self, path: &str, is_directory: bool) -> bool {
+        if self.only_directories && !is_directory {
+            return false;
+        }
+        let relative = if self.directory.is_empty() {
+            path
+        } else {
+            match path
+                .strip_prefix(self.directory.as_str())
+                .and_then(|rest| rest.strip_prefix('/'))
+            {
+                Some(relative) => relative,
+                None => return false,
+            }
+        };
+        match self.pattern.strip_prefix('/') {
+            Some(anchored) => wildcard_match(anchored, relative),
+            None if self.pattern.contains('/') => wildcard_match(&self.pattern, relative),
+            None => wildcard_match(
+                &self.pattern,
+                relative.rsplit('/').next().unwrap_or(relative),
+            ),
+        }
+    }
+
+    /// Returns what the rule says about an attribute, if it mentions it.
+    fn get(&self, name: &str) -> Option<&AttributeValue> {
+        self.attributes
+            .iter()
+            .rev()
+            .find(|(attribute, _)| attribute == name)
+            .map(|(_, value)| value)
+    }
+}
+
+/// Matches a path against a pattern where `*` and `?` do not match a `/`, and `**` matches any
+/// number of directories.
+pub fn wildcard_match(pattern: &str, text: &str) -> bool {
+    if let Some(rest) = pattern.strip_prefix("**") {
+        if rest.is_empty() {
+            return true;
+        }
+        let rest = rest.strip_prefix('/').unwrap_or(rest);
+        return (0..=text.len())
+            .filter(|&position| {
+                position == 0 || text.is_char_boundary(position) && text[..position].ends_with('/')
+            })
+            .any(|position| wildcard_match(rest, &text[position..]));
+    }
+    let mut pattern_chars = pattern.chars();
+    let mut text_chars = text.chars();
+    match pattern_chars.next() {
+        None => text.is_empty(),
+        Some('*') => {
+            let rest = pattern_chars.as_str();
+            text.char_indices()
+                .map(|(position, _)| position)
+                .chain([text.len()])
+                .take_while(|&position| !text[..position].contains('/'))
+                .any(|position| wildcard_match(rest, &text[position..]))
+        }
+        Some('?') => {
+            matches!(text_chars.next(), Some(c) if c != '/')
+                && wildcard_match(pattern_chars.as_str(), text_chars.as_str())
+        }
+        Some(c) => {
+            text_chars.next() == Some(c)
+                && wildcard_match(pattern_chars.as_str(), text_chars.as_str())
+        }
+    }
+}
+
+/// Parses the content of a `.gitattributes` file.
+///
+/// Blank lines and lines starting with `#` are skipped. The `binary` macro is expanded to
+/// `-diff -merge -text`.
+///
+/// # Arguments
+///
+/// * `content` - The content of the file.
+/// * `directory` - The directory of the file, relative to the root of the tree.
+///
+/// # Returns
+///
+/// One rule per line with a pattern, in the order of the file.
+///
+pub fn parse_rules(content: &str, directory: &str) -> Vec<AttributeRule> {
+    let mut rules = Vec::new();
+    for line in content.lines() {
+        let mut fields = line.split_whitespace();
+        let Some(pattern) = fields.next() else {
+            continue;
+        };
+        if pattern.starts_with('#') {
+            continue;
+        }
+        let mut attributes = Vec::new();
+        for attribute in fields {
+            match AttributeValue::parse(attribute) {
+                (BINARY, AttributeValue::Set) => {
+                    for name in [DIFF, MERGE, TEXT] {
+                        attributes.push((name.to_string(), AttributeValue::Unset));
+                    }
+                }
+                (name, value) => attributes.push((name.to_string(), value)),
+            }
+        }
+        rules.push(AttributeRule {
+            directory: directory.to_string(),
+            pattern: pattern.trim_end_matches('/').to_string(),
+            only_directories: pattern.ends_with('/'),
+            attributes,
+        });
+    }
+    rules
+}
+
+/// Looks up an attribute of a path.
+///
+/// Rules read later take precedence, so the rules of a directory must come after the ones of
+/// its parents.
+///
+/// # Arguments
+///
+/// * `rules` - The rules of every `.gitattributes` file that may apply to the path.
+/// * `path` - The path, relative to the root of the tree.
+/// * `is_directory` - Whether the path is a directory.
+/// * `name` - The name of the attribute.
+///
+/// # Returns
+///
+/// The state of the attribute given by the last matching rule that mentions it, or `None` if it
+/// is unspecified.
+///
+pub fn lookup<'a>(
+    rules: &'a [AttributeRule],
+    path: &str,
+    is_directory: bool,
+    name: &str,
+) -> Option<&'a AttributeValue> {
+    rules
+        .iter()
+        .rev()
+        .filter(|rule| rule.matches(path, is_directory))
+        .find_map(|rule| rule.get(name))
+        .filter(|value| **value != AttributeValue::Unspecified)
+}
+
+/// Guesses whether a content is binary, like git does: it is if its first bytes contain a NUL.
+pub fn is_binary_content(content: &[u8]) -> bool {
+    content
+        .iter()
+        .take(BINARY_CHECK_SIZE)
+        .any(|byte| *byte == 0)
+}
+
+/// The attributes of a file of the working tree.
+#[derive(Debug, Default)]
+pub struct Attributes {
+    /// The root of the working tree the attributes were read from.
+    root_dir: String,
+    /// The path of the file, relative to the root.
+    path: String,
+    values: HashMap<String, AttributeValue>,
+}
+
+impl Attributes {
+    /// Reads the attributes of a file from the `.gitattributes` files of the working tree.
+    ///
+    /// The files of the root and of every directory leading to the path are read, so the ones
+    /// closer to the file take precedence.
+    ///
+    /// # Arguments
+    ///
+    /// * `root_dir` - The root of the working tree.
+    /// * `path` - The path of the file, relative to the root.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a `.gitattributes` file exists but cannot be read.
+    ///
+    pub fn load(root_dir: &str, path: &str) -> io::Result<Self> {
+        let mut rules = Vec::new();
+        let mut directory = String::new();
+        let mut components = path.split('/').collect::<Vec<&str>>();
+        components.pop();
+        for component in [""].into_iter().chain(components) {
+            if !component.is_empty() {
+                if !directory.is_empty() {
+                    directory.push('/');
+                }
+                directory.push_str(component);
+            }
+            let file = [root_dir, directory.as_str(), ATTRIBUTES_FILE]
+                .iter()
+                .filter(|part| !part.is_empty())
+                .copied()
+                .collect::<Vec<&str>>()
+                .join("/");
+            match fs::read_to_string(&file) {
+                Ok(content) => rules.extend(parse_rules(&content, &directory)),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        let mut values = HashMap::new();
+        let names: Vec<&String> = rules
+            .iter()
+            .flat_map(|rule| rule.attributes.iter().map(|(name, _)| name))
+            .collect();
+        for name in names {
+            if let Some(value) = lookup(&rules, path, false, name) {
+                values.insert(name.to_string(), value.clone());
+            }
+        }
+        Ok(Self {
+            root_dir: root_dir.to_string(),
+            path: path.to_string(),
+            values,
+        })
+    }
+
+    /// Reads the attributes of a file given by its path in the file system, rather than relative
+    /// to the root of the working tree of the repository.
+    ///
+    /// A file outside of the working tree has no attributes. The file itself does not need to
+    /// exist, only its directory.
+    ///
+    /// # Arguments
+    ///
+    /// * `git_dir` - The path to the git directory.
+    /// * `file_path` - The path to the file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a `.gitattributes` file exists but cannot be read.
+    ///
+    pub fn for_file(git_dir: &str, file_path: &str) -> io::Result<Self> {
+        let root_dir = match utils::get_working_dir(git_dir) {
+            Ok(root_dir) if root_dir.is_empty() => ".".to_string(),
+            Ok(root_dir) => root_dir,
+            Err(_) => return Ok(Self::default()),
+        };
+        let (Ok(root_dir), Ok(file_path)) =
+            (fs::canonicalize(root_dir), canonicalize_new_file(file_path))
+        else {
+            return Ok(Self::default());
+        };
+        match file_path.strip_prefix(&root_dir) {
+            Ok(relative) => {
+                let relative: Vec<String> = relative
+                    .components()
+                    .map(|component| component.as_os_str().to_string_lossy().to_string())
+                    .collect();
+                Self::load(&root_dir.to_string_lossy(), &relative.join("/"))
+            }
+            Err(_) => Ok(Self::default()),
+        }
+    }
+
+    /// Returns the state of an attribute, or `None` if it is unspecified.
+    pub fn get(&self, name: &str) -> Option<&AttributeValue> {
+        self.values.get(name)
+    }
+
+    /// Returns the root of the working tree the attributes were read from.
+    pub fn root_dir(&self) -> &str {
+        &self.root_dir
+    }
+
+    /// Returns the path of the file, relative to the root of the working tree.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Returns the name of the filter driver of the file, if it has one.
+    pub fn filter(&self) -> Option<&str> {
+        match self.get(FILTER) {
+            Some(AttributeValue::Value(name)) => Some(name),
+            _ => None,
+        }
+    }
+
+    /// Checks whether the line endings of a content are normalized.
+    ///
+    /// They are when `text` is set, when it is `auto` and the content is not binary, or when it
+    /// is unspecified but `eol` is given.
+    fn is_text(&self, content: &str) -> bool {
+        match self.get(TEXT) {
+            Some(AttributeValue::Set) => true,
+            Some(AttributeValue::Unset) => false,
+            Some(AttributeValue::Value(value)) if value == "auto" => {
+                !is_binary_content(content.as_bytes())
+            }
+            _ => matches!(self.get(EOL), Some(AttributeValue::Value(_))),
+        }
+    }
+
+    /// Converts the content of a file of the working tree into what is stored in the repository,
+    /// replacing `CRLF` line endings with `LF` for text files.
+    pub fn convert_to_git(&self, content: String) -> String {
+        if self.is_text(&content) && content.contains("\r\n") {
+            content.replace("\r\n", "\n")
+        } else {
+            content
+        }
+    }
+
+    /// Converts the content stored in the repository into what is written to the working tree,
+    /// using `CRLF` line endings for text files with `eol=crlf`.
+    pub fn convert_to_working_tree(&self, content: String) -> String {
+        let crlf = matches!(self.get(EOL), Some(AttributeValue::Value(value)) if value == "crlf");
+        if crlf && self.is_text(&content) {
+            content.replace("\r\n", "\n").replace('\n', "\r\n")
+        } else {
+            content
+        }
+    }
+
+    /// Checks whether the changes between some versions of the file are shown as a binary
+    /// change: they are if `diff` is unset, or if it is unspecified and a version is binary.
+    pub fn is_binary_for_diff(&self, contents: &[&[u8]]) -> bool {
+        match self.get(DIFF) {
+            Some(AttributeValue::Unset) => true,
+            Some(_) => false,
+            None => contents.iter().any(|content| is_binary_content(content)),
+        }
+    }
+
+    /// Checks whether the versions of the file cannot be merged line by line: they cannot if
+    /// `merge` is unset or `binary`, or if it is unspecified and a version is binary.
+    pub fn is_binary_for_merge(&self, contents: &[&[u8]]) -> bool {
+        match self.get(MERGE) {
+            Some(AttributeValue::Unset) => true,
+            Some(AttributeValue::Value(value)) if value == BINARY => true,
+            Some(_) => false,
+            None => contents.iter().any(|content| is_binary_content(content)),
+        }
+    }
+}
+
+// Makes a path absolute like `fs::canonicalize`, resolving its directory when the file does not
+// exist yet, as when it is about to be written
+fn canonicalize_new_file(file_path: &str) -> io::Result<PathBuf> {
+    if let Ok(path) = fs::canonicalize(file_path) {
+        return Ok(path);
+    }
+    let path = Path::new(file_path);
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Invalid file path"))?;
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    Ok(fs::canonicalize(parent)?.join(file_name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match("*.log", "debug.log"));
+        assert!(!wildcard_match("*.log", "logs/debug.log"));
+        assert!(wildcard_match("docs/**", "docs/a/b.md"));
+        assert!(wildcard_match("**/build", "a/b/build"));
+        assert!(wildcard_match("**/build", "build"));
+        assert!(!wildcard_match("**/build", "rebuild"));
+        assert!(wildcard_match("file?.txt", "file1.txt"));
+        assert!(wildcard_match("**/*.txt", "año/ñandú.txt"));
+    }
+
+    #[test]
+    fn test_lookup_uses_the_last_matching_rule_and_expands_binary() {
+        let mut rules = parse_rules("# comment\n*.txt text eol=crlf\n*.png binary\n", "");
+        rules.extend(parse_rules("*.txt -text\nkeep.txt !text\n", "dir"));
+
+        assert_eq!(
+            lookup(&rules, "a.txt", false, TEXT),
+            Some(&AttributeValue::Set)
+        );
+        assert_eq!(
+            lookup(&rules, "dir/a.txt", false, EOL),
+            Some(&AttributeValue::Value("crlf".to_string()))
+        );
+        assert_eq!(
+            lookup(&rules, "dir/a.txt", false, TEXT),
+            Some(&AttributeValue::Unset)
+        );
+        assert_eq!(lookup(&rules, "dir/keep.txt", false, TEXT), None);
+        for name in [DIFF, MERGE, TEXT] {
+            assert_eq!(
+                lookup(&rules, "img/logo.png", false, name),
+                Some(&AttributeValue::Unset)
+            );
+        }
+    }
+
+    #[test]
+    fn test_attributes_convert_line_endings_and_detect_binaries() {
+        let root_dir = "tests/attributes/load";
+        let _ = fs::remove_dir_all(root_dir);
+        fs::create_dir_all(format!("{}/win", root_dir)).unwrap();
+        fs::write(
+            format!("{}/{}", root_dir, ATTRIBUTES_FILE),
+            "* text=auto\n*.bin -diff merge=binary\n",
+        )
+        .unwrap();
+        fs::write(
+            format!("{}/win/{}", root_dir, ATTRIBUTES_FILE),
+            "*.bat eol=crlf\n",
+        )
+        .unwrap();
+
+        let text = Attributes::load(root_dir, "a.txt").unwrap();
+        assert_eq!(text.convert_to_git("a\r\nb\r\n".to_string()), "a\nb\n");
+        assert_eq!(text.convert_to_working_tree("a\nb\n".to_string()), "a\nb\n");
+        assert_eq!(text.convert_to_git("a\0\r\n".to_string()), "a\0\r\n");
+        assert!(!text.is_binary_for_diff(&[b"a\n", b"b\n"]));
+        assert!(text.is_binary_for_diff(&[b"a\n", b"b\0"]));
+
+        let script = Attributes::load(root_dir, "win/run.bat").unwrap();
+        assert_eq!(
+            script.convert_to_working_tree("a\nb\r\n".to_string()),
+            "a\r\nb\r\n"
+        );
+        assert_eq!(
+            Attributes::load(root_dir, "run.bat")
+                .unwrap()
+                .convert_to_working_tree("a\n".to_string()),
+            "a\n"
+        );
+
+        let binary = Attributes::load(root_dir, "win/data.bin").unwrap();
+        assert!(binary.is_binary_for_diff(&[b"a\n"]));
+        assert!(binary.is_binary_for_merge(&[b"a\n"]));
+        assert!(!text.is_binary_for_merge(&[b"a\n"]));
+
+        fs::remove_dir_all(root_dir).unwrap();
+    }
+}
@@ -0,0 +1,629 @@
+use crate::cat_file;
+use crate::checkout;
+use crate::commit;
+use crate::configuration::LOGGER_COMMANDS_FILE;
+use crate::logger::Logger;
+use crate::show;
+use crate::utils::{self, get_current_time};
+use std::collections::{HashSet, VecDeque};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::process::Command;
+
+const BISECT_START: &str = "BISECT_START";
+const BISECT_BAD: &str = "BISECT_BAD";
+const BISECT_GOOD: &str = "BISECT_GOOD";
+const BISECT_SKIP: &str = "BISECT_SKIP";
+const BISECT_LOG: &str = "BISECT_LOG";
+const BISECT_USAGE: &str = "usage: git bisect [start [<bad> [<good>...]] | bad [<rev>] | good [<rev>...] | skip [<rev>...] | reset | log | run <cmd> [<arg>...]]";
+
+/// Exit status with which a `bisect run` command asks to skip the current commit.
+const SKIP_EXIT_CODE: i32 = 125;
+
+/// Logs the 'git bisect' command with the specified subcommand.
+///
+/// This function logs the 'git bisect' command with the provided Git directory and subcommand
+/// to a file named 'logger_commands.txt'.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the Git directory.
+/// * `subcommand` - The bisect subcommand that was executed (start, good, bad, skip, ...).
+///
+/// # Errors
+///
+/// Returns an `io::Result` indicating whether the operation was successful.
+///
+fn log_bisect(git_dir: &str, subcommand: &str) -> io::Result<()> {
+    let log_file_path = LOGGER_COMMANDS_FILE;
+    let mut logger = Logger::new(log_file_path)?;
+
+    let full_message = format!(
+        "Command 'git bisect': Git Directory '{}', Subcommand '{}', {}",
+        git_dir,
+        subcommand,
+        get_current_time()
+    );
+    logger.write_all(full_message.as_bytes())?;
+    logger.flush()?;
+    Ok(())
+}
+
+/// What the bisection has to do after the known good and bad commits changed.
+#[derive(Debug, PartialEq, Eq)]
+enum BisectStep {
+    /// A good or a bad commit is still missing, as explained by the message.
+    Waiting(String),
+    /// The given commit was checked out to be tested, and roughly this many revisions are
+    /// left to test after it.
+    Testing(String, usize),
+    /// The given commit is the first bad commit.
+    Found(String),
+    /// Only skipped commits are left, so the first bad commit is any of these.
+    OnlySkipped(Vec<String>),
+}
+
+/// Returns the path of a bisect state file.
+fn state_path(git_dir: &str, name: &str) -> String {
+    format!("{}/{}", git_dir, name)
+}
+
+/// Returns whether a bisection is in progress.
+fn is_bisecting(git_dir: &str) -> bool {
+    Path::new(&state_path(git_dir, BISECT_START)).exists()
+}
+
+/// Reads the non-empty lines of a bisect state file, or nothing if the file does not exist.
+fn read_state_lines(git_dir: &str, name: &str) -> io::Result<Vec<String>> {
+    let path = state_path(git_dir, name);
+    if !Path::new(&path).exists() {
+        return Ok(Vec::new());
+    }
+    Ok(fs::read_to_string(path)?
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(|line| line.to_string())
+        .collect())
+}
+
+/// Appends a line to a bisect state file, creating it if needed.
+fn append_state_line(git_dir: &str, name: &str, line: &str) -> io::Result<()> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(state_path(git_dir, name))?;
+    writeln!(file, "{}", line)
+}
+
+/// Removes every bisect state file.
+fn clear_state(git_dir: &str) -> io::Result<()> {
+    for name in [
+        BISECT_START,
+        BISECT_BAD,
+        BISECT_GOOD,
+        BISECT_SKIP,
+        BISECT_LOG,
+    ] {
+        let path = state_path(git_dir, name);
+        if Path::new(&path).exists() {
+            fs::remove_file(path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Returns an error telling that no bisection is in progress.
+fn not_bisecting_error() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        "You need to start by \"git bisect start\"",
+    )
+}
+
+/// Returns the first line of the message of a commit.
+fn commit_subject(git_dir: &str, commit_hash: &str) -> io::Result<String> {
+    let content = cat_file::cat_file_return_content(commit_hash, git_dir)?;
+    let message = content.split_once("\n\n").map(|(_, message)| message);
+    Ok(message
+        .and_then(|message| message.lines().next())
+        .unwrap_or_default()
+        .to_string())
+}
+
+/// Records a commit as `bad`, `good` or `skip`, both in its state file and in the bisect log.
+fn mark_commit(git_dir: &str, term: &str, commit_hash: &str) -> io::Result<()> {
+    match term {
+        "bad" => fs::write(
+            state_path(git_dir, BISECT_BAD),
+            format!("{}\n", commit_hash),
+        )?,
+        "good" => append_state_line(git_dir, BISECT_GOOD, commit_hash)?,
+        _ => append_state_line(git_dir, BISECT_SKIP, commit_hash)?,
+    }
+    let subject = commit_subject(git_dir, commit_hash)?;
+    append_state_line(
+        git_dir,
+        BISECT_LOG,
+        &format!("# {}: [{}] {}", term, commit_hash, subject),
+    )?;
+    append_state_line(
+        git_dir,
+        BISECT_LOG,
+        &format!("git bisect {} {}", term, commit_hash),
+    )
+}
+
+/// Collects every ancestor of the given commits, the commits themselves included.
+fn ancestors(git_dir: &str, commits: &[String]) -> io::Result<HashSet<String>> {
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut pending: Vec<String> = commits.to_vec();
+    while let Some(commit_hash) = pending.pop() {
+        if seen.insert(commit_hash.clone()) {
+            pending.extend(commit::get_commit_parents(&commit_hash, git_dir)?);
+        }
+    }
+    Ok(seen)
+}
+
+/// Lists the commits that may be the first bad one: the ancestors of the bad commit that are
+/// not ancestors of a good commit. They are ordered from the bad commit backwards.
+fn find_candidates(git_dir: &str, bad: &str, goods: &[String]) -> io::Result<Vec<String>> {
+    let excluded = ancestors(git_dir, goods)?;
+    let mut candidates = Vec::new();
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut queue = VecDeque::from([bad.to_string()]);
+    while let Some(commit_hash) = queue.pop_front() {
+        if excluded.contains(&commit_hash) || !seen.insert(commit_hash.clone()) {
+            continue;
+        }
+        queue.extend(commit::get_commit_parents(&commit_hash, git_dir)?);
+        candidates.push(commit_hash);
+    }
+    Ok(candidates)
+}
+
+/// Counts how many candidates are reachable from `commit_hash`, itself included.
+fn count_reachable_candidates(
+    git_dir: &str,
+    commit_hash: &str,
+    candidates: &HashSet<&String>,
+) -> io::Result<usize> {
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut pending = vec![commit_hash.to_string()];
+    while let Some(current) = pending.pop() {
+        if !candidates.contains(&current) || !seen.insert(current.clone()) {
+            continue;
+        }
+        pending.extend(commit::get_commit_parents(&current, git_dir)?);
+    }
+    Ok(seen.len())
+}
+
+/// Decides the next step of the bisection and checks out the next commit to test, if any.
+///
+/// The commit checked out is the one that best halves the candidates, that is, the one whose
+/// number of reachable candidates is closest to half of them. In a linear history this is the
+/// midpoint between the good and the bad commits. Skipped commits are never chosen.
+///
+/// # Errors
+///
+/// Returns an error if the history cannot be read or the checkout fails.
+///
+fn next_step(git_dir: &str, root_dir: &str) -> io::Result<BisectStep> {
+    let bad = read_state_lines(git_dir, BISECT_BAD)?.into_iter().next();
+    let goods = read_state_lines(git_dir, BISECT_GOOD)?;
+    let bad = match (bad, goods.is_empty()) {
+        (None, true) => {
+            return Ok(BisectStep::Waiting(
+                "status: waiting for both good and bad commits".to_string(),
+            ))
+        }
+        (None, false) => {
+            return Ok(BisectStep::Waiting(format!(
+                "status: waiting for bad commit, {} good commit(s) known",
+                goods.len()
+            )))
+        }
+        (Some(_), true) => {
+            return Ok(BisectStep::Waiting(
+                "status: waiting for good commit(s), bad commit known".to_string(),
+            ))
+        }
+        (Some(bad), false) => bad,
+    };
+
+    let candidates = find_candidates(git_dir, &bad, &goods)?;
+    if candidates.len() <= 1 {
+        return Ok(BisectStep::Found(bad));
+    }
+    let skipped: HashSet<String> = read_state_lines(git_dir, BISECT_SKIP)?
+        .into_iter()
+        .collect();
+    let testable: Vec<&String> = candidates
+        .iter()
+        .filter(|candidate| **candidate != bad && !skipped.contains(*candidate))
+        .collect();
+    if testable.is_empty() {
+        return Ok(BisectStep::OnlySkipped(candidates));
+    }
+
+    let candidate_set: HashSet<&String> = candidates.iter().collect();
+    let mut best: Option<(usize, &String)> = None;
+    for commit_hash in testable.iter() {
+        let reachable = count_reachable_candidates(git_dir, commit_hash, &candidate_set)?;
+        let distance = reachable.min(candidates.len() - reachable);
+        if best.is_none_or(|(best_distance, _)| distance > best_distance) {
+            best = Some((distance, commit_hash));
+        }
+    }
+    let next = match best {
+        Some((_, commit_hash)) => commit_hash.to_string(),
+        None => return Ok(BisectStep::OnlySkipped(candidates)),
+    };
+    checkout::checkout_commit_detached(Path::new(git_dir), root_dir, &next)?;
+    Ok(BisectStep::Testing(next, testable.len() / 2))
+}
+
+/// Writes the outcome of a bisection step, recording the first bad commit in the log when it
+/// is found.
+fn write_step(git_dir: &str, step: &BisectStep, output: &mut impl Write) -> io::Result<()> {
+    match step {
+        BisectStep::Waiting(message) => writeln!(output, "{}", message),
+        BisectStep::Testing(commit_hash, left) => {
+            let steps = (usize::BITS - left.leading_zeros()) as usize;
+            writeln!(
+                output,
+                "Bisecting: {} revision{} left to test after this (roughly {} step{})",
+                left,
+                if *left == 1 { "" } else { "s" },
+                steps,
+                if steps == 1 { "" } else { "s" }
+            )?;
+            writeln!(
+                output,
+                "[{}] {}",
+                commit_hash,
+                commit_subject(git_dir, commit_hash)?
+            )
+        }
+        BisectStep::Found(commit_hash) => {
+            let subject = commit_subject(git_dir, commit_hash)?;
+            append_state_line(
+                git_dir,
+                BISECT_LOG,
+                &format!("# first bad commit: [{}] {}", commit_hash, subject),
+            )?;
+            writeln!(output, "{} is the first bad commit", commit_hash)?;
+            let line = ["git", "show", "--stat", commit_hash]
+                .iter()
+                .map(|arg| arg.to_string())
+                .collect();
+            show::git_show(git_dir, line, output)
+        }
+        BisectStep::OnlySkipped(commits) => {
+            writeln!(output, "There are only 'skip'ped commits left to test.")?;
+            writeln!(output, "The first bad commit could be any of:")?;
+            for commit_hash in commits {
+                writeln!(output, "{}", commit_hash)?;
+            }
+            writeln!(output, "We cannot bisect more!")
+        }
+    }
+}
+
+/// Resolves the revisions given to `good`, `bad` or `skip`, which default to `HEAD`.
+fn resolve_commits(git_dir: &str, revisions: &[String]) -> io::Result<Vec<String>> {
+    if revisions.is_empty() {
+        return Ok(vec![utils::resolve_revision(git_dir, "HEAD")?]);
+    }
+    revisions
+        .iter()
+        .map(|revision| utils::resolve_revision(git_dir, revision))
+        .collect()
+}
+
+/// Starts a bisection, forgetting any previous one.
+///
+/// The current branch (or commit, if HEAD is detached) is saved so `bisect reset` can go back
+/// to it. The first revision, if any, is the bad commit and the rest are good commits.
+fn bisect_start(
+    git_dir: &str,
+    root_dir: &str,
+    args: &[String],
+    output: &mut impl Write,
+) -> io::Result<()> {
+    let commits = args
+        .iter()
+        .map(|revision| utils::resolve_revision(git_dir, revision))
+        .collect::<io::Result<Vec<String>>>()?;
+    let head = fs::read_to_string(format!("{}/HEAD", git_dir))?;
+    let original = match head.trim().strip_prefix("ref: refs/heads/") {
+        Some(branch) => branch.to_string(),
+        None => utils::resolve_revision(git_dir, "HEAD")?,
+    };
+
+    clear_state(git_dir)?;
+    fs::write(state_path(git_dir, BISECT_START), format!("{}\n", original))?;
+    let quoted: Vec<String> = args.iter().map(|arg| format!("'{}'", arg)).collect();
+    append_state_line(
+        git_dir,
+        BISECT_LOG,
+        format!("git bisect start {}", quoted.join(" ")).trim_end(),
+    )?;
+    for (position, commit_hash) in commits.iter().enumerate() {
+        let term = if position == 0 { "bad" } else { "good" };
+        mark_commit(git_dir, term, commit_hash)?;
+    }
+    let step = next_step(git_dir, root_dir)?;
+    write_step(git_dir, &step, output)
+}
+
+/// Ends the bisection, checking out the branch or commit that was current when it started.
+fn bisect_reset(git_dir: &str, root_dir: &str, output: &mut impl Write) -> io::Result<()> {
+    if !is_bisecting(git_dir) {
+        return writeln!(output, "We are not bisecting.");
+    }
+    let original = fs::read_to_string(state_path(git_dir, BISECT_START))?
+        .trim()
+        .to_string();
+    let git_dir_path = Path::new(git_dir);
+    if Path::new(&utils::git_path(
+        git_dir,
+        &format!("refs/heads/{}", original),
+    ))
+    .is_file()
+    {
+        checkout::checkout_branch(git_dir_path, root_dir, &original)?;
+        writeln!(output, "Switched to branch '{}'", original)?;
+    } else {
+        checkout::checkout_commit_detached(git_dir_path, root_dir, &original)?;
+        writeln!(output, "HEAD is now at {}", original)?;
+    }
+    clear_state(git_dir)
+}
+
+/// Runs a command on every commit to test, marking it from the exit status of the command:
+/// 0 means good, 125 means the commit cannot be tested and any other status below 128 means
+/// bad. The bisection stops when the first bad commit is found or the command fails harder.
+fn bisect_run(
+    git_dir: &str,
+    root_dir: &str,
+    command: &[String],
+    output: &mut impl Write,
+) -> io::Result<()> {
+    if command.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, BISECT_USAGE));
+    }
+    match next_step(git_dir, root_dir)? {
+        BisectStep::Testing(..) => {}
+        step => return write_step(git_dir, &step, output),
+    }
+    let current_dir = if root_dir.is_empty() { "." } else { root_dir };
+    loop {
+        writeln!(output, "running {}", command.join(" "))?;
+        let status = Command::new(&command[0])
+            .args(&command[1..])
+            .current_dir(current_dir)
+            .status()?;
+        let term = match status.code() {
+            Some(0) => "good",
+            Some(SKIP_EXIT_CODE) => "skip",
+            Some(code) if (1..128).contains(&code) => "bad",
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "bisect run failed: exit code {:?} is not valid",
+                        status.code()
+                    ),
+                ))
+            }
+        };
+        let head = utils::resolve_revision(git_dir, "HEAD")?;
+        mark_commit(git_dir, term, &head)?;
+        let step = next_step(git_dir, root_dir)?;
+        write_step(git_dir, &step, output)?;
+        if !matches!(step, BisectStep::Testing(..)) {
+            return Ok(());
+        }
+    }
+}
+
+/// Finds the commit that introduced a bug by binary search through the history.
+///
+/// Supports the following subcommands:
+/// - `git bisect start [<bad> [<good>...]]`
+/// - `git bisect bad [<rev>]`
+/// - `git bisect good [<rev>...]`
+/// - `git bisect skip [<rev>...]`
+/// - `git bisect reset`
+/// - `git bisect log`
+/// - `git bisect run <cmd> [<arg>...]`
+///
+/// The state of the bisection lives in the `BISECT_*` files of the Git directory. After each
+/// `good`, `bad` or `skip` the commit that best halves the remaining candidates is checked out,
+/// until a single bad commit is left.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the Git directory.
+/// * `root_dir` - The root of the working directory. Paths in the index are relative to it.
+/// * `line` - A vector of strings representing the command line arguments.
+/// * `output` - Where the results of the command are written.
+///
+/// # Errors
+///
+/// Returns an error if the arguments are not valid, no bisection is in progress when one is
+/// needed, a revision cannot be resolved or a checkout would overwrite local changes.
+///
+pub fn git_bisect(
+    git_dir: &str,
+    root_dir: &str,
+    line: Vec<String>,
+    output: &mut impl Write,
+) -> io::Result<()> {
+    let subcommand = match line.get(2) {
+        Some(subcommand) => subcommand.as_str(),
+        None => return Err(io::Error::new(io::ErrorKind::InvalidInput, BISECT_USAGE)),
+    };
+    let args = &line[3..];
+    log_bisect(git_dir, subcommand)?;
+
+    match subcommand {
+        "start" => bisect_start(git_dir, root_dir, args, output),
+        "good" | "bad" | "skip" => {
+            if !is_bisecting(git_dir) {
+                return Err(not_bisecting_error());
+            }
+            if subcommand == "bad" && args.len() > 1 {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "'git bisect bad' can take only one argument.",
+                ));
+            }
+            for commit_hash in resolve_commits(git_dir, args)? {
+                mark_commit(git_dir, subcommand, &commit_hash)?;
+            }
+            let step = next_step(git_dir, root_dir)?;
+            write_step(git_dir, &step, output)
+        }
+        "reset" => bisect_reset(git_dir, root_dir, output),
+        "log" => {
+            if !is_bisecting(git_dir) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "We are not bisecting.",
+                ));
+            }
+            let log = fs::read_to_string(state_path(git_dir, BISECT_LOG)).unwrap_or_default();
+            write!(output, "{}", log)
+        }
+        "run" => {
+            if !is_bisecting(git_dir) {
+                return Err(not_bisecting_error());
+            }
+            bisect_run(git_dir, root_dir, args, output)
+        }
+        _ => Err(io::Error::new(io::ErrorKind::InvalidInput, BISECT_USAGE)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+
+    /// Creates a repository in `root_dir` with a linear history of `count` commits on `main`.
+    /// The n-th commit writes `n` into `version.txt` and has the message `version n`.
+    fn create_history(root_dir: &str, count: usize) -> (String, Vec<String>) {
+        let git_dir = test_utils::create_repo(root_dir);
+        let commits = (1..=count)
+            .map(|version| {
+                let content = format!("{}\n", version);
+                let message = format!("version {}", version);
+                test_utils::commit_files(&git_dir, root_dir, &[("version.txt", &content)], &message)
+            })
+            .collect();
+        (git_dir, commits)
+    }
+
+    fn run(git_dir: &str, root_dir: &str, args: &[&str]) -> io::Result<String> {
+        let mut line = vec!["git".to_string(), "bisect".to_string()];
+        line.extend(args.iter().map(|arg| arg.to_string()));
+        let mut output: Vec<u8> = vec![];
+        git_bisect(git_dir, root_dir, line, &mut output)?;
+        Ok(String::from_utf8(output).unwrap())
+    }
+
+    fn head(git_dir: &str) -> String {
+        utils::resolve_revision(git_dir, "HEAD").unwrap()
+    }
+
+    #[test]
+    fn test_bisect_checks_out_midpoints_until_the_first_bad_commit() {
+        let root_dir = "tests/bisect/manual";
+        let (git_dir, commits) = create_history(root_dir, 8);
+
+        let output = run(&git_dir, root_dir, &["start", &commits[7], &commits[0]]).unwrap();
+        assert!(output.starts_with("Bisecting: 3 revisions left to test after this"));
+        assert_eq!(head(&git_dir), commits[4]);
+        assert_eq!(
+            fs::read_to_string(format!("{}/version.txt", root_dir)).unwrap(),
+            "5\n"
+        );
+
+        run(&git_dir, root_dir, &["bad"]).unwrap();
+        assert_eq!(head(&git_dir), commits[2]);
+        run(&git_dir, root_dir, &["good"]).unwrap();
+        assert_eq!(head(&git_dir), commits[3]);
+        let output = run(&git_dir, root_dir, &["good"]).unwrap();
+        assert!(output.starts_with(&format!("{} is the first bad commit\n", commits[4])));
+
+        let log = run(&git_dir, root_dir, &["log"]).unwrap();
+        let lines: Vec<&str> = log.lines().collect();
+        assert_eq!(
+            lines[0],
+            format!("git bisect start '{}' '{}'", commits[7], commits[0])
+        );
+        assert_eq!(lines[4], format!("git bisect good {}", commits[0]));
+        assert_eq!(
+            lines.last().unwrap().to_string(),
+            format!("# first bad commit: [{}] version 5", commits[4])
+        );
+
+        let output = run(&git_dir, root_dir, &["reset"]).unwrap();
+        assert_eq!(output, "Switched to branch 'main'\n");
+        assert_eq!(
+            fs::read_to_string(format!("{}/HEAD", git_dir)).unwrap(),
+            "ref: refs/heads/main\n"
+        );
+        assert_eq!(
+            fs::read_to_string(format!("{}/version.txt", root_dir)).unwrap(),
+            "8\n"
+        );
+        assert!(!is_bisecting(&git_dir));
+        fs::remove_dir_all(root_dir).unwrap();
+    }
+
+    #[test]
+    fn test_bisect_skip_and_waiting_states() {
+        let root_dir = "tests/bisect/skip";
+        let (git_dir, commits) = create_history(root_dir, 4);
+
+        assert!(run(&git_dir, root_dir, &["good"]).is_err());
+        let output = run(&git_dir, root_dir, &["start"]).unwrap();
+        assert_eq!(output, "status: waiting for both good and bad commits\n");
+        let output = run(&git_dir, root_dir, &["bad"]).unwrap();
+        assert_eq!(
+            output,
+            "status: waiting for good commit(s), bad commit known\n"
+        );
+        run(&git_dir, root_dir, &["good", &commits[1]]).unwrap();
+        assert_eq!(head(&git_dir), commits[2]);
+
+        let output = run(&git_dir, root_dir, &["skip"]).unwrap();
+        assert!(output.starts_with("There are only 'skip'ped commits left to test."));
+        assert!(output.contains(&commits[2]) && output.contains(&commits[3]));
+        run(&git_dir, root_dir, &["reset"]).unwrap();
+        fs::remove_dir_all(root_dir).unwrap();
+    }
+
+    #[test]
+    fn test_bisect_run_classifies_commits_with_the_exit_status() {
+        let root_dir = "tests/bisect/run";
+        let (git_dir, commits) = create_history(root_dir, 10);
+
+        run(&git_dir, root_dir, &["start", &commits[9], &commits[0]]).unwrap();
+        let output = run(
+            &git_dir,
+            root_dir,
+            &["run", "sh", "-c", "test $(cat version.txt) -lt 7"],
+        )
+        .unwrap();
+        assert!(output.contains(&format!("{} is the first bad commit\n", commits[6])));
+        assert!(output.contains("version 7"));
+        run(&git_dir, root_dir, &["reset"]).unwrap();
+        fs::remove_dir_all(root_dir).unwrap();
+    }
+}
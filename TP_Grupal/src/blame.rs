@@ -0,0 +1,572 @@
+use crate::cat_file;
+use crate::commit;
+use crate::configuration::LOGGER_COMMANDS_FILE;
+use crate::diff::{self, DiffLine, RenameOptions};
+use crate::log::{format_date_with, DateMode};
+use crate::logger::Logger;
+use crate::tree_handler::{self, Tree};
+use crate::utils::{self, get_current_time};
+use std::collections::{BinaryHeap, HashMap};
+use std::io::{self, Write};
+
+const BLAME_USAGE: &str = "usage: git blame [-L <start>,<end>] [<rev>] [--] <file>";
+
+/// A line of a file together with the commit that introduced it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlameLine {
+    /// Hash of the commit that introduced the line.
+    pub commit: String,
+    /// Name of the author of that commit.
+    pub author: String,
+    /// Author date of that commit, as `<timestamp> <offset>`.
+    pub date: String,
+    /// Path of the file in that commit, which differs from the blamed path after a rename.
+    pub path: String,
+    /// Number of the line in the blamed file, starting at 1.
+    pub line_number: usize,
+    /// Content of the line, without the line break.
+    pub content: String,
+}
+
+/// Logs the 'git blame' command with the specified revision and file.
+///
+/// This function logs the 'git blame' command with the provided revision and path to a file
+/// named 'logger_commands.txt'.
+///
+/// # Arguments
+///
+/// * `revision` - The revision whose version of the file is blamed.
+/// * `path` - The blamed file.
+///
+/// # Errors
+///
+/// Returns an `io::Result` indicating whether the operation was successful.
+///
+fn log_blame(revision: &str, path: &str) -> io::Result<()> {
+    let log_file_path = LOGGER_COMMANDS_FILE;
+    let mut logger = Logger::new(log_file_path)?;
+
+    let full_message = format!(
+        "Command 'git blame': Revision '{}', File '{}', {}",
+        revision,
+        path,
+        get_current_time()
+    );
+    logger.write_all(full_message.as_bytes())?;
+    logger.flush()?;
+    Ok(())
+}
+
+/// Author name and author date of a commit, plus the committer timestamp used to walk
+/// the history from the newest commit to the oldest one.
+struct CommitInfo {
+    author: String,
+    date: String,
+    timestamp: i64,
+}
+
+/// Reads the author and committer lines of a commit.
+///
+/// An identity line looks like `author <name> <email> <timestamp> <offset>`, where the name
+/// may contain spaces.
+fn read_commit_info(git_dir: &str, commit_hash: &str) -> io::Result<CommitInfo> {
+    let content = cat_file::cat_file_return_content(commit_hash, git_dir)?;
+    let header = content.split("\n\n").next().unwrap_or_default();
+    let mut info = CommitInfo {
+        author: String::new(),
+        date: "0 +0000".to_string(),
+        timestamp: 0,
+    };
+    for line in header.lines() {
+        let (kind, identity) = match line.split_once(' ') {
+            Some(pair) => pair,
+            None => continue,
+        };
+        let mut parts = identity.rsplitn(3, ' ');
+        let offset = parts.next().unwrap_or("+0000");
+        let timestamp = parts.next().unwrap_or("0");
+        let person = parts.next().unwrap_or_default();
+        match kind {
+            "author" => {
+                info.author = match person.rsplit_once(' ') {
+                    Some((name, _email)) => name.to_string(),
+                    None => person.to_string(),
+                };
+                info.date = format!("{} {}", timestamp, offset);
+            }
+            "committer" => info.timestamp = timestamp.parse().unwrap_or(0),
+            _ => {}
+        }
+    }
+    Ok(info)
+}
+
+/// Finds the path that `path` had in a parent commit.
+///
+/// The path is kept when the parent has it. Otherwise the file is looked up among the
+/// renames and copies between both trees, so blame keeps walking past a rename.
+fn find_parent_path(
+    git_dir: &str,
+    parent_tree: &Tree,
+    tree: &Tree,
+    path: &str,
+) -> io::Result<Option<String>> {
+    if parent_tree.get_hash_from_path(path).is_some() {
+        return Ok(Some(path.to_string()));
+    }
+    let changes = diff::get_tree_changes_with_renames(
+        Some(parent_tree),
+        Some(tree),
+        git_dir,
+        Some(RenameOptions::default()),
+    )?;
+    Ok(changes
+        .into_iter()
+        .find(|change| change.path == path)
+        .and_then(|change| change.source)
+        .map(|source| source.path))
+}
+
+/// Maps every line of `new` to the line of `old` it was kept from, if any.
+fn map_lines(old: &str, new: &str) -> Vec<Option<usize>> {
+    let mut mapping = Vec::new();
+    let mut old_index = 0;
+    for line in diff::diff_lines(old, new) {
+        match line {
+            DiffLine::Context(_) => {
+                mapping.push(Some(old_index));
+                old_index += 1;
+            }
+            DiffLine::Removed(_) => old_index += 1,
+            DiffLine::Added(_) => mapping.push(None),
+        }
+    }
+    mapping
+}
+
+/// Lines of the blamed file still waiting for a commit, as pairs of
+/// `(line in the blamed file, line in the version of the commit)`.
+type PendingLines = Vec<(usize, usize)>;
+
+/// Attributes every line of a file to the commit that introduced it.
+///
+/// The history is walked from `commit_hash`, newest commit first. Each commit passes to its
+/// parents the lines it kept unchanged from them, matching the lines with the diff engine,
+/// and the lines no parent has are blamed on the commit. When the file was renamed, the walk
+/// follows it under its previous name.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the git directory.
+/// * `commit_hash` - The commit whose version of the file is blamed.
+/// * `path` - The path of the file in that commit.
+///
+/// # Returns
+///
+/// One `BlameLine` for each line of the file, in order.
+///
+/// # Errors
+///
+/// Returns an error if the file does not exist in the commit or an object cannot be read.
+///
+pub fn blame(git_dir: &str, commit_hash: &str, path: &str) -> io::Result<Vec<BlameLine>> {
+    let mut trees: HashMap<String, Tree> = HashMap::new();
+    let tree = tree_handler::load_tree_from_commit(commit_hash, git_dir)?;
+    let blob = tree.get_hash_from_path(path).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("fatal: no such path '{}' in {}", path, commit_hash),
+        )
+    })?;
+    let content = cat_file::cat_file_return_content(&blob, git_dir)?;
+    let lines: Vec<&str> = content.lines().collect();
+    trees.insert(commit_hash.to_string(), tree);
+
+    let mut infos: HashMap<String, CommitInfo> = HashMap::new();
+    let mut pending: HashMap<(String, String), PendingLines> = HashMap::new();
+    let mut queue = BinaryHeap::new();
+    let mut result: Vec<Option<BlameLine>> = vec![None; lines.len()];
+
+    let info = read_commit_info(git_dir, commit_hash)?;
+    queue.push((info.timestamp, commit_hash.to_string(), path.to_string()));
+    infos.insert(commit_hash.to_string(), info);
+    pending.insert(
+        (commit_hash.to_string(), path.to_string()),
+        (0..lines.len()).map(|index| (index, index)).collect(),
+    );
+
+    while let Some((_, commit, file)) = queue.pop() {
+        let mut suspects = match pending.remove(&(commit.clone(), file.clone())) {
+            Some(suspects) => suspects,
+            None => continue,
+        };
+        let tree = match trees.get(&commit) {
+            Some(tree) => tree.clone(),
+            None => tree_handler::load_tree_from_commit(&commit, git_dir)?,
+        };
+        let blob = tree.get_hash_from_path(&file).unwrap_or_default();
+        let content = cat_file::cat_file_return_content(&blob, git_dir)?;
+
+        for parent in commit::get_commit_parents(&commit, git_dir)? {
+            if suspects.is_empty() {
+                break;
+            }
+            if !trees.contains_key(&parent) {
+                let parent_tree = tree_handler::load_tree_from_commit(&parent, git_dir)?;
+                trees.insert(parent.clone(), parent_tree);
+            }
+            let parent_tree = &trees[&parent];
+            let parent_file = match find_parent_path(git_dir, parent_tree, &tree, &file)? {
+                Some(parent_file) => parent_file,
+                None => continue,
+            };
+            let parent_blob = parent_tree
+                .get_hash_from_path(&parent_file)
+                .unwrap_or_default();
+            let mapping = if parent_blob == blob {
+                (0..content.lines().count()).map(Some).collect()
+            } else {
+                let parent_content = cat_file::cat_file_return_content(&parent_blob, git_dir)?;
+                map_lines(&parent_content, &content)
+            };
+
+            let mut passed = Vec::new();
+            suspects.retain(
+                |&(final_index, index)| match mapping.get(index).copied().flatten() {
+                    Some(parent_index) => {
+                        passed.push((final_index, parent_index));
+                        false
+                    }
+                    None => true,
+                },
+            );
+            if passed.is_empty() {
+                continue;
+            }
+            let key = (parent.clone(), parent_file.clone());
+            if !pending.contains_key(&key) {
+                if !infos.contains_key(&parent) {
+                    infos.insert(parent.clone(), read_commit_info(git_dir, &parent)?);
+                }
+                queue.push((infos[&parent].timestamp, parent.clone(), parent_file));
+            }
+            pending.entry(key).or_default().extend(passed);
+        }
+
+        let info = &infos[&commit];
+        for (final_index, _) in suspects {
+            result[final_index] = Some(BlameLine {
+                commit: commit.clone(),
+                author: info.author.clone(),
+                date: info.date.clone(),
+                path: file.clone(),
+                line_number: final_index + 1,
+                content: lines[final_index].to_string(),
+            });
+        }
+    }
+
+    Ok(result.into_iter().flatten().collect())
+}
+
+/// Parses the value of `-L`, which selects the lines `<start>,<end>` of a file with
+/// `total` lines.
+///
+/// Both ends are 1-based and inclusive. The start defaults to the first line and the end to
+/// the last one, and the end may be given as `+<count>` lines from the start.
+///
+/// # Errors
+///
+/// Returns an `InvalidInput` error if the range is malformed or starts past the end of the
+/// file.
+///
+fn parse_line_range(value: &str, total: usize) -> io::Result<(usize, usize)> {
+    let invalid = || {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("fatal: invalid -L argument '{}'", value),
+        )
+    };
+    let (start, end) = value.split_once(',').unwrap_or((value, ""));
+    let start = match start {
+        "" => 1,
+        start => start.parse::<usize>().map_err(|_| invalid())?,
+    };
+    let end = match end {
+        "" => total,
+        end => match end.strip_prefix('+') {
+            Some(count) => {
+                let count = count.parse::<usize>().map_err(|_| invalid())?;
+                (start + count).saturating_sub(1)
+            }
+            None => end.parse::<usize>().map_err(|_| invalid())?,
+        },
+    };
+    if start == 0 || end < start {
+        return Err(invalid());
+    }
+    if start > total {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("fatal: file has only {} lines", total),
+        ));
+    }
+    Ok((start, end.min(total)))
+}
+
+/// Writes the blamed lines, one per line, as
+/// `<hash> [<path>] (<author> <date> <line number>) <content>`.
+///
+/// The path of the commit is only shown when some line comes from a file with another name.
+fn write_blame(lines: &[BlameLine], blamed_path: &str, output: &mut impl Write) -> io::Result<()> {
+    let show_path = lines.iter().any(|line| line.path != blamed_path);
+    let path_width = lines.iter().map(|line| line.path.len()).max().unwrap_or(0);
+    let author_width = lines
+        .iter()
+        .map(|line| line.author.len())
+        .max()
+        .unwrap_or(0);
+    let number_width = lines
+        .iter()
+        .map(|line| line.line_number.to_string().len())
+        .max()
+        .unwrap_or(1);
+    for line in lines {
+        let hash = &line.commit[..line.commit.len().min(8)];
+        let path = if show_path {
+            format!(" {:<width$}", line.path, width = path_width)
+        } else {
+            String::new()
+        };
+        writeln!(
+            output,
+            "{}{} ({:<author_width$} {} {:>number_width$}) {}",
+            hash,
+            path,
+            line.author,
+            format_date_with(&line.date, DateMode::Iso),
+            line.line_number,
+            line.content,
+            author_width = author_width,
+            number_width = number_width
+        )?;
+    }
+    Ok(())
+}
+
+/// Shows the commit that last modified each line of a file.
+///
+/// The command line is `git blame [-L <start>,<end>] [<rev>] [--] <file>`. The revision
+/// defaults to `HEAD` and `-L` may be repeated to show several ranges of lines.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the git directory.
+/// * `line` - The command line, starting with `git blame`.
+/// * `output` - Where the annotated lines are written.
+///
+/// # Errors
+///
+/// Returns an error if the arguments are invalid, the revision cannot be resolved or the file
+/// does not exist in it.
+///
+pub fn git_blame(git_dir: &str, line: Vec<String>, output: &mut impl Write) -> io::Result<()> {
+    let mut ranges = Vec::new();
+    let mut operands = Vec::new();
+    let mut paths = Vec::new();
+    let mut after_separator = false;
+    let mut args = line.iter().skip(2);
+    while let Some(arg) = args.next() {
+        if after_separator {
+            paths.push(arg.clone());
+        } else if arg == "--" {
+            after_separator = true;
+        } else if arg == "-L" {
+            match args.next() {
+                Some(range) => ranges.push(range.clone()),
+                None => {
+                    return Err(io::Error::new(io::ErrorKind::InvalidInput, BLAME_USAGE));
+                }
+            }
+        } else if let Some(range) = arg.strip_prefix("-L") {
+            ranges.push(range.to_string());
+        } else if arg.starts_with('-') {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("error: unknown option '{}'\n{}", arg, BLAME_USAGE),
+            ));
+        } else {
+            operands.push(arg.clone());
+        }
+    }
+
+    let (revision, path) = match (operands.as_slice(), paths.as_slice()) {
+        ([], [path]) => ("HEAD".to_string(), path.clone()),
+        ([revision], [path]) => (revision.clone(), path.clone()),
+        ([path], []) => ("HEAD".to_string(), path.clone()),
+        ([revision, path], []) => (revision.clone(), path.clone()),
+        _ => return Err(io::Error::new(io::ErrorKind::InvalidInput, BLAME_USAGE)),
+    };
+    let commit_hash = utils::resolve_revision(git_dir, &revision)?;
+    let lines = blame(git_dir, &commit_hash, &path)?;
+
+    let selected = if ranges.is_empty() {
+        lines
+    } else {
+        let mut selected: Vec<BlameLine> = Vec::new();
+        for range in &ranges {
+            let (start, end) = parse_line_range(range, lines.len())?;
+            for line in &lines[start - 1..end] {
+                if !selected.contains(line) {
+                    selected.push(line.clone());
+                }
+            }
+        }
+        selected
+    };
+    write_blame(&selected, &path, output)?;
+    log_blame(&revision, &path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{add, hash_object, init};
+    use std::fs;
+    use std::path::Path;
+
+    /// Adds the files to the index and returns the hash of the resulting tree.
+    fn stage_tree(git_dir: &str, files: &[(&str, &str)]) -> String {
+        let index = format!("{}/index", git_dir);
+        for (path, content) in files {
+            fs::write(path, content).unwrap();
+            add::add(path, &index, git_dir, "", None).unwrap();
+        }
+        let tree = tree_handler::build_tree_from_index(&index, git_dir, "").unwrap();
+        tree_handler::write_tree(&tree, git_dir).unwrap().0
+    }
+
+    /// Stores a commit with a fixed author and date.
+    fn store_commit(
+        git_dir: &str,
+        tree: &str,
+        parents: &[&str],
+        author: &str,
+        time: i64,
+    ) -> String {
+        let mut content = format!("tree {}\n", tree);
+        for parent in parents {
+            content.push_str(&format!("parent {}\n", parent));
+        }
+        content.push_str(&format!(
+            "author {author} {time} -0300\ncommitter {author} {time} -0300\n\nmessage\0",
+            author = author,
+            time = time
+        ));
+        hash_object::store_string_to_file(&content, git_dir, "commit").unwrap()
+    }
+
+    fn prepare_repository(test_dir: &str) -> String {
+        if Path::new(test_dir).exists() {
+            fs::remove_dir_all(test_dir).unwrap();
+        }
+        fs::create_dir_all(test_dir).unwrap();
+        init::git_init(test_dir, ".mgit", "master", None).unwrap();
+        format!("{}/.mgit", test_dir)
+    }
+
+    fn run_blame(git_dir: &str, args: &[&str]) -> io::Result<String> {
+        let line = ["git", "blame"]
+            .iter()
+            .chain(args)
+            .map(|arg| arg.to_string())
+            .collect();
+        let mut output = Vec::new();
+        git_blame(git_dir, line, &mut output)?;
+        Ok(String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn test_parse_line_range() {
+        assert_eq!(parse_line_range("2,4", 10).unwrap(), (2, 4));
+        assert_eq!(parse_line_range("3,+2", 10).unwrap(), (3, 4));
+        assert_eq!(parse_line_range(",3", 10).unwrap(), (1, 3));
+        assert_eq!(parse_line_range("8,", 10).unwrap(), (8, 10));
+        assert_eq!(parse_line_range("8,20", 10).unwrap(), (8, 10));
+        assert!(parse_line_range("4,2", 10).is_err());
+        assert!(parse_line_range("11,12", 10).is_err());
+        assert!(parse_line_range("a,b", 10).is_err());
+    }
+
+    #[test]
+    fn test_blame_attributes_lines_to_the_commits_that_introduced_them() {
+        let test_dir = "tests/blame/linear";
+        let git_dir = prepare_repository(test_dir);
+        let file = format!("{}/file.txt", test_dir);
+
+        let tree = stage_tree(&git_dir, &[(&file, "one\ntwo\nthree\n")]);
+        let first = store_commit(&git_dir, &tree, &[], "alice alice@mail.com", 1000);
+        let tree = stage_tree(&git_dir, &[(&file, "one\n2\nthree\nfour\n")]);
+        let second = store_commit(&git_dir, &tree, &[&first], "bob bob@mail.com", 2000);
+        fs::write(format!("{}/refs/heads/master", git_dir), &second).unwrap();
+
+        let lines = blame(&git_dir, &second, &file).unwrap();
+        let commits: Vec<&str> = lines.iter().map(|line| line.commit.as_str()).collect();
+        assert_eq!(commits, [&first, &second, &first, &second]);
+        let authors: Vec<&str> = lines.iter().map(|line| line.author.as_str()).collect();
+        assert_eq!(authors, ["alice", "bob", "alice", "bob"]);
+
+        let output = run_blame(&git_dir, &["-L", "2,3", "--", &file]).unwrap();
+        let expected = format!(
+            "{} (bob   {} 2) 2\n{} (alice {} 3) three\n",
+            &second[..8],
+            format_date_with("2000 -0300", DateMode::Iso),
+            &first[..8],
+            format_date_with("1000 -0300", DateMode::Iso),
+        );
+        assert_eq!(output, expected);
+
+        let output = run_blame(&git_dir, &[&first, &file]).unwrap();
+        assert_eq!(output.lines().count(), 3);
+        assert!(run_blame(&git_dir, &["-L", "9,10", &file]).is_err());
+        fs::remove_dir_all(test_dir).unwrap();
+    }
+
+    #[test]
+    fn test_blame_follows_renamed_files() {
+        let test_dir = "tests/blame/rename";
+        let git_dir = prepare_repository(test_dir);
+        let old = format!("{}/old.txt", test_dir);
+        let new = format!("{}/new.txt", test_dir);
+
+        let tree = stage_tree(&git_dir, &[(&old, "a\nb\nc\nd\n")]);
+        let create = store_commit(&git_dir, &tree, &[], "alice alice@mail.com", 1000);
+        fs::remove_file(&old).unwrap();
+        fs::write(format!("{}/index", git_dir), "").unwrap();
+        let tree = stage_tree(&git_dir, &[(&new, "a\nb\nc\nx\n")]);
+        let rename = store_commit(&git_dir, &tree, &[&create], "bob bob@mail.com", 2000);
+
+        let lines = blame(&git_dir, &rename, &new).unwrap();
+        let origins: Vec<(&str, &str)> = lines
+            .iter()
+            .map(|line| (line.commit.as_str(), line.path.as_str()))
+            .collect();
+        assert_eq!(
+            origins,
+            [
+                (create.as_str(), old.as_str()),
+                (create.as_str(), old.as_str()),
+                (create.as_str(), old.as_str()),
+                (rename.as_str(), new.as_str()),
+            ]
+        );
+
+        fs::write(format!("{}/refs/heads/master", git_dir), &rename).unwrap();
+        let output = run_blame(&git_dir, &[&new]).unwrap();
+        let output: Vec<&str> = output.lines().collect();
+        assert!(output[0].starts_with(&format!("{} {} (alice", &create[..8], old)));
+        assert!(output[3].starts_with(&format!("{} {} (bob  ", &rename[..8], new)));
+        fs::remove_dir_all(test_dir).unwrap();
+    }
+}
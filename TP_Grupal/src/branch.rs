@@ -1,7 +1,8 @@
 use crate::commit;
 use crate::commit::get_branch_name;
 use crate::configuration::LOGGER_COMMANDS_FILE;
-use crate::utils::get_current_time;
+use crate::utils::{self, get_current_time};
+use crate::worktree;
 use crate::{logger::Logger, utils::obtain_git_dir};
 use std::{
     fs::{self, File},
@@ -48,7 +49,7 @@ pub fn get_current_branch_path(git_dir_path: &str) -> io::Result<String> {
 /// in case any issue occurs during the operation.
 ///
 pub fn get_branch_commit_hash(branch_name: &str, git_dir: &str) -> io::Result<String> {
-    let branch_path = git_dir.to_string() + "/refs/heads/" + branch_name;
+    let branch_path = utils::git_path(git_dir, &format!("refs/heads/{}", branch_name));
     let mut branch_file = std::fs::File::open(branch_path)?;
     let mut branch_content = String::new();
     branch_file.read_to_string(&mut branch_content)?;
@@ -78,7 +79,7 @@ pub fn update_branch_commit_hash(
     commit_hash: &str,
     git_dir: &str,
 ) -> io::Result<()> {
-    let branch_path = git_dir.to_string() + "/refs/heads/" + branch_name;
+    let branch_path = utils::git_path(git_dir, &format!("refs/heads/{}", branch_name));
     let mut branch_file = std::fs::File::create(branch_path)?;
     branch_file.write_all(commit_hash.as_bytes())?;
     Ok(())
@@ -88,7 +89,7 @@ pub fn update_branch_commit_hash(
 ///
 /// This function reads the commit hash of the currently checked-out branch in the local Git repository located
 /// in the directory specified by `git_dir_path`. It accesses the reference file of the current branch to obtain
-/// the commit hash.
+/// the commit hash. When HEAD is detached, the commit it points to is returned instead.
 ///
 /// # Arguments
 ///
@@ -100,8 +101,14 @@ pub fn update_branch_commit_hash(
 /// in case any issue occurs during the operation.
 ///
 pub fn get_current_branch_commit(git_dir_path: &str) -> io::Result<String> {
+    let head = fs::read_to_string(format!("{}/HEAD", git_dir_path))?;
+    if !head.starts_with("ref: ") {
+        if let Some(commit) = head.split_whitespace().next() {
+            return Ok(commit.to_string());
+        }
+    }
     let branch_path = get_current_branch_path(git_dir_path)?;
-    let complete_path = git_dir_path.to_string() + "/" + &branch_path;
+    let complete_path = utils::git_path(git_dir_path, &branch_path);
     let mut branch_file = File::open(complete_path)?;
     let mut branch_content = String::new();
     branch_file.read_to_string(&mut branch_content)?;
@@ -112,7 +119,8 @@ pub fn get_current_branch_commit(git_dir_path: &str) -> io::Result<String> {
 ///
 /// This function is used to delete a specific Git branch named `branch_name` from the local Git repository
 /// located in the directory specified by `git_dir`. If the branch exists, its reference file is removed.
-/// If the branch does not exist, an error message is printed to the standard output.
+/// If the branch does not exist, or if it is checked out in this or another working tree, an error
+/// message is printed to the standard output.
 ///
 /// # Arguments
 ///
@@ -124,14 +132,23 @@ pub fn get_current_branch_commit(git_dir_path: &str) -> io::Result<String> {
 /// Returns a `Result` indicating success or failure. In case of success, an `io::Result<()>` is returned.
 ///
 pub fn delete_branch(git_dir: &str, branch_name: &str, output: &mut impl Write) -> io::Result<()> {
-    let branch_path = git_dir.to_string() + "/refs/heads/" + branch_name;
+    let branch_path = utils::git_path(git_dir, &format!("refs/heads/{}", branch_name));
     let path = Path::new(&branch_path);
 
     if path.exists() {
         let path_relative_to_refs = format!("{}/{}", "refs/heads", branch_name);
         let head_path = format!("{}/HEAD", git_dir);
         let content = fs::read_to_string(head_path)?;
-        if content.eq(&path_relative_to_refs) {
+        let checked_out_elsewhere = worktree::checked_out_elsewhere(git_dir, branch_name)?;
+        if let Some(working_dir) = checked_out_elsewhere {
+            output.write_all(
+                format!(
+                    "error: Cannot delete branch {} checked out at {}",
+                    branch_name, working_dir
+                )
+                .as_bytes(),
+            )?;
+        } else if content.trim() == format!("ref: {}", path_relative_to_refs) {
             if let Some(working_dir) = Path::new(git_dir).parent() {
                 output.write_all(
                     format!(
@@ -180,7 +197,7 @@ fn create_branch_from_existing_one(
     from: &str,
     output: &mut impl Write,
 ) -> io::Result<()> {
-    let new_refs = (&git_dir).to_string() + "/refs/heads/" + branch_name;
+    let new_refs = utils::git_path(git_dir, &format!("refs/heads/{}", branch_name));
     let refs_path = Path::new(&new_refs);
     if refs_path.exists() {
         let buffer = format!("fatal: A branch named '{}' already exists\n", branch_name);
@@ -191,7 +208,7 @@ fn create_branch_from_existing_one(
         ));
     }
 
-    let from_refs = (&git_dir).to_string() + "/refs/heads/" + from;
+    let from_refs = utils::git_path(git_dir, &format!("refs/heads/{}", from));
     let from_path = Path::new(&from_refs);
     if !from_path.exists() {
         let buffer = format!("fatal: Not a valid object name: '{}'.\n", from);
@@ -228,7 +245,7 @@ fn create_branch_from_current_one(
     branch_name: &str,
     output: &mut impl Write,
 ) -> io::Result<()> {
-    let heads_dir = (&git_dir).to_string() + "/refs/heads";
+    let heads_dir = utils::git_path(git_dir, "refs/heads");
     let entries = fs::read_dir(heads_dir)?;
     if entries.count() == 0 {
         let buffer = "fatal: Please commit something to create a branch\n".to_string();
@@ -239,7 +256,7 @@ fn create_branch_from_current_one(
         ));
     }
 
-    let new_refs = (&git_dir).to_string() + "/refs/heads/" + branch_name;
+    let new_refs = utils::git_path(git_dir, &format!("refs/heads/{}", branch_name));
     let refs_path = Path::new(&new_refs);
     if refs_path.exists() {
         let buffer = format!("fatal: A branch named '{}' already exists\n", branch_name);
@@ -285,7 +302,7 @@ pub fn create_new_branch(
 /// Lists all the branches in the repo. It writes the output in the given output.
 /// If the branch is the current one, it will be marked with a `*` and in green.
 pub fn list_branches(git_dir: &str, output: &mut impl Write) -> io::Result<()> {
-    let heads_dir = (&git_dir).to_string() + "/refs/heads";
+    let heads_dir = utils::git_path(git_dir, "refs/heads");
     let entries = fs::read_dir(&heads_dir)?;
     let current_branch = commit::get_branch_name(git_dir)?;
     if entries.count() > 0 {
@@ -326,8 +343,14 @@ pub fn modify_branch(
     new_name: &str,
     output: &mut impl Write,
 ) -> io::Result<()> {
-    let branch_path = PathBuf::from(git_dir).join("refs/heads").join(branch_name);
-    let new_branch_path = PathBuf::from(git_dir).join("refs/heads").join(new_name);
+    let branch_path = PathBuf::from(utils::git_path(
+        git_dir,
+        &format!("refs/heads/{}", branch_name),
+    ));
+    let new_branch_path = PathBuf::from(utils::git_path(
+        git_dir,
+        &format!("refs/heads/{}", new_name),
+    ));
 
     if branch_path.exists() {
         if new_branch_path.exists() {
@@ -454,7 +477,7 @@ fn log_command(command: &str, option: &str, _git_dir: &Path) -> io::Result<()> {
 /// If the branches directory is not found, an error is returned.
 pub fn get_all_branches(git_dir: &str) -> io::Result<Vec<String>> {
     let mut branches = vec![];
-    let heads_dir = (&git_dir).to_string() + "/refs/heads";
+    let heads_dir = utils::git_path(git_dir, "refs/heads");
     let entries = fs::read_dir(&heads_dir)?;
     if entries.count() > 0 {
         let entries = fs::read_dir(&heads_dir)?;
@@ -512,7 +535,7 @@ pub fn remove_ansi_escape_codes(input: &str) -> String {
 /// - `false`: The specified Git branch does not exist in the provided Git directory.
 ///
 pub fn is_an_existing_branch(branch: &str, git_dir: &str) -> bool {
-    let path = format!("{}/refs/heads/{}", git_dir, branch);
+    let path = utils::git_path(git_dir, &format!("refs/heads/{}", branch));
 
     if let Ok(metadata) = fs::metadata(path) {
         metadata.is_file()
@@ -1,4 +1,4 @@
-use crate::utils::get_current_time;
+use crate::utils::{self, get_current_time};
 use crate::{configuration::LOGGER_COMMANDS_FILE, logger::Logger};
 use flate2::bufread::ZlibDecoder;
 use std::{
@@ -66,7 +66,7 @@ pub fn cat_file(hash: &str, directory: &str, output: &mut impl Write) -> io::Res
 /// * `hash` - The complete hash of the file to print.
 /// * `directory` - The path to the git directory.
 pub fn cat_file_return_content(hash: &str, directory: &str) -> io::Result<String> {
-    let file_dir = format!("{}/objects/{}", directory, &hash[..2]);
+    let file_dir = utils::git_path(directory, &format!("objects/{}", &hash[..2]));
     let file = File::open(format!("{}/{}", file_dir, &hash[2..]))?;
     let content = decompress_file(file)?;
     let partes = content.split('\0').nth(1);
@@ -76,6 +76,58 @@ pub fn cat_file_return_content(hash: &str, directory: &str) -> io::Result<String
     }
 }
 
+/// Returns the content of a Git object as bytes, without its header.
+///
+/// Unlike `cat_file_return_content`, the content does not need to be valid UTF-8, so it can be
+/// used for binary blobs.
+///
+/// # Arguments
+///
+/// * `hash`: The complete hash of the object.
+/// * `directory`: The path to the git directory.
+///
+/// # Errors
+///
+/// Returns an error if the object cannot be read.
+///
+pub fn cat_file_return_bytes(hash: &str, directory: &str) -> io::Result<Vec<u8>> {
+    let file_dir = utils::git_path(directory, &format!("objects/{}", &hash[..2]));
+    let file = File::open(format!("{}/{}", file_dir, &hash[2..]))?;
+    let mut content = decompress_into_bytes(file)?;
+    match content.iter().position(|&x| x == 0) {
+        Some(pos) => Ok(content.split_off(pos + 1)),
+        None => Ok(content),
+    }
+}
+
+/// Returns the type of a Git object, as written in its header.
+///
+/// # Arguments
+///
+/// * `hash`: The complete hash of the object.
+/// * `directory`: The path to the git directory.
+///
+/// # Returns
+///
+/// Returns the object type: `blob`, `tree`, `commit` or `tag`.
+///
+/// # Errors
+///
+/// Returns an error if the object cannot be read or its header is malformed.
+///
+pub fn get_object_type(hash: &str, directory: &str) -> io::Result<String> {
+    let file_dir = utils::git_path(directory, &format!("objects/{}", &hash[..2]));
+    let file = File::open(format!("{}/{}", file_dir, &hash[2..]))?;
+    let content = decompress_into_bytes(file)?;
+    match content.iter().position(|&x| x == b' ') {
+        Some(pos) => Ok(String::from_utf8_lossy(&content[..pos]).to_string()),
+        None => Err(io::Error::new(
+            ErrorKind::InvalidData,
+            "Object header not found",
+        )),
+    }
+}
+
 /// Extracts and parses the content of a Git tree object.
 ///
 /// This function reads and parses the content of a Git tree object identified by its `hash` from the local
@@ -98,7 +150,7 @@ pub fn cat_file_return_content(hash: &str, directory: &str) -> io::Result<String
 /// in the tree object. In case of success, the result is wrapped in an `io::Result<Vec<(String, String, String)>>`.
 ///
 pub fn cat_tree(hash: &str, directory: &str) -> io::Result<Vec<(String, String, String)>> {
-    let file_dir = format!("{}/objects/{}", directory, &hash[..2]);
+    let file_dir = utils::git_path(directory, &format!("objects/{}", &hash[..2]));
     let file = File::open(format!("{}/{}", file_dir, &hash[2..]))?;
 
     let content = decompress_into_bytes(file)?;
@@ -198,6 +250,13 @@ mod tests {
         assert_eq!(content, "Hello World!");
     }
 
+    #[test]
+    fn test_get_object_type() {
+        let hash = "c57eff55ebc0c54973903af5f72bac72762cf4f4";
+        let object_type = get_object_type(hash, "tests/cat_file").unwrap();
+        assert_eq!(object_type, "blob");
+    }
+
     #[test]
     fn test_decompress_file() {
         let file =
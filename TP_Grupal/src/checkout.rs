@@ -1,12 +1,25 @@
+use crate::attributes::Attributes;
 use crate::branch;
-use crate::configuration::LOGGER_COMMANDS_FILE;
+use crate::cat_file;
+use crate::configuration::{GIT_IGNORE, LOGGER_COMMANDS_FILE};
+use crate::diff;
+use crate::filter;
+use crate::hash_object;
+use crate::hooks;
+use crate::index::Index;
 use crate::logger::Logger;
-use crate::tree_handler;
-use crate::utils::get_current_time;
+use crate::restore;
+use crate::tree_handler::{self, Tree};
+use crate::utils::{self, get_current_time};
+use crate::worktree;
+use std::collections::HashMap;
 use std::fs;
 use std::io;
 use std::io::Write;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+/// Hash given to the `post-checkout` hook for a HEAD without commits.
+const NULL_HASH: &str = "0000000000000000000000000000000000000000";
 
 /// Logs the 'git checkout' command with the specified parameters.
 ///
@@ -62,7 +75,10 @@ fn log_checkout(
 /// files and directories of the new branch.
 ///
 /// If the branch reference file does not exist, or if there are errors during the process, the
-/// function prints an error message to the standard error output.
+/// function prints an error message to the standard error output. If switching branches would
+/// overwrite files with local changes, or untracked files in the way, nothing is changed and an
+/// error listing those files is returned. A branch checked out in another working tree cannot be
+/// checked out.
 pub fn checkout_branch(git_dir_path: &Path, root_dir: &str, branch_name: &str) -> io::Result<()> {
     let git_dir_path_str = match git_dir_path.to_str() {
         Some(path) => path,
@@ -83,6 +99,22 @@ pub fn checkout_branch(git_dir_path: &Path, root_dir: &str, branch_name: &str) -
             ))
         }
     };
+    let previous_head = head_commit(git_dir_path_str);
+
+    if let Some(path) = worktree::checked_out_elsewhere(git_dir_path_str, branch_name)? {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "fatal: '{}' is already checked out at '{}'",
+                branch_name, path
+            ),
+        ));
+    }
+
+    let branch_ref_file = branch_ref_path(git_dir_path, branch_name);
+    if let Ok(branch_commit_id) = fs::read_to_string(branch_ref_file) {
+        check_checkout_is_safe(git_dir_path_str, root_dir, branch_commit_id.trim())?;
+    }
 
     match checkout_branch_references(git_dir_path, branch_name) {
         Ok(old_commit_id) => {
@@ -90,6 +122,7 @@ pub fn checkout_branch(git_dir_path: &Path, root_dir: &str, branch_name: &str) -
             match replace_working_tree(git_dir_path_str, root_dir, &old_commit_id, &new_commit_id) {
                 Ok(_) => {
                     log_checkout(&current_branch, branch_name, "Checkout", git_dir_path)?;
+                    run_post_checkout_hook(git_dir_path_str, &previous_head, true);
                     Ok(())
                 }
                 Err(err) => Err(err),
@@ -117,8 +150,7 @@ pub fn checkout_branch(git_dir_path: &Path, root_dir: &str, branch_name: &str) -
 /// in case any issue occurs during the operation. The result is wrapped in an `io::Result<String>`.
 ///
 fn checkout_branch_references(git_dir: &Path, branch_name: &str) -> io::Result<String> {
-    let refs_dir = git_dir.join("refs").join("heads");
-    let branch_ref_file = refs_dir.join(branch_name);
+    let branch_ref_file = branch_ref_path(git_dir, branch_name);
     let git_dir_str = match git_dir.to_str() {
         Some(path) => path,
         None => {
@@ -183,10 +215,13 @@ pub fn create_and_checkout_branch(
         Err(_) => "Unknown".to_string(),
     };
 
+    let previous_head = head_commit(git_dir_str);
+
     let old_commit_id = create_and_checkout_branch_references(git_dir_str, branch_name)?;
     let branch_commit_id = branch::get_current_branch_commit(git_dir_str)?;
     replace_working_tree(git_dir_str, root_dir, &old_commit_id, &branch_commit_id)?;
     log_checkout(&current_branch, branch_name, "Create and Checkout", git_dir)?;
+    run_post_checkout_hook(git_dir_str, &previous_head, true);
     Ok(())
 }
 
@@ -228,8 +263,7 @@ fn create_and_checkout_branch_references(
 /// * `branch_name` - A string containing the name of the branch to create or reset.
 ///
 pub fn create_or_reset_branch(git_dir: &Path, root_dir: &str, branch_name: &str) -> io::Result<()> {
-    let refs_dir = git_dir.join("refs").join("heads");
-    let branch_ref_file = refs_dir.join(branch_name);
+    let branch_ref_file = branch_ref_path(git_dir, branch_name);
     let git_dir_str = match git_dir.to_str() {
         Some(path) => path,
         None => {
@@ -267,6 +301,9 @@ pub fn create_or_reset_branch(git_dir: &Path, root_dir: &str, branch_name: &str)
 /// * `git_dir` - A reference to the `std::path::Path` representing the Git repository directory.
 /// * `commit_id` - A string containing the ID of the commit to check out in detached mode.
 ///
+/// The checkout is refused, leaving HEAD untouched, if it would overwrite files with local changes
+/// or untracked files in the way.
+///
 pub fn checkout_commit_detached(git_dir: &Path, root_dir: &str, commit_id: &str) -> io::Result<()> {
     let git_dir_str = match git_dir.to_str() {
         Some(path) => path,
@@ -278,6 +315,9 @@ pub fn checkout_commit_detached(git_dir: &Path, root_dir: &str, commit_id: &str)
         }
     };
 
+    check_checkout_is_safe(git_dir_str, root_dir, commit_id)?;
+    let previous_head = head_commit(git_dir_str);
+
     match checkout_commit_detached_references(git_dir_str, commit_id) {
         Ok(old_commit_id) => {
             match replace_working_tree(git_dir_str, root_dir, &old_commit_id, commit_id) {
@@ -286,6 +326,7 @@ pub fn checkout_commit_detached(git_dir: &Path, root_dir: &str, commit_id: &str)
                     let current_branch = branch::get_current_branch_path(git_dir_str)
                         .unwrap_or_else(|_| "Unknown".to_string());
                     log_checkout(&current_branch, commit_id, "Create or reset", git_dir)?;
+                    run_post_checkout_hook(git_dir_str, &previous_head, true);
                     Ok(())
                 }
                 Err(err) => Err(err),
@@ -322,8 +363,9 @@ fn checkout_commit_detached_references(git_dir_str: &str, commit_id: &str) -> io
 /// Replaces the working tree of a Git repository with the content of a new commit.
 ///
 /// This function replaces the working tree of a Git repository with the content associated with a new commit
-/// identified by `new_commit_id`. It removes files and directories that were part of the previous commit
-/// identified by `old_commit_id` and creates new files and directories from the content of the new commit.
+/// identified by `new_commit_id`. Only the paths that differ between the commit identified by `old_commit_id`
+/// and the new one are written or removed, so local changes to any other file are kept. Callers are expected
+/// to have checked with `check_overwritten_paths` that none of those paths has local changes.
 ///
 /// # Arguments
 ///
@@ -344,12 +386,300 @@ fn replace_working_tree(
 ) -> io::Result<()> {
     let commit_tree = tree_handler::load_tree_from_commit(new_commit_id, git_dir)?;
     let latest_tree = tree_handler::load_tree_from_commit(old_commit_id, git_dir)?;
+    update_working_tree(git_dir, root_dir, Some(&latest_tree), &commit_tree)
+}
+
+/// Checks that moving the working tree from the current HEAD commit to `new_commit_id` would not
+/// overwrite any local change.
+///
+/// # Arguments
+///
+/// * `git_dir`: The path to the local directory containing the Git repository.
+/// * `root_dir`: The root directory of the working tree.
+/// * `new_commit_id`: The identifier of the commit that is about to be checked out.
+///
+/// # Errors
+///
+/// Returns an error listing the affected files if any of them has local changes or is an untracked
+/// file in the way, or if one of the commits cannot be read.
+///
+fn check_checkout_is_safe(git_dir: &str, root_dir: &str, new_commit_id: &str) -> io::Result<()> {
+    let old_commit_id = branch::get_current_branch_commit(git_dir)?;
+    if old_commit_id == new_commit_id {
+        return Ok(());
+    }
+    let old_tree = tree_handler::load_tree_from_commit(&old_commit_id, git_dir)?;
+    let new_tree = tree_handler::load_tree_from_commit(new_commit_id, git_dir)?;
+    check_overwritten_paths(git_dir, root_dir, Some(&old_tree), &new_tree, "checkout")
+}
+
+/// Finds the files that would be overwritten when moving the working tree from `old_tree` to `new_tree`.
+///
+/// Only the paths that differ between both trees are considered. A path is reported as modified when
+/// its index entry or its working copy differs from both trees, and as untracked when it is not part
+/// of `old_tree` nor of the index but a different file already exists in the working tree.
+///
+/// # Arguments
+///
+/// * `git_dir`: The path to the local directory containing the Git repository.
+/// * `root_dir`: The root directory of the working tree.
+/// * `old_tree`: The tree the working tree currently corresponds to, or `None` if there is none.
+/// * `new_tree`: The tree that is about to be written into the working tree.
+///
+/// # Returns
+///
+/// Returns a tuple with the list of locally modified paths and the list of untracked paths in the way.
+///
+/// # Errors
+///
+/// Returns an error if the index or the working files cannot be read.
+///
+pub fn get_overwritten_paths(
+    git_dir: &str,
+    root_dir: &str,
+    old_tree: Option<&Tree>,
+    new_tree: &Tree,
+) -> io::Result<(Vec<String>, Vec<String>)> {
+    let index = load_index(git_dir, root_dir)?;
+    let mut modified = Vec::new();
+    let mut untracked = Vec::new();
+    for change in diff::get_tree_changes(old_tree, Some(new_tree)) {
+        let old_hash = change.old_hash.as_ref();
+        let new_hash = change.new_hash.as_ref();
+        let staged_hash = index.get_hash(&change.path);
+        let working_hash = hash_working_file(root_dir, &change.path)?;
+        let clean_index = staged_hash == old_hash || staged_hash == new_hash;
+        let clean_working = working_hash.is_none()
+            || working_hash.as_ref() == old_hash
+            || working_hash.as_ref() == new_hash;
+        if clean_index && clean_working {
+            continue;
+        }
+        if old_hash.is_none() && staged_hash.is_none() {
+            untracked.push(change.path);
+        } else {
+            modified.push(change.path);
+        }
+    }
+    Ok((modified, untracked))
+}
+
+/// Makes sure that moving the working tree from `old_tree` to `new_tree` does not overwrite local changes.
+///
+/// # Arguments
+///
+/// * `git_dir`: The path to the local directory containing the Git repository.
+/// * `root_dir`: The root directory of the working tree.
+/// * `old_tree`: The tree the working tree currently corresponds to, or `None` if there is none.
+/// * `new_tree`: The tree that is about to be written into the working tree.
+/// * `operation`: The name of the command, used in the error message (e.g. "checkout" or "merge").
+///
+/// # Errors
+///
+/// Returns an `AlreadyExists` error whose message lists the locally modified files and the untracked
+/// files that would be overwritten, so the caller can abort before touching anything.
+///
+pub fn check_overwritten_paths(
+    git_dir: &str,
+    root_dir: &str,
+    old_tree: Option<&Tree>,
+    new_tree: &Tree,
+    operation: &str,
+) -> io::Result<()> {
+    let (modified, untracked) = get_overwritten_paths(git_dir, root_dir, old_tree, new_tree)?;
+    if modified.is_empty() && untracked.is_empty() {
+        return Ok(());
+    }
+    let action = match operation {
+        "checkout" => "switch branches",
+        _ => operation,
+    };
+    let mut message = String::new();
+    if !modified.is_empty() {
+        message.push_str(&format!(
+            "error: Your local changes to the following files would be overwritten by {}:\n",
+            operation
+        ));
+        for path in &modified {
+            message.push_str(&format!("\t{}\n", path));
+        }
+        message.push_str(&format!(
+            "Please commit your changes or stash them before you {}.\n",
+            action
+        ));
+    }
+    if !untracked.is_empty() {
+        message.push_str(&format!(
+            "error: The following untracked working tree files would be overwritten by {}:\n",
+            operation
+        ));
+        for path in &untracked {
+            message.push_str(&format!("\t{}\n", path));
+        }
+        message.push_str(&format!(
+            "Please move or remove them before you {}.\n",
+            action
+        ));
+    }
+    message.push_str("Aborting");
+    Err(io::Error::new(io::ErrorKind::AlreadyExists, message))
+}
+
+/// Moves the working tree and the index from `old_tree` to `new_tree`.
+///
+/// Every path that differs between both trees is written with its content in `new_tree`, or removed
+/// if `new_tree` does not contain it, and its index entry is updated accordingly. Files that are the
+/// same in both trees are left untouched, keeping any local change made to them, including their
+/// removal.
+///
+/// # Arguments
+///
+/// * `git_dir`: The path to the local directory containing the Git repository.
+/// * `root_dir`: The root directory of the working tree.
+/// * `old_tree`: The tree the working tree currently corresponds to, or `None` if there is none.
+/// * `new_tree`: The tree to write into the working tree.
+///
+/// # Errors
+///
+/// Returns an error if a blob cannot be read or if the working tree or the index cannot be written.
+///
+pub fn update_working_tree(
+    git_dir: &str,
+    root_dir: &str,
+    old_tree: Option<&Tree>,
+    new_tree: &Tree,
+) -> io::Result<()> {
+    let mut index = load_index(git_dir, root_dir)?;
+    for change in diff::get_tree_changes(old_tree, Some(new_tree)) {
+        restore_path(root_dir, git_dir, &change.path, change.new_hash.as_ref())?;
+        match &change.new_hash {
+            Some(hash) => index.add_file(&change.path, hash)?,
+            None => {
+                if index.contains(&change.path) {
+                    index.remove_file(&change.path)?;
+                }
+            }
+        }
+    }
+    index.write_file()
+}
+
+/// Resets the working tree and the index to the content of a commit, discarding local changes.
+///
+/// This is the forced counterpart of `update_working_tree`: every tracked file that is not part of
+/// the commit is removed and every file of the commit whose working copy differs is rewritten,
+/// without checking for local modifications. It is meant for callers that moved a branch reference
+/// themselves, such as rebase, and need the working tree to follow it.
+///
+/// # Arguments
+///
+/// * `git_dir`: The path to the local directory containing the Git repository.
+/// * `root_dir`: The root directory of the working tree.
+/// * `commit_id`: The identifier of the commit to reset to.
+///
+/// # Errors
+///
+/// Returns an error if the commit cannot be read or if the working tree or the index cannot be written.
+///
+pub fn reset_working_tree(git_dir: &str, root_dir: &str, commit_id: &str) -> io::Result<()> {
+    let commit_tree = tree_handler::load_tree_from_commit(commit_id, git_dir)?;
+    let commit_files: HashMap<String, String> =
+        commit_tree.squash_tree_into_vec("").into_iter().collect();
+    let mut index = load_index(git_dir, root_dir)?;
+    let tracked_paths: Vec<String> = index.iter().map(|(path, _)| path.to_string()).collect();
+    for path in tracked_paths {
+        if !commit_files.contains_key(&path) {
+            restore_path(root_dir, git_dir, &path, None)?;
+            index.remove_file(&path)?;
+        }
+    }
+    for (path, hash) in &commit_files {
+        if hash_working_file(root_dir, path)?.as_ref() != Some(hash) {
+            restore_path(root_dir, git_dir, path, Some(hash))?;
+        }
+        index.add_file(path, hash)?;
+    }
+    index.write_file()
+}
+
+/// Checks out individual paths from the index or from a revision, without switching branches.
+///
+/// Without a revision, the matching files of the working tree are rewritten from the index,
+/// discarding their unstaged changes. With a revision, the matching files are taken from the tree
+/// of that revision and written both into the index and into the working tree. Files that are not
+/// matched by any pathspec are left untouched.
+///
+/// # Arguments
+///
+/// * `git_dir` - A reference to the `std::path::Path` representing the Git repository directory.
+/// * `root_dir` - A string representing the path to the root directory of the repository.
+/// * `revision` - The revision to take the files from, or `None` to take them from the index.
+/// * `pathspecs` - The paths to check out. Directories match every file inside them.
+/// * `output` - The writer where the number of updated paths is reported.
+///
+/// # Errors
+///
+/// Returns an error if the revision cannot be resolved, if a pathspec does not match any file of
+/// the source, or if the index or the working tree cannot be written.
+///
+pub fn checkout_paths(
+    git_dir: &Path,
+    root_dir: &str,
+    revision: Option<&str>,
+    pathspecs: &[String],
+    output: &mut impl Write,
+) -> io::Result<()> {
+    let git_dir_str = match git_dir.to_str() {
+        Some(path) => path,
+        None => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Error when reading path",
+            ))
+        }
+    };
+    let previous_head = head_commit(git_dir_str);
+    let mut index = load_index(git_dir_str, root_dir)?;
+    let source_files: HashMap<String, String> = match revision {
+        Some(revision) => restore::get_revision_files(git_dir_str, revision)?,
+        None => index
+            .iter()
+            .map(|(path, hash)| (path.to_string(), hash.to_string()))
+            .collect(),
+    };
+    let selected = restore::select_paths(source_files.keys(), pathspecs)?;
+    for path in &selected {
+        let hash = source_files.get(path);
+        restore_path(root_dir, git_dir_str, path, hash)?;
+        if let Some(hash) = hash {
+            index.add_file(path, hash)?;
+        }
+    }
+    if revision.is_some() {
+        index.write_file()?;
+    }
 
-    latest_tree.delete_directories(root_dir)?;
-    commit_tree.create_directories(root_dir, git_dir)?;
-    let index_path = git_dir.to_string() + "/index";
-    let index = commit_tree.build_index_file_from_tree(&index_path, git_dir, "")?;
-    index.write_file()?;
+    let source = match revision {
+        Some(revision) => utils::resolve_revision(git_dir_str, revision)?[..7].to_string(),
+        None => "the index".to_string(),
+    };
+    let noun = if selected.len() == 1 { "path" } else { "paths" };
+    writeln!(
+        output,
+        "Updated {} {} from {}",
+        selected.len(),
+        noun,
+        source
+    )?;
+    let current_branch =
+        branch::get_current_branch_path(git_dir_str).unwrap_or_else(|_| "Unknown".to_string());
+    log_checkout(
+        &current_branch,
+        &pathspecs.join(" "),
+        "Checkout paths",
+        git_dir,
+    )?;
+    run_post_checkout_hook(git_dir_str, &previous_head, false);
     Ok(())
 }
 
@@ -368,14 +698,15 @@ fn replace_working_tree(
 ///                       ID (e.g., "a1b2c3d4e5").
 ///
 pub fn force_checkout(git_dir: &Path, branch_or_commit: &str) -> Result<(), io::Error> {
+    let git_dir_str = git_dir.to_str().unwrap_or_default();
+    let previous_head = head_commit(git_dir_str);
     // Check if a branch or a commit is provided
     let is_branch = branch_or_commit.starts_with("refs/heads/");
 
     if is_branch {
         // Check if the specified branch exists
         let branch_name = branch_or_commit.trim_start_matches("refs/heads/");
-        let refs_dir = git_dir.join("refs").join("heads");
-        let branch_ref_file = refs_dir.join(branch_name);
+        let branch_ref_file = branch_ref_path(git_dir, branch_name);
 
         if branch_ref_file.exists() {
             // Update the HEAD file to force the branch change
@@ -390,6 +721,7 @@ pub fn force_checkout(git_dir: &Path, branch_or_commit: &str) -> Result<(), io::
             log_checkout(&current_branch, branch_name, "Force checkout", git_dir)?;
 
             println!("Force switched to branch: {}", branch_name);
+            run_post_checkout_hook(git_dir_str, &previous_head, true);
             Ok(())
         } else {
             eprintln!("Branch '{}' not found in the repository", branch_name);
@@ -397,7 +729,7 @@ pub fn force_checkout(git_dir: &Path, branch_or_commit: &str) -> Result<(), io::
         }
     } else {
         // Check if the specified commit exists
-        let objects_dir = git_dir.join("objects");
+        let objects_dir = PathBuf::from(utils::git_path(&git_dir.to_string_lossy(), "objects"));
         let commit_id = branch_or_commit;
 
         if objects_dir.join(commit_id).exists() {
@@ -413,6 +745,7 @@ pub fn force_checkout(git_dir: &Path, branch_or_commit: &str) -> Result<(), io::
             log_checkout(&current_branch, commit_id, "Force checkout", git_dir)?;
 
             println!("Force switched to commit (detached mode): {}", commit_id);
+            run_post_checkout_hook(git_dir_str, &previous_head, true);
             Ok(())
         } else {
             eprintln!(
@@ -427,6 +760,114 @@ pub fn force_checkout(git_dir: &Path, branch_or_commit: &str) -> Result<(), io::
     }
 }
 
+/// Returns the path to the file of a branch, among the refs shared by every working tree.
+fn branch_ref_path(git_dir: &Path, branch_name: &str) -> PathBuf {
+    let git_dir = git_dir.to_string_lossy();
+    PathBuf::from(utils::git_path(
+        &git_dir,
+        &format!("refs/heads/{}", branch_name),
+    ))
+}
+
+/// Returns the commit HEAD points to, or the null hash if there is none yet.
+fn head_commit(git_dir: &str) -> String {
+    utils::resolve_revision(git_dir, "HEAD").unwrap_or(NULL_HASH.to_string())
+}
+
+/// Runs the `post-checkout` hook after a successful checkout, with the previous HEAD, the new
+/// HEAD and `1` for a checkout of a branch or a commit, or `0` for a checkout of paths.
+///
+/// Like git, the exit status of the hook does not undo the checkout.
+fn run_post_checkout_hook(git_dir: &str, previous_head: &str, branch_checkout: bool) {
+    let flag = if branch_checkout { "1" } else { "0" };
+    let new_head = head_commit(git_dir);
+    let _ = hooks::run_hook(
+        git_dir,
+        "post-checkout",
+        &[previous_head, &new_head, flag],
+        None,
+    );
+}
+
+/// Returns the path of a file in the working directory given its path in the index.
+pub fn working_path(root_dir: &str, path: &str) -> String {
+    if root_dir.is_empty() {
+        path.to_string()
+    } else {
+        format!("{}/{}", root_dir, path)
+    }
+}
+
+/// Loads the index of the repository, or an empty one if there is no index file yet.
+pub fn load_index(git_dir: &str, root_dir: &str) -> io::Result<Index> {
+    let index_path = utils::get_index_file_path(git_dir);
+    let gitignore_path = working_path(root_dir, GIT_IGNORE);
+    match Index::load_from_path_if_exists(&index_path, git_dir, &gitignore_path)? {
+        Some(index) => Ok(index),
+        None => Ok(Index::new(&index_path, git_dir, &gitignore_path)),
+    }
+}
+
+/// Removes the parent directories of a path that were left empty, up to the root of the working directory.
+pub fn remove_empty_parents(root_dir: &str, path: &str) -> io::Result<()> {
+    let mut parent = Path::new(path).parent();
+    while let Some(dir) = parent {
+        if dir.as_os_str().is_empty() {
+            break;
+        }
+        let dir_path = working_path(root_dir, &dir.to_string_lossy());
+        let is_empty = match fs::read_dir(&dir_path) {
+            Ok(mut entries) => entries.next().is_none(),
+            Err(_) => false,
+        };
+        if !is_empty {
+            break;
+        }
+        fs::remove_dir(&dir_path)?;
+        parent = dir.parent();
+    }
+    Ok(())
+}
+
+/// Writes the blob with the given hash into the working directory, or deletes the file if there is no hash.
+pub fn restore_path(
+    root_dir: &str,
+    git_dir: &str,
+    path: &str,
+    hash: Option<&String>,
+) -> io::Result<()> {
+    let file_path = working_path(root_dir, path);
+    match hash {
+        Some(hash) => {
+            if let Some(parent) = Path::new(&file_path).parent() {
+                if !parent.as_os_str().is_empty() {
+                    fs::create_dir_all(parent)?;
+                }
+            }
+            let content = cat_file::cat_file_return_content(hash, git_dir)?;
+            let attributes = Attributes::load(root_dir, path)?;
+            let content = filter::convert_to_working_tree(git_dir, &attributes, content)?;
+            fs::write(&file_path, content)
+        }
+        None => {
+            if Path::new(&file_path).exists() {
+                fs::remove_file(&file_path)?;
+            }
+            remove_empty_parents(root_dir, path)
+        }
+    }
+}
+
+/// Returns the hash of a file in the working directory, or `None` if it does not exist.
+pub fn hash_working_file(root_dir: &str, path: &str) -> io::Result<Option<String>> {
+    let file_path = working_path(root_dir, path);
+    if Path::new(&file_path).is_file() {
+        Ok(Some(hash_object::hash_working_file(root_dir, path)?))
+    } else {
+        Ok(None)
+    }
+}
+
 // Importa las bibliotecas necesarias para los tests
 #[cfg(test)]
 mod tests {
@@ -873,4 +1314,157 @@ mod tests {
 
         fs::remove_dir_all("tests/checkout4").expect("Failed to delete directory");
     }
+
+    fn prepare_two_branches(test_dir: &str) -> String {
+        if Path::new(test_dir).exists() {
+            fs::remove_dir_all(test_dir).expect("Failed to delete directory");
+        }
+        fs::create_dir_all(test_dir).expect("Failed to create test directory");
+        let git_dir = test_dir.to_string() + "/.mgit";
+        prepare_dir(&git_dir);
+        prepare_commit_1(test_dir);
+        commit::new_commit(&git_dir, "Hola", "").unwrap();
+        create_and_checkout_branch(Path::new(&git_dir), "", "new_branch").unwrap();
+        prepare_commit_2(test_dir);
+        commit::new_commit(&git_dir, "Hola", "").unwrap();
+        git_dir
+    }
+
+    #[test]
+    fn test_checkout_refuses_to_overwrite_local_changes() {
+        let git_dir = prepare_two_branches("tests/checkout5");
+        let file1 = Path::new("tests/checkout5/archivo.txt");
+        fs::write(file1, "Cambio local").expect("Failed to write file");
+
+        let result = checkout_branch(Path::new(&git_dir), "", "master");
+
+        let error = result.unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::AlreadyExists);
+        assert!(error
+            .to_string()
+            .contains("\ttests/checkout5/archivo.txt\n"));
+        let head = fs::read_to_string(git_dir.clone() + "/HEAD").unwrap();
+        assert_eq!(head, "ref: refs/heads/new_branch\n");
+        assert_eq!(fs::read_to_string(file1).unwrap(), "Cambio local");
+        assert!(Path::new("tests/checkout5/otro_dir/nuevo_archivo.txt").exists());
+
+        fs::remove_dir_all("tests/checkout5").expect("Failed to delete directory");
+    }
+
+    #[test]
+    fn test_checkout_refuses_to_overwrite_untracked_files() {
+        let git_dir = prepare_two_branches("tests/checkout6");
+        checkout_branch(Path::new(&git_dir), "", "master").unwrap();
+        assert!(!Path::new("tests/checkout6/otro_dir").exists());
+        let untracked = Path::new("tests/checkout6/otro_dir/nuevo_archivo.txt");
+        fs::create_dir_all("tests/checkout6/otro_dir").expect("Failed to create dirs");
+        fs::write(untracked, "Sin trackear").expect("Failed to write file");
+
+        let result = checkout_branch(Path::new(&git_dir), "", "new_branch");
+
+        let error = result.unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("untracked working tree files would be overwritten by checkout"));
+        let head = fs::read_to_string(git_dir.clone() + "/HEAD").unwrap();
+        assert_eq!(head, "ref: refs/heads/master\n");
+        assert_eq!(fs::read_to_string(untracked).unwrap(), "Sin trackear");
+
+        fs::remove_dir_all("tests/checkout6").expect("Failed to delete directory");
+    }
+
+    #[test]
+    fn test_checkout_keeps_local_changes_to_unaffected_files() {
+        let git_dir = prepare_two_branches("tests/checkout7");
+        let file2 = Path::new("tests/checkout7/otro_archivo.txt");
+        fs::write(file2, "Cambio local").expect("Failed to write file");
+
+        checkout_branch(Path::new(&git_dir), "", "master").unwrap();
+
+        assert_eq!(fs::read_to_string(file2).unwrap(), "Cambio local");
+        let file1 = Path::new("tests/checkout7/archivo.txt");
+        assert_eq!(fs::read_to_string(file1).unwrap(), "Hola");
+        assert!(!Path::new("tests/checkout7/otro_dir").exists());
+
+        fs::remove_dir_all("tests/checkout7").expect("Failed to delete directory");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_checkout_runs_the_post_checkout_hook() {
+        use std::os::unix::fs::PermissionsExt;
+        let git_dir = prepare_two_branches("tests/checkout10");
+        let hook_path = format!("{}/hooks/post-checkout", git_dir);
+        fs::create_dir_all(format!("{}/hooks", git_dir)).unwrap();
+        fs::write(
+            &hook_path,
+            "#!/bin/sh\necho \"$1 $2 $3\" >> post-checkout.txt\n",
+        )
+        .unwrap();
+        fs::set_permissions(&hook_path, fs::Permissions::from_mode(0o755)).unwrap();
+        let new_branch_commit = utils::resolve_revision(&git_dir, "HEAD").unwrap();
+
+        checkout_branch(Path::new(&git_dir), "", "master").unwrap();
+        let master_commit = utils::resolve_revision(&git_dir, "HEAD").unwrap();
+        checkout_commit_detached(Path::new(&git_dir), "", &new_branch_commit).unwrap();
+
+        assert_eq!(
+            fs::read_to_string("tests/checkout10/post-checkout.txt").unwrap(),
+            format!("{0} {1} 1\n{1} {0} 1\n", new_branch_commit, master_commit)
+        );
+
+        fs::remove_dir_all("tests/checkout10").expect("Failed to delete directory");
+    }
+
+    #[test]
+    fn test_checkout_keeps_deleted_files_deleted() {
+        let git_dir = prepare_two_branches("tests/checkout9");
+        let file2 = Path::new("tests/checkout9/otro_archivo.txt");
+        fs::remove_file(file2).expect("Failed to delete file");
+
+        checkout_branch(Path::new(&git_dir), "", "master").unwrap();
+
+        assert!(!file2.exists());
+        let head = fs::read_to_string(git_dir.clone() + "/HEAD").unwrap();
+        assert_eq!(head, "ref: refs/heads/master\n");
+
+        fs::remove_dir_all("tests/checkout9").expect("Failed to delete directory");
+    }
+
+    #[test]
+    fn test_checkout_paths_from_index_and_revision() {
+        let git_dir = prepare_two_branches("tests/checkout8");
+        let file1 = Path::new("tests/checkout8/archivo.txt");
+        fs::write(file1, "Cambio local").expect("Failed to write file");
+        let pathspecs = vec!["tests/checkout8/archivo.txt".to_string()];
+
+        let mut output = Vec::new();
+        checkout_paths(Path::new(&git_dir), "", None, &pathspecs, &mut output).unwrap();
+        assert_eq!(fs::read_to_string(file1).unwrap(), "Este es otro hola");
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "Updated 1 path from the index\n"
+        );
+
+        checkout_paths(
+            Path::new(&git_dir),
+            "",
+            Some("master"),
+            &pathspecs,
+            &mut Vec::new(),
+        )
+        .unwrap();
+        assert_eq!(fs::read_to_string(file1).unwrap(), "Hola");
+        let head = fs::read_to_string(git_dir.clone() + "/HEAD").unwrap();
+        assert_eq!(head, "ref: refs/heads/new_branch\n");
+        let index = load_index(&git_dir, "").unwrap();
+        let master_files = restore::get_revision_files(&git_dir, "master").unwrap();
+        assert_eq!(
+            index.get_hash("tests/checkout8/archivo.txt"),
+            master_files.get("tests/checkout8/archivo.txt")
+        );
+        assert!(Path::new("tests/checkout8/otro_dir/nuevo_archivo.txt").exists());
+
+        fs::remove_dir_all("tests/checkout8").expect("Failed to delete directory");
+    }
 }
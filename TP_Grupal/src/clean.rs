@@ -0,0 +1,358 @@
+use crate::checkout;
+use crate::configuration::{GIT_DIR, LOGGER_COMMANDS_FILE};
+use crate::ignorer::is_subpath;
+use crate::index::Index;
+use crate::logger::Logger;
+use crate::utils::get_current_time;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+const CLEAN_USAGE: &str = "usage: git clean [-n] [-f] [-d] [-x | -X] [--] [<pathspec>...]";
+
+/// Logs the 'git clean' command.
+///
+/// This function logs the 'git clean' command with the paths removed to a file named
+/// 'logger_commands.txt'.
+///
+/// # Arguments
+///
+/// * `removed` - The paths removed, or that would be removed on a dry run.
+/// * `dry_run` - Whether the paths were only listed.
+///
+/// # Errors
+///
+/// Returns an `io::Result` indicating whether the operation was successful.
+///
+fn log_clean(removed: &[String], dry_run: bool) -> io::Result<()> {
+    let log_file_path = LOGGER_COMMANDS_FILE;
+    let mut logger = Logger::new(log_file_path)?;
+
+    let full_message = format!(
+        "Command 'git clean': Dry run '{}', Paths '{}', {}",
+        dry_run,
+        removed.join(" "),
+        get_current_time()
+    );
+    logger.write_all(full_message.as_bytes())?;
+    logger.flush()?;
+    Ok(())
+}
+
+/// Which untracked files `git clean` removes, according to the ignore rules.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IgnoredMode {
+    /// Only the files that are not ignored. The default.
+    #[default]
+    Exclude,
+    /// `-x`: ignored files too.
+    Include,
+    /// `-X`: only ignored files.
+    Only,
+}
+
+/// The options of `git clean`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CleanOptions {
+    /// `-n`: only list what would be removed.
+    pub dry_run: bool,
+    /// `-d`: remove untracked directories too.
+    pub directories: bool,
+    /// `-x` and `-X`: how ignored files are treated.
+    pub ignored: IgnoredMode,
+}
+
+impl CleanOptions {
+    /// Tells whether an untracked path is removed, given whether it is ignored.
+    fn removes(&self, ignored: bool) -> bool {
+        match self.ignored {
+            IgnoredMode::Exclude => !ignored,
+            IgnoredMode::Include => true,
+            IgnoredMode::Only => ignored,
+        }
+    }
+}
+
+/// Tells whether the index tracks a file inside a directory.
+fn has_tracked_files(index: &Index, dir: &str) -> bool {
+    index.iter().any(|(path, _)| is_subpath(path, dir))
+}
+
+/// Walks a directory of the working tree, collecting the untracked paths to remove. Directories
+/// are collected with a trailing `/`.
+fn collect_paths(
+    root_dir: &str,
+    current_dir: &Path,
+    index: &Index,
+    pathspecs: &[String],
+    options: CleanOptions,
+    paths: &mut Vec<String>,
+) -> io::Result<()> {
+    let mut entries: Vec<_> = fs::read_dir(current_dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .collect();
+    entries.sort();
+    for entry_path in entries {
+        if entry_path.file_name().and_then(|name| name.to_str()) == Some(GIT_DIR) {
+            continue;
+        }
+        let relative = match entry_path.strip_prefix(root_dir) {
+            Ok(relative) => relative.to_string_lossy().to_string(),
+            Err(_) => return Err(io::Error::new(io::ErrorKind::Interrupted, "Fatal error.\n")),
+        };
+        let in_pathspec = pathspecs.is_empty()
+            || pathspecs
+                .iter()
+                .any(|pathspec| is_subpath(&relative, pathspec));
+        let ignored = index.path_should_be_ignored(&relative);
+        if entry_path.is_dir() && !entry_path.is_symlink() {
+            let leads_to_pathspec = pathspecs
+                .iter()
+                .any(|pathspec| is_subpath(pathspec, &relative) && pathspec != &relative);
+            if has_tracked_files(index, &relative) || leads_to_pathspec {
+                collect_paths(root_dir, &entry_path, index, pathspecs, options, paths)?;
+            } else if !in_pathspec || !options.directories || entry_path.join(GIT_DIR).exists() {
+                // Untracked directories are only touched with -d, and other repositories never.
+            } else if options.removes(ignored) {
+                paths.push(format!("{}/", relative));
+            } else if options.ignored == IgnoredMode::Only {
+                collect_paths(root_dir, &entry_path, index, pathspecs, options, paths)?;
+            }
+        } else if in_pathspec && !index.contains(&relative) && options.removes(ignored) {
+            paths.push(relative);
+        }
+    }
+    Ok(())
+}
+
+/// Removes the untracked files of the working tree.
+///
+/// Files tracked by the index are never touched. Untracked directories are only removed when
+/// `options.directories` is set, and directories holding another repository are always kept.
+/// The ignore rules decide which untracked files are removed, see [`IgnoredMode`].
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the Git directory.
+/// * `root_dir` - The root of the working directory.
+/// * `pathspecs` - Limits the removal to these paths, relative to the root. Empty for all.
+/// * `options` - The options of the command.
+/// * `output` - Where `Removing <path>` or, on a dry run, `Would remove <path>` is written for
+///   every path.
+///
+/// # Returns
+///
+/// Returns the paths removed, or that would be removed on a dry run.
+///
+/// # Errors
+///
+/// Returns an error if the index cannot be loaded or the working tree cannot be read or written.
+///
+pub fn clean(
+    git_dir: &str,
+    root_dir: &str,
+    pathspecs: &[String],
+    options: CleanOptions,
+    output: &mut impl Write,
+) -> io::Result<Vec<String>> {
+    let index = checkout::load_index(git_dir, root_dir)?;
+    let pathspecs: Vec<String> = pathspecs
+        .iter()
+        .map(|pathspec| pathspec.trim_end_matches('/').to_string())
+        .collect();
+    let mut paths = Vec::new();
+    collect_paths(
+        root_dir,
+        Path::new(root_dir),
+        &index,
+        &pathspecs,
+        options,
+        &mut paths,
+    )?;
+    for path in &paths {
+        if options.dry_run {
+            writeln!(output, "Would remove {}", path)?;
+            continue;
+        }
+        let working_path = checkout::working_path(root_dir, path.trim_end_matches('/'));
+        if path.ends_with('/') {
+            fs::remove_dir_all(&working_path)?;
+        } else {
+            fs::remove_file(&working_path)?;
+        }
+        writeln!(output, "Removing {}", path)?;
+    }
+    Ok(paths)
+}
+
+/// Removes untracked files from the working tree, like `git clean`.
+///
+/// The command line is `git clean [-n] [-f] [-d] [-x | -X] [--] [<pathspec>...]`:
+///
+/// - `-n` or `--dry-run` only lists what would be removed.
+/// - `-f` or `--force` is required to remove anything, unless `-n` is given.
+/// - `-d` removes untracked directories too.
+/// - `-x` removes ignored files too, and `-X` removes only ignored files.
+///
+/// Short options can be combined, as in `-fdx`. See [`clean`] for which paths are removed.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the Git directory.
+/// * `root_dir` - The root of the working directory.
+/// * `line` - The command line, starting with `git clean`.
+/// * `output` - Where the paths removed are written.
+///
+/// # Errors
+///
+/// Returns an error if the arguments are invalid, neither `-f` nor `-n` is given, or the
+/// removal fails.
+///
+pub fn git_clean(
+    git_dir: &str,
+    root_dir: &str,
+    line: Vec<String>,
+    output: &mut impl Write,
+) -> io::Result<()> {
+    let mut options = CleanOptions::default();
+    let mut force = false;
+    let mut pathspecs: Vec<String> = Vec::new();
+    let invalid = |arg: &str| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("error: unknown option '{}'\n{}", arg, CLEAN_USAGE),
+        )
+    };
+    let conflicting = || {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "fatal: options '-x' and '-X' cannot be used together",
+        )
+    };
+    let mut args = line.iter().skip(2);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--" => pathspecs.extend(args.by_ref().cloned()),
+            "--dry-run" => options.dry_run = true,
+            "--force" => force = true,
+            _ if arg.starts_with("--") => return Err(invalid(arg)),
+            _ if arg.starts_with('-') && arg.len() > 1 => {
+                for flag in arg.chars().skip(1) {
+                    match flag {
+                        'n' => options.dry_run = true,
+                        'f' => force = true,
+                        'd' => options.directories = true,
+                        'x' if options.ignored == IgnoredMode::Only => return Err(conflicting()),
+                        'X' if options.ignored == IgnoredMode::Include => return Err(conflicting()),
+                        'x' => options.ignored = IgnoredMode::Include,
+                        'X' => options.ignored = IgnoredMode::Only,
+                        _ => return Err(invalid(arg)),
+                    }
+                }
+            }
+            _ => pathspecs.push(arg.to_string()),
+        }
+    }
+    if !force && !options.dry_run {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "fatal: clean.requireForce defaults to true and neither -n nor -f given; refusing to clean",
+        ));
+    }
+    let removed = clean(git_dir, root_dir, &pathspecs, options, output)?;
+    log_clean(&removed, options.dry_run)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::configuration::GIT_IGNORE;
+    use crate::test_utils;
+
+    /// Creates a repository with a tracked file, an untracked file, an untracked directory, an
+    /// ignored file and an ignored directory.
+    fn create_repo(root_dir: &str) -> String {
+        let git_dir = test_utils::create_repo_with_git_dir(root_dir, GIT_DIR);
+        test_utils::stage_files(
+            &git_dir,
+            root_dir,
+            &[
+                (GIT_IGNORE, "build\nsrc/debug.log\n"),
+                ("src/main.rs", "fn main() {}\n"),
+            ],
+        );
+        fs::create_dir_all(format!("{}/build", root_dir)).unwrap();
+        fs::create_dir_all(format!("{}/scratch", root_dir)).unwrap();
+        fs::write(format!("{}/src/notes.txt", root_dir), "notes\n").unwrap();
+        fs::write(format!("{}/src/debug.log", root_dir), "log\n").unwrap();
+        fs::write(format!("{}/build/out", root_dir), "out\n").unwrap();
+        fs::write(format!("{}/scratch/draft.txt", root_dir), "draft\n").unwrap();
+        git_dir
+    }
+
+    fn run_clean(git_dir: &str, root_dir: &str, args: &[&str]) -> io::Result<String> {
+        let mut line = vec!["git".to_string(), "clean".to_string()];
+        line.extend(args.iter().map(|arg| arg.to_string()));
+        let mut output = Vec::new();
+        git_clean(git_dir, root_dir, line, &mut output)?;
+        Ok(String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn test_clean_requires_force_and_lists_on_dry_run() {
+        let root_dir = "tests/clean/dry_run";
+        let git_dir = create_repo(root_dir);
+
+        assert!(run_clean(&git_dir, root_dir, &[]).is_err());
+        assert!(run_clean(&git_dir, root_dir, &["-f", "-x", "-X"]).is_err());
+        assert_eq!(
+            run_clean(&git_dir, root_dir, &["-n"]).unwrap(),
+            "Would remove src/notes.txt\n"
+        );
+        assert_eq!(
+            run_clean(&git_dir, root_dir, &["-nd"]).unwrap(),
+            "Would remove scratch/\nWould remove src/notes.txt\n"
+        );
+        assert_eq!(
+            run_clean(&git_dir, root_dir, &["-ndx"]).unwrap(),
+            "Would remove build/\nWould remove scratch/\nWould remove src/debug.log\nWould remove src/notes.txt\n"
+        );
+        assert_eq!(
+            run_clean(&git_dir, root_dir, &["-n", "-d", "-X"]).unwrap(),
+            "Would remove build/\nWould remove src/debug.log\n"
+        );
+        assert!(Path::new(&format!("{}/src/notes.txt", root_dir)).exists());
+
+        fs::remove_dir_all(root_dir).unwrap();
+    }
+
+    #[test]
+    fn test_clean_removes_untracked_paths() {
+        let root_dir = "tests/clean/force";
+        let git_dir = create_repo(root_dir);
+
+        assert_eq!(
+            run_clean(&git_dir, root_dir, &["-f", "--", "scratch"]).unwrap(),
+            ""
+        );
+        assert_eq!(
+            run_clean(&git_dir, root_dir, &["-fd", "--", "scratch"]).unwrap(),
+            "Removing scratch/\n"
+        );
+        assert!(!Path::new(&format!("{}/scratch", root_dir)).exists());
+
+        assert_eq!(
+            run_clean(&git_dir, root_dir, &["-fX"]).unwrap(),
+            "Removing src/debug.log\n"
+        );
+        assert_eq!(
+            run_clean(&git_dir, root_dir, &["-f"]).unwrap(),
+            "Removing src/notes.txt\n"
+        );
+        assert!(Path::new(&format!("{}/src/main.rs", root_dir)).exists());
+        assert!(Path::new(&format!("{}/build/out", root_dir)).exists());
+
+        fs::remove_dir_all(root_dir).unwrap();
+    }
+}
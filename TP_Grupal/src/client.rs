@@ -6,7 +6,7 @@ use std::{
     path::PathBuf,
 };
 
-use crate::{packfile, server_utils::*};
+use crate::{hooks, log::get_ancestors, packfile, server_utils::*, utils};
 
 const VERSION: &str = "1";
 const GIT_UPLOAD_PACK: &str = "git-upload-pack";
@@ -24,6 +24,8 @@ pub struct Client {
     git_dir: String,
     remote: String,
     server_refs: HashMap<String, String>,
+    pre_push: Option<(String, String)>,
+    remote_messages: Vec<String>,
 }
 
 /// This is a git client that is able to connect to a git server
@@ -49,6 +51,23 @@ impl Client {
         }
     }
 
+    /// Runs the `pre-push` hook before every push of this client, with the name and the URL of
+    /// the remote as its arguments. If the hook fails, nothing is pushed.
+    ///
+    /// # Arguments
+    ///
+    /// * `remote_name` - The name of the remote, such as `origin`.
+    /// * `url` - The URL of the remote.
+    pub fn set_pre_push_hook(&mut self, remote_name: &str, url: &str) {
+        self.pre_push = Some((remote_name.to_string(), url.to_string()));
+    }
+
+    /// Returns the messages the server sent during the last push, such as the output of its
+    /// hooks, as they were shown to the user: `remote: <message>`.
+    pub fn remote_messages(&self) -> &[String] {
+        &self.remote_messages
+    }
+
     // Establish a connection with the server and asks for the refs in the remote.
     // A hashmap with the path of the refs as keys and the last commit hash as values is returned.
     //
@@ -69,6 +88,9 @@ impl Client {
     /// Else, the server is asked for the missing objects and a packfile is unpacked.
     /// Then the remote refs are updated.
     ///
+    /// The notes in refs/notes are fetched too, and stored in the local refs/notes
+    /// as long as they are a fast-forward of the local notes.
+    ///
     /// # Arguments
     ///
     /// * `wanted_branchs` - A vector with the names of the branchs to fetch.
@@ -110,21 +132,37 @@ impl Client {
         self.wait_server_refs()?;
 
         let fetched_remotes_refs = self.want_branchs(wanted_branchs)?;
-        if fetched_remotes_refs.is_empty() {
+        let fetched_notes_refs = self.want_notes()?;
+        if fetched_remotes_refs.is_empty() && fetched_notes_refs.is_empty() {
             log("Already up to date.")?;
             self.end_connection()?;
             return Ok(());
         }
+        let wants = fetched_remotes_refs
+            .values()
+            .chain(fetched_notes_refs.values())
+            .collect();
+        self.send_wants(wants)?;
+        let mut client_refs = get_client_refs(&self.git_dir, &self.remote)?;
+        client_refs.extend(get_notes_refs(&self.git_dir)?);
+        self.send_haves(client_refs)?;
+        self.done()?;
         self.wait_and_unpack_packfile()?;
         for (branch, hash) in fetched_remotes_refs {
             self.update_remote(&branch, &hash)?;
         }
+        for (notes_ref, hash) in fetched_notes_refs {
+            self.update_notes(&notes_ref, &hash)?;
+        }
         Ok(())
     }
 
     /// Establish a connection with the server and sends the local refs to the server.
     /// If the remote refs are up to date, then nothing is done.
     ///
+    /// Besides the branch, the notes in refs/notes are sent whenever they are a
+    /// fast-forward of the notes in the server.
+    ///
     /// Refs can be updated, created or deleted. However, deletion is not implemented yet.
     ///
     /// # Arguments
@@ -160,6 +198,8 @@ impl Client {
     ///
     /// This method returns an `io::Result<()>`. The `Err` variant is used to represent various
     /// error conditions, including communication failures or the absence of expected references.
+    /// It is also returned when the server declines some of the refs, even if it accepted the
+    /// others.
     ///
     fn receive_pack_do(&mut self, branch: &str, git_dir: &str) -> io::Result<()> {
         self.clear();
@@ -179,22 +219,56 @@ impl Client {
         };
         let prev_hash = match self.server_refs.get(&pushing_ref) {
             Some(hash) => hash.clone(),
-            None => String::new(),
+            None => ZERO_HASH.to_string(),
         };
-        if &prev_hash == new_hash {
+        let mut updates = vec![];
+        if &prev_hash != new_hash {
+            updates.push((pushing_ref, prev_hash, new_hash.clone()));
+        }
+        updates.extend(self.pushed_notes()?);
+        if updates.is_empty() {
             log("Already up to date.")?;
             return Ok(());
         }
-        if prev_hash.is_empty() {
-            self.receive_pack_create(&pushing_ref, new_hash)?;
-        } else {
-            self.receive_pack_update(&pushing_ref, &prev_hash, new_hash)?;
+        if let Some((remote_name, url)) = &self.pre_push {
+            let input: String = updates
+                .iter()
+                .map(|(reference, old, new)| {
+                    format!("{} {} {} {}\n", reference, new, reference, old)
+                })
+                .collect();
+            hooks::run_hook(git_dir, "pre-push", &[remote_name, url], Some(&input))?;
         }
-        while let Ok((size, line)) = read_pkt_line(self.socket()?) {
+        self.receive_pack_update(&updates)?;
+        let mut declined = vec![];
+        loop {
+            let (size, line) = match read_pkt_line(self.socket()?) {
+                Ok(response) => response,
+                // The server rejected the push
+                Err(e) if e.kind() == io::ErrorKind::Other => return Err(e),
+                Err(_) => break,
+            };
             if size < 4 {
                 break;
             }
             log(&format!("Server response after packfile: {}", line))?;
+            // Messages sent by the server hooks
+            if let Some(message) = line.strip_prefix('\u{2}') {
+                let message = format!("remote: {}", message.trim_end());
+                eprintln!("{}", message);
+                self.remote_messages.push(message);
+            } else if let Some(status) = line.strip_prefix("ng ") {
+                match status.trim_end().split_once(' ') {
+                    Some((ref_name, reason)) => declined.push(format!("{} ({})", ref_name, reason)),
+                    None => declined.push(status.trim_end().to_string()),
+                }
+            }
+        }
+        if !declined.is_empty() {
+            return Err(Error::other(format!(
+                "failed to push some refs: {}",
+                declined.join(", ")
+            )));
         }
         Ok(())
     }
@@ -204,6 +278,7 @@ impl Client {
         self.git_dir = String::new();
         self.remote = String::new();
         self.server_refs.clear();
+        self.remote_messages.clear();
     }
 
     // Connects to the server and returns a Tcp socket
@@ -250,11 +325,10 @@ impl Client {
         Ok(())
     }
 
-    // Auxiliar function. Given a vector of branchs, will ask the server for the missing objects
+    // Auxiliar function. Given a vector of branchs, returns the ones that are not up to date
+    // with the hash the server has for them
     //
     // Will fail if the server does not have the wanted branchs
-    //
-    // If the server has the wanted branchs, then it will send the 'want' and 'have' messages
     fn want_branchs(&mut self, branchs: Vec<String>) -> io::Result<HashMap<String, String>> {
         let mut fetched_remotes_refs = HashMap::new();
 
@@ -277,14 +351,48 @@ impl Client {
             }
             fetched_remotes_refs.insert(branch.to_string(), hash);
         }
-        if !fetched_remotes_refs.is_empty() {
-            self.send_wants(fetched_remotes_refs.values().collect())?;
-            self.send_haves(client_refs)?;
-            self.done()?;
-        }
         Ok(fetched_remotes_refs)
     }
 
+    // Auxiliar function. Returns the notes refs of the server that differ from the local ones
+    fn want_notes(&self) -> io::Result<HashMap<String, String>> {
+        let client_notes = get_notes_refs(&self.git_dir)?;
+        log(&format!("Client notes: {:?}", client_notes))?;
+        Ok(self
+            .server_refs
+            .iter()
+            .filter(|(ref_name, hash)| {
+                ref_name.starts_with(NOTES_REFS_PREFIX) && client_notes.get(*ref_name) != Some(hash)
+            })
+            .map(|(ref_name, hash)| (ref_name.clone(), hash.clone()))
+            .collect())
+    }
+
+    // Auxiliar function. Returns the updates of the notes refs to send to the server,
+    // as (ref, old hash, new hash)
+    //
+    // Notes that would drop notes only present in the server are not sent
+    fn pushed_notes(&self) -> io::Result<Vec<(String, String, String)>> {
+        let mut updates = vec![];
+        for (notes_ref, hash) in get_notes_refs(&self.git_dir)? {
+            let prev_hash = match self.server_refs.get(&notes_ref) {
+                Some(prev_hash) if prev_hash == &hash => continue,
+                Some(prev_hash) => prev_hash.clone(),
+                None => ZERO_HASH.to_string(),
+            };
+            if prev_hash != ZERO_HASH && !get_ancestors(&self.git_dir, &hash)?.contains(&prev_hash)
+            {
+                log(&format!(
+                    "Not pushing {}: the server has notes that are not in local",
+                    notes_ref
+                ))?;
+                continue;
+            }
+            updates.push((notes_ref, prev_hash, hash));
+        }
+        Ok(updates)
+    }
+
     // Sends a 'want' message to the server for each hash in the vector
     fn send_wants(&mut self, hashes: Vec<&String>) -> io::Result<()> {
         let first_want = format!("want {} {}\n", hashes[0], CAPABILITIES_UPLOAD);
@@ -337,38 +445,70 @@ impl Client {
             "Updating remote ref: {} with hash: {}",
             remote_ref, hash
         ))?;
-        let pathbuf = PathBuf::from(&self.git_dir);
-        let remote = pathbuf.join("refs").join("remotes").join(&self.remote);
+        let remote = PathBuf::from(utils::git_path(
+            &self.git_dir,
+            &format!("refs/remotes/{}", self.remote),
+        ));
         fs::create_dir_all(&remote)?;
         let remote = remote.join(remote_ref);
         let mut file = fs::File::create(remote)?;
         writeln!(file, "{}", hash)
     }
 
-    // Sends a 'create' message to the server using 'update' message
-    fn receive_pack_create(&mut self, pushing_ref: &str, hash: &str) -> io::Result<()> {
-        self.receive_pack_update(pushing_ref, ZERO_HASH, hash)
+    // Updates local notes ref with the fetched hash
+    // The ref is only moved forward: if the local notes are not an ancestor of the
+    // fetched ones, they are kept
+    fn update_notes(&self, notes_ref: &str, hash: &str) -> io::Result<()> {
+        let path = PathBuf::from(utils::git_path(&self.git_dir, notes_ref));
+        if path.exists() {
+            let local_hash = fs::read_to_string(&path)?.trim().to_string();
+            if !get_ancestors(&self.git_dir, hash)?.contains(&local_hash) {
+                log(&format!(
+                    "Not updating {}: the local notes are not in the remote",
+                    notes_ref
+                ))?;
+                return Ok(());
+            }
+        }
+        log(&format!(
+            "Updating notes ref: {} with hash: {}",
+            notes_ref, hash
+        ))?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = fs::File::create(path)?;
+        writeln!(file, "{}", hash)
     }
 
-    // Sends a 'update' message to the server
+    // Sends an 'update' message to the server for each (ref, old hash, new hash)
+    // A ref is created when the old hash is the zero hash
     // Then, it sends the missing objects to the server in a packfile
-    fn receive_pack_update(
-        &mut self,
-        pushing_ref: &str,
-        prev_hash: &str,
-        new_hash: &str,
-    ) -> io::Result<()> {
-        let update = format!(
-            "{} {} {}\0{}\n",
-            prev_hash, new_hash, pushing_ref, CAPABILITIES_UPLOAD
-        );
-        self.send(&pkt_line(&update))?;
+    fn receive_pack_update(&mut self, updates: &[(String, String, String)]) -> io::Result<()> {
+        for (i, (pushing_ref, prev_hash, new_hash)) in updates.iter().enumerate() {
+            let update = if i == 0 {
+                format!(
+                    "{} {} {}\0{}\n",
+                    prev_hash, new_hash, pushing_ref, CAPABILITIES_UPLOAD
+                )
+            } else {
+                format!("{} {} {}\n", prev_hash, new_hash, pushing_ref)
+            };
+            self.send(&pkt_line(&update))?;
+        }
         self.flush()?;
 
-        let mut haves = HashSet::new();
-        haves.insert(prev_hash.to_string());
+        let haves: HashSet<String> = updates
+            .iter()
+            .map(|(_, prev_hash, _)| prev_hash.clone())
+            .collect();
 
-        let missing_objects = get_missing_objects_from(new_hash, &haves, &self.git_dir)?;
+        let mut missing_objects = HashSet::new();
+        for (_, _, new_hash) in updates {
+            missing_objects.extend(get_missing_objects_from(new_hash, &haves, &self.git_dir)?);
+        }
+        let mut missing_objects: Vec<String> = missing_objects.into_iter().collect();
+        missing_objects.sort();
         log(&format!("Missing objects: {:?}", missing_objects))?;
         let packfile = packfile::handler::create_packfile(&missing_objects, &self.git_dir)?;
         let packfile: Vec<u8> = [vec![1], packfile].concat();
@@ -414,3 +554,99 @@ impl Client {
         self.send("0009done\n")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{notes, test_utils};
+
+    const NOTES_REF: &str = "refs/notes/commits";
+
+    /// Creates a repository in `root_dir` whose notes were changed twice, returning its git
+    /// directory and both notes commits.
+    fn create_repo_with_notes(root_dir: &str) -> (String, String, String) {
+        let git_dir = test_utils::create_repo(root_dir);
+        test_utils::commit_files(&git_dir, root_dir, &[("file.txt", "one\n")], "one");
+        let add_note = |message: &str| {
+            let line = ["git", "notes", "add", "-f", "-m", message]
+                .iter()
+                .map(|arg| arg.to_string())
+                .collect();
+            notes::git_notes(&git_dir, line, &mut Vec::new()).unwrap();
+            read_notes_ref(&git_dir)
+        };
+        let first = add_note("first");
+        let second = add_note("second");
+        (git_dir, first, second)
+    }
+
+    fn read_notes_ref(git_dir: &str) -> String {
+        fs::read_to_string(format!("{}/{}", git_dir, NOTES_REF))
+            .unwrap()
+            .trim()
+            .to_string()
+    }
+
+    fn write_notes_ref(git_dir: &str, hash: &str) {
+        fs::write(format!("{}/{}", git_dir, NOTES_REF), format!("{}\n", hash)).unwrap();
+    }
+
+    fn client_with_server_notes(git_dir: &str, server_hash: Option<&str>) -> Client {
+        let mut client = Client::new("localhost:9418", "repo", "localhost");
+        client.git_dir = git_dir.to_string();
+        if let Some(hash) = server_hash {
+            client
+                .server_refs
+                .insert(NOTES_REF.to_string(), hash.to_string());
+        }
+        client
+    }
+
+    #[test]
+    fn test_pushed_notes_only_fast_forward_the_server() {
+        let root_dir = "tests/client/pushed_notes";
+        let (git_dir, first, second) = create_repo_with_notes(root_dir);
+
+        let client = client_with_server_notes(&git_dir, None);
+        assert_eq!(
+            client.pushed_notes().unwrap(),
+            vec![(NOTES_REF.to_string(), ZERO_HASH.to_string(), second.clone())]
+        );
+
+        let client = client_with_server_notes(&git_dir, Some(&first));
+        assert_eq!(
+            client.pushed_notes().unwrap(),
+            vec![(NOTES_REF.to_string(), first.clone(), second.clone())]
+        );
+
+        let client = client_with_server_notes(&git_dir, Some(&second));
+        assert!(client.pushed_notes().unwrap().is_empty());
+
+        // The server has notes the client lacks
+        write_notes_ref(&git_dir, &first);
+        let client = client_with_server_notes(&git_dir, Some(&second));
+        assert!(client.pushed_notes().unwrap().is_empty());
+
+        fs::remove_dir_all(root_dir).unwrap();
+    }
+
+    #[test]
+    fn test_update_notes_keeps_local_notes_that_are_not_ancestors() {
+        let root_dir = "tests/client/update_notes";
+        let (git_dir, first, second) = create_repo_with_notes(root_dir);
+        let client = client_with_server_notes(&git_dir, None);
+
+        client.update_notes(NOTES_REF, &first).unwrap();
+        assert_eq!(read_notes_ref(&git_dir), second);
+
+        write_notes_ref(&git_dir, &first);
+        client.update_notes(NOTES_REF, &second).unwrap();
+        assert_eq!(read_notes_ref(&git_dir), second);
+
+        fs::remove_file(format!("{}/{}", git_dir, NOTES_REF)).unwrap();
+        client.update_notes(NOTES_REF, &first).unwrap();
+        assert_eq!(read_notes_ref(&git_dir), first);
+
+        fs::remove_dir_all(root_dir).unwrap();
+    }
+}
@@ -2,7 +2,8 @@ use crate::configuration::{
     GIT_DIR, INITIAL_BRANCH, INITIAL_BRANCH_REF, LOGGER_COMMANDS_FILE, REMOTE,
 };
 use crate::logger::Logger;
-use crate::utils::get_current_time;
+use crate::server_utils::NOTES_REFS_PREFIX;
+use crate::utils::{self, get_current_time};
 use crate::{client::Client, config, init, tree_handler};
 use std::{
     collections::HashMap,
@@ -56,7 +57,7 @@ pub fn log_clone(repo_url: &str, destination: &str) -> io::Result<()> {
 
 //Ver de dónde sale la default branch
 fn get_default_branch_commit(local_git_dir: &str) -> io::Result<String> {
-    let path_to_file = local_git_dir.to_string() + "/refs/remotes/origin/master";
+    let path_to_file = utils::git_path(local_git_dir, "refs/remotes/origin/master");
     println!("{}", path_to_file);
     let mut branch_file = std::fs::File::open(path_to_file)?;
     let mut branch_content = String::new();
@@ -72,6 +73,8 @@ fn get_default_branch_commit(local_git_dir: &str) -> io::Result<String> {
 /// by splitting the string at '/' and taking the last part. It returns a new vector containing only
 /// the last components of the references.
 ///
+/// The notes refs are left out, since the client fetches them on its own.
+///
 /// # Arguments
 ///
 /// * `refs`: A vector of reference strings to process.
@@ -83,6 +86,7 @@ fn get_default_branch_commit(local_git_dir: &str) -> io::Result<String> {
 fn get_clean_refs(refs: HashMap<String, String>) -> Vec<String> {
     let clean_refs = refs
         .iter()
+        .filter(|x| !x.0.starts_with(NOTES_REFS_PREFIX))
         .map(|x| match x.0.split('/').last() {
             Some(string) => string.to_string(),
             None => "".to_string(),
@@ -108,7 +112,7 @@ fn get_clean_refs(refs: HashMap<String, String>) -> Vec<String> {
 fn create_working_dir(local_git_dir: &str, working_dir: &str) -> io::Result<()> {
     let default_branch_commit = get_default_branch_commit(local_git_dir)?;
     let commit_tree = tree_handler::load_tree_from_commit(&default_branch_commit, local_git_dir)?;
-    let branch_file_path = local_git_dir.to_string() + "/refs/heads/master";
+    let branch_file_path = utils::git_path(local_git_dir, "refs/heads/master");
     let mut branch_file = std::fs::File::create(branch_file_path)?;
     branch_file.write_all(default_branch_commit.as_bytes())?;
     commit_tree.create_directories(working_dir, local_git_dir)?;
@@ -122,7 +126,7 @@ fn create_working_dir(local_git_dir: &str, working_dir: &str) -> io::Result<()>
 
 /// Create the refs/remotes/origin/dir
 fn create_remote_dir(local_git_dir: &str) -> io::Result<()> {
-    let path_to_file = local_git_dir.to_string() + "/refs/remotes/origin/";
+    let path_to_file = utils::git_path(local_git_dir, "refs/remotes/origin/");
     fs::create_dir_all(path_to_file)?;
     Ok(())
 }
@@ -1,9 +1,13 @@
 use crate::cat_file;
+use crate::checkout;
 use crate::config::Config;
 use crate::configuration::EMAIL;
+use crate::configuration::GIT_IGNORE;
 use crate::configuration::LOGGER_COMMANDS_FILE;
 use crate::configuration::USER;
 use crate::hash_object;
+use crate::hooks;
+use crate::log::Log;
 use crate::logger::Logger;
 use crate::tree_handler;
 use crate::tree_handler::has_tree_changed_since_last_commit;
@@ -15,9 +19,12 @@ use std::io;
 use std::io::Read;
 use std::io::Write;
 use std::path::Path;
+use std::process::Command;
 
 const NO_PARENT: &str = "0000000000000000000000000000000000000000";
 const INDEX_FILE_NAME: &str = "index";
+const COMMIT_USAGE: &str =
+    "usage: git commit [-a] [-n] [--amend] [--no-edit] [-m <message>]... [-F <file>]";
 
 /// Logs the 'git commit' command with the specified Git directory, commit message, and Git ignore path.
 ///
@@ -71,15 +78,7 @@ fn create_new_commit_file(
 
     let (timestamp, offset) = utils::get_timestamp()?;
     let time = format!("{} {}", timestamp, offset);
-    let mut user = USER.to_string();
-    let mut email = EMAIL.to_string();
-    let config = Config::load(directory);
-
-    if let Ok(config) = config {
-        if let Ok(result) = config.get_user_name_and_email() {
-            (user, email) = result;
-        }
-    }
+    let (user, email) = get_user_and_email(directory);
 
     if parent_commit == NO_PARENT {
         let commit_content = format!(
@@ -107,6 +106,107 @@ fn create_new_commit_file(
     }
 }
 
+/// Returns the user name and email stored in the config file of the repository.
+/// If they are not configured, the default ones are returned.
+pub fn get_user_and_email(directory: &str) -> (String, String) {
+    if let Ok(config) = Config::load(directory) {
+        if let Ok(result) = config.get_user_name_and_email() {
+            return result;
+        }
+    }
+    (USER.to_string(), EMAIL.to_string())
+}
+
+/// Creates a new commit object pointing to an already stored tree.
+///
+/// Unlike `new_commit`, this function does not read the index nor updates any branch,
+/// so it can be used to create commits that are referenced from other places (for
+/// example, the stash).
+///
+/// ## Parameters
+///
+/// * `git_dir_path` - The path to the git directory.
+/// * `tree_hash` - The hash of the tree of the commit.
+/// * `parents` - The hashes of the parents of the commit, in order. It can be empty.
+/// * `message` - The commit message.
+///
+/// ## Returns
+///
+/// The hash of the new commit.
+///
+pub fn new_commit_from_tree(
+    git_dir_path: &str,
+    tree_hash: &str,
+    parents: &[String],
+    message: &str,
+) -> io::Result<String> {
+    store_commit(git_dir_path, tree_hash, parents, None, message)
+}
+
+/// Creates a new commit object pointing to an already stored tree, keeping a given author.
+///
+/// The committer is the configured user, at the current time. It is meant for commands that
+/// recreate someone else's commit, such as `am`.
+///
+/// ## Parameters
+///
+/// * `git_dir_path` - The path to the git directory.
+/// * `tree_hash` - The hash of the tree of the commit.
+/// * `parents` - The hashes of the parents of the commit, in order. It can be empty.
+/// * `author` - The author and date, as stored in the commit: `<name> <email> <timestamp> <offset>`.
+/// * `message` - The commit message.
+pub fn new_commit_with_author(
+    git_dir_path: &str,
+    tree_hash: &str,
+    parents: &[String],
+    author: &str,
+    message: &str,
+) -> io::Result<String> {
+    store_commit(git_dir_path, tree_hash, parents, Some(author), message)
+}
+
+/// Stores a commit object committed by the configured user at the current time. Without an
+/// author, the committer is the author too.
+fn store_commit(
+    git_dir_path: &str,
+    tree_hash: &str,
+    parents: &[String],
+    author: Option<&str>,
+    message: &str,
+) -> io::Result<String> {
+    let (timestamp, offset) = utils::get_timestamp()?;
+    let (user, email) = get_user_and_email(git_dir_path);
+    let committer = format!("{} {} {} {}", user, email, timestamp, offset);
+    let mut commit_content = format!("tree {}\n", tree_hash);
+    for parent in parents {
+        commit_content.push_str(&format!("parent {}\n", parent));
+    }
+    commit_content.push_str(&format!(
+        "author {}\ncommitter {}\n\n{}\0",
+        author.unwrap_or(&committer),
+        committer,
+        message
+    ));
+    hash_object::store_string_to_file(&commit_content, git_dir_path, "commit")
+}
+
+/// Returns every parent of the given commit, in the order they are stored.
+/// A commit without parents returns an empty vector.
+///
+/// ## Parameters
+///
+/// * `commit_hash` - The hash of the commit that you want the parents of.
+/// * `git_dir_path` - The path to the git directory.
+pub fn get_commit_parents(commit_hash: &str, git_dir_path: &str) -> io::Result<Vec<String>> {
+    let commit_file = cat_file::cat_file_return_content(commit_hash, git_dir_path)?;
+    Ok(commit_file
+        .lines()
+        .take_while(|line| !line.is_empty())
+        .filter_map(|line| line.strip_prefix("parent "))
+        .map(|parent| parent.trim().to_string())
+        .collect())
+}
+
 /// Retrieves the name of the currently checked-out branch in a Git repository.
 ///
 /// This function reads the contents of the Git repository's "HEAD" file to determine the currently
@@ -167,7 +267,7 @@ pub fn get_branch_name(git_dir_path: &str) -> io::Result<String> {
 ///
 pub fn new_commit(git_dir_path: &str, message: &str, git_ignore_path: &str) -> io::Result<String> {
     let branch_name = get_branch_name(git_dir_path)?;
-    let branch_path = git_dir_path.to_string() + "/refs/heads/" + &branch_name;
+    let branch_path = utils::git_path(git_dir_path, &format!("refs/heads/{}", branch_name));
     let parent_hash = match std::fs::File::open(&branch_path) {
         Ok(mut file) => {
             let mut parent_hash = String::new();
@@ -214,7 +314,7 @@ pub fn new_merge_commit(
     );
     let commit_hash = hash_object::store_string_to_file(&commit_content, git_dir_path, "commit")?;
     let branch_name = get_branch_name(git_dir_path)?;
-    let branch_path = git_dir_path.to_string() + "/refs/heads/" + &branch_name;
+    let branch_path = utils::git_path(git_dir_path, &format!("refs/heads/{}", branch_name));
     let mut branch_file = std::fs::File::create(branch_path)?;
     branch_file.write_all(commit_hash.as_bytes())?;
     Ok(commit_hash)
@@ -254,7 +354,7 @@ pub fn new_rebase_commit(
 
     let commit_hash = hash_object::store_string_to_file(&commit_content, git_dir_path, "commit")?;
     let branch_name = get_branch_name(git_dir_path)?;
-    let branch_path = git_dir_path.to_string() + "/refs/heads/" + &branch_name;
+    let branch_path = utils::git_path(git_dir_path, &format!("refs/heads/{}", branch_name));
     let mut branch_file = std::fs::File::create(branch_path)?;
     branch_file.write_all(commit_hash.as_bytes())?;
     Ok(commit_hash)
@@ -348,7 +448,7 @@ pub fn read_head_commit_hash(git_dir: &str) -> io::Result<String> {
 
     match last_commit_ref {
         Some(refs) => {
-            let heads_path = format!("{}/{}", git_dir, refs);
+            let heads_path = utils::git_path(git_dir, refs);
             if Path::new(&heads_path).exists() {
                 fs::read_to_string(heads_path)
             } else {
@@ -464,12 +564,395 @@ pub fn new_pr_merge_commit(
     let time = format!("{} {}", timestamp, offset);
     let commit_content = format!("tree {tree_hash}\nparent {parent_hash}\nparent {parent_hash2}\nauthor {} {} {time}\ncommitter {} {} {time}\n\n{message}\0", "user", "email", "user", "email", message = message, time = time, tree_hash = tree_hash, parent_hash = parent_hash, parent_hash2 = parent_hash2);
     let commit_hash = hash_object::store_string_to_file(&commit_content, git_dir_path, "commit")?;
-    let branch_path = git_dir_path.to_string() + "/refs/heads/" + target_branch;
+    let branch_path = utils::git_path(git_dir_path, &format!("refs/heads/{}", target_branch));
     let mut branch_file = std::fs::File::create(branch_path)?;
     branch_file.write_all(commit_hash.as_bytes())?;
     Ok(commit_hash)
 }
 
+/// The options of `git commit`.
+#[derive(Debug, Clone, Default)]
+pub struct CommitOptions {
+    /// The message given with `-m` or read with `-F`. Without it, the editor is launched.
+    pub message: Option<String>,
+    /// `-a`: stage the modified and deleted tracked files before committing.
+    pub all: bool,
+    /// `--amend`: replace the tip of the branch instead of adding a child to it.
+    pub amend: bool,
+    /// `--no-edit`: with `--amend`, keep the message without launching the editor.
+    pub no_edit: bool,
+    /// The editor to write the message with, usually `$EDITOR`. It is run by the shell with the
+    /// path of the message file as its last argument.
+    pub editor: Option<String>,
+    /// `--no-verify`: skip the `pre-commit` and `commit-msg` hooks.
+    pub no_verify: bool,
+}
+
+/// Stages every tracked file that was modified or deleted in the working tree, like `git add -u`.
+///
+/// # Arguments
+///
+/// * `git_dir_path` - The path to the git directory.
+/// * `root_dir` - The root of the working directory.
+pub fn stage_tracked_changes(git_dir_path: &str, root_dir: &str) -> io::Result<()> {
+    let mut index = checkout::load_index(git_dir_path, root_dir)?;
+    let mut entries: Vec<(String, String)> = index
+        .iter()
+        .map(|(path, hash)| (path.to_string(), hash.to_string()))
+        .collect();
+    entries.sort();
+    for (path, hash) in entries {
+        match checkout::hash_working_file(root_dir, &path)? {
+            None => index.remove_file(&path)?,
+            Some(working_hash) if working_hash != hash => {
+                let file_path = checkout::working_path(root_dir, &path);
+                let stored_hash = hash_object::store_file(&file_path, git_dir_path)?;
+                index.add_file(&path, &stored_hash)?;
+            }
+            Some(_) => {}
+        }
+    }
+    index.write_file()
+}
+
+/// Returns the content of the file named by `commit.template` in the configuration, if any.
+/// A leading `~/` is expanded to the home directory.
+pub fn get_commit_template(git_dir_path: &str) -> Option<String> {
+    let path = Config::load(git_dir_path)
+        .ok()?
+        .get_value("commit.template")?;
+    let path = match (path.strip_prefix("~/"), std::env::var("HOME")) {
+        (Some(rest), Ok(home)) => format!("{}/{}", home, rest),
+        _ => path,
+    };
+    fs::read_to_string(path).ok()
+}
+
+/// Removes the comment lines, starting with `#`, and the surrounding blank lines of a message.
+fn clean_message(message: &str) -> String {
+    message
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .map(str::trim_end)
+        .collect::<Vec<&str>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
+
+/// Lets the user write the commit message in an editor, starting from `initial`.
+///
+/// The message is written to `COMMIT_EDITMSG` in the git directory, followed by a few comment
+/// lines explaining how it is used, and the editor is run on that file through the shell.
+///
+/// # Errors
+///
+/// Returns an error if the editor cannot be run or fails, or if the message is left empty.
+fn edit_message(git_dir_path: &str, editor: &str, initial: &str) -> io::Result<String> {
+    let message_path = format!("{}/COMMIT_EDITMSG", git_dir_path);
+    let mut content = initial.trim_end().to_string();
+    content.push_str(
+        "\n\n# Please enter the commit message for your changes. Lines starting\n\
+         # with '#' will be ignored, and an empty message aborts the commit.\n",
+    );
+    fs::write(&message_path, content)?;
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(format!("{} \"$@\"", editor))
+        .arg(editor)
+        .arg(&message_path)
+        .status()?;
+    if !status.success() {
+        return Err(io::Error::other(format!(
+            "error: there was a problem with the editor '{}'",
+            editor
+        )));
+    }
+    let message = clean_message(&fs::read_to_string(&message_path)?);
+    if message.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Aborting commit due to empty commit message.",
+        ));
+    }
+    Ok(message)
+}
+
+/// Decides the message of the commit: the one given, the one of the amended commit with
+/// `--no-edit`, or the one written in the editor, which starts from the amended message or the
+/// `commit.template`.
+fn resolve_message(
+    git_dir_path: &str,
+    options: &CommitOptions,
+    amended_message: Option<&str>,
+) -> io::Result<String> {
+    if let Some(message) = &options.message {
+        return Ok(message.to_string());
+    }
+    if let (Some(message), true) = (amended_message, options.no_edit) {
+        return Ok(message.to_string());
+    }
+    let editor = options.editor.as_deref().ok_or(io::Error::new(
+        io::ErrorKind::InvalidInput,
+        "error: no commit message given; use -m, -F or set $EDITOR",
+    ))?;
+    match amended_message {
+        Some(message) => edit_message(git_dir_path, editor, message),
+        None => {
+            let template = get_commit_template(git_dir_path).unwrap_or_default();
+            let message = edit_message(git_dir_path, editor, &template)?;
+            if !template.trim().is_empty() && message == clean_message(&template) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "Aborting commit; you did not edit the message.",
+                ));
+            }
+            Ok(message)
+        }
+    }
+}
+
+/// Replaces the commit HEAD points to with a new one, made from the index, with the same parents
+/// and author. HEAD, or the branch it points to, is moved to the new commit.
+///
+/// # Arguments
+///
+/// * `git_dir_path` - The path to the git directory.
+/// * `root_dir` - The root of the working directory.
+/// * `message` - The message of the new commit.
+///
+/// # Returns
+///
+/// The hash of the new commit.
+///
+/// # Errors
+///
+/// Returns an error if there is no commit to amend or the new commit cannot be written.
+pub fn amend_commit(git_dir_path: &str, root_dir: &str, message: &str) -> io::Result<String> {
+    let tip = utils::resolve_revision(git_dir_path, "HEAD").map_err(|_| {
+        io::Error::new(io::ErrorKind::NotFound, "fatal: You have nothing to amend.")
+    })?;
+    let parents = get_commit_parents(&tip, git_dir_path)?;
+    let content = cat_file::cat_file_return_content(&tip, git_dir_path)?;
+    let author = content
+        .lines()
+        .take_while(|line| !line.is_empty())
+        .find_map(|line| line.strip_prefix("author "))
+        .ok_or(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("fatal: commit {} has no author", tip),
+        ))?;
+    let index = checkout::load_index(git_dir_path, root_dir)?;
+    let tree = tree_handler::build_tree_from_entries(index.iter())?;
+    let (tree_hash, _) = tree_handler::write_tree(&tree, git_dir_path)?;
+    let commit_hash = new_commit_with_author(git_dir_path, &tree_hash, &parents, author, message)?;
+    let head = fs::read_to_string(format!("{}/HEAD", git_dir_path))?;
+    match head.trim().strip_prefix("ref: ") {
+        Some(reference) => fs::write(utils::git_path(git_dir_path, reference), &commit_hash)?,
+        None => fs::write(
+            format!("{}/HEAD", git_dir_path),
+            format!("{} (commit)\n", commit_hash),
+        )?,
+    }
+    Ok(commit_hash)
+}
+
+/// Records the changes of the index in a new commit, with the given options.
+///
+/// See [`CommitOptions`] for how `-a`, `--amend` and the message are handled. Without
+/// `--amend`, the commit is made with [`new_commit`], so it is refused if nothing changed.
+///
+/// Unless `options.no_verify` is set, the `pre-commit` hook runs before the message is decided
+/// and the `commit-msg` hook gets the path to a file with the message, which it may edit; if
+/// either fails, nothing is committed. The `post-commit` hook runs after the commit.
+///
+/// # Arguments
+///
+/// * `git_dir_path` - The path to the git directory.
+/// * `root_dir` - The root of the working directory.
+/// * `options` - The options of the command.
+///
+/// # Returns
+///
+/// The hash of the new commit.
+///
+/// # Errors
+///
+/// Returns an error if there is no message, nothing to commit, or the commit cannot be written.
+pub fn commit_with_options(
+    git_dir_path: &str,
+    root_dir: &str,
+    options: &CommitOptions,
+) -> io::Result<String> {
+    if options.all {
+        stage_tracked_changes(git_dir_path, root_dir)?;
+    }
+    if !options.no_verify {
+        hooks::run_hook(git_dir_path, "pre-commit", &[], None)?;
+    }
+    let amended_message = match options.amend {
+        true => {
+            let tip = utils::resolve_revision(git_dir_path, "HEAD")?;
+            Some(Log::load(Some(&tip), git_dir_path)?.expand_format("%B"))
+        }
+        false => None,
+    };
+    let mut message = resolve_message(
+        git_dir_path,
+        options,
+        amended_message.as_deref().map(str::trim_end),
+    )?;
+    if !options.no_verify && hooks::find_hook(git_dir_path, "commit-msg").is_some() {
+        message = run_commit_msg_hook(git_dir_path, &message)?;
+    }
+    let commit_hash = if options.amend {
+        let commit_hash = amend_commit(git_dir_path, root_dir, &message)?;
+        log_commit(git_dir_path, &message, "")?;
+        commit_hash
+    } else {
+        let git_ignore_path = checkout::working_path(root_dir, GIT_IGNORE);
+        new_commit(git_dir_path, &message, &git_ignore_path)?
+    };
+    // Like git, the exit status of the post-commit hook does not undo the commit.
+    let _ = hooks::run_hook(git_dir_path, "post-commit", &[], None);
+    Ok(commit_hash)
+}
+
+/// Runs the `commit-msg` hook on a message, returning the message as the hook left it, since the
+/// hook may edit it.
+///
+/// # Errors
+///
+/// Returns an error if the hook rejects the message or leaves it empty.
+fn run_commit_msg_hook(git_dir_path: &str, message: &str) -> io::Result<String> {
+    let message_path = format!("{}/COMMIT_EDITMSG", git_dir_path);
+    fs::write(&message_path, format!("{}\n", message))?;
+    let absolute_path = fs::canonicalize(&message_path)?;
+    hooks::run_hook(
+        git_dir_path,
+        "commit-msg",
+        &[&absolute_path.to_string_lossy()],
+        None,
+    )?;
+    let message = clean_message(&fs::read_to_string(&message_path)?);
+    if message.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Aborting commit due to empty commit message.",
+        ));
+    }
+    Ok(message)
+}
+
+/// Records the changes of the index in a new commit, like `git commit`.
+///
+/// The command line is
+/// `git commit [-a] [-n] [--amend] [--no-edit] [-m <message>]... [-F <file>]`:
+///
+/// - `-a` or `--all` stages the modified and deleted tracked files first.
+/// - `--amend` replaces the last commit, keeping its parents and author. Its message is kept
+///   with `--no-edit`, or offered in the editor otherwise.
+/// - `-m` or `--message` gives the message. Several ones are joined as paragraphs.
+/// - `-F` or `--file` reads the message from a file, or from the standard input with `-`.
+/// - `-n` or `--no-verify` skips the `pre-commit` and `commit-msg` hooks.
+///
+/// Without a message, the editor in `$EDITOR` is launched, starting from the file named by the
+/// `commit.template` configuration. Short options can be combined, as in `-am <message>`.
+///
+/// # Arguments
+///
+/// * `git_dir_path` - The path to the git directory.
+/// * `root_dir` - The root of the working directory.
+/// * `line` - The command line, starting with `git commit`.
+/// * `output` - Where the summary of the new commit is written.
+///
+/// # Errors
+///
+/// Returns an error if the arguments are invalid or the commit cannot be made.
+pub fn git_commit(
+    git_dir_path: &str,
+    root_dir: &str,
+    line: Vec<String>,
+    output: &mut impl Write,
+) -> io::Result<()> {
+    let mut options = CommitOptions {
+        editor: std::env::var("EDITOR")
+            .ok()
+            .filter(|editor| !editor.is_empty()),
+        ..Default::default()
+    };
+    let mut paragraphs: Vec<String> = Vec::new();
+    let mut message_file: Option<String> = None;
+    let missing_value = |option: &str| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("error: switch '{}' requires a value", option),
+        )
+    };
+    let invalid = |arg: &str| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("error: unknown option '{}'\n{}", arg, COMMIT_USAGE),
+        )
+    };
+    let mut args = line.iter().skip(2);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--all" => options.all = true,
+            "--amend" => options.amend = true,
+            "--no-edit" => options.no_edit = true,
+            "--no-verify" => options.no_verify = true,
+            "--message" => paragraphs.push(args.next().ok_or(missing_value("m"))?.to_string()),
+            "--file" => message_file = Some(args.next().ok_or(missing_value("F"))?.to_string()),
+            _ if arg.starts_with('-') && !arg.starts_with("--") && arg.len() > 1 => {
+                for flag in arg.chars().skip(1) {
+                    match flag {
+                        'a' => options.all = true,
+                        'n' => options.no_verify = true,
+                        'm' => paragraphs.push(args.next().ok_or(missing_value("m"))?.to_string()),
+                        'F' => {
+                            message_file = Some(args.next().ok_or(missing_value("F"))?.to_string())
+                        }
+                        _ => return Err(invalid(arg)),
+                    }
+                }
+            }
+            _ => return Err(invalid(arg)),
+        }
+    }
+    options.message = match (paragraphs.is_empty(), message_file) {
+        (false, Some(_)) => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "fatal: options '-m' and '-F' cannot be used together",
+            ))
+        }
+        (false, None) => Some(paragraphs.join("\n\n")),
+        (true, Some(path)) => {
+            let content = if path == "-" {
+                io::read_to_string(io::stdin())?
+            } else {
+                fs::read_to_string(&path).map_err(|error| {
+                    io::Error::new(
+                        error.kind(),
+                        format!("fatal: could not read log file '{}': {}", path, error),
+                    )
+                })?
+            };
+            Some(clean_message(&content))
+        }
+        (true, None) => None,
+    };
+    let commit_hash = commit_with_options(git_dir_path, root_dir, &options)?;
+    let subject = Log::load(Some(&commit_hash), git_dir_path)?.expand_format("%s");
+    let head = fs::read_to_string(format!("{}/HEAD", git_dir_path))?;
+    let branch = match head.trim().strip_prefix("ref: refs/heads/") {
+        Some(branch) => branch.to_string(),
+        None => "detached HEAD".to_string(),
+    };
+    writeln!(output, "[{} {}] {}", branch, &commit_hash[..7], subject)
+}
+
 #[cfg(test)]
 mod tests {
     fn create_git_dir(git_dir_path: &str) {
@@ -497,6 +980,7 @@ mod tests {
         index_file.write_all(index_file_content.as_bytes()).unwrap();
     }
     use super::*;
+    use crate::test_utils;
 
     fn reset_refs_file(git_dir_path: &str) {
         let refs_path = git_dir_path.to_string() + "/refs/heads/main";
@@ -649,4 +1133,195 @@ mod tests {
         assert_eq!(commit_message, message);
         let _ = std::fs::remove_dir_all(git_dir_path);
     }
+
+    /// Creates a repository with a working tree, committing `a.txt` and `b.txt`.
+    fn create_repo_with_files(root_dir: &str) -> String {
+        let git_dir = test_utils::create_repo(root_dir);
+        test_utils::commit_files(
+            &git_dir,
+            root_dir,
+            &[("a.txt", "a.txt\n"), ("b.txt", "b.txt\n")],
+            "first",
+        );
+        git_dir
+    }
+
+    fn run_commit(git_dir: &str, root_dir: &str, args: &[&str]) -> io::Result<String> {
+        let mut line = vec!["git".to_string(), "commit".to_string()];
+        line.extend(args.iter().map(|arg| arg.to_string()));
+        let mut output = Vec::new();
+        git_commit(git_dir, root_dir, line, &mut output)?;
+        Ok(String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn test_commit_all_stages_changes_and_amend_replaces_the_tip() {
+        let root_dir = "tests/commit/options_amend";
+        let git_dir = create_repo_with_files(root_dir);
+        let first = read_head_commit_hash(&git_dir).unwrap();
+        std::fs::write(format!("{}/a.txt", root_dir), "changed\n").unwrap();
+        std::fs::remove_file(format!("{}/b.txt", root_dir)).unwrap();
+
+        let output = run_commit(&git_dir, root_dir, &["-am", "second"]).unwrap();
+        let second = read_head_commit_hash(&git_dir).unwrap();
+        assert_eq!(output, format!("[main {}] second\n", &second[..7]));
+        let index = checkout::load_index(&git_dir, root_dir).unwrap();
+        assert!(!index.contains("b.txt"));
+        assert_eq!(
+            index.get_hash("a.txt"),
+            checkout::hash_working_file(root_dir, "a.txt")
+                .unwrap()
+                .as_ref()
+        );
+
+        std::fs::write(format!("{}/a.txt", root_dir), "fixed\n").unwrap();
+        run_commit(&git_dir, root_dir, &["-a", "--amend", "--no-edit"]).unwrap();
+        let amended = read_head_commit_hash(&git_dir).unwrap();
+        assert_ne!(amended, second);
+        assert_eq!(get_commit_parents(&amended, &git_dir).unwrap(), vec![first]);
+        assert_eq!(get_commit_message(&amended, &git_dir).unwrap(), "second");
+
+        run_commit(&git_dir, root_dir, &["--amend", "-m", "reworded"]).unwrap();
+        let reworded = read_head_commit_hash(&git_dir).unwrap();
+        assert_eq!(get_commit_message(&reworded, &git_dir).unwrap(), "reworded");
+        assert_eq!(
+            get_commit_parents(&reworded, &git_dir).unwrap(),
+            get_commit_parents(&amended, &git_dir).unwrap()
+        );
+        let _ = std::fs::remove_dir_all(root_dir);
+    }
+
+    #[test]
+    fn test_amend_on_detached_head_keeps_head_detached() {
+        let root_dir = "tests/commit/options_amend_detached";
+        let git_dir = create_repo_with_files(root_dir);
+        let first = read_head_commit_hash(&git_dir).unwrap();
+        checkout::checkout_commit_detached(Path::new(&git_dir), root_dir, &first).unwrap();
+
+        run_commit(&git_dir, root_dir, &["--amend", "-m", "reworded"]).unwrap();
+
+        let head = std::fs::read_to_string(format!("{}/HEAD", git_dir)).unwrap();
+        let amended = utils::resolve_revision(&git_dir, "HEAD").unwrap();
+        assert_eq!(head, format!("{} (commit)\n", amended));
+        assert_ne!(amended, first);
+        assert_eq!(get_commit_message(&amended, &git_dir).unwrap(), "reworded");
+        assert_eq!(
+            std::fs::read_to_string(format!("{}/refs/heads/main", git_dir)).unwrap(),
+            first
+        );
+        let _ = std::fs::remove_dir_all(root_dir);
+    }
+
+    #[test]
+    fn test_commit_reads_the_message_from_a_file_or_the_editor() {
+        let root_dir = "tests/commit/options_message";
+        let git_dir = create_repo_with_files(root_dir);
+        let message_path = format!("{}/message.txt", root_dir);
+        std::fs::write(&message_path, "# ignored\nsubject\n\nbody\n\n").unwrap();
+        std::fs::write(format!("{}/a.txt", root_dir), "changed\n").unwrap();
+        run_commit(&git_dir, root_dir, &["-a", "-F", &message_path]).unwrap();
+        let head = read_head_commit_hash(&git_dir).unwrap();
+        assert_eq!(
+            Log::load(Some(&head), &git_dir)
+                .unwrap()
+                .expand_format("%B"),
+            "subject\n\nbody"
+        );
+
+        let template_path = format!("{}/template.txt", root_dir);
+        std::fs::write(&template_path, "TODO: subject\n# explain why\n").unwrap();
+        std::fs::write(
+            format!("{}/config", git_dir),
+            format!("[commit]\n\ttemplate = {}\n", template_path),
+        )
+        .unwrap();
+        std::fs::write(format!("{}/b.txt", root_dir), "changed\n").unwrap();
+        let mut options = CommitOptions {
+            all: true,
+            editor: Some("true".to_string()),
+            ..Default::default()
+        };
+        assert!(commit_with_options(&git_dir, root_dir, &options).is_err());
+        options.editor = Some("sed -i s/TODO/done/".to_string());
+        let commit_hash = commit_with_options(&git_dir, root_dir, &options).unwrap();
+        assert_eq!(
+            get_commit_message(&commit_hash, &git_dir).unwrap(),
+            "done: subject"
+        );
+        let _ = std::fs::remove_dir_all(root_dir);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_commit_runs_the_hooks_unless_no_verify() {
+        use std::os::unix::fs::PermissionsExt;
+        let root_dir = "tests/commit/options_hooks";
+        let git_dir = create_repo_with_files(root_dir);
+        let install_hook = |name: &str, script: &str| {
+            let path = format!("{}/hooks/{}", git_dir, name);
+            std::fs::create_dir_all(format!("{}/hooks", git_dir)).unwrap();
+            std::fs::write(&path, format!("#!/bin/sh\n{}\n", script)).unwrap();
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        };
+        install_hook("pre-commit", "test -f allow");
+        install_hook("commit-msg", "printf '\\nRefs: #42\\n' >> \"$1\"");
+        install_hook("post-commit", "touch committed");
+        let first = read_head_commit_hash(&git_dir).unwrap();
+
+        std::fs::write(format!("{}/a.txt", root_dir), "changed\n").unwrap();
+        let error = run_commit(&git_dir, root_dir, &["-am", "second"]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "error: hook 'pre-commit' exited with status 1"
+        );
+        assert_eq!(read_head_commit_hash(&git_dir).unwrap(), first);
+        assert!(!Path::new(&format!("{}/committed", root_dir)).exists());
+
+        std::fs::write(format!("{}/allow", root_dir), "").unwrap();
+        run_commit(&git_dir, root_dir, &["-m", "second"]).unwrap();
+        let second = read_head_commit_hash(&git_dir).unwrap();
+        assert_eq!(
+            Log::load(Some(&second), &git_dir)
+                .unwrap()
+                .expand_format("%B"),
+            "second\n\nRefs: #42"
+        );
+        assert!(Path::new(&format!("{}/committed", root_dir)).exists());
+
+        std::fs::remove_file(format!("{}/allow", root_dir)).unwrap();
+        std::fs::write(format!("{}/b.txt", root_dir), "changed\n").unwrap();
+        run_commit(&git_dir, root_dir, &["-a", "--no-verify", "-m", "third"]).unwrap();
+        let third = read_head_commit_hash(&git_dir).unwrap();
+        assert_eq!(get_commit_message(&third, &git_dir).unwrap(), "third");
+        let _ = std::fs::remove_dir_all(root_dir);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_commit_with_options_runs_the_hooks_without_the_command_line() {
+        use std::os::unix::fs::PermissionsExt;
+        let root_dir = "tests/commit/options_hooks_direct";
+        let git_dir = create_repo_with_files(root_dir);
+        let path = format!("{}/hooks/post-commit", git_dir);
+        std::fs::create_dir_all(format!("{}/hooks", git_dir)).unwrap();
+        std::fs::write(
+            &path,
+            "#!/bin/sh
+touch committed
+",
+        )
+        .unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        test_utils::stage_files(&git_dir, root_dir, &[("a.txt", "changed\n")]);
+
+        let options = CommitOptions {
+            message: Some("second".to_string()),
+            ..Default::default()
+        };
+        let second = commit_with_options(&git_dir, root_dir, &options).unwrap();
+
+        assert_eq!(read_head_commit_hash(&git_dir).unwrap(), second);
+        assert!(Path::new(&format!("{}/committed", root_dir)).exists());
+        let _ = std::fs::remove_dir_all(root_dir);
+    }
 }
@@ -1,3 +1,4 @@
+use crate::utils;
 use crate::{branch_handler::Branch, remote_handler::Remote};
 use std::{
     fs::{self, File, OpenOptions},
@@ -37,7 +38,7 @@ impl Config {
     /// `std::io::Error` in case of any errors during the loading process.
     ///
     pub fn load(git_dir: &str) -> io::Result<Config> {
-        let file_name = format!("{}/config", git_dir);
+        let file_name = utils::git_path(git_dir, "config");
         let mut config = Config::new(file_name.clone());
         let file = File::open(&file_name)?;
 
@@ -763,6 +764,50 @@ impl Config {
             ))
         }
     }
+
+    /// Retrieves the value of a variable from the configuration file.
+    ///
+    /// The variable is named as in `git config`: the section, the optional subsection and the
+    /// key, separated by dots, such as `commit.template` for the `template` key of the
+    /// `[commit]` section, or `filter.crlf.clean` for the `clean` key of the `[filter "crlf"]`
+    /// section. Sections and keys are matched ignoring case; if a variable is set several times,
+    /// the last value wins.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the variable.
+    ///
+    /// # Returns
+    ///
+    /// Returns the value of the variable, or `None` if it is not set or the file cannot be read.
+    ///
+    pub fn get_value(&self, name: &str) -> Option<String> {
+        let (section, key) = name.rsplit_once('.')?;
+        let content = fs::read_to_string(&self.config_file_path).ok()?;
+        let mut current_section = String::new();
+        let mut value = None;
+        for line in content.lines().map(str::trim) {
+            if let Some(header) = line.strip_prefix('[').and_then(|h| h.strip_suffix(']')) {
+                current_section = match header.split_once(' ') {
+                    Some((name, subsection)) => {
+                        format!(
+                            "{}.{}",
+                            name.to_lowercase(),
+                            subsection.trim().trim_matches('"')
+                        )
+                    }
+                    None => header.to_lowercase(),
+                };
+            } else if let Some((line_key, line_value)) = line.split_once('=') {
+                if current_section.eq_ignore_ascii_case(section)
+                    && line_key.trim().eq_ignore_ascii_case(key)
+                {
+                    value = Some(line_value.trim().trim_matches('"').to_string());
+                }
+            }
+        }
+        value
+    }
 }
 
 #[cfg(test)]
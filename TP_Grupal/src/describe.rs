@@ -0,0 +1,388 @@
+use crate::cat_file;
+use crate::checkout;
+use crate::configuration::LOGGER_COMMANDS_FILE;
+use crate::log::{self, Log};
+use crate::logger::Logger;
+use crate::tree_handler;
+use crate::utils::{self, get_current_time};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+const DESCRIBE_USAGE: &str =
+    "usage: git describe [--tags] [--long] [--dirty[=<mark>]] [<commit-ish>]";
+
+/// Number of characters of the abbreviated hash printed after the tag.
+const ABBREV_LENGTH: usize = 7;
+/// Suffix printed by `--dirty` when no other mark is given.
+const DEFAULT_DIRTY_MARK: &str = "-dirty";
+
+/// Logs the 'git describe' command with the specified revision.
+///
+/// This function logs the 'git describe' command with the provided revision to a file named
+/// 'logger_commands.txt'.
+///
+/// # Arguments
+///
+/// * `revision` - The revision being described.
+///
+/// # Errors
+///
+/// Returns an `io::Result` indicating whether the operation was successful.
+///
+fn log_describe(revision: &str) -> io::Result<()> {
+    let log_file_path = LOGGER_COMMANDS_FILE;
+    let mut logger = Logger::new(log_file_path)?;
+
+    let full_message = format!(
+        "Command 'git describe': Revision '{}', {}",
+        revision,
+        get_current_time()
+    );
+    logger.write_all(full_message.as_bytes())?;
+    logger.flush()?;
+    Ok(())
+}
+
+/// A tag that may describe a commit.
+struct TagCandidate {
+    /// The name of the tag, relative to `refs/tags`.
+    name: String,
+    /// The commit the tag points to, after peeling annotated tags.
+    commit: String,
+    /// Whether the tag is annotated, that is, it points to a tag object.
+    annotated: bool,
+    /// When the tag was created: the tagger date of annotated tags and the commit date of
+    /// lightweight ones. It decides between tags on the same commit.
+    timestamp: i64,
+}
+
+/// Reads every tag of `refs/tags`, including the ones in subdirectories.
+fn read_tags(git_dir: &str, directory: &Path, prefix: &str) -> io::Result<Vec<TagCandidate>> {
+    let mut tags = Vec::new();
+    let Ok(entries) = fs::read_dir(directory) else {
+        return Ok(tags);
+    };
+    for entry in entries.flatten() {
+        let name = format!("{}{}", prefix, entry.file_name().to_string_lossy());
+        if entry.path().is_dir() {
+            tags.extend(read_tags(git_dir, &entry.path(), &format!("{}/", name))?);
+            continue;
+        }
+        let hash = fs::read_to_string(entry.path())?.trim().to_string();
+        let annotated = cat_file::get_object_type(&hash, git_dir)? == "tag";
+        let commit = utils::resolve_revision(git_dir, &hash)?;
+        let timestamp = if annotated {
+            tagger_timestamp(&cat_file::cat_file_return_content(&hash, git_dir)?)
+        } else {
+            Log::load(Some(&commit), git_dir)?
+                .expand_format("%ct")
+                .parse()
+                .unwrap_or_default()
+        };
+        tags.push(TagCandidate {
+            name,
+            commit,
+            annotated,
+            timestamp,
+        });
+    }
+    Ok(tags)
+}
+
+/// Reads the timestamp of the `tagger` line of a tag object.
+fn tagger_timestamp(content: &str) -> i64 {
+    content
+        .lines()
+        .find_map(|line| line.strip_prefix("tagger "))
+        .and_then(|tagger| tagger.split_whitespace().rev().nth(1))
+        .and_then(|timestamp| timestamp.parse().ok())
+        .unwrap_or_default()
+}
+
+/// Describes a commit by the nearest tag it can reach, like `v1.2-14-gabc1234`.
+///
+/// The description is the name of the tag, the number of commits reachable from the commit but
+/// not from the tag, and `g` followed by the abbreviated hash of the commit. When the commit
+/// itself is tagged only the name of the tag is printed, unless `long` is set.
+///
+/// The nearest tag is the one with the fewest commits after it. Ties are resolved preferring
+/// annotated tags, and then the most recent one.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the Git directory.
+/// * `commit_hash` - The commit to describe.
+/// * `tags` - Whether lightweight tags may be used, and not only annotated ones.
+/// * `long` - Whether to print the number of commits and the hash even for tagged commits.
+///
+/// # Errors
+///
+/// Returns a `NotFound` error if no tag can describe the commit, or an error if a tag or
+/// commit cannot be read.
+///
+pub fn describe(git_dir: &str, commit_hash: &str, tags: bool, long: bool) -> io::Result<String> {
+    let tags_dir = utils::git_path(git_dir, "refs/tags");
+    let all_tags = read_tags(git_dir, Path::new(&tags_dir), "")?;
+    let ancestors = log::get_ancestors(git_dir, commit_hash)?;
+    let reachable: Vec<&TagCandidate> = all_tags
+        .iter()
+        .filter(|tag| ancestors.contains(&tag.commit))
+        .collect();
+    let candidates: Vec<&TagCandidate> = reachable
+        .iter()
+        .copied()
+        .filter(|tag| tags || tag.annotated)
+        .collect();
+
+    let mut distances: HashMap<&str, usize> = HashMap::new();
+    for tag in &candidates {
+        if !distances.contains_key(tag.commit.as_str()) {
+            let tag_ancestors = log::get_ancestors(git_dir, &tag.commit)?;
+            let distance = ancestors.difference(&tag_ancestors).count();
+            distances.insert(&tag.commit, distance);
+        }
+    }
+    let Some(best) = candidates.iter().min_by_key(|tag| {
+        (
+            distances[tag.commit.as_str()],
+            !tag.annotated,
+            Reverse(tag.timestamp),
+            tag.name.as_str(),
+        )
+    }) else {
+        let message = if all_tags.is_empty() {
+            "fatal: No names found, cannot describe anything.".to_string()
+        } else if !reachable.is_empty() {
+            format!(
+                "fatal: No annotated tags can describe '{}'.\n\
+                 However, there were unannotated tags: try --tags.",
+                commit_hash
+            )
+        } else {
+            format!(
+                "fatal: No tags can describe '{}'.\nTry --always, or create some tags.",
+                commit_hash
+            )
+        };
+        return Err(io::Error::new(io::ErrorKind::NotFound, message));
+    };
+    let distance = distances[best.commit.as_str()];
+    if distance == 0 && !long {
+        return Ok(best.name.clone());
+    }
+    Ok(format!(
+        "{}-{}-g{}",
+        best.name,
+        distance,
+        &commit_hash[..ABBREV_LENGTH.min(commit_hash.len())]
+    ))
+}
+
+/// Checks whether the index or the tracked files of the working tree differ from HEAD.
+///
+/// Untracked files are not taken into account.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the Git directory.
+/// * `root_dir` - The root of the working directory.
+///
+/// # Errors
+///
+/// Returns an error if the index, the tree of HEAD or a working file cannot be read.
+///
+pub fn is_dirty(git_dir: &str, root_dir: &str) -> io::Result<bool> {
+    let index = checkout::load_index(git_dir, root_dir)?;
+    let head_files: HashMap<String, String> = match utils::resolve_revision(git_dir, "HEAD") {
+        Ok(head) => tree_handler::load_tree_from_commit(&head, git_dir)?
+            .squash_tree_into_vec("")
+            .into_iter()
+            .collect(),
+        Err(_) => HashMap::new(),
+    };
+    if head_files.len() != index.iter().count() {
+        return Ok(true);
+    }
+    for (path, hash) in index.iter() {
+        if head_files.get(path) != Some(hash) {
+            return Ok(true);
+        }
+        if checkout::hash_working_file(root_dir, path)?.as_ref() != Some(hash) {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Gives a name to a commit based on the nearest tag it can reach, like `git describe`.
+///
+/// The command line is `git describe [--tags] [--long] [--dirty[=<mark>]] [<commit-ish>]`:
+///
+/// - `--tags` also uses lightweight tags, and not only annotated ones.
+/// - `--long` always prints the number of commits and the abbreviated hash.
+/// - `--dirty` describes HEAD and appends `-dirty`, or the given mark, when the index or the
+///   working tree differ from it. It cannot be combined with a commit-ish.
+///
+/// The commit-ish is `HEAD` by default. See [`describe`] for the format of the description.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the Git directory.
+/// * `root_dir` - The root of the working directory, checked by `--dirty`.
+/// * `line` - The command line, starting with `git describe`.
+/// * `output` - Where the description is written.
+///
+/// # Errors
+///
+/// Returns an error if the arguments are invalid, the commit-ish cannot be resolved or no tag
+/// can describe it.
+///
+pub fn git_describe(
+    git_dir: &str,
+    root_dir: &str,
+    line: Vec<String>,
+    output: &mut impl Write,
+) -> io::Result<()> {
+    let mut tags = false;
+    let mut long = false;
+    let mut dirty_mark: Option<String> = None;
+    let mut revisions: Vec<String> = Vec::new();
+    for arg in line.iter().skip(2) {
+        match arg.as_str() {
+            "--tags" => tags = true,
+            "--long" => long = true,
+            "--dirty" => dirty_mark = Some(DEFAULT_DIRTY_MARK.to_string()),
+            _ => {
+                if let Some(mark) = arg.strip_prefix("--dirty=") {
+                    dirty_mark = Some(mark.to_string());
+                } else if arg.starts_with('-') {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("error: unknown option '{}'\n{}", arg, DESCRIBE_USAGE),
+                    ));
+                } else {
+                    revisions.push(arg.to_string());
+                }
+            }
+        }
+    }
+    if revisions.len() > 1 {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, DESCRIBE_USAGE));
+    }
+    if dirty_mark.is_some() && !revisions.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "fatal: option '--dirty' and commit-ishes cannot be used together",
+        ));
+    }
+    let revision = revisions.pop().unwrap_or_else(|| "HEAD".to_string());
+    log_describe(&revision)?;
+
+    let commit_hash = utils::resolve_revision(git_dir, &revision)?;
+    let mut description = describe(git_dir, &commit_hash, tags, long)?;
+    if let Some(mark) = dirty_mark {
+        if is_dirty(git_dir, root_dir)? {
+            description.push_str(&mark);
+        }
+    }
+    writeln!(output, "{}", description)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash_object;
+    use crate::test_utils;
+
+    /// Tags a commit with an annotated tag, written like `git tag -a` does.
+    fn annotated_tag(git_dir: &str, name: &str, commit: &str, timestamp: i64) {
+        let content = format!(
+            "object {}\ntype commit\ntag {}\ntagger alice alice@mail.com {} -0300\n\nRelease\n",
+            commit, name, timestamp
+        );
+        let hash = hash_object::store_string_to_file(&content, git_dir, "tag").unwrap();
+        fs::write(format!("{}/refs/tags/{}", git_dir, name), hash).unwrap();
+    }
+
+    fn run_describe(git_dir: &str, root_dir: &str, args: &[&str]) -> io::Result<String> {
+        let mut line = vec!["git".to_string(), "describe".to_string()];
+        line.extend(args.iter().map(|arg| arg.to_string()));
+        let mut output = Vec::new();
+        git_describe(git_dir, root_dir, line, &mut output)?;
+        Ok(String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn test_describe_uses_the_nearest_tag() {
+        let root_dir = "tests/describe/nearest";
+        let git_dir = test_utils::create_repo(root_dir);
+        let first = test_utils::commit_files(&git_dir, root_dir, &[("file.txt", "1\n")], "first");
+        assert!(run_describe(&git_dir, root_dir, &[])
+            .unwrap_err()
+            .to_string()
+            .contains("No names found"));
+
+        annotated_tag(&git_dir, "v0.9", &first, 1000);
+        annotated_tag(&git_dir, "v1.0", &first, 2000);
+        test_utils::commit_files(&git_dir, root_dir, &[("file.txt", "2\n")], "second");
+        let third = test_utils::commit_files(&git_dir, root_dir, &[("file.txt", "3\n")], "third");
+        fs::create_dir_all(format!("{}/refs/tags/nightly", git_dir)).unwrap();
+        fs::write(format!("{}/refs/tags/nightly/light", git_dir), &third).unwrap();
+        let head = test_utils::commit_files(&git_dir, root_dir, &[("file.txt", "4\n")], "fourth");
+
+        assert_eq!(
+            run_describe(&git_dir, root_dir, &[]).unwrap(),
+            format!("v1.0-3-g{}\n", &head[..7])
+        );
+        assert_eq!(
+            run_describe(&git_dir, root_dir, &["--tags"]).unwrap(),
+            format!("nightly/light-1-g{}\n", &head[..7])
+        );
+        assert_eq!(
+            run_describe(&git_dir, root_dir, &["v0.9"]).unwrap(),
+            "v1.0\n"
+        );
+        assert_eq!(
+            run_describe(&git_dir, root_dir, &["--long", "HEAD~3"]).unwrap(),
+            format!("v1.0-0-g{}\n", &first[..7])
+        );
+        assert_eq!(
+            run_describe(&git_dir, root_dir, &["--tags", &third]).unwrap(),
+            "nightly/light\n"
+        );
+
+        fs::remove_dir_all(root_dir).unwrap();
+    }
+
+    #[test]
+    fn test_describe_dirty() {
+        let root_dir = "tests/describe/dirty";
+        let git_dir = test_utils::create_repo(root_dir);
+        let first = test_utils::commit_files(&git_dir, root_dir, &[("file.txt", "1\n")], "first");
+        fs::write(format!("{}/refs/tags/light", git_dir), &first).unwrap();
+
+        assert!(run_describe(&git_dir, root_dir, &[])
+            .unwrap_err()
+            .to_string()
+            .contains("try --tags"));
+        assert_eq!(
+            run_describe(&git_dir, root_dir, &["--tags", "--dirty"]).unwrap(),
+            "light\n"
+        );
+        fs::write(format!("{}/file.txt", root_dir), "changed\n").unwrap();
+        assert_eq!(
+            run_describe(&git_dir, root_dir, &["--tags", "--dirty"]).unwrap(),
+            "light-dirty\n"
+        );
+        assert_eq!(
+            run_describe(&git_dir, root_dir, &["--tags", "--dirty=.mod"]).unwrap(),
+            "light.mod\n"
+        );
+        assert!(run_describe(&git_dir, root_dir, &["--dirty", "HEAD"]).is_err());
+
+        fs::remove_dir_all(root_dir).unwrap();
+    }
+}
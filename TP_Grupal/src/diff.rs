@@ -1,9 +1,174 @@
-use crate::cat_file::cat_file_return_content;
+use crate::attributes::Attributes;
+use crate::cat_file::{cat_file_return_bytes, cat_file_return_content};
+use crate::configuration::LOGGER_COMMANDS_FILE;
+use crate::logger::Logger;
+use crate::tree_handler::{self, Tree};
+use crate::utils::{self, get_current_time};
+use crate::{checkout, restore, stash};
 use std::{
+    collections::{BTreeMap, HashMap, HashSet},
     fs::File,
-    io::{BufRead, BufReader},
+    io::{self, BufRead, BufReader, Write},
 };
 
+const DIFF_USAGE: &str =
+    "usage: git diff [<options>] [--cached] [<commit> [<commit>]] [-- <path>...]";
+
+/// Number of unchanged lines shown around every change in a unified diff.
+const CONTEXT_LINES: usize = 3;
+
+/// A single line of an edit script between two texts.
+///
+/// Every line keeps its trailing `\n` (if it had one), so a line that only differs
+/// in the final newline is still reported as a change.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum DiffLine<'a> {
+    Context(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+/// Similarity, as a percentage, a file must keep to be detected as renamed or copied.
+pub const DEFAULT_RENAME_THRESHOLD: u32 = 50;
+
+/// A change to a single path between two trees.
+///
+/// `old_hash` is `None` when the file was added and `new_hash` is `None` when it was deleted.
+/// When rename detection finds where a file came from, `source` holds the original path and
+/// `old_hash` is the hash of the file at that path.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct FileChange {
+    pub path: String,
+    pub old_hash: Option<String>,
+    pub new_hash: Option<String>,
+    pub source: Option<ChangeSource>,
+}
+
+/// The path a file was renamed or copied from.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ChangeSource {
+    pub path: String,
+    /// How much of the content both versions share, as a percentage.
+    pub similarity: u32,
+    /// `true` if the original path still exists, so the file was copied instead of renamed.
+    pub copied: bool,
+}
+
+impl FileChange {
+    /// Returns the status of the change as shown by `--name-status`: `A`, `D` or `M`, or `R`
+    /// and `C` followed by the similarity for renames and copies.
+    pub fn status(&self) -> String {
+        match (&self.source, &self.old_hash, &self.new_hash) {
+            (Some(source), _, _) => {
+                let letter = if source.copied { 'C' } else { 'R' };
+                format!("{}{:03}", letter, source.similarity)
+            }
+            (None, None, _) => "A".to_string(),
+            (None, _, None) => "D".to_string(),
+            _ => "M".to_string(),
+        }
+    }
+
+    /// Returns the path before the change, which differs from `path` for renames and copies.
+    pub fn old_path(&self) -> &str {
+        match &self.source {
+            Some(source) => &source.path,
+            None => &self.path,
+        }
+    }
+
+    /// Returns the path as shown by `--stat` and `--numstat`: `old => new` for renames and
+    /// copies.
+    pub fn display_path(&self) -> String {
+        match &self.source {
+            Some(source) => format!("{} => {}", source.path, self.path),
+            None => self.path.clone(),
+        }
+    }
+}
+
+/// How renamed and copied files are detected, selected with `-M` and `-C`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RenameOptions {
+    /// The minimum similarity, as a percentage, of a rename or copy.
+    pub threshold: u32,
+    /// Whether added files are also compared with the files that were kept, to detect copies.
+    pub copies: bool,
+}
+
+impl Default for RenameOptions {
+    fn default() -> Self {
+        Self {
+            threshold: DEFAULT_RENAME_THRESHOLD,
+            copies: false,
+        }
+    }
+}
+
+impl RenameOptions {
+    /// Parses `-M[<n>]`, `--find-renames[=<n>]`, `-C[<n>]` and `--find-copies[=<n>]`.
+    ///
+    /// Like git, `<n>` is a percentage when it ends with `%` and the decimal part of a fraction
+    /// otherwise, so `-M9`, `-M90` and `-M90%` all mean 90%.
+    ///
+    /// # Returns
+    ///
+    /// Returns `None` if the option is not one of the above or the threshold is invalid.
+    ///
+    pub fn parse(option: &str) -> Option<Self> {
+        let (copies, threshold) = if let Some(value) = option.strip_prefix("--find-renames") {
+            (
+                false,
+                value
+                    .strip_prefix('=')
+                    .or(Some(value).filter(|v| v.is_empty()))?,
+            )
+        } else if let Some(value) = option.strip_prefix("--find-copies") {
+            (
+                true,
+                value
+                    .strip_prefix('=')
+                    .or(Some(value).filter(|v| v.is_empty()))?,
+            )
+        } else if let Some(value) = option.strip_prefix("-M") {
+            (false, value)
+        } else if let Some(value) = option.strip_prefix("-C") {
+            (true, value)
+        } else {
+            return None;
+        };
+        Some(Self {
+            threshold: parse_similarity_threshold(threshold)?,
+            copies,
+        })
+    }
+}
+
+/// Parses the threshold of `-M` and `-C`, as described in `RenameOptions::parse`.
+fn parse_similarity_threshold(value: &str) -> Option<u32> {
+    if value.is_empty() {
+        return Some(DEFAULT_RENAME_THRESHOLD);
+    }
+    let (digits, percentage) = match value.strip_suffix('%') {
+        Some(digits) => (digits, true),
+        None => (digits_of(value)?, false),
+    };
+    let number: u64 = digits.parse().ok()?;
+    let threshold = if percentage {
+        number
+    } else {
+        number * 100 / 10u64.checked_pow(digits.len() as u32)?
+    };
+    u32::try_from(threshold)
+        .ok()
+        .filter(|threshold| *threshold <= 100)
+}
+
+/// Returns the value if it only has ASCII digits.
+fn digits_of(value: &str) -> Option<&str> {
+    Some(value).filter(|value| value.chars().all(|c| c.is_ascii_digit()))
+}
+
 /// Prints the difference between two text files using line-by-line comparison.
 ///
 /// This function reads the contents of two text files specified by their file paths and compares them
@@ -231,3 +396,1407 @@ pub fn return_object_diff_string(
     }
     Ok(output.join(""))
 }
+
+/// Splits a text into lines, keeping the line terminators.
+fn split_lines_inclusive(content: &str) -> Vec<&str> {
+    content.split_inclusive('\n').collect()
+}
+
+/// Computes the line-by-line edit script that turns `old` into `new`.
+///
+/// The script is based on the longest common subsequence of both texts, the same
+/// approach used by `print_diff`, but it is computed iteratively so it can be used on
+/// whole files.
+///
+/// # Arguments
+///
+/// * `old`: The original content.
+/// * `new`: The modified content.
+///
+/// # Returns
+///
+/// A vector of `DiffLine` in the order they should be read.
+///
+pub fn diff_lines<'a>(old: &'a str, new: &'a str) -> Vec<DiffLine<'a>> {
+    let a = split_lines_inclusive(old);
+    let b = split_lines_inclusive(new);
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut result = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            result.push(DiffLine::Context(a[i]));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            result.push(DiffLine::Removed(a[i]));
+            i += 1;
+        } else {
+            result.push(DiffLine::Added(b[j]));
+            j += 1;
+        }
+    }
+    result.extend(a[i..].iter().map(|line| DiffLine::Removed(line)));
+    result.extend(b[j..].iter().map(|line| DiffLine::Added(line)));
+    result
+}
+
+/// Formats the range of a hunk header the way git does: the count is omitted when it is 1.
+fn format_hunk_range(start: usize, count: usize) -> String {
+    if count == 1 {
+        format!("{}", start)
+    } else {
+        format!("{},{}", start, count)
+    }
+}
+
+/// Writes a single diff line with its prefix, marking lines without a final newline.
+fn push_diff_line(output: &mut String, prefix: &str, line: &str) {
+    output.push_str(prefix);
+    output.push_str(line);
+    if !line.ends_with('\n') {
+        output.push_str("\n\\ No newline at end of file\n");
+    }
+}
+
+/// Builds the hunks (`@@ ... @@` sections) of a unified diff between two texts.
+///
+/// # Arguments
+///
+/// * `old`: The original content.
+/// * `new`: The modified content.
+///
+/// # Returns
+///
+/// The hunks as a single string. It is empty if both texts are equal.
+///
+pub fn unified_hunks(old: &str, new: &str) -> String {
+    let lines = diff_lines(old, new);
+    let mut old_positions = Vec::with_capacity(lines.len() + 1);
+    let mut new_positions = Vec::with_capacity(lines.len() + 1);
+    let (mut old_pos, mut new_pos) = (0, 0);
+    for line in &lines {
+        old_positions.push(old_pos);
+        new_positions.push(new_pos);
+        match line {
+            DiffLine::Context(_) => {
+                old_pos += 1;
+                new_pos += 1;
+            }
+            DiffLine::Removed(_) => old_pos += 1,
+            DiffLine::Added(_) => new_pos += 1,
+        }
+    }
+    old_positions.push(old_pos);
+    new_positions.push(new_pos);
+
+    let mut output = String::new();
+    let mut index = 0;
+    while index < lines.len() {
+        if let DiffLine::Context(_) = lines[index] {
+            index += 1;
+            continue;
+        }
+        let start = index.saturating_sub(CONTEXT_LINES);
+        let mut last_change = index;
+        let mut cursor = index;
+        while cursor < lines.len() {
+            if let DiffLine::Context(_) = lines[cursor] {
+                let run_end = lines[cursor..]
+                    .iter()
+                    .position(|line| !matches!(line, DiffLine::Context(_)))
+                    .map(|offset| cursor + offset);
+                match run_end {
+                    Some(end) if end - cursor <= 2 * CONTEXT_LINES => cursor = end,
+                    _ => break,
+                }
+            } else {
+                last_change = cursor;
+                cursor += 1;
+            }
+        }
+        let end = (last_change + 1 + CONTEXT_LINES).min(lines.len());
+
+        let old_count = old_positions[end] - old_positions[start];
+        let new_count = new_positions[end] - new_positions[start];
+        let old_start = if old_count == 0 {
+            old_positions[start]
+        } else {
+            old_positions[start] + 1
+        };
+        let new_start = if new_count == 0 {
+            new_positions[start]
+        } else {
+            new_positions[start] + 1
+        };
+        output.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            format_hunk_range(old_start, old_count),
+            format_hunk_range(new_start, new_count)
+        ));
+        for line in &lines[start..end] {
+            match line {
+                DiffLine::Context(text) => push_diff_line(&mut output, " ", text),
+                DiffLine::Removed(text) => push_diff_line(&mut output, "-", text),
+                DiffLine::Added(text) => push_diff_line(&mut output, "+", text),
+            }
+        }
+        index = end;
+    }
+    output
+}
+
+/// Builds the git style patch of a single file.
+///
+/// # Arguments
+///
+/// * `path`: The path of the file, relative to the working directory.
+/// * `old`: The original content, or `None` if the file is new.
+/// * `new`: The new content, or `None` if the file was deleted.
+///
+/// # Returns
+///
+/// The patch, starting with the `diff --git` line. It is empty if nothing changed.
+///
+pub fn file_patch(path: &str, old: Option<&str>, new: Option<&str>) -> String {
+    let hunks = unified_hunks(old.unwrap_or(""), new.unwrap_or(""));
+    if hunks.is_empty() && old.is_some() == new.is_some() {
+        return String::new();
+    }
+    let mut output = format!("diff --git a/{path} b/{path}\n", path = path);
+    match (old, new) {
+        (None, _) => {
+            output.push_str("new file mode 100644\n");
+            output.push_str(&format!("--- /dev/null\n+++ b/{}\n", path));
+        }
+        (_, None) => {
+            output.push_str("deleted file mode 100644\n");
+            output.push_str(&format!("--- a/{}\n+++ /dev/null\n", path));
+        }
+        _ => output.push_str(&format!("--- a/{path}\n+++ b/{path}\n", path = path)),
+    }
+    output.push_str(&hunks);
+    output
+}
+
+/// Lists the files that differ between two trees, sorted by path.
+///
+/// # Arguments
+///
+/// * `old_tree`: The original tree, or `None` to compare against an empty tree.
+/// * `new_tree`: The modified tree, or `None` to compare against an empty tree.
+///
+/// # Returns
+///
+/// A vector with one `FileChange` per added, deleted or modified file.
+///
+pub fn get_tree_changes(old_tree: Option<&Tree>, new_tree: Option<&Tree>) -> Vec<FileChange> {
+    let mut files: BTreeMap<String, (Option<String>, Option<String>)> = BTreeMap::new();
+    if let Some(tree) = old_tree {
+        for (path, hash) in tree.squash_tree_into_vec("") {
+            files.entry(path).or_default().0 = Some(hash);
+        }
+    }
+    if let Some(tree) = new_tree {
+        for (path, hash) in tree.squash_tree_into_vec("") {
+            files.entry(path).or_default().1 = Some(hash);
+        }
+    }
+    files
+        .into_iter()
+        .filter(|(_, (old_hash, new_hash))| old_hash != new_hash)
+        .map(|(path, (old_hash, new_hash))| FileChange {
+            path,
+            old_hash,
+            new_hash,
+            source: None,
+        })
+        .collect()
+}
+
+/// Computes how similar two texts are, as the percentage of lines of the longest one that
+/// both share.
+///
+/// # Returns
+///
+/// A number from 0 to 100, where 100 means the texts are equal.
+///
+pub fn similarity_index(old: &str, new: &str) -> u32 {
+    let lines = diff_lines(old, new);
+    let common = lines
+        .iter()
+        .filter(|line| matches!(line, DiffLine::Context(_)))
+        .count();
+    let old_lines = lines.len() - count_added(&lines);
+    let new_lines = common + count_added(&lines);
+    let longest = old_lines.max(new_lines);
+    if longest == 0 {
+        return 100;
+    }
+    (common * 100 / longest) as u32
+}
+
+/// Counts the added lines of an edit script.
+fn count_added(lines: &[DiffLine]) -> usize {
+    lines
+        .iter()
+        .filter(|line| matches!(line, DiffLine::Added(_)))
+        .count()
+}
+
+/// Pairs added files with deleted files (and, if enabled, kept files) they are similar to,
+/// turning them into renames and copies.
+///
+/// Every added file is paired with the most similar candidate whose similarity reaches the
+/// threshold; identical files are always paired first. A deleted file is the source of at
+/// most one rename, and its deletion is then dropped from the changes. Copies can come from
+/// any file of the old tree that was not deleted.
+///
+/// # Arguments
+///
+/// * `changes`: The changes between both trees, as returned by `get_tree_changes`.
+/// * `old_tree`: The original tree, where copies come from.
+/// * `git_dir`: The path to the git directory where the blobs are stored.
+/// * `options`: The similarity threshold and whether copies are detected.
+///
+/// # Errors
+///
+/// Returns an error if a blob cannot be read.
+///
+pub fn detect_renames(
+    changes: Vec<FileChange>,
+    old_tree: Option<&Tree>,
+    git_dir: &str,
+    options: RenameOptions,
+) -> io::Result<Vec<FileChange>> {
+    let deleted: Vec<(String, String)> = changes
+        .iter()
+        .filter_map(|change| match (&change.old_hash, &change.new_hash) {
+            (Some(hash), None) => Some((change.path.clone(), hash.clone())),
+            _ => None,
+        })
+        .collect();
+    let kept: Vec<(String, String)> = match old_tree {
+        Some(tree) if options.copies => tree
+            .squash_tree_into_vec("")
+            .into_iter()
+            .filter(|(path, _)| !deleted.iter().any(|(deleted, _)| deleted == path))
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    let mut contents: HashMap<String, String> = HashMap::new();
+    let mut read = |hash: &str| -> io::Result<String> {
+        if !contents.contains_key(hash) {
+            contents.insert(hash.to_string(), cat_file_return_content(hash, git_dir)?);
+        }
+        Ok(contents[hash].clone())
+    };
+    let mut renamed: HashSet<String> = HashSet::new();
+    let mut result = Vec::new();
+    for mut change in changes {
+        let new_hash = match (&change.old_hash, &change.new_hash) {
+            (None, Some(hash)) => hash.clone(),
+            _ => {
+                result.push(change);
+                continue;
+            }
+        };
+        let candidates = deleted
+            .iter()
+            .filter(|(path, _)| !renamed.contains(path))
+            .map(|candidate| (candidate, false))
+            .chain(kept.iter().map(|candidate| (candidate, true)));
+        let mut best: Option<(&(String, String), bool, u32)> = None;
+        for (candidate, copied) in candidates {
+            let (_, hash) = candidate;
+            let similarity = if *hash == new_hash {
+                100
+            } else {
+                similarity_index(&read(hash)?, &read(&new_hash)?)
+            };
+            if similarity >= options.threshold
+                && best.is_none_or(|(_, _, best_similarity)| similarity > best_similarity)
+            {
+                best = Some((candidate, copied, similarity));
+            }
+            if similarity == 100 {
+                break;
+            }
+        }
+        if let Some(((path, hash), copied, similarity)) = best {
+            if !copied {
+                renamed.insert(path.clone());
+            }
+            change.old_hash = Some(hash.clone());
+            change.source = Some(ChangeSource {
+                path: path.clone(),
+                similarity,
+                copied,
+            });
+        }
+        result.push(change);
+    }
+    result.retain(|change| change.new_hash.is_some() || !renamed.contains(&change.path));
+    Ok(result)
+}
+
+/// Lists the files that differ between two trees, detecting renames and copies if requested.
+///
+/// # Arguments
+///
+/// * `old_tree`: The original tree, or `None` to compare against an empty tree.
+/// * `new_tree`: The modified tree, or `None` to compare against an empty tree.
+/// * `git_dir`: The path to the git directory where the blobs are stored.
+/// * `renames`: How renames and copies are detected, or `None` to list them as additions
+///   and deletions.
+///
+/// # Errors
+///
+/// Returns an error if a blob cannot be read while detecting renames.
+///
+pub fn get_tree_changes_with_renames(
+    old_tree: Option<&Tree>,
+    new_tree: Option<&Tree>,
+    git_dir: &str,
+    renames: Option<RenameOptions>,
+) -> io::Result<Vec<FileChange>> {
+    let changes = get_tree_changes(old_tree, new_tree);
+    match renames {
+        Some(options) => detect_renames(changes, old_tree, git_dir, options),
+        None => Ok(changes),
+    }
+}
+
+/// Reads the content of the blobs involved in a change.
+fn read_change_contents(
+    change: &FileChange,
+    git_dir: &str,
+) -> io::Result<(Option<String>, Option<String>)> {
+    let old = match &change.old_hash {
+        Some(hash) => Some(cat_file_return_content(hash, git_dir)?),
+        None => None,
+    };
+    let new = match &change.new_hash {
+        Some(hash) => Some(cat_file_return_content(hash, git_dir)?),
+        None => None,
+    };
+    Ok((old, new))
+}
+
+/// Writes the patch of every change to the output.
+///
+/// # Arguments
+///
+/// * `changes`: The changes to print, usually obtained with `get_tree_changes`.
+/// * `git_dir`: The path to the git directory where the blobs are stored.
+/// * `output`: Where the patch is written.
+///
+/// # Errors
+///
+/// Returns an error if a blob cannot be read or the output cannot be written.
+///
+pub fn write_patch(
+    changes: &[FileChange],
+    git_dir: &str,
+    output: &mut impl Write,
+) -> io::Result<()> {
+    let root_dir = utils::get_working_dir(git_dir)?;
+    for change in changes {
+        if is_binary_change(change, git_dir, &root_dir)? {
+            output.write_all(binary_file_patch(change).as_bytes())?;
+            continue;
+        }
+        let (old, new) = read_change_contents(change, git_dir)?;
+        let patch = match &change.source {
+            Some(source) => moved_file_patch(source, &change.path, old.as_deref(), new.as_deref()),
+            None => file_patch(&change.path, old.as_deref(), new.as_deref()),
+        };
+        output.write_all(patch.as_bytes())?;
+    }
+    Ok(())
+}
+
+/// Builds the patch of a binary file, which only tells that it changed, like git does.
+///
+/// # Arguments
+///
+/// * `change`: The change of the file.
+///
+pub fn binary_file_patch(change: &FileChange) -> String {
+    let mut output = match &change.source {
+        Some(source) => moved_file_patch(source, &change.path, None, None),
+        None => format!("diff --git a/{path} b/{path}\n", path = change.path),
+    };
+    match (&change.old_hash, &change.new_hash) {
+        (None, _) => output.push_str("new file mode 100644\n"),
+        (_, None) => output.push_str("deleted file mode 100644\n"),
+        (Some(old), Some(new)) if old == new => return output,
+        _ => {}
+    }
+    let old_name = match &change.old_hash {
+        Some(_) => format!("a/{}", change.old_path()),
+        None => "/dev/null".to_string(),
+    };
+    let new_name = match &change.new_hash {
+        Some(_) => format!("b/{}", change.path),
+        None => "/dev/null".to_string(),
+    };
+    output.push_str(&format!(
+        "Binary files {} and {} differ\n",
+        old_name, new_name
+    ));
+    output
+}
+
+/// Checks whether the changes of a file are shown as a binary change, because the attributes of
+/// the file say so or because one of its versions is binary.
+fn is_binary_change(change: &FileChange, git_dir: &str, root_dir: &str) -> io::Result<bool> {
+    let mut contents = Vec::new();
+    for hash in [&change.old_hash, &change.new_hash].into_iter().flatten() {
+        contents.push(cat_file_return_bytes(hash, git_dir)?);
+    }
+    let contents: Vec<&[u8]> = contents.iter().map(Vec::as_slice).collect();
+    Ok(Attributes::load(root_dir, &change.path)?.is_binary_for_diff(&contents))
+}
+
+/// Builds the patch of a renamed or copied file, like git does.
+///
+/// The header names both paths and the similarity; the hunks are only present if the
+/// content changed.
+///
+/// # Arguments
+///
+/// * `source`: Where the file was renamed or copied from.
+/// * `path`: The path of the file after the change.
+/// * `old`: The content of the file at the original path.
+/// * `new`: The content of the file after the change.
+///
+pub fn moved_file_patch(
+    source: &ChangeSource,
+    path: &str,
+    old: Option<&str>,
+    new: Option<&str>,
+) -> String {
+    let operation = if source.copied { "copy" } else { "rename" };
+    let mut output = format!(
+        "diff --git a/{old_path} b/{path}\nsimilarity index {similarity}%\n{operation} from {old_path}\n{operation} to {path}\n",
+        old_path = source.path,
+        path = path,
+        similarity = source.similarity,
+        operation = operation
+    );
+    let hunks = unified_hunks(old.unwrap_or(""), new.unwrap_or(""));
+    if !hunks.is_empty() {
+        output.push_str(&format!("--- a/{}\n+++ b/{}\n", source.path, path));
+        output.push_str(&hunks);
+    }
+    output
+}
+
+/// Selects how the changes of a commit are described by `log` and `show`.
+///
+/// Several descriptions can be combined; they are written in the order of the fields.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DiffFormat {
+    /// Every changed path preceded by `A`, `M` or `D` (`--name-status`).
+    pub name_status: bool,
+    /// Inserted and deleted lines of every path, separated by tabs (`--numstat`).
+    pub numstat: bool,
+    /// A diffstat with bars and a summary line (`--stat`).
+    pub stat: bool,
+    /// The unified patch (`-p`).
+    pub patch: bool,
+    /// How renames and copies are detected (`-M`, `-C`), or `None` to show them as additions
+    /// and deletions.
+    pub renames: Option<RenameOptions>,
+}
+
+impl DiffFormat {
+    /// Updates the format with a command line option.
+    ///
+    /// Recognizes `-p`, `-u`, `--patch`, `--stat`, `--numstat`, `--name-status`, `-s` or
+    /// `--no-patch`, which hides every description, and the rename detection options parsed
+    /// by `RenameOptions::parse`.
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the option was recognized.
+    ///
+    pub fn parse_option(&mut self, option: &str) -> bool {
+        match option {
+            "-p" | "-u" | "--patch" => self.patch = true,
+            "--stat" => self.stat = true,
+            "--numstat" => self.numstat = true,
+            "--name-status" => self.name_status = true,
+            "-s" | "--no-patch" => {
+                *self = Self {
+                    renames: self.renames,
+                    ..Self::default()
+                }
+            }
+            _ => match RenameOptions::parse(option) {
+                Some(renames) => self.renames = Some(renames),
+                None => return false,
+            },
+        }
+        true
+    }
+
+    /// Returns `true` if no description of the changes was selected.
+    pub fn is_empty(&self) -> bool {
+        !(self.name_status || self.numstat || self.stat || self.patch)
+    }
+}
+
+/// Writes the status letter and path of every change, like `git diff --name-status`.
+///
+/// Renames and copies are followed by the original path and the new one.
+///
+/// # Arguments
+///
+/// * `changes`: The changes to list.
+/// * `output`: Where the list is written.
+///
+/// # Errors
+///
+/// Returns an error if the output cannot be written.
+///
+pub fn write_name_status(changes: &[FileChange], output: &mut impl Write) -> io::Result<()> {
+    for change in changes {
+        match &change.source {
+            Some(source) => writeln!(
+                output,
+                "{}\t{}\t{}",
+                change.status(),
+                source.path,
+                change.path
+            )?,
+            None => writeln!(output, "{}\t{}", change.status(), change.path)?,
+        }
+    }
+    Ok(())
+}
+
+/// Writes the inserted and deleted lines of every change, like `git diff --numstat`.
+///
+/// # Arguments
+///
+/// * `changes`: The changes to count.
+/// * `git_dir`: The path to the git directory where the blobs are stored.
+/// * `output`: Where the counts are written.
+///
+/// # Errors
+///
+/// Returns an error if a blob cannot be read or the output cannot be written.
+///
+pub fn write_numstat(
+    changes: &[FileChange],
+    git_dir: &str,
+    output: &mut impl Write,
+) -> io::Result<()> {
+    for (path, insertions, deletions) in count_changed_lines(changes, git_dir)? {
+        writeln!(output, "{}\t{}\t{}", insertions, deletions, path)?;
+    }
+    Ok(())
+}
+
+/// Writes the changes in every description selected by the format.
+///
+/// When the patch follows another description, they are separated by a blank line.
+///
+/// # Arguments
+///
+/// * `changes`: The changes to describe.
+/// * `git_dir`: The path to the git directory where the blobs are stored.
+/// * `format`: The descriptions to write.
+/// * `output`: Where the descriptions are written.
+///
+/// # Errors
+///
+/// Returns an error if a blob cannot be read or the output cannot be written.
+///
+pub fn write_changes(
+    changes: &[FileChange],
+    git_dir: &str,
+    format: DiffFormat,
+    output: &mut impl Write,
+) -> io::Result<()> {
+    if changes.is_empty() {
+        return Ok(());
+    }
+    if format.name_status {
+        write_name_status(changes, output)?;
+    }
+    if format.numstat {
+        write_numstat(changes, git_dir, output)?;
+    }
+    if format.stat {
+        write_stat(changes, git_dir, output)?;
+    }
+    if format.patch {
+        if format.name_status || format.numstat || format.stat {
+            writeln!(output)?;
+        }
+        write_patch(changes, git_dir, output)?;
+    }
+    Ok(())
+}
+
+/// A row of a combined diff against several parents.
+///
+/// `Line` is a line of the result, with one flag per parent telling whether the line was added
+/// with respect to that parent. `Lost` is a line that some of the parents had and the result does
+/// not, with one flag per parent telling which of them lost it.
+enum CombinedRow<'a> {
+    Line(&'a str, Vec<bool>),
+    Lost(&'a str, Vec<bool>),
+}
+
+impl CombinedRow<'_> {
+    /// Returns whether the row is an unchanged line of every parent.
+    fn is_context(&self) -> bool {
+        matches!(self, CombinedRow::Line(_, added) if !added.contains(&true))
+    }
+
+    /// Returns whether the row consumes a line of the given parent.
+    fn in_parent(&self, parent: usize) -> bool {
+        match self {
+            CombinedRow::Line(_, added) => !added[parent],
+            CombinedRow::Lost(_, lost) => lost[parent],
+        }
+    }
+
+    /// Returns whether the row is a change with respect to the given parent.
+    fn changes_parent(&self, parent: usize) -> bool {
+        match self {
+            CombinedRow::Line(_, flags) | CombinedRow::Lost(_, flags) => flags[parent],
+        }
+    }
+}
+
+/// Computes the rows of a combined diff between several parents and a result.
+///
+/// Lines lost from more than one parent at the same position are merged into a single row when
+/// their contents match, so they are printed once with a `-` in every column that lost them.
+fn combined_rows<'a>(parents: &[&'a str], result: &'a str) -> Vec<CombinedRow<'a>> {
+    let result_lines = split_lines_inclusive(result);
+    let mut added = vec![vec![false; parents.len()]; result_lines.len()];
+    let mut lost: Vec<Vec<(&str, Vec<bool>)>> = vec![vec![]; result_lines.len() + 1];
+    for (parent, content) in parents.iter().enumerate() {
+        let mut position = 0;
+        let mut cursor = 0;
+        for line in diff_lines(content, result) {
+            match line {
+                DiffLine::Context(_) => {
+                    position += 1;
+                    cursor = 0;
+                }
+                DiffLine::Added(_) => {
+                    added[position][parent] = true;
+                    position += 1;
+                    cursor = 0;
+                }
+                DiffLine::Removed(text) => {
+                    let slot = &mut lost[position];
+                    let existing = slot[cursor..]
+                        .iter()
+                        .position(|(lost_text, flags)| *lost_text == text && !flags[parent]);
+                    match existing {
+                        Some(offset) => {
+                            slot[cursor + offset].1[parent] = true;
+                            cursor += offset + 1;
+                        }
+                        None => {
+                            let mut flags = vec![false; parents.len()];
+                            flags[parent] = true;
+                            slot.push((text, flags));
+                            cursor = slot.len();
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    let mut rows = Vec::new();
+    for (position, slot) in lost.into_iter().enumerate() {
+        rows.extend(
+            slot.into_iter()
+                .map(|(text, flags)| CombinedRow::Lost(text, flags)),
+        );
+        if position < result_lines.len() {
+            rows.push(CombinedRow::Line(
+                result_lines[position],
+                added[position].clone(),
+            ));
+        }
+    }
+    rows
+}
+
+/// Builds the hunks (`@@@ ... @@@` sections) of a combined diff between several parents and a
+/// result, in the dense form used by `git show` for merge commits.
+///
+/// Every line is prefixed with one column per parent. Hunks where the result matches one of
+/// the parents are left out, since the change was simply taken from the other side of the merge.
+///
+/// # Arguments
+///
+/// * `parents`: The content of the file in each parent.
+/// * `result`: The content of the file in the merge result.
+///
+/// # Returns
+///
+/// The hunks as a single string. It is empty if there is nothing interesting to show.
+///
+pub fn combined_hunks(parents: &[&str], result: &str) -> String {
+    let rows = combined_rows(parents, result);
+    let mut parent_positions = vec![Vec::with_capacity(rows.len() + 1); parents.len()];
+    let mut result_positions = Vec::with_capacity(rows.len() + 1);
+    let mut parent_pos = vec![0; parents.len()];
+    let mut result_pos = 0;
+    for row in &rows {
+        for (parent, positions) in parent_positions.iter_mut().enumerate() {
+            positions.push(parent_pos[parent]);
+            if row.in_parent(parent) {
+                parent_pos[parent] += 1;
+            }
+        }
+        result_positions.push(result_pos);
+        if let CombinedRow::Line(..) = row {
+            result_pos += 1;
+        }
+    }
+    for (parent, positions) in parent_positions.iter_mut().enumerate() {
+        positions.push(parent_pos[parent]);
+    }
+    result_positions.push(result_pos);
+
+    let markers = "@".repeat(parents.len() + 1);
+    let mut output = String::new();
+    let mut index = 0;
+    while index < rows.len() {
+        if rows[index].is_context() {
+            index += 1;
+            continue;
+        }
+        let start = index.saturating_sub(CONTEXT_LINES);
+        let mut last_change = index;
+        let mut cursor = index;
+        while cursor < rows.len() {
+            if rows[cursor].is_context() {
+                let run_end = rows[cursor..]
+                    .iter()
+                    .position(|row| !row.is_context())
+                    .map(|offset| cursor + offset);
+                match run_end {
+                    Some(end) if end - cursor <= 2 * CONTEXT_LINES => cursor = end,
+                    _ => break,
+                }
+            } else {
+                last_change = cursor;
+                cursor += 1;
+            }
+        }
+        let end = (last_change + 1 + CONTEXT_LINES).min(rows.len());
+        index = end;
+
+        let interesting = (0..parents.len()).all(|parent| {
+            rows[start..end]
+                .iter()
+                .any(|row| row.changes_parent(parent))
+        });
+        if !interesting {
+            continue;
+        }
+
+        let mut ranges: Vec<String> = parent_positions
+            .iter()
+            .map(|positions| hunk_range(positions, start, end))
+            .map(|range| format!("-{}", range))
+            .collect();
+        ranges.push(format!("+{}", hunk_range(&result_positions, start, end)));
+        output.push_str(&format!("{} {} {}\n", markers, ranges.join(" "), markers));
+        for row in &rows[start..end] {
+            let (text, prefix) = match row {
+                CombinedRow::Line(text, added) => (
+                    text,
+                    added
+                        .iter()
+                        .map(|added| if *added { '+' } else { ' ' })
+                        .collect::<String>(),
+                ),
+                CombinedRow::Lost(text, lost) => (
+                    text,
+                    lost.iter()
+                        .map(|lost| if *lost { '-' } else { ' ' })
+                        .collect::<String>(),
+                ),
+            };
+            push_diff_line(&mut output, &prefix, text);
+        }
+    }
+    output
+}
+
+/// Formats the range covered by the rows `start..end` given the line position before each row.
+fn hunk_range(positions: &[usize], start: usize, end: usize) -> String {
+    let count = positions[end] - positions[start];
+    if count == 0 {
+        format_hunk_range(positions[start], count)
+    } else {
+        format_hunk_range(positions[start] + 1, count)
+    }
+}
+
+/// Builds the combined patch of a single file of a merge commit.
+///
+/// # Arguments
+///
+/// * `path`: The path of the file, relative to the working directory.
+/// * `parents`: The content of the file in each parent, or `None` where it did not exist.
+/// * `result`: The content of the file in the merge result, or `None` if it was deleted.
+///
+/// # Returns
+///
+/// The patch, starting with the `diff --cc` line. It is empty if there is nothing interesting
+/// to show.
+///
+pub fn combined_file_patch(path: &str, parents: &[Option<&str>], result: Option<&str>) -> String {
+    let contents: Vec<&str> = parents
+        .iter()
+        .map(|content| content.unwrap_or(""))
+        .collect();
+    let hunks = combined_hunks(&contents, result.unwrap_or(""));
+    if hunks.is_empty() {
+        return String::new();
+    }
+    let mut output = format!("diff --cc {}\n", path);
+    match result {
+        Some(_) => output.push_str(&format!("--- a/{path}\n+++ b/{path}\n", path = path)),
+        None => output.push_str(&format!("--- a/{}\n+++ /dev/null\n", path)),
+    }
+    output.push_str(&hunks);
+    output
+}
+
+/// Writes the combined patch of a merge commit, like `git show` does for merges.
+///
+/// Only the files whose content in the result differs from every parent are shown.
+///
+/// # Arguments
+///
+/// * `parent_trees`: The trees of the parents of the merge.
+/// * `result_tree`: The tree of the merge commit.
+/// * `git_dir`: The path to the git directory where the blobs are stored.
+/// * `output`: Where the patch is written.
+///
+/// # Errors
+///
+/// Returns an error if a blob cannot be read or the output cannot be written.
+///
+pub fn write_combined_patch(
+    parent_trees: &[Tree],
+    result_tree: &Tree,
+    git_dir: &str,
+    output: &mut impl Write,
+) -> io::Result<()> {
+    let mut files: BTreeMap<String, (Vec<Option<String>>, Option<String>)> = BTreeMap::new();
+    for (parent, tree) in parent_trees.iter().enumerate() {
+        for (path, hash) in tree.squash_tree_into_vec("") {
+            files
+                .entry(path)
+                .or_insert_with(|| (vec![None; parent_trees.len()], None))
+                .0[parent] = Some(hash);
+        }
+    }
+    for (path, hash) in result_tree.squash_tree_into_vec("") {
+        files
+            .entry(path)
+            .or_insert_with(|| (vec![None; parent_trees.len()], None))
+            .1 = Some(hash);
+    }
+    for (path, (parent_hashes, result_hash)) in files {
+        if parent_hashes.contains(&result_hash) {
+            continue;
+        }
+        let mut parents = Vec::new();
+        for hash in &parent_hashes {
+            parents.push(match hash {
+                Some(hash) => Some(cat_file_return_content(hash, git_dir)?),
+                None => None,
+            });
+        }
+        let result = match &result_hash {
+            Some(hash) => Some(cat_file_return_content(hash, git_dir)?),
+            None => None,
+        };
+        let parents: Vec<Option<&str>> = parents.iter().map(|content| content.as_deref()).collect();
+        let patch = combined_file_patch(&path, &parents, result.as_deref());
+        output.write_all(patch.as_bytes())?;
+    }
+    Ok(())
+}
+
+/// Counts the inserted and deleted lines of every change.
+///
+/// # Returns
+///
+/// A vector of `(path, insertions, deletions)` in the same order as `changes`, where the path
+/// of a rename or copy is shown as `old => new`.
+///
+pub fn count_changed_lines(
+    changes: &[FileChange],
+    git_dir: &str,
+) -> io::Result<Vec<(String, usize, usize)>> {
+    let mut result = Vec::new();
+    for change in changes {
+        let (old, new) = read_change_contents(change, git_dir)?;
+        let old = old.unwrap_or_default();
+        let new = new.unwrap_or_default();
+        let lines = diff_lines(&old, &new);
+        let insertions = lines
+            .iter()
+            .filter(|line| matches!(line, DiffLine::Added(_)))
+            .count();
+        let deletions = lines
+            .iter()
+            .filter(|line| matches!(line, DiffLine::Removed(_)))
+            .count();
+        result.push((change.display_path(), insertions, deletions));
+    }
+    Ok(result)
+}
+
+/// Writes a diffstat of the changes, like `git diff --stat`.
+///
+/// # Arguments
+///
+/// * `changes`: The changes to summarize.
+/// * `git_dir`: The path to the git directory where the blobs are stored.
+/// * `output`: Where the diffstat is written.
+///
+/// # Errors
+///
+/// Returns an error if a blob cannot be read or the output cannot be written.
+///
+pub fn write_stat(
+    changes: &[FileChange],
+    git_dir: &str,
+    output: &mut impl Write,
+) -> io::Result<()> {
+    let counts = count_changed_lines(changes, git_dir)?;
+    let width = counts
+        .iter()
+        .map(|(path, _, _)| path.len())
+        .max()
+        .unwrap_or(0);
+    let (mut total_insertions, mut total_deletions) = (0, 0);
+    for (path, insertions, deletions) in &counts {
+        writeln!(
+            output,
+            " {:<width$} | {} {}{}",
+            path,
+            insertions + deletions,
+            "+".repeat(*insertions),
+            "-".repeat(*deletions),
+            width = width
+        )?;
+        total_insertions += insertions;
+        total_deletions += deletions;
+    }
+    writeln!(
+        output,
+        " {} file{} changed, {} insertion{}(+), {} deletion{}(-)",
+        counts.len(),
+        if counts.len() == 1 { "" } else { "s" },
+        total_insertions,
+        if total_insertions == 1 { "" } else { "s" },
+        total_deletions,
+        if total_deletions == 1 { "" } else { "s" },
+    )
+}
+
+/// Logs the 'git diff' command with the specified revisions and paths.
+///
+/// This function logs the 'git diff' command with the provided revisions and paths to a file
+/// named 'logger_commands.txt'.
+///
+/// # Arguments
+///
+/// * `revisions` - The revisions given to the command.
+/// * `paths` - The paths the diff is limited to.
+///
+/// # Errors
+///
+/// Returns an `io::Result` indicating whether the operation was successful.
+///
+fn log_diff(revisions: &[String], paths: &[String]) -> io::Result<()> {
+    let log_file_path = LOGGER_COMMANDS_FILE;
+    let mut logger = Logger::new(log_file_path)?;
+
+    let full_message = format!(
+        "Command 'git diff': Revisions '{}', Paths '{}', {}",
+        revisions.join(" "),
+        paths.join(" "),
+        get_current_time()
+    );
+    logger.write_all(full_message.as_bytes())?;
+    logger.flush()?;
+    Ok(())
+}
+
+/// Loads the tree of a revision.
+fn load_revision_tree(git_dir: &str, revision: &str) -> io::Result<Tree> {
+    let commit = utils::resolve_revision(git_dir, revision)?;
+    tree_handler::load_tree_from_commit(&commit, git_dir)
+}
+
+/// Shows the changes between commits, the index and the working tree, like `git diff`.
+///
+/// - Without revisions, the working tree is compared with the index.
+/// - With `--cached` (or `--staged`), the index is compared with the given commit or `HEAD`.
+/// - With one revision, the working tree is compared with that commit.
+/// - With two revisions, or a range `A..B`, the first commit is compared with the second.
+///
+/// The changes are shown as a patch by default; `--stat`, `--numstat` and `--name-status`
+/// select other descriptions, and `-M`/`-C` detect renamed and copied files (see `DiffFormat`).
+/// Paths after `--` limit the diff to them.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the Git directory.
+/// * `root_dir` - The root directory of the working tree.
+/// * `line` - The command line, starting with the program name and "diff".
+/// * `output` - Where the changes are written.
+///
+/// # Errors
+///
+/// Returns an `InvalidInput` error if the command is malformed, or an error if a revision cannot
+/// be resolved or an object cannot be read.
+///
+pub fn git_diff(
+    git_dir: &str,
+    root_dir: &str,
+    line: Vec<String>,
+    output: &mut impl Write,
+) -> io::Result<()> {
+    let mut format = DiffFormat::default();
+    let mut cached = false;
+    let mut revisions: Vec<String> = Vec::new();
+    let mut paths: Vec<String> = Vec::new();
+    let mut args = line.iter().skip(2);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--cached" | "--staged" => cached = true,
+            "--" => paths.extend(args.by_ref().cloned()),
+            _ if format.parse_option(arg) => {}
+            _ if arg.starts_with('-') => {
+                writeln!(output, "{}", DIFF_USAGE)?;
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("error: unknown option '{}'", arg),
+                ));
+            }
+            _ => match arg.split_once("..") {
+                Some((from, to)) => {
+                    revisions.push(if from.is_empty() { "HEAD" } else { from }.to_string());
+                    revisions.push(if to.is_empty() { "HEAD" } else { to }.to_string());
+                }
+                None => revisions.push(arg.to_string()),
+            },
+        }
+    }
+    if revisions.len() > 2 || (cached && revisions.len() > 1) {
+        writeln!(output, "{}", DIFF_USAGE)?;
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "error: too many revisions",
+        ));
+    }
+    if format.is_empty() {
+        format.patch = true;
+    }
+    log_diff(&revisions, &paths)?;
+
+    let index = checkout::load_index(git_dir, root_dir)?;
+    let index_tree = tree_handler::build_tree_from_entries(index.iter())?;
+    let (old_tree, new_tree) = match (revisions.as_slice(), cached) {
+        ([from, to], _) => (
+            Some(load_revision_tree(git_dir, from)?),
+            load_revision_tree(git_dir, to)?,
+        ),
+        ([revision], true) => (Some(load_revision_tree(git_dir, revision)?), index_tree),
+        ([], true) => (load_revision_tree(git_dir, "HEAD").ok(), index_tree),
+        (revisions, _) => {
+            let worktree = stash::snapshot_tracked_files(&index, root_dir, git_dir)?;
+            let worktree_tree = tree_handler::build_tree_from_entries(worktree.iter())?;
+            match revisions.first() {
+                Some(revision) => (Some(load_revision_tree(git_dir, revision)?), worktree_tree),
+                None => (Some(index_tree), worktree_tree),
+            }
+        }
+    };
+
+    let mut changes =
+        get_tree_changes_with_renames(old_tree.as_ref(), Some(&new_tree), git_dir, format.renames)?;
+    if !paths.is_empty() {
+        changes.retain(|change| {
+            paths.iter().any(|path| {
+                restore::path_matches_pathspec(&change.path, path)
+                    || restore::path_matches_pathspec(change.old_path(), path)
+            })
+        });
+    }
+    write_changes(&changes, git_dir, format, output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_lines_detects_changes() {
+        let lines = diff_lines("a\nb\nc\n", "a\nx\nc\n");
+        assert_eq!(
+            lines,
+            vec![
+                DiffLine::Context("a\n"),
+                DiffLine::Removed("b\n"),
+                DiffLine::Added("x\n"),
+                DiffLine::Context("c\n"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unified_hunks_of_equal_texts_is_empty() {
+        assert_eq!(unified_hunks("a\nb\n", "a\nb\n"), "");
+    }
+
+    #[test]
+    fn test_unified_hunks_keeps_three_lines_of_context() {
+        let old = "1\n2\n3\n4\n5\n6\n7\n8\n9\n";
+        let new = "1\n2\n3\n4\nfive\n6\n7\n8\n9\n";
+        let expected = "@@ -2,7 +2,7 @@\n 2\n 3\n 4\n-5\n+five\n 6\n 7\n 8\n";
+        assert_eq!(unified_hunks(old, new), expected);
+    }
+
+    #[test]
+    fn test_unified_hunks_marks_missing_newline() {
+        let expected = "@@ -1 +1 @@\n-a\n\\ No newline at end of file\n+a\n";
+        assert_eq!(unified_hunks("a", "a\n"), expected);
+    }
+
+    #[test]
+    fn test_file_patch_of_new_file() {
+        let patch = file_patch("dir/file.txt", None, Some("hola\n"));
+        let expected = "diff --git a/dir/file.txt b/dir/file.txt\nnew file mode 100644\n--- /dev/null\n+++ b/dir/file.txt\n@@ -0,0 +1 @@\n+hola\n";
+        assert_eq!(patch, expected);
+    }
+
+    #[test]
+    fn test_write_patch_shows_binary_files() {
+        let root_dir = "tests/diff/binary";
+        let _ = std::fs::remove_dir_all(root_dir);
+        let git_dir = format!("{}/.mgit", root_dir);
+        std::fs::create_dir_all(format!("{}/objects", git_dir)).unwrap();
+        std::fs::write(format!("{}/.gitattributes", root_dir), "*.dat -diff\n").unwrap();
+        let store = |content: &str| {
+            Some(crate::hash_object::store_string_to_file(content, &git_dir, "blob").unwrap())
+        };
+        let changes = vec![
+            FileChange {
+                path: "a.dat".to_string(),
+                old_hash: store("a\n"),
+                new_hash: store("b\n"),
+                source: None,
+            },
+            FileChange {
+                path: "logo.png".to_string(),
+                old_hash: None,
+                new_hash: store("\0PNG"),
+                source: None,
+            },
+        ];
+
+        let mut output = Vec::new();
+        write_patch(&changes, &git_dir, &mut output).unwrap();
+        let expected = "diff --git a/a.dat b/a.dat\nBinary files a/a.dat and b/a.dat differ\ndiff --git a/logo.png b/logo.png\nnew file mode 100644\nBinary files /dev/null and b/logo.png differ\n";
+        assert_eq!(String::from_utf8(output).unwrap(), expected);
+
+        std::fs::remove_dir_all(root_dir).unwrap();
+    }
+
+    #[test]
+    fn test_diff_format_parses_options() {
+        let mut format = DiffFormat::default();
+        assert!(format.is_empty());
+        assert!(format.parse_option("--stat"));
+        assert!(format.parse_option("-p"));
+        assert!(!format.parse_option("--oneline"));
+        assert!(format.stat && format.patch && !format.numstat && !format.name_status);
+        assert!(format.parse_option("-s"));
+        assert!(format.is_empty());
+    }
+
+    #[test]
+    fn test_write_name_status_marks_added_modified_and_deleted_files() {
+        let change = |path: &str, old: Option<&str>, new: Option<&str>| FileChange {
+            path: path.to_string(),
+            old_hash: old.map(str::to_string),
+            new_hash: new.map(str::to_string),
+            source: None,
+        };
+        let changes = [
+            change("added", None, Some("1")),
+            change("deleted", Some("2"), None),
+            change("modified", Some("3"), Some("4")),
+        ];
+        let mut output = Vec::new();
+        write_name_status(&changes, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "A\tadded\nD\tdeleted\nM\tmodified\n"
+        );
+    }
+
+    #[test]
+    fn test_rename_options_parse_thresholds_like_git() {
+        let threshold = |option: &str| RenameOptions::parse(option).map(|r| r.threshold);
+        assert_eq!(threshold("-M"), Some(DEFAULT_RENAME_THRESHOLD));
+        assert_eq!(threshold("-M9"), Some(90));
+        assert_eq!(threshold("-M90"), Some(90));
+        assert_eq!(threshold("-M05"), Some(5));
+        assert_eq!(threshold("-M75%"), Some(75));
+        assert_eq!(threshold("--find-renames=80%"), Some(80));
+        assert_eq!(threshold("-M101%"), None);
+        assert_eq!(threshold("--find-renamesx"), None);
+        assert_eq!(threshold("-p"), None);
+        assert_eq!(RenameOptions::parse("-C").map(|r| r.copies), Some(true));
+    }
+
+    #[test]
+    fn test_similarity_index_counts_shared_lines_of_the_longest_text() {
+        assert_eq!(similarity_index("a\nb\n", "a\nb\n"), 100);
+        assert_eq!(similarity_index("a\nb\nc\nd\n", "a\nb\nx\nd\n"), 75);
+        assert_eq!(similarity_index("a\nb\n", "a\nb\nc\nd\n"), 50);
+        assert_eq!(similarity_index("a\n", "b\n"), 0);
+    }
+
+    #[test]
+    fn test_detect_renames_pairs_identical_files() {
+        let change = |path: &str, old: Option<&str>, new: Option<&str>| FileChange {
+            path: path.to_string(),
+            old_hash: old.map(str::to_string),
+            new_hash: new.map(str::to_string),
+            source: None,
+        };
+        let changes = vec![
+            change("new", None, Some("1")),
+            change("old", Some("1"), None),
+        ];
+        let changes = detect_renames(changes, None, "", RenameOptions::default()).unwrap();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].status(), "R100");
+        assert_eq!(changes[0].display_path(), "old => new");
+        let mut output = Vec::new();
+        write_name_status(&changes, &mut output).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "R100\told\tnew\n");
+    }
+
+    #[test]
+    fn test_moved_file_patch_shows_rename_header() {
+        let source = ChangeSource {
+            path: "old".to_string(),
+            similarity: 50,
+            copied: false,
+        };
+        let patch = moved_file_patch(&source, "new", Some("a\nb\n"), Some("a\nc\n"));
+        let expected = "diff --git a/old b/new\nsimilarity index 50%\nrename from old\nrename to new\n--- a/old\n+++ b/new\n@@ -1,2 +1,2 @@\n a\n-b\n+c\n";
+        assert_eq!(patch, expected);
+        let patch = moved_file_patch(&source, "new", Some("a\n"), Some("a\n"));
+        assert!(!patch.contains("---"));
+    }
+
+    #[test]
+    fn test_git_diff_detects_staged_renames() {
+        let test_dir = "tests/diff/renames";
+        if std::path::Path::new(test_dir).exists() {
+            std::fs::remove_dir_all(test_dir).unwrap();
+        }
+        std::fs::create_dir_all(test_dir).unwrap();
+        crate::init::git_init(test_dir, ".mgit", "master", None).unwrap();
+        let git_dir = format!("{}/.mgit", test_dir);
+        let index = format!("{}/index", git_dir);
+        let old = format!("{}/old.txt", test_dir);
+        let new = format!("{}/new.txt", test_dir);
+        let content = "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n";
+        std::fs::write(&old, content).unwrap();
+        crate::add::add(&old, &index, &git_dir, "", None).unwrap();
+        crate::commit::new_commit(&git_dir, "Add old", "").unwrap();
+        std::fs::remove_file(&old).unwrap();
+        std::fs::write(&new, content.replace("10\n", "ten\n")).unwrap();
+        let mut staged = crate::index::Index::load(&index, &git_dir, "").unwrap();
+        staged.remove_file(&old).unwrap();
+        staged.write_file().unwrap();
+        crate::add::add(&new, &index, &git_dir, "", None).unwrap();
+
+        let diff = |args: &[&str]| {
+            let mut line = vec!["git".to_string(), "diff".to_string()];
+            line.extend(args.iter().map(|arg| arg.to_string()));
+            let mut output = Vec::new();
+            git_diff(&git_dir, "", line, &mut output).unwrap();
+            String::from_utf8(output).unwrap()
+        };
+        let expected = format!("A\t{}\nD\t{}\n", new, old);
+        assert_eq!(diff(&["--cached", "--name-status"]), expected);
+        let expected = format!("R090\t{}\t{}\n", old, new);
+        assert_eq!(diff(&["--cached", "--name-status", "-M"]), expected);
+        assert_eq!(
+            diff(&["--cached", "--name-status", "-M95%"])
+                .lines()
+                .count(),
+            2
+        );
+        assert_eq!(diff(&[]), "");
+        std::fs::write(&new, content).unwrap();
+        assert_eq!(diff(&["--name-status"]), format!("M\t{}\n", new));
+        std::fs::remove_dir_all(test_dir).unwrap();
+    }
+
+    #[test]
+    fn test_combined_hunks_shows_lines_changed_against_every_parent() {
+        let ours = "a\nours\nc\n";
+        let theirs = "a\ntheirs\nc\n";
+        let result = "a\nresolved\nc\n";
+        let expected = "@@@ -1,3 -1,3 +1,3 @@@\n  a\n- ours\n -theirs\n++resolved\n  c\n";
+        assert_eq!(combined_hunks(&[ours, theirs], result), expected);
+    }
+
+    #[test]
+    fn test_combined_hunks_skips_changes_taken_from_one_parent() {
+        let ours = "a\nb\nc\n";
+        let theirs = "a\nx\nc\n";
+        assert_eq!(combined_hunks(&[ours, theirs], theirs), "");
+    }
+
+    #[test]
+    fn test_combined_file_patch_merges_lines_lost_from_both_parents() {
+        let patch = combined_file_patch("file", &[Some("a\nb\n"), Some("b\nc\n")], Some("b\n"));
+        let expected =
+            "diff --cc file\n--- a/file\n+++ b/file\n@@@ -1,2 -1,2 +1 @@@\n- a\n  b\n -c\n";
+        assert_eq!(patch, expected);
+    }
+}
@@ -1,6 +1,7 @@
-use crate::configuration::{GIT_DIR, HOST, LOGGER_COMMANDS_FILE, REMOTE};
+use crate::configuration::{HOST, LOGGER_COMMANDS_FILE, REMOTE};
 use crate::logger::Logger;
-use crate::utils::get_current_time;
+use crate::server_utils::NOTES_REFS_PREFIX;
+use crate::utils::{get_current_time, get_git_dir_of};
 use crate::{client::Client, config};
 use std::{
     collections::HashMap,
@@ -153,6 +154,8 @@ impl FetchHead {
 /// by splitting the string at '/' and taking the last part. It returns a new vector containing only the last
 /// components of the references, effectively removing any preceding namespace or hierarchy.
 ///
+/// The notes refs are left out, since the client fetches them on its own.
+///
 /// # Arguments
 ///
 /// * `refs`: A vector of Git reference strings to process.
@@ -164,6 +167,7 @@ impl FetchHead {
 fn get_clean_refs(refs: &HashMap<String, String>) -> Vec<String> {
     let clean_refs = refs
         .iter()
+        .filter(|x| !x.0.starts_with(NOTES_REFS_PREFIX))
         .map(|x| match x.0.split('/').last() {
             Some(string) => string.to_string(),
             None => "".to_string(),
@@ -227,7 +231,7 @@ pub fn git_fetch(
     _host: &str,
     local_dir: &str,
 ) -> io::Result<Vec<String>> {
-    let git_dir = local_dir.to_string() + "/" + GIT_DIR;
+    let git_dir = get_git_dir_of(local_dir);
     let config_file = config::Config::load(&git_dir)?;
     let remote_name = REMOTE;
     let remote_url = config_file.get_url(remote_name, &mut io::stdout())?;
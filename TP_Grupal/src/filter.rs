@@ -0,0 +1,287 @@
+use crate::attributes::Attributes;
+use crate::config::Config;
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+use std::thread;
+
+/// Key of the command run on the content of a file when it is stored.
+const CLEAN: &str = "clean";
+/// Key of the command run on the content of a file when it is written into the working tree.
+const SMUDGE: &str = "smudge";
+/// Key telling whether the operation fails when the filter does.
+const REQUIRED: &str = "required";
+
+/// A filter driver, set up in the `[filter "<name>"]` section of the configuration and selected
+/// for a path with the `filter=<name>` attribute.
+#[derive(Debug, Default, PartialEq)]
+pub struct FilterDriver {
+    pub name: String,
+    pub clean: Option<String>,
+    pub smudge: Option<String>,
+    /// Whether a failure of the filter makes the operation fail, instead of keeping the content
+    /// as it was.
+    pub required: bool,
+}
+
+impl FilterDriver {
+    /// Reads a filter driver from the configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * `git_dir` - The path to the git directory.
+    /// * `name` - The name of the driver.
+    ///
+    /// # Returns
+    ///
+    /// The driver, or `None` if the configuration does not mention it.
+    ///
+    pub fn load(git_dir: &str, name: &str) -> Option<Self> {
+        let config = Config::load(git_dir).ok()?;
+        let value = |key: &str| config.get_value(&format!("filter.{}.{}", name, key));
+        let driver = Self {
+            name: name.to_string(),
+            clean: value(CLEAN),
+            smudge: value(SMUDGE),
+            required: value(REQUIRED).is_some_and(|value| {
+                ["true", "yes", "on", "1"]
+                    .iter()
+                    .any(|enabled| value.eq_ignore_ascii_case(enabled))
+            }),
+        };
+        if driver.clean.is_none() && driver.smudge.is_none() && !driver.required {
+            return None;
+        }
+        Some(driver)
+    }
+}
+
+/// Runs a filter command, giving it the content on its standard input and returning what it
+/// writes to its standard output.
+///
+/// The command is run by the shell from the root of the working tree, with `%f` replaced by the
+/// quoted path of the file.
+///
+/// # Errors
+///
+/// Returns an error if the command cannot be run, exits with a non-zero status or its output is
+/// not valid UTF-8.
+///
+fn run_filter(command: &str, attributes: &Attributes, content: &str) -> io::Result<String> {
+    let path = format!("'{}'", attributes.path().replace('\'', "'\\''"));
+    let command = command.replace("%f", &path);
+    let root_dir = match attributes.root_dir() {
+        "" => ".",
+        root_dir => root_dir,
+    };
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(&command)
+        .current_dir(root_dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+    let mut stdin = child
+        .stdin
+        .take()
+        .ok_or_else(|| io::Error::other("error: cannot write to the filter"))?;
+    // The content is written from another thread, so a filter that writes its output while it
+    // reads cannot block on a full pipe.
+    let input = content.as_bytes().to_vec();
+    let writer = thread::spawn(move || stdin.write_all(&input));
+    let output = child.wait_with_output()?;
+    // A filter may exit without reading its input, which is not an error.
+    let _ = writer.join();
+    if !output.status.success() {
+        return Err(io::Error::other(format!(
+            "error: external filter '{}' failed",
+            command
+        )));
+    }
+    String::from_utf8(output.stdout).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "error: external filter '{}' returned invalid UTF-8",
+                command
+            ),
+        )
+    })
+}
+
+/// Runs the `clean` or `smudge` command of the filter driver of a file, if it has one.
+///
+/// When the command fails, the content is kept as it was, unless the driver is `required`.
+fn apply_filter(
+    git_dir: &str,
+    attributes: &Attributes,
+    content: String,
+    key: &str,
+) -> io::Result<String> {
+    let Some(driver) = attributes
+        .filter()
+        .and_then(|name| FilterDriver::load(git_dir, name))
+    else {
+        return Ok(content);
+    };
+    let command = if key == CLEAN {
+        &driver.clean
+    } else {
+        &driver.smudge
+    };
+    let result = match command {
+        Some(command) => run_filter(command, attributes, &content),
+        None if driver.required => Err(io::Error::other(format!(
+            "error: filter '{}' has no {} command",
+            driver.name, key
+        ))),
+        None => return Ok(content),
+    };
+    match result {
+        Ok(filtered) => Ok(filtered),
+        Err(e) if driver.required => {
+            eprintln!("{}", e);
+            Err(io::Error::other(format!(
+                "fatal: {}: {} filter '{}' failed",
+                attributes.path(),
+                key,
+                driver.name
+            )))
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            Ok(content)
+        }
+    }
+}
+
+/// Converts the content of a file of the working tree into what is stored in the repository.
+///
+/// The `clean` command of the filter driver of the file runs first, and then the line endings
+/// are normalized as the attributes of the file say.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the git directory, whose configuration holds the filter drivers.
+/// * `attributes` - The attributes of the file.
+/// * `content` - The content of the file in the working tree.
+///
+/// # Errors
+///
+/// Returns an error if a `required` filter fails.
+///
+pub fn convert_to_git(
+    git_dir: &str,
+    attributes: &Attributes,
+    content: String,
+) -> io::Result<String> {
+    let content = apply_filter(git_dir, attributes, content, CLEAN)?;
+    Ok(attributes.convert_to_git(content))
+}
+
+/// Converts the content stored in the repository into what is written to the working tree.
+///
+/// This undoes `convert_to_git`: the line endings are converted first, and then the `smudge`
+/// command of the filter driver of the file runs.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the git directory, whose configuration holds the filter drivers.
+/// * `attributes` - The attributes of the file.
+/// * `content` - The content stored in the repository.
+///
+/// # Errors
+///
+/// Returns an error if a `required` filter fails.
+///
+pub fn convert_to_working_tree(
+    git_dir: &str,
+    attributes: &Attributes,
+    content: String,
+) -> io::Result<String> {
+    let content = attributes.convert_to_working_tree(content);
+    apply_filter(git_dir, attributes, content, SMUDGE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::configuration::GIT_DIR;
+    use crate::test_utils;
+    use crate::{checkout, hash_object, tree_handler};
+    use std::collections::HashMap;
+    use std::fs;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_filters_run_when_storing_and_checking_out_files() {
+        let root_dir = "tests/filter/drivers";
+        // The git directory of the working tree is looked up when hashing its files.
+        let git_dir = test_utils::create_repo_with_git_dir(root_dir, GIT_DIR);
+        fs::write(
+            format!("{}/config", git_dir),
+            "[filter \"upper\"]\n\tclean = tr a-z A-Z\n\tsmudge = tr A-Z a-z\n[filter \"broken\"]\n\tclean = false\n\trequired = true\n[filter \"flaky\"]\n\tclean = exit 1\n[filter \"strict\"]\n\tsmudge = false\n\trequired = true\n",
+        )
+        .unwrap();
+        fs::write(
+            format!("{}/.gitattributes", root_dir),
+            "*.up filter=upper\n*.bad filter=broken\n*.flaky filter=flaky\n*.strict filter=strict\n",
+        )
+        .unwrap();
+        let write = |name: &str, content: &str| {
+            let path = format!("{}/{}", root_dir, name);
+            fs::write(&path, content).unwrap();
+            path
+        };
+        let read_blob = |hash: &str| crate::cat_file::cat_file_return_content(hash, &git_dir);
+
+        let path = write("a.up", "hello\n");
+        let hash = hash_object::store_file(&path, &git_dir).unwrap();
+        assert_eq!(read_blob(&hash).unwrap(), "HELLO\n");
+        assert_eq!(
+            hash_object::hash_working_file(root_dir, "a.up").unwrap(),
+            hash
+        );
+
+        fs::remove_file(&path).unwrap();
+        checkout::restore_path(root_dir, &git_dir, "a.up", Some(&hash)).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "hello\n");
+
+        let entries = HashMap::from([("dir/b.up".to_string(), hash.clone())]);
+        let tree = tree_handler::build_tree_from_entries(entries.iter()).unwrap();
+        tree.create_directories(root_dir, &git_dir).unwrap();
+        assert_eq!(
+            fs::read_to_string(format!("{}/dir/b.up", root_dir)).unwrap(),
+            "hello\n"
+        );
+
+        // A failing required smudge filter leaves the working file untouched.
+        let path = write("dir/e.strict", "local\n");
+        let entries = HashMap::from([("dir/e.strict".to_string(), hash.clone())]);
+        let tree = tree_handler::build_tree_from_entries(entries.iter()).unwrap();
+        assert!(tree.create_directories(root_dir, &git_dir).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "local\n");
+
+        let path = write("c.flaky", "kept\n");
+        let hash = hash_object::store_file(&path, &git_dir).unwrap();
+        assert_eq!(read_blob(&hash).unwrap(), "kept\n");
+
+        assert_eq!(
+            FilterDriver::load(&git_dir, "broken"),
+            Some(FilterDriver {
+                name: "broken".to_string(),
+                clean: Some("false".to_string()),
+                smudge: None,
+                required: true,
+            })
+        );
+        assert_eq!(FilterDriver::load(&git_dir, "missing"), None);
+        let path = write("d.bad", "data\n");
+        let error = hash_object::store_file(&path, &git_dir).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "fatal: d.bad: clean filter 'broken' failed"
+        );
+
+        fs::remove_dir_all(root_dir).unwrap();
+    }
+}
@@ -0,0 +1,329 @@
+use crate::configuration::LOGGER_COMMANDS_FILE;
+use crate::diff;
+use crate::log::{Log, LogIter};
+use crate::logger::Logger;
+use crate::utils::get_current_time;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+const FORMAT_PATCH_USAGE: &str =
+    "usage: git format-patch [-o <dir>] [--stdout] [-n] [-<n>] (<since> | <revision-range>)";
+
+/// Longest part of a patch file name taken from the subject of the commit.
+const MAX_FILE_NAME_SUBJECT: usize = 52;
+
+/// Logs the 'git format-patch' command with the specified revision.
+///
+/// This function logs the 'git format-patch' command with the provided revision to a file
+/// named 'logger_commands.txt'.
+///
+/// # Arguments
+///
+/// * `revision` - The revision or range of the exported commits.
+///
+/// # Errors
+///
+/// Returns an `io::Result` indicating whether the operation was successful.
+///
+fn log_format_patch(revision: &str) -> io::Result<()> {
+    let log_file_path = LOGGER_COMMANDS_FILE;
+    let mut logger = Logger::new(log_file_path)?;
+
+    let full_message = format!(
+        "Command 'git format-patch': Revision '{}', {}",
+        revision,
+        get_current_time()
+    );
+    logger.write_all(full_message.as_bytes())?;
+    logger.flush()?;
+    Ok(())
+}
+
+/// Builds the name of the file of a patch, like `0001-Fix-the-parser.patch`.
+///
+/// Every run of characters of the subject that are not letters, digits, dots or underscores is
+/// replaced by a single `-`.
+fn patch_file_name(number: usize, subject: &str) -> String {
+    let mut name = String::new();
+    for c in subject.chars() {
+        if c.is_ascii_alphanumeric() || c == '.' || c == '_' {
+            name.push(c);
+        } else if !name.ends_with('-') {
+            name.push('-');
+        }
+    }
+    let name: String = name
+        .trim_matches(|c| c == '-' || c == '.')
+        .chars()
+        .take(MAX_FILE_NAME_SUBJECT)
+        .collect();
+    format!("{:04}-{}.patch", number, name.trim_end_matches('-'))
+}
+
+/// Writes a commit as an email in mbox format, as sent by `git format-patch`.
+///
+/// The email has `From`, `Date` and `Subject` headers with the author, date and subject of the
+/// commit, the rest of the message as its body, and after a `---` line the diffstat and the
+/// patch of the commit against its parent.
+///
+/// # Arguments
+///
+/// * `log` - The commit to export.
+/// * `git_dir` - The path to the Git directory.
+/// * `number` - The position of the patch in the series, starting at 1.
+/// * `total` - The number of patches of the series. The subject is only numbered when it is
+///   greater than 1.
+///
+/// # Errors
+///
+/// Returns an error if the changes of the commit cannot be read.
+///
+pub fn format_patch_email(
+    log: &Log,
+    git_dir: &str,
+    number: usize,
+    total: usize,
+) -> io::Result<String> {
+    let prefix = if total > 1 {
+        format!("[PATCH {}/{}]", number, total)
+    } else {
+        "[PATCH]".to_string()
+    };
+    let mut email = log.expand_format(&format!(
+        "From %H Mon Sep 17 00:00:00 2001%nFrom: %an <%ae>%nDate: %aD%nSubject: {} %s%n%n",
+        prefix
+    ));
+    let body = log.expand_format("%b");
+    if !body.trim().is_empty() {
+        email.push_str(body.trim_end());
+        email.push_str("\n\n");
+    }
+    email.push_str("---\n");
+
+    let changes = log.get_changes(None)?;
+    let mut description = Vec::new();
+    diff::write_stat(&changes, git_dir, &mut description)?;
+    writeln!(description)?;
+    diff::write_patch(&changes, git_dir, &mut description)?;
+    email.push_str(&String::from_utf8_lossy(&description));
+    email.push_str(&format!("-- \nmessi {}\n\n", env!("CARGO_PKG_VERSION")));
+    Ok(email)
+}
+
+/// Exports commits as patches that can be sent by email and applied with `git am`.
+///
+/// The command line is
+/// `git format-patch [-o <dir>] [--stdout] [-n] [-<n>] (<since> | <revision-range>)`:
+///
+/// - `<since>` exports the commits reachable from `HEAD` but not from `<since>`.
+/// - `<a>..<b>` exports the commits reachable from `<b>` but not from `<a>`.
+/// - `-<n>` exports the last `n` commits of `HEAD`.
+///
+/// Merge commits are left out, and the rest are exported oldest first, each one into its own
+/// `NNNN-<subject>.patch` file of the output directory (the current one by default), whose
+/// name is printed. With `--stdout` the emails are written to the output instead, forming a
+/// single mailbox. `-n` numbers the subjects even when a single patch is exported.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the Git directory.
+/// * `line` - The command line, starting with `git format-patch`.
+/// * `output` - Where the names of the files, or the emails, are written.
+///
+/// # Errors
+///
+/// Returns an error if the arguments are invalid, a revision cannot be resolved or a patch
+/// file cannot be written.
+///
+pub fn git_format_patch(
+    git_dir: &str,
+    line: Vec<String>,
+    output: &mut impl Write,
+) -> io::Result<()> {
+    let mut directory = ".".to_string();
+    let mut stdout = false;
+    let mut numbered = false;
+    let mut max_count: Option<usize> = None;
+    let mut revisions: Vec<String> = Vec::new();
+    let mut args = line.iter().skip(2);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-o" | "--output-directory" => match args.next() {
+                Some(dir) => directory = dir.to_string(),
+                None => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        FORMAT_PATCH_USAGE,
+                    ))
+                }
+            },
+            "--stdout" => stdout = true,
+            "-n" | "--numbered" => numbered = true,
+            _ => {
+                if let Some(dir) = arg.strip_prefix("--output-directory=") {
+                    directory = dir.to_string();
+                } else if let Some(count) = arg.strip_prefix('-') {
+                    max_count = Some(count.parse().map_err(|_| {
+                        io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            format!("error: unknown option '{}'\n{}", arg, FORMAT_PATCH_USAGE),
+                        )
+                    })?);
+                } else {
+                    revisions.push(arg.to_string());
+                }
+            }
+        }
+    }
+
+    let revision = match (revisions.as_slice(), max_count) {
+        ([], Some(_)) => "HEAD".to_string(),
+        ([revision], _) if revision.contains("..") => revision.to_string(),
+        ([revision], None) => format!("{}..HEAD", revision),
+        ([revision], Some(_)) => revision.to_string(),
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                FORMAT_PATCH_USAGE,
+            ))
+        }
+    };
+    let mut logs: Vec<Log> =
+        LogIter::from_revisions(git_dir, std::slice::from_ref(&revision), false)?
+            .filter(|log| log.expand_format("%P").split_whitespace().count() <= 1)
+            .take(max_count.unwrap_or(usize::MAX))
+            .collect();
+    logs.reverse();
+
+    if !stdout && !logs.is_empty() {
+        fs::create_dir_all(&directory)?;
+    }
+    let total = if numbered {
+        logs.len().max(2)
+    } else {
+        logs.len()
+    };
+    for (position, log) in logs.iter().enumerate() {
+        let email = format_patch_email(log, git_dir, position + 1, total)?;
+        if stdout {
+            output.write_all(email.as_bytes())?;
+        } else {
+            let name = patch_file_name(position + 1, &log.expand_format("%s"));
+            let path = Path::new(&directory).join(name);
+            fs::write(&path, email)?;
+            writeln!(output, "{}", path.to_string_lossy())?;
+        }
+    }
+    log_format_patch(&revision)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{add, hash_object, init, tree_handler};
+
+    /// Adds the files to the index and returns the hash of the resulting tree.
+    fn stage_tree(git_dir: &str, files: &[(&str, &str)]) -> String {
+        let index = format!("{}/index", git_dir);
+        for (path, content) in files {
+            fs::write(path, content).unwrap();
+            add::add(path, &index, git_dir, "", None).unwrap();
+        }
+        let tree = tree_handler::build_tree_from_index(&index, git_dir, "").unwrap();
+        tree_handler::write_tree(&tree, git_dir).unwrap().0
+    }
+
+    /// Stores a commit with a fixed author and date.
+    fn store_commit(
+        git_dir: &str,
+        tree: &str,
+        parents: &[&str],
+        time: i64,
+        message: &str,
+    ) -> String {
+        let mut content = format!("tree {}\n", tree);
+        for parent in parents {
+            content.push_str(&format!("parent {}\n", parent));
+        }
+        content.push_str(&format!(
+            "author alice alice@mail.com {time} -0300\ncommitter alice alice@mail.com {time} -0300\n\n{message}\0",
+            time = time,
+            message = message
+        ));
+        hash_object::store_string_to_file(&content, git_dir, "commit").unwrap()
+    }
+
+    #[test]
+    fn test_patch_file_name() {
+        assert_eq!(
+            patch_file_name(1, "Fix the parser: handle [brackets]!"),
+            "0001-Fix-the-parser-handle-brackets.patch"
+        );
+        assert_eq!(
+            patch_file_name(12, "v1.2 release"),
+            "0012-v1.2-release.patch"
+        );
+    }
+
+    #[test]
+    fn test_format_patch_writes_one_email_per_commit() {
+        let test_dir = "tests/format_patch/series";
+        if Path::new(test_dir).exists() {
+            fs::remove_dir_all(test_dir).unwrap();
+        }
+        fs::create_dir_all(test_dir).unwrap();
+        init::git_init(test_dir, ".mgit", "master", None).unwrap();
+        let git_dir = format!("{}/.mgit", test_dir);
+        let file = format!("{}/file.txt", test_dir);
+
+        let tree = stage_tree(&git_dir, &[(&file, "one\n")]);
+        let first = store_commit(&git_dir, &tree, &[], 1000, "Create file");
+        let tree = stage_tree(&git_dir, &[(&file, "one\ntwo\n")]);
+        let second = store_commit(
+            &git_dir,
+            &tree,
+            &[&first],
+            2000,
+            "Add two\n\nIt was missing.",
+        );
+        let tree = stage_tree(&git_dir, &[(&file, "one\n2\n")]);
+        let third = store_commit(&git_dir, &tree, &[&second], 3000, "Use digits");
+        fs::write(format!("{}/refs/heads/master", git_dir), &third).unwrap();
+
+        let out_dir = format!("{}/patches", test_dir);
+        let line = ["git", "format-patch", "-o", &out_dir, &first]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        let mut output = Vec::new();
+        git_format_patch(&git_dir, line, &mut output).unwrap();
+        let first_patch = format!("{}/0001-Add-two.patch", out_dir);
+        let second_patch = format!("{}/0002-Use-digits.patch", out_dir);
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            format!("{}\n{}\n", first_patch, second_patch)
+        );
+
+        let email = fs::read_to_string(first_patch).unwrap();
+        let expected = format!(
+            "From {second} Mon Sep 17 00:00:00 2001\nFrom: alice <alice@mail.com>\nDate: Wed, 31 Dec 1969 21:33:20 -0300\nSubject: [PATCH 1/2] Add two\n\nIt was missing.\n\n---\n {file} | 1 +\n 1 file changed, 1 insertion(+), 0 deletions(-)\n\ndiff --git a/{file} b/{file}\n--- a/{file}\n+++ b/{file}\n@@ -1 +1,2 @@\n one\n+two\n-- \nmessi {version}\n\n",
+            second = second,
+            file = file,
+            version = env!("CARGO_PKG_VERSION")
+        );
+        assert_eq!(email, expected);
+
+        let line = ["git", "format-patch", "--stdout", "-1"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        let mut output = Vec::new();
+        git_format_patch(&git_dir, line, &mut output).unwrap();
+        let mailbox = String::from_utf8(output).unwrap();
+        assert!(mailbox.starts_with(&format!("From {} Mon Sep 17", third)));
+        assert!(mailbox.contains("Subject: [PATCH] Use digits\n"));
+        fs::remove_dir_all(test_dir).unwrap();
+    }
+}
@@ -0,0 +1,157 @@
+/// The rows drawn by a `Graph` for a single commit.
+///
+/// `commit` goes in front of the first line of the commit, `padding` in front of the rest of its
+/// lines, and `transition` are the rows that connect it with the next commit.
+#[derive(Debug, PartialEq, Eq)]
+pub struct GraphRows {
+    pub commit: String,
+    pub padding: String,
+    pub transition: Vec<String>,
+}
+
+/// Draws the lanes of an ASCII commit graph, like `git log --graph`.
+///
+/// Every lane is a column waiting for a commit. Commits must be given children first; each
+/// commit is drawn as a `*` in its lane, which is then handed over to its parents. Lanes that
+/// branch out of a merge are drawn with `\` and lanes that join into an existing one with `/`.
+#[derive(Debug, Default)]
+pub struct Graph {
+    columns: Vec<String>,
+}
+
+impl Graph {
+    /// Creates a graph without lanes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Draws the next commit and updates the lanes with its parents.
+    ///
+    /// A commit that no lane is waiting for, such as the tip of a branch, starts a new lane on
+    /// the right.
+    ///
+    /// # Arguments
+    ///
+    /// * `hash` - The hash of the commit.
+    /// * `parents` - The parents of the commit that will be drawn later.
+    ///
+    /// # Returns
+    ///
+    /// Returns the rows to print for this commit.
+    ///
+    pub fn next_commit(&mut self, hash: &str, parents: &[String]) -> GraphRows {
+        let index = match self.columns.iter().position(|column| column == hash) {
+            Some(index) => index,
+            None => {
+                self.columns.push(hash.to_string());
+                self.columns.len() - 1
+            }
+        };
+        let commit = draw_lanes(
+            self.columns.len(),
+            |lane| {
+                if lane == index {
+                    '*'
+                } else {
+                    '|'
+                }
+            },
+        );
+        let padding = draw_lanes(self.columns.len(), |lane| {
+            if lane == index && parents.is_empty() {
+                ' '
+            } else {
+                '|'
+            }
+        });
+
+        let mut next: Vec<String> = Vec::new();
+        for (lane, column) in self.columns.iter().enumerate() {
+            let waiting = if lane == index {
+                parents
+            } else {
+                std::slice::from_ref(column)
+            };
+            for hash in waiting {
+                if !next.contains(hash) {
+                    next.push(hash.clone());
+                }
+            }
+        }
+        let position = |hash: &String| next.iter().position(|column| column == hash);
+        let mut edges: Vec<(usize, usize)> = Vec::new();
+        for (lane, column) in self.columns.iter().enumerate() {
+            if lane == index {
+                edges.extend(parents.iter().filter_map(position).map(|to| (lane, to)));
+            } else if let Some(to) = position(column) {
+                edges.push((lane, to));
+            }
+        }
+
+        let width = 2 * self.columns.len().max(next.len());
+        let mut transition = Vec::new();
+        while edges.iter().any(|(from, to)| from != to) {
+            let mut row = vec![' '; width];
+            for (from, to) in edges.iter_mut() {
+                if *from == *to {
+                    row[2 * *from] = '|';
+                } else if *to < *from {
+                    row[2 * *from - 1] = '/';
+                    *from -= 1;
+                } else {
+                    row[2 * *from + 1] = '\\';
+                    *from += 1;
+                }
+            }
+            transition.push(row.into_iter().collect::<String>().trim_end().to_string());
+        }
+
+        self.columns = next;
+        GraphRows {
+            commit,
+            padding,
+            transition,
+        }
+    }
+}
+
+/// Draws one character per lane, separated by spaces.
+fn draw_lanes(lanes: usize, lane_char: impl Fn(usize) -> char) -> String {
+    let row: Vec<String> = (0..lanes).map(|lane| lane_char(lane).to_string()).collect();
+    row.join(" ").trim_end().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn draw(commits: &[(&str, &[&str])]) -> Vec<String> {
+        let mut graph = Graph::new();
+        let mut rows = Vec::new();
+        for (hash, parents) in commits {
+            let parents: Vec<String> = parents.iter().map(|parent| parent.to_string()).collect();
+            let drawn = graph.next_commit(hash, &parents);
+            rows.push(format!("{} {}", drawn.commit, hash));
+            rows.extend(drawn.transition);
+        }
+        rows
+    }
+
+    #[test]
+    fn test_linear_history_is_a_single_lane() {
+        let rows = draw(&[("c", &["b"]), ("b", &["a"]), ("a", &[])]);
+        assert_eq!(rows, ["* c", "* b", "* a"]);
+    }
+
+    #[test]
+    fn test_merge_branches_out_and_joins_back() {
+        let rows = draw(&[("m", &["b", "c"]), ("c", &["a"]), ("b", &["a"]), ("a", &[])]);
+        assert_eq!(rows, ["* m", "|\\", "| * c", "* | b", "|/", "* a"]);
+    }
+
+    #[test]
+    fn test_branch_tips_start_new_lanes() {
+        let rows = draw(&[("b", &["a"]), ("c", &["a"]), ("a", &[])]);
+        assert_eq!(rows, ["* b", "| * c", "|/", "* a"]);
+    }
+}
@@ -0,0 +1,424 @@
+use crate::cat_file;
+use crate::checkout;
+use crate::configuration::LOGGER_COMMANDS_FILE;
+use crate::ignorer::is_subpath;
+use crate::logger::Logger;
+use crate::tree_handler;
+use crate::utils::{self, get_current_time};
+use regex::{Regex, RegexBuilder};
+use std::fs;
+use std::io::{self, Write};
+
+const GREP_USAGE: &str =
+    "usage: git grep [-i] [-n] [-l] [-w] [-e] <pattern> [<rev>...] [-- <pathspec>...]";
+
+/// Number of bytes at the start of a file looked at to tell whether it is binary, as git does.
+const BINARY_CHECK_SIZE: usize = 8000;
+
+/// Logs the 'git grep' command with the specified pattern.
+///
+/// This function logs the 'git grep' command with the provided pattern to a file named
+/// 'logger_commands.txt'.
+///
+/// # Arguments
+///
+/// * `pattern` - The pattern searched for.
+///
+/// # Errors
+///
+/// Returns an `io::Result` indicating whether the operation was successful.
+///
+fn log_grep(pattern: &str) -> io::Result<()> {
+    let log_file_path = LOGGER_COMMANDS_FILE;
+    let mut logger = Logger::new(log_file_path)?;
+
+    let full_message = format!(
+        "Command 'git grep': Pattern '{}', {}",
+        pattern,
+        get_current_time()
+    );
+    logger.write_all(full_message.as_bytes())?;
+    logger.flush()?;
+    Ok(())
+}
+
+/// How matches are looked for and printed.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct GrepOptions {
+    /// `-i`: ignore the case of letters.
+    pub ignore_case: bool,
+    /// `-n`: print the number of every matching line.
+    pub line_number: bool,
+    /// `-l`: print only the names of the files with matches.
+    pub files_with_matches: bool,
+    /// `-w`: only match whole words.
+    pub word_regexp: bool,
+}
+
+/// Compiles the pattern to search for, according to the options.
+fn build_regex(pattern: &str, options: GrepOptions) -> io::Result<Regex> {
+    let expression = if options.word_regexp {
+        format!(r"\b(?:{})\b", pattern)
+    } else {
+        pattern.to_string()
+    };
+    RegexBuilder::new(&expression)
+        .case_insensitive(options.ignore_case)
+        .build()
+        .map_err(|error| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("fatal: invalid regular expression '{}': {}", pattern, error),
+            )
+        })
+}
+
+/// Tells whether a content is binary: whether it has a NUL byte near its start.
+fn is_binary(content: &[u8]) -> bool {
+    content[..content.len().min(BINARY_CHECK_SIZE)].contains(&0)
+}
+
+/// Checks whether a path is selected by the pathspecs: whether it is below any of them.
+fn is_selected(path: &str, pathspecs: &[String]) -> bool {
+    pathspecs.is_empty() || pathspecs.iter().any(|pathspec| is_subpath(path, pathspec))
+}
+
+/// Prints the lines of a file that match, each one prefixed by the name of the file.
+///
+/// Binary files are skipped.
+///
+/// # Returns
+///
+/// Whether the file had any match.
+///
+fn grep_content(
+    regex: &Regex,
+    name: &str,
+    content: &[u8],
+    options: GrepOptions,
+    output: &mut impl Write,
+) -> io::Result<bool> {
+    if is_binary(content) {
+        return Ok(false);
+    }
+    let text = String::from_utf8_lossy(content);
+    let mut found = false;
+    for (number, line) in text.lines().enumerate() {
+        if !regex.is_match(line) {
+            continue;
+        }
+        found = true;
+        if options.files_with_matches {
+            writeln!(output, "{}", name)?;
+            break;
+        }
+        if options.line_number {
+            writeln!(output, "{}:{}:{}", name, number + 1, line)?;
+        } else {
+            writeln!(output, "{}:{}", name, line)?;
+        }
+    }
+    Ok(found)
+}
+
+/// Searches the tracked files of the working tree, the ones in the index.
+fn grep_working_tree(
+    git_dir: &str,
+    root_dir: &str,
+    regex: &Regex,
+    pathspecs: &[String],
+    options: GrepOptions,
+    output: &mut impl Write,
+) -> io::Result<bool> {
+    let index = checkout::load_index(git_dir, root_dir)?;
+    let mut paths: Vec<&String> = index
+        .iter()
+        .map(|(path, _)| path)
+        .filter(|path| is_selected(path, pathspecs))
+        .collect();
+    paths.sort();
+    let mut found = false;
+    for path in paths {
+        let content = match fs::read(checkout::working_path(root_dir, path)) {
+            Ok(content) => content,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => continue,
+            Err(error) => return Err(error),
+        };
+        found |= grep_content(regex, path, &content, options, output)?;
+    }
+    Ok(found)
+}
+
+/// Searches the files of the tree of a revision, reading them from the object store.
+fn grep_revision(
+    git_dir: &str,
+    revision: &str,
+    regex: &Regex,
+    pathspecs: &[String],
+    options: GrepOptions,
+    output: &mut impl Write,
+) -> io::Result<bool> {
+    let hash = utils::resolve_revision(git_dir, revision)?;
+    let tree = match cat_file::get_object_type(&hash, git_dir)?.as_str() {
+        "commit" => tree_handler::load_tree_from_commit(&hash, git_dir)?,
+        "tree" => tree_handler::load_tree_from_file(&hash, git_dir)?,
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("fatal: unable to read tree ({})", hash),
+            ))
+        }
+    };
+    let mut files = tree.squash_tree_into_vec("");
+    files.retain(|(path, _)| is_selected(path, pathspecs));
+    files.sort();
+    let mut found = false;
+    for (path, blob) in files {
+        let content = cat_file::cat_file_return_bytes(&blob, git_dir)?;
+        let name = format!("{}:{}", revision, path);
+        found |= grep_content(regex, &name, &content, options, output)?;
+    }
+    Ok(found)
+}
+
+/// Searches for lines matching a pattern in the tracked files or in past revisions.
+///
+/// Without revisions, the files tracked in the index are read from the working tree and every
+/// matching line is printed as `path:text`. With revisions, the files of their trees are read
+/// from the object store instead and lines are printed as `rev:path:text`, so nothing needs to
+/// be checked out. Binary files are skipped.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the Git directory.
+/// * `root_dir` - The root of the working directory. Paths in the index are relative to it.
+/// * `pattern` - The regular expression to search for.
+/// * `revisions` - The revisions to search. When empty, the working tree is searched.
+/// * `pathspecs` - When not empty, only the files below these paths are searched.
+/// * `options` - How matches are looked for and printed.
+/// * `output` - Where the matches are written.
+///
+/// # Returns
+///
+/// Whether any line matched.
+///
+/// # Errors
+///
+/// Returns an error if the pattern is invalid, a revision cannot be resolved or a file cannot
+/// be read.
+///
+pub fn grep(
+    git_dir: &str,
+    root_dir: &str,
+    pattern: &str,
+    revisions: &[String],
+    pathspecs: &[String],
+    options: GrepOptions,
+    output: &mut impl Write,
+) -> io::Result<bool> {
+    let regex = build_regex(pattern, options)?;
+    let pathspecs: Vec<String> = pathspecs
+        .iter()
+        .map(|pathspec| pathspec.trim_end_matches('/').to_string())
+        .collect();
+    if revisions.is_empty() {
+        return grep_working_tree(git_dir, root_dir, &regex, &pathspecs, options, output);
+    }
+    let mut found = false;
+    for revision in revisions {
+        found |= grep_revision(git_dir, revision, &regex, &pathspecs, options, output)?;
+    }
+    Ok(found)
+}
+
+/// Searches for lines matching a pattern, like `git grep`.
+///
+/// The command line is
+/// `git grep [-i] [-n] [-l] [-w] [-e] <pattern> [<rev>...] [-- <pathspec>...]`:
+///
+/// - `-i` or `--ignore-case` ignores the case of letters.
+/// - `-n` or `--line-number` prints the number of every matching line, as `rev:path:line:text`.
+/// - `-l` or `--files-with-matches` prints only the names of the files with matches.
+/// - `-w` or `--word-regexp` only matches whole words.
+/// - `-e` marks the next argument as the pattern, even if it starts with `-`.
+///
+/// Short options can be combined, as in `-in`. See [`grep`] for where the files are read from.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the Git directory.
+/// * `root_dir` - The root of the working directory.
+/// * `line` - The command line, starting with `git grep`.
+/// * `output` - Where the matches are written.
+///
+/// # Errors
+///
+/// Returns an error if the arguments are invalid or the search fails.
+///
+pub fn git_grep(
+    git_dir: &str,
+    root_dir: &str,
+    line: Vec<String>,
+    output: &mut impl Write,
+) -> io::Result<()> {
+    let mut options = GrepOptions::default();
+    let mut pattern: Option<String> = None;
+    let mut revisions: Vec<String> = Vec::new();
+    let mut pathspecs: Vec<String> = Vec::new();
+    let invalid = |arg: &str| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("error: unknown option '{}'\n{}", arg, GREP_USAGE),
+        )
+    };
+    let mut args = line.iter().skip(2);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--" => pathspecs.extend(args.by_ref().cloned()),
+            "-e" => match args.next() {
+                Some(value) => pattern = Some(value.to_string()),
+                None => return Err(io::Error::new(io::ErrorKind::InvalidInput, GREP_USAGE)),
+            },
+            "--ignore-case" => options.ignore_case = true,
+            "--line-number" => options.line_number = true,
+            "--files-with-matches" => options.files_with_matches = true,
+            "--word-regexp" => options.word_regexp = true,
+            _ if arg.starts_with("--") => return Err(invalid(arg)),
+            _ if arg.starts_with('-') && arg.len() > 1 => {
+                for flag in arg.chars().skip(1) {
+                    match flag {
+                        'i' => options.ignore_case = true,
+                        'n' => options.line_number = true,
+                        'l' => options.files_with_matches = true,
+                        'w' => options.word_regexp = true,
+                        _ => return Err(invalid(arg)),
+                    }
+                }
+            }
+            _ if pattern.is_none() => pattern = Some(arg.to_string()),
+            _ => revisions.push(arg.to_string()),
+        }
+    }
+    let Some(pattern) = pattern else {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, GREP_USAGE));
+    };
+    log_grep(&pattern)?;
+    grep(
+        git_dir, root_dir, &pattern, &revisions, &pathspecs, options, output,
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{commit, hash_object, test_utils};
+
+    /// Stages the given files, which may be binary, and commits them.
+    fn commit_files(git_dir: &str, root_dir: &str, files: &[(&str, &[u8])], message: &str) {
+        let mut index = checkout::load_index(git_dir, root_dir).unwrap();
+        for (path, content) in files {
+            let working_path = checkout::working_path(root_dir, path);
+            if let Some(parent) = std::path::Path::new(&working_path).parent() {
+                fs::create_dir_all(parent).unwrap();
+            }
+            fs::write(&working_path, content).unwrap();
+            let hash =
+                hash_object::store_bytes_array_to_file(content.to_vec(), git_dir, "blob").unwrap();
+            index.add_file(path, &hash).unwrap();
+        }
+        index.write_file().unwrap();
+        commit::new_commit(git_dir, message, "").unwrap();
+    }
+
+    /// Creates a repository with two commits, tagging the first one as `v1`.
+    fn create_repo(root_dir: &str) -> String {
+        let git_dir = test_utils::create_repo(root_dir);
+        commit_files(
+            &git_dir,
+            root_dir,
+            &[
+                (
+                    "main.rs",
+                    b"fn parse_args() {}\nfn main() {\n    parse_args();\n}\n",
+                ),
+                ("src/util.rs", b"// Parse helpers\nfn parse() {}\n"),
+                ("logo.png", b"\x89PNG\0parse_args"),
+            ],
+            "first",
+        );
+        let first = utils::resolve_revision(&git_dir, "HEAD").unwrap();
+        fs::write(format!("{}/refs/tags/v1", git_dir), first).unwrap();
+        commit_files(
+            &git_dir,
+            root_dir,
+            &[(
+                "main.rs",
+                b"fn read_args() {}\nfn main() {\n    read_args();\n}\n",
+            )],
+            "second",
+        );
+        git_dir
+    }
+
+    fn run_grep(git_dir: &str, root_dir: &str, args: &[&str]) -> String {
+        let mut line = vec!["git".to_string(), "grep".to_string()];
+        line.extend(args.iter().map(|arg| arg.to_string()));
+        let mut output = Vec::new();
+        git_grep(git_dir, root_dir, line, &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_grep_working_tree() {
+        let root_dir = "tests/grep/working_tree";
+        let git_dir = create_repo(root_dir);
+        fs::write(format!("{}/untracked.rs", root_dir), "read_args\n").unwrap();
+
+        assert_eq!(
+            run_grep(&git_dir, root_dir, &["-n", "read_args"]),
+            "main.rs:1:fn read_args() {}\nmain.rs:3:    read_args();\n"
+        );
+        assert_eq!(
+            run_grep(&git_dir, root_dir, &["-i", "parse"]),
+            "src/util.rs:// Parse helpers\nsrc/util.rs:fn parse() {}\n"
+        );
+        assert_eq!(
+            run_grep(&git_dir, root_dir, &["-iw", "parse"]),
+            "src/util.rs:// Parse helpers\nsrc/util.rs:fn parse() {}\n"
+        );
+        assert_eq!(run_grep(&git_dir, root_dir, &["-w", "pars"]), "");
+        assert_eq!(
+            run_grep(&git_dir, root_dir, &["-l", "fn"]),
+            "main.rs\nsrc/util.rs\n"
+        );
+        assert_eq!(
+            run_grep(&git_dir, root_dir, &["fn", "--", "src"]),
+            "src/util.rs:fn parse() {}\n"
+        );
+
+        fs::remove_dir_all(root_dir).unwrap();
+    }
+
+    #[test]
+    fn test_grep_revisions() {
+        let root_dir = "tests/grep/revisions";
+        let git_dir = create_repo(root_dir);
+
+        assert_eq!(
+            run_grep(&git_dir, root_dir, &["-n", "parse_args", "v1"]),
+            "v1:main.rs:1:fn parse_args() {}\nv1:main.rs:3:    parse_args();\n"
+        );
+        assert_eq!(run_grep(&git_dir, root_dir, &["parse_args", "HEAD"]), "");
+        assert_eq!(
+            run_grep(&git_dir, root_dir, &["-l", "_args", "HEAD", "v1"]),
+            "HEAD:main.rs\nv1:main.rs\n"
+        );
+        assert_eq!(
+            run_grep(&git_dir, root_dir, &["-e", "-", "HEAD~1", "--", "main.rs"]),
+            ""
+        );
+
+        fs::remove_dir_all(root_dir).unwrap();
+    }
+}
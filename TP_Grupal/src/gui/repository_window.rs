@@ -1,5 +1,6 @@
 use crate::add;
 use crate::add::add;
+use crate::blame;
 use crate::branch;
 use crate::branch::git_branch;
 use crate::branch::is_an_existing_branch;
@@ -60,6 +61,7 @@ use crate::push;
 use crate::remote::git_remote;
 use crate::rm::git_rm;
 use crate::show_ref::git_show_ref;
+use crate::stash;
 use crate::status;
 use crate::tree_handler;
 use crate::tree_handler::Tree;
@@ -165,6 +167,12 @@ fn setup_repository_window(builder: &gtk::Builder, new_window: &gtk::Window) ->
     let builder_clone_for_git_config = builder.clone();
     config_window(&builder_clone_for_git_config);
 
+    let builder_clone_for_stash = builder.clone();
+    stash_window(&builder_clone_for_stash);
+
+    let builder_clone_for_blame = builder.clone();
+    blame_window(&builder_clone_for_blame)?;
+
     let builder_clone_for_rebase = builder.clone();
     rebase_window(&builder_clone_for_rebase)?;
 
@@ -367,7 +375,7 @@ fn handle_git_push() -> io::Result<()> {
         }
     };
     let branch_name = get_branch_name(&git_dir)?;
-    push::git_push(&branch_name, &git_dir)
+    push::git_push(&branch_name, &git_dir, false)
 }
 
 /// Setup a button with the specified `button_id` using the given GTK builder. This function applies the
@@ -2766,15 +2774,7 @@ pub fn obtain_text_from_add(texto: &str) -> Result<String, io::Error> {
 /// Returns a tuple containing the Git directory path and Git ignore file path if found.
 pub fn find_git_directory_and_ignore() -> Result<(String, String), io::Error> {
     let git_dir = obtain_git_dir()?;
-    let working_dir = match Path::new(&git_dir).parent() {
-        Some(dir) => dir.to_string_lossy().to_string(),
-        None => {
-            return Err(io::Error::new(
-                io::ErrorKind::NotFound,
-                "Working dir not found\n",
-            ))
-        }
-    };
+    let working_dir = utils::get_working_dir(&git_dir)?;
     let git_ignore_path = format!("{}/{}", working_dir, GIT_IGNORE);
 
     Ok((git_dir, git_ignore_path))
@@ -4142,6 +4142,278 @@ pub fn show_ref_window(builder: &Builder) {
     verify_ref_button_on_clicked(&verify_ref_button, &show_ref_view, &show_ref_entry);
 }
 
+/// Handles the click event of one of the buttons of the "Stash" window.
+///
+/// This function is connected to the click event of a GTK button. When the button is clicked,
+/// it runs the given `git stash` subcommand and shows its output in the stash `TextView`.
+/// The text of the entry is used as the stash message for `push` and as the stash reference
+/// (for example `stash@{1}`) for the rest of the subcommands. After a subcommand that changes
+/// the working directory, the staging area and the stash list are refreshed.
+///
+/// # Arguments
+///
+/// * `builder` - The GTK `Builder` used to refresh the staging area view.
+/// * `button` - The GTK `Button` triggering the click event.
+/// * `text_view` - The GTK `TextView` where the output will be displayed.
+/// * `entry` - The GTK `Entry` with the stash message or reference.
+/// * `subcommand` - The stash subcommand to run. `push-untracked` runs `push --include-untracked`.
+///
+fn stash_button_on_clicked(
+    builder: &Builder,
+    button: &Button,
+    text_view: &gtk::TextView,
+    entry: &Entry,
+    subcommand: &'static str,
+) {
+    let builder_clone = builder.clone();
+    let cloned_text_view = text_view.clone();
+    let cloned_entry = entry.clone();
+    button.connect_clicked(move |_| {
+        let git_dir = match obtain_git_dir() {
+            Ok(dir) => dir,
+            Err(_e) => {
+                show_message_dialog(
+                    "Fatal error",
+                    "Something went wrong while trying to retrieve the data. :(",
+                );
+                return;
+            }
+        };
+        let working_dir = match Path::new(&git_dir).parent() {
+            Some(dir) => dir.to_string_lossy().to_string(),
+            None => {
+                show_message_dialog("Fatal error", "Can't find working dir.");
+                return;
+            }
+        };
+
+        let text = cloned_entry.get_text().to_string();
+        let mut line = vec!["git".to_string(), "stash".to_string()];
+        match subcommand {
+            "push" | "push-untracked" => {
+                line.push("push".to_string());
+                if subcommand == "push-untracked" {
+                    line.push("--include-untracked".to_string());
+                }
+                if !text.is_empty() {
+                    line.push("-m".to_string());
+                    line.push(text);
+                }
+            }
+            "list" => line.push(subcommand.to_string()),
+            _ => {
+                line.push(subcommand.to_string());
+                if subcommand == "show" {
+                    line.push("-p".to_string());
+                }
+                if !text.is_empty() {
+                    line.push(text);
+                }
+            }
+        }
+
+        let mut output: Vec<u8> = vec![];
+        if let Err(error) = stash::git_stash(&git_dir, &working_dir, line, &mut output) {
+            show_message_dialog("Error", &error.to_string());
+            return;
+        }
+        if subcommand != "list" && subcommand != "show" {
+            cloned_entry.set_text("");
+            if let Err(error) = set_staging_area_texts(&builder_clone) {
+                eprintln!("{:?}", error);
+            }
+            let list_line = vec!["git".to_string(), "stash".to_string(), "list".to_string()];
+            if let Err(error) = stash::git_stash(&git_dir, &working_dir, list_line, &mut output) {
+                eprintln!("{:?}", error);
+            }
+        }
+        update_show_ref_view(&cloned_text_view, output);
+    });
+}
+
+/// Sets up the "Stash" window with its buttons and their corresponding actions.
+///
+/// This function initializes the components of the "Stash" window, such as the text view,
+/// the entry and the buttons, and connects every button to the `git stash` subcommand it runs.
+///
+/// # Arguments
+///
+/// * `builder` - The GTK `Builder` containing the UI elements for the "Stash" window.
+///
+pub fn stash_window(builder: &Builder) {
+    let stash_view = match get_text_view(builder, "stash-view") {
+        Some(view) => view,
+        None => {
+            eprintln!("Failed to obtain the text view..");
+            return;
+        }
+    };
+
+    let stash_entry = match get_entry(builder, "stash-entry") {
+        Some(entry) => entry,
+        None => {
+            eprintln!("Failed to obtain the entry.");
+            return;
+        }
+    };
+
+    apply_entry_style(&stash_entry);
+
+    let buttons = [
+        ("stash-push-button", "push"),
+        ("stash-untracked-button", "push-untracked"),
+        ("stash-list-button", "list"),
+        ("stash-show-button", "show"),
+        ("stash-apply-button", "apply"),
+        ("stash-pop-button", "pop"),
+        ("stash-drop-button", "drop"),
+    ];
+    for (button_id, subcommand) in buttons {
+        let button = get_button(builder, button_id);
+        handle_apply_button_style(&button);
+        stash_button_on_clicked(builder, &button, &stash_view, &stash_entry, subcommand);
+    }
+}
+
+/// Fills the combo box of the "Blame" window with the files tracked in the index.
+///
+/// The previous items are removed first, so the list can be refreshed after files are added
+/// or removed.
+///
+/// # Arguments
+///
+/// * `combo_box` - The GTK `ComboBoxText` listing the files that can be blamed.
+///
+/// # Errors
+///
+/// Returns an error if the git directory or the index cannot be read.
+///
+fn update_blame_files(combo_box: &ComboBoxText) -> io::Result<()> {
+    let git_dir = obtain_git_dir()?;
+    let index_path = format!("{}/{}", git_dir, INDEX);
+    let gitignore_path = format!("{}/{}", git_dir, GIT_IGNORE);
+    let index = Index::load(&index_path, &git_dir, &gitignore_path)?;
+    let mut paths: Vec<&String> = index.iter().map(|(path, _)| path).collect();
+    paths.sort();
+
+    combo_box.remove_all();
+    for path in paths {
+        combo_box.append_text(path);
+    }
+    Ok(())
+}
+
+/// Handles the click event of the "Blame" button of the "Blame" window.
+///
+/// This function is connected to the click event of a GTK button. When the button is clicked,
+/// it runs `git blame` on the file selected in the combo box and shows, for every line, the
+/// commit that last modified it. The text of the entry is appended to the command line, so it
+/// can hold a revision and `-L <start>,<end>` ranges.
+///
+/// # Arguments
+///
+/// * `button` - The GTK `Button` triggering the click event.
+/// * `text_view` - The GTK `TextView` where the output will be displayed.
+/// * `combo_box` - The GTK `ComboBoxText` with the selected file.
+/// * `entry` - The GTK `Entry` with the optional revision and line ranges.
+///
+fn blame_button_on_clicked(
+    button: &Button,
+    text_view: &gtk::TextView,
+    combo_box: &ComboBoxText,
+    entry: &Entry,
+) {
+    let cloned_text_view = text_view.clone();
+    let cloned_combo_box = combo_box.clone();
+    let cloned_entry = entry.clone();
+    button.connect_clicked(move |_| {
+        let git_dir = match obtain_git_dir() {
+            Ok(dir) => dir,
+            Err(_e) => {
+                show_message_dialog(
+                    "Fatal error",
+                    "Something went wrong while trying to retrieve the data. :(",
+                );
+                return;
+            }
+        };
+        let path = match cloned_combo_box.get_active_text() {
+            Some(path) => path.to_string(),
+            None => {
+                show_message_dialog("Error", "Select a file to blame.");
+                return;
+            }
+        };
+
+        let mut line = vec!["git".to_string(), "blame".to_string()];
+        let options = cloned_entry.get_text().to_string();
+        line.extend(options.split_whitespace().map(|option| option.to_string()));
+        line.push("--".to_string());
+        line.push(path);
+
+        let mut output: Vec<u8> = vec![];
+        if let Err(error) = blame::git_blame(&git_dir, line, &mut output) {
+            show_message_dialog("Error", &error.to_string());
+            return;
+        }
+        update_show_ref_view(&cloned_text_view, output);
+    });
+}
+
+/// Sets up the "Blame" window with its buttons and their corresponding actions.
+///
+/// This function initializes the components of the "Blame" window: the combo box is filled
+/// with the tracked files, the "Reload files" button refreshes that list and the "Blame"
+/// button annotates the selected file.
+///
+/// # Arguments
+///
+/// * `builder` - The GTK `Builder` containing the UI elements for the "Blame" window.
+///
+/// # Errors
+///
+/// Returns an error if a widget of the window cannot be found.
+///
+pub fn blame_window(builder: &Builder) -> io::Result<()> {
+    let blame_view = match get_text_view(builder, "blame-view") {
+        Some(view) => view,
+        None => {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "Failed to obtain the blame text view.",
+            ));
+        }
+    };
+    let blame_entry = match get_entry(builder, "blame-entry") {
+        Some(entry) => entry,
+        None => {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "Failed to obtain the blame entry.",
+            ));
+        }
+    };
+    let blame_files = get_combo_box(builder, "blame-files")?;
+    apply_entry_style(&blame_entry);
+    if let Err(error) = update_blame_files(&blame_files) {
+        eprintln!("{:?}", error);
+    }
+
+    let reload_button = get_button(builder, "blame-reload-button");
+    handle_apply_button_style(&reload_button);
+    let cloned_blame_files = blame_files.clone();
+    reload_button.connect_clicked(move |_| {
+        if let Err(error) = update_blame_files(&cloned_blame_files) {
+            show_message_dialog("Error", &error.to_string());
+        }
+    });
+
+    let blame_button = get_button(builder, "blame-button");
+    handle_apply_button_style(&blame_button);
+    blame_button_on_clicked(&blame_button, &blame_view, &blame_files, &blame_entry);
+    Ok(())
+}
+
 /// Calls the `git config set-user-info` command to update the user's name and email in the Git configuration.
 ///
 /// This function obtains the Git directory (assumed to be in a folder named ".mgit") and constructs
@@ -5113,9 +5385,13 @@ fn check_commit_message(message: &str) -> io::Result<()> {
     Ok(())
 }
 
-/// Make a new commit with the provided message.
-fn create_new_commit(git_dir_path: &str, message: &str, git_ignore_path: &str) -> io::Result<()> {
-    let result = commit::new_commit(git_dir_path, message, git_ignore_path);
+/// Make a new commit with the provided message, running the commit hooks.
+fn create_new_commit(git_dir_path: &str, message: &str, root_dir: &str) -> io::Result<()> {
+    let options = commit::CommitOptions {
+        message: Some(message.to_string()),
+        ..Default::default()
+    };
+    let result = commit::commit_with_options(git_dir_path, root_dir, &options);
     match result {
         Ok(_) => {}
         Err(e) => {
@@ -5129,10 +5405,9 @@ fn create_new_commit(git_dir_path: &str, message: &str, git_ignore_path: &str) -
 fn perform_commit(builder: &gtk::Builder, message: String) -> io::Result<()> {
     let current_dir_str = get_current_dir_string()?;
     let git_dir_path = get_git_directory_path(&PathBuf::from(&current_dir_str))?;
-    let git_ignore_path = format!("{}/{}", current_dir_str, GIT_IGNORE);
 
     check_commit_message(&message)?;
-    create_new_commit(&git_dir_path, &message, &git_ignore_path)?;
+    create_new_commit(&git_dir_path, &message, &current_dir_str)?;
 
     set_commit_history_view(builder)?;
     Ok(())
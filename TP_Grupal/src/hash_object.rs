@@ -4,7 +4,9 @@ use std::{
     path::Path,
 };
 
-use crate::utils::get_current_time;
+use crate::attributes::Attributes;
+use crate::filter;
+use crate::utils::{self, get_current_time};
 use crate::{configuration::LOGGER_COMMANDS_FILE, logger::Logger};
 use flate2::{write::ZlibEncoder, Compression};
 use sha1::{Digest, Sha1};
@@ -75,7 +77,8 @@ pub fn hash_file_content(path: &str, file_type: &str) -> io::Result<String> {
 ///
 pub fn get_file_object_path(path: &str, git_dir_path: &str) -> io::Result<String> {
     let content_hash = hash_file_content(path, "blob")?;
-    let output_file_dir = git_dir_path.to_string() + "/objects/" + &content_hash[..2] + "/";
+    let output_file_dir =
+        utils::git_path(git_dir_path, &format!("objects/{}/", &content_hash[..2]));
     let output_file_str = output_file_dir + &content_hash[2..];
     Ok(output_file_str)
 }
@@ -101,6 +104,9 @@ fn create_directory(name: &str) -> io::Result<()> {
 /// The file is compressed using zlib.
 ///
 /// The content is prepended with the header: blob <size>\0. The size is the size of the content.
+/// The content goes through the `clean` command of the filter driver of the file, and text files
+/// are stored with `LF` line endings, when the `.gitattributes` files of the working tree ask for
+/// it.
 ///
 /// If the directory is not a git directory, it returns an error.
 /// If the directory does not have an objects folder, it returns an error.
@@ -113,15 +119,37 @@ fn create_directory(name: &str) -> io::Result<()> {
 ///
 ///
 pub fn store_file(path: &str, git_dir_path: &str) -> io::Result<String> {
-    let content_hash = hash_file_content(path, "blob")?;
-    let output_file_dir: String = git_dir_path.to_string() + "/objects/" + &content_hash[..2] + "/";
-    create_directory(&output_file_dir)?;
-    let output_file_str = output_file_dir + &content_hash[2..];
-    compress_content(path, output_file_str.as_str(), "blob")?;
+    let content = std::fs::read_to_string(path)?;
+    let attributes = Attributes::for_file(git_dir_path, path)?;
+    let content = filter::convert_to_git(git_dir_path, &attributes, content)?;
+    let content_hash = store_string_to_file(&content, git_dir_path, "blob")?;
     log_hash_object(path, git_dir_path)?;
     Ok(content_hash)
 }
 
+/// Returns the sha1 hash of a file of the working tree, as it would be stored by `store_file`.
+/// Unlike `hash_file_content`, the content is first filtered and normalized as the
+/// `.gitattributes` files of the working tree say, so files that only differ in their line
+/// endings get the same hash.
+/// **It does not store the file**.
+///
+/// ## Parameters
+/// * `root_dir` - The root of the working tree.
+/// * `path` - The path to the file, relative to the root.
+///
+pub fn hash_working_file(root_dir: &str, path: &str) -> io::Result<String> {
+    let file_path = if root_dir.is_empty() {
+        path.to_string()
+    } else {
+        format!("{}/{}", root_dir, path)
+    };
+    let content = std::fs::read_to_string(file_path)?;
+    let git_dir = utils::get_git_dir_of(if root_dir.is_empty() { "." } else { root_dir });
+    let attributes = Attributes::load(root_dir, path)?;
+    let content = filter::convert_to_git(&git_dir, &attributes, content)?;
+    Ok(hash_string(&format!("blob {}\0{}", content.len(), content)))
+}
+
 /// Stores the given content in the objects folder of the given directory.
 /// Directory must be the path to the git folder.
 /// Returns the hash of the content or an error if the directory is not a git directory or if the directory does not have an objects folder.
@@ -148,7 +176,8 @@ pub fn store_string_to_file(
 ) -> io::Result<String> {
     let content_hash = hash_string(&format!("{} {}\0{}", file_type, content.len(), content));
 
-    let output_file_dir = git_dir_path.to_string() + "/objects/" + &content_hash[..2] + "/";
+    let output_file_dir =
+        utils::git_path(git_dir_path, &format!("objects/{}/", &content_hash[..2]));
     create_directory(&output_file_dir)?;
     let output_file_str = output_file_dir + &content_hash[2..];
 
@@ -209,7 +238,8 @@ pub fn store_bytes_array_to_file(
     let content_hash = hash_byte_array(&complete);
 
     //Create the directory where the file will be stored
-    let output_file_dir = git_dir_path.to_string() + "/objects/" + &content_hash[..2] + "/";
+    let output_file_dir =
+        utils::git_path(git_dir_path, &format!("objects/{}/", &content_hash[..2]));
     create_directory(&output_file_dir)?;
 
     //Create the path where the file will be stored
@@ -266,7 +296,7 @@ pub fn store_tree_to_file(
         data.write_all(&hash)?;
     }
     let tree_hash = hash_byte_array(&data);
-    let output_file_dir = git_dir_path.to_string() + "/objects/" + &tree_hash[..2] + "/";
+    let output_file_dir = utils::git_path(git_dir_path, &format!("objects/{}/", &tree_hash[..2]));
     create_directory(&output_file_dir)?;
     let output_file_str = output_file_dir + &tree_hash[2..];
     compress_tree(data, &output_file_str)?;
@@ -424,4 +454,26 @@ mod tests {
         let hash = hash_file_content(absolute_path, "blob").unwrap();
         assert_eq!(hash, "c57eff55ebc0c54973903af5f72bac72762cf4f4");
     }
+
+    #[test]
+    fn test_store_file_normalizes_line_endings_of_text_files() {
+        let root_dir = "tests/hash_object/attributes";
+        let _ = fs::remove_dir_all(root_dir);
+        let git_dir = format!("{}/.mgit", root_dir);
+        fs::create_dir_all(format!("{}/objects", git_dir)).unwrap();
+        fs::write(format!("{}/.gitattributes", root_dir), "*.txt text\n").unwrap();
+        fs::write(format!("{}/crlf.txt", root_dir), "a\r\nb\r\n").unwrap();
+        fs::write(format!("{}/crlf.bin", root_dir), "a\r\nb\r\n").unwrap();
+
+        let normalized = hash_string("blob 4\0a\nb\n");
+        let hash = store_file(&format!("{}/crlf.txt", root_dir), &git_dir).unwrap();
+        assert_eq!(hash, normalized);
+        assert_eq!(hash_working_file(root_dir, "crlf.txt").unwrap(), normalized);
+        assert_eq!(
+            hash_working_file(root_dir, "crlf.bin").unwrap(),
+            hash_file_content(&format!("{}/crlf.bin", root_dir), "blob").unwrap()
+        );
+
+        fs::remove_dir_all(root_dir).unwrap();
+    }
 }
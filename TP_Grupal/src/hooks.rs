@@ -0,0 +1,338 @@
+use crate::configuration::LOGGER_COMMANDS_FILE;
+use crate::logger::Logger;
+use crate::utils::{self, get_current_time};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread;
+
+/// Directory of the git directory holding the hooks.
+pub const HOOKS_DIR: &str = "hooks";
+
+/// Logs that a hook was run, with its exit status.
+///
+/// # Arguments
+///
+/// * `name` - The name of the hook, such as `pre-commit`.
+/// * `status` - How the hook finished.
+///
+/// # Errors
+///
+/// Returns an `io::Result` indicating whether the operation was successful.
+///
+fn log_hook(name: &str, status: &str) -> io::Result<()> {
+    let log_file_path = LOGGER_COMMANDS_FILE;
+    let mut logger = Logger::new(log_file_path)?;
+
+    let full_message = format!(
+        "Hook '{}': Status '{}', {}",
+        name,
+        status,
+        get_current_time()
+    );
+    logger.write_all(full_message.as_bytes())?;
+    logger.flush()?;
+    Ok(())
+}
+
+/// Tells whether a file can be executed.
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    match fs::metadata(path) {
+        Ok(metadata) => metadata.is_file() && metadata.permissions().mode() & 0o111 != 0,
+        Err(_) => false,
+    }
+}
+
+/// Tells whether a file can be executed.
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+/// Returns the path to a hook, if it is installed and executable.
+///
+/// Hooks live in the `hooks` directory of the main git directory, so linked working trees share
+/// them.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the git directory.
+/// * `name` - The name of the hook, such as `pre-commit`.
+///
+pub fn find_hook(git_dir: &str, name: &str) -> Option<PathBuf> {
+    let path = Path::new(&utils::get_common_dir(git_dir))
+        .join(HOOKS_DIR)
+        .join(name);
+    if is_executable(&path) {
+        Some(path)
+    } else {
+        None
+    }
+}
+
+/// Runs a hook, if it is installed.
+///
+/// The hook runs from the root of the working tree, with `GIT_DIR` set to the absolute path of
+/// the git directory, and its output goes to the terminal. Hooks that take input, like
+/// `pre-push`, get it on their standard input.
+///
+/// The caller decides what a failure means: it aborts the operation for `pre-*` hooks and
+/// `commit-msg`, while the exit status of `post-*` hooks is ignored, as git does.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the git directory.
+/// * `name` - The name of the hook, such as `pre-commit`.
+/// * `args` - The arguments given to the hook.
+/// * `input` - What is written to the standard input of the hook, if anything.
+///
+/// # Errors
+///
+/// Returns an error if the hook cannot be run or exits with a non-zero status. A hook that is
+/// not installed is not an error.
+///
+pub fn run_hook(git_dir: &str, name: &str, args: &[&str], input: Option<&str>) -> io::Result<()> {
+    let Some(path) = find_hook(git_dir, name) else {
+        return Ok(());
+    };
+    let working_dir = match utils::get_working_dir(git_dir)? {
+        dir if dir.is_empty() => ".".to_string(),
+        dir => dir,
+    };
+    let mut child = hook_command(&path, git_dir, args, &working_dir, input.is_some())?.spawn()?;
+    if let (Some(mut stdin), Some(input)) = (child.stdin.take(), input) {
+        // A hook may exit without reading its input, which is not an error.
+        let _ = stdin.write_all(input.as_bytes());
+    }
+    let status = child.wait()?;
+    log_hook(name, &status.to_string())?;
+    if status.success() {
+        return Ok(());
+    }
+    Err(hook_failed_error(name, status.code()))
+}
+
+/// What a hook run on behalf of a client printed, and whether it succeeded.
+#[derive(Debug, Default, PartialEq)]
+pub struct HookOutput {
+    /// Whether the hook exited with a zero status.
+    pub success: bool,
+    /// What the hook wrote to its standard output, followed by its standard error.
+    pub output: String,
+}
+
+/// Runs one of the hooks invoked while receiving a push, if it is installed.
+///
+/// Unlike [`run_hook`], these hooks always run from the git directory, as there may be no working
+/// tree on the server, and their output is collected so it can be sent back to the client instead
+/// of being printed.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the git directory receiving the push.
+/// * `name` - The name of the hook, such as `pre-receive`.
+/// * `args` - The arguments given to the hook.
+/// * `input` - What is written to the standard input of the hook, if anything.
+///
+/// # Returns
+///
+/// The output of the hook, or `None` if it is not installed.
+///
+/// # Errors
+///
+/// Returns an error if the hook cannot be run. A hook exiting with a non-zero status is not an
+/// error, it is reported in the returned `HookOutput`.
+///
+pub fn run_receive_hook(
+    git_dir: &str,
+    name: &str,
+    args: &[&str],
+    input: Option<&str>,
+) -> io::Result<Option<HookOutput>> {
+    let Some(path) = find_hook(git_dir, name) else {
+        return Ok(None);
+    };
+    let mut child = hook_command(&path, git_dir, args, git_dir, input.is_some())?
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    // The input is written from another thread, so a hook that prints while it reads cannot
+    // block on a full pipe.
+    let writer = match (child.stdin.take(), input) {
+        (Some(mut stdin), Some(input)) => {
+            let input = input.as_bytes().to_vec();
+            Some(thread::spawn(move || stdin.write_all(&input)))
+        }
+        _ => None,
+    };
+    let result = child.wait_with_output()?;
+    // A hook may exit without reading its input, which is not an error.
+    if let Some(writer) = writer {
+        let _ = writer.join();
+    }
+    log_hook(name, &result.status.to_string())?;
+    let mut output = String::from_utf8_lossy(&result.stdout).to_string();
+    output.push_str(&String::from_utf8_lossy(&result.stderr));
+    Ok(Some(HookOutput {
+        success: result.status.success(),
+        output,
+    }))
+}
+
+/// Builds the command running a hook.
+///
+/// The hook runs from `current_dir`, so its path and the git directory are made absolute first.
+///
+/// # Arguments
+///
+/// * `path` - The path to the hook.
+/// * `git_dir` - The path to the git directory, exported as `GIT_DIR`.
+/// * `args` - The arguments given to the hook.
+/// * `current_dir` - The directory the hook runs from.
+/// * `piped_input` - Whether the standard input of the hook is piped, or closed.
+///
+/// # Errors
+///
+/// Returns an error if the paths cannot be made absolute.
+///
+fn hook_command(
+    path: &Path,
+    git_dir: &str,
+    args: &[&str],
+    current_dir: &str,
+    piped_input: bool,
+) -> io::Result<Command> {
+    let path = fs::canonicalize(path)?;
+    let git_dir_path = fs::canonicalize(git_dir)?;
+    let mut command = Command::new(path);
+    command
+        .args(args)
+        .current_dir(current_dir)
+        .env("GIT_DIR", git_dir_path)
+        .stdin(if piped_input {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        });
+    Ok(command)
+}
+
+/// Returns the error reported when a hook exits with a non-zero status.
+///
+/// # Arguments
+///
+/// * `name` - The name of the hook.
+/// * `code` - The exit code of the hook, or `None` if it was terminated by a signal.
+///
+fn hook_failed_error(name: &str, code: Option<i32>) -> io::Error {
+    io::Error::other(match code {
+        Some(code) => format!("error: hook '{}' exited with status {}", name, code),
+        None => format!("error: hook '{}' was terminated by a signal", name),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Installs a shell script as a hook.
+    #[cfg(unix)]
+    fn install_hook(git_dir: &str, name: &str, script: &str) {
+        use std::os::unix::fs::PermissionsExt;
+        let hooks_dir = format!("{}/{}", git_dir, HOOKS_DIR);
+        fs::create_dir_all(&hooks_dir).unwrap();
+        let path = format!("{}/{}", hooks_dir, name);
+        fs::write(&path, format!("#!/bin/sh\n{}\n", script)).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_hook_passes_arguments_and_input_and_reports_failures() {
+        let root_dir = "tests/hooks/run";
+        let _ = fs::remove_dir_all(root_dir);
+        let git_dir = format!("{}/.mgit", root_dir);
+        fs::create_dir_all(&git_dir).unwrap();
+
+        assert!(run_hook(&git_dir, "pre-push", &[], None).is_ok());
+        install_hook(
+            &git_dir,
+            "pre-push",
+            "echo \"$1 $2\" > args.txt\ncat > input.txt\ntest -d \"$GIT_DIR/hooks\"",
+        );
+        run_hook(&git_dir, "pre-push", &["origin", "url"], Some("refs\n")).unwrap();
+        assert_eq!(
+            fs::read_to_string(format!("{}/args.txt", root_dir)).unwrap(),
+            "origin url\n"
+        );
+        assert_eq!(
+            fs::read_to_string(format!("{}/input.txt", root_dir)).unwrap(),
+            "refs\n"
+        );
+
+        install_hook(&git_dir, "pre-commit", "exit 3");
+        let error = run_hook(&git_dir, "pre-commit", &[], None).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "error: hook 'pre-commit' exited with status 3"
+        );
+
+        // Hooks that are not executable are not run.
+        let post_commit = format!("{}/{}/post-commit", git_dir, HOOKS_DIR);
+        fs::write(&post_commit, "#!/bin/sh\nexit 1\n").unwrap();
+        assert!(run_hook(&git_dir, "post-commit", &[], None).is_ok());
+
+        fs::remove_dir_all(root_dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_receive_hook_runs_from_git_dir_and_collects_output() {
+        let git_dir = "tests/hooks/receive";
+        let _ = fs::remove_dir_all(git_dir);
+        fs::create_dir_all(git_dir).unwrap();
+
+        assert_eq!(
+            run_receive_hook(git_dir, "update", &[], None).unwrap(),
+            None
+        );
+        install_hook(
+            git_dir,
+            "pre-receive",
+            "test -d hooks || exit 2\nwhile read old new name; do echo \"$name\"; done\necho denied >&2\nexit 1",
+        );
+        let result = run_receive_hook(git_dir, "pre-receive", &[], Some("a b refs/heads/main\n"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            result,
+            HookOutput {
+                success: false,
+                output: "refs/heads/main\ndenied\n".to_string(),
+            }
+        );
+
+        fs::remove_dir_all(git_dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_receive_hook_echoing_a_large_input_does_not_block() {
+        let git_dir = "tests/hooks/receive_large";
+        let _ = fs::remove_dir_all(git_dir);
+        fs::create_dir_all(git_dir).unwrap();
+
+        install_hook(git_dir, "post-receive", "cat");
+        let input = "a b refs/heads/main\n".repeat(50_000);
+        let result = run_receive_hook(git_dir, "post-receive", &[], Some(&input))
+            .unwrap()
+            .unwrap();
+        assert!(result.success);
+        assert_eq!(result.output, input);
+
+        fs::remove_dir_all(git_dir).unwrap();
+    }
+}
@@ -215,13 +215,45 @@ impl Index {
 
     /// Lets the user to dump the index to a file that can be read un the future by Index
     ///
+    /// The entries are first written to `<path>.lock`, which then replaces the index, so a
+    /// failure halfway never leaves a truncated index behind. The lock file must not exist:
+    /// if it does, another process is writing the index and nothing is written.
+    ///
     /// May fail for an I/O error.
     pub fn write_file(&self) -> io::Result<()> {
-        let mut index_file = fs::File::create(&self.path)?;
+        let lock_path = format!("{}.lock", self.path);
+        let lock_file = match fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&lock_path)
+        {
+            Ok(file) => file,
+            Err(error) if error.kind() == io::ErrorKind::AlreadyExists => {
+                return Err(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    format!(
+                        "fatal: Unable to create '{}': File exists.\n\nAnother git process seems to be running in this repository.",
+                        lock_path
+                    ),
+                ))
+            }
+            Err(error) => return Err(error),
+        };
+        match self.write_entries(lock_file) {
+            Ok(()) => fs::rename(&lock_path, &self.path),
+            Err(error) => {
+                let _ = fs::remove_file(&lock_path);
+                Err(error)
+            }
+        }
+    }
+
+    /// Writes the entries of the index to the given file.
+    fn write_entries(&self, mut index_file: fs::File) -> io::Result<()> {
         for line in &self.map {
             writeln!(index_file, "{} {}", line.1, line.0)?;
         }
-        Ok(())
+        index_file.sync_all()
     }
 
     /// Returns an iterator over the key-value pairs in the map.
@@ -234,6 +266,11 @@ impl Index {
         self.map.iter()
     }
 
+    /// Returns the path to the Git directory where the objects of the index are stored.
+    pub fn get_git_dir(&self) -> &str {
+        &self.git_dir
+    }
+
     /// Checks if a path should be ignored based on the provided `ignorer`.
     ///
     /// This function checks if a given `path` should be ignored by using the provided `ignorer`.
@@ -633,4 +670,41 @@ mod tests {
         assert!(index.contains("tests/add/dir_to_add/recursive/recursive/recursive/a.txt"));
         Ok(())
     }
+
+    /// Test that the index is not written while its lock file exists.
+    ///
+    /// The test performs the following steps:
+    /// 1. Write an index file and a lock file next to it, as another process would.
+    /// 2. Try to write a new index with the `write_file` method.
+    /// 3. Assert that it fails, and that both the index and the lock file are left untouched.
+    /// 4. Remove the lock file and assert that the index can be written.
+    ///
+    /// # Panics
+    ///
+    /// The test may panic if any of the assertions fail.
+    #[test]
+    fn test_write_file_fails_if_the_index_is_locked() -> io::Result<()> {
+        let dir = "tests/index/lock";
+        let _ = fs::remove_dir_all(dir);
+        fs::create_dir_all(dir)?;
+        let index_path = format!("{}/index", dir);
+        let lock_path = format!("{}.lock", index_path);
+        fs::write(&index_path, "hashed old.txt\n")?;
+        fs::write(&lock_path, "")?;
+        let mut index = Index::new(&index_path, dir, "");
+        index.load_content("hashed new.txt");
+
+        let error = index.write_file().unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(fs::read_to_string(&index_path)?, "hashed old.txt\n");
+        assert!(fs::metadata(&lock_path).is_ok());
+
+        fs::remove_file(&lock_path)?;
+        index.write_file()?;
+        assert_eq!(fs::read_to_string(&index_path)?, "hashed new.txt\n");
+        assert!(fs::metadata(&lock_path).is_err());
+
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
 }
@@ -66,13 +66,44 @@ fn create_file_if_not_exists(file: &str, content: &str) -> io::Result<()> {
     Ok(())
 }
 
+/// Copies a directory of the template into the git directory, recursively.
+///
+/// Files that already exist are kept, and copied files keep their permissions, so executable
+/// hooks stay executable.
+///
+/// ## Parameters
+///
+/// - `source`: Path to the directory of the template.
+/// - `destination`: Path where the directory is copied to.
+///
+/// ## Returns
+///
+/// Returns an `io::Result<()>` indicating whether the copy was successful or if an error occurred.
+///
+fn copy_template_directory(source: &Path, destination: &Path) -> io::Result<()> {
+    fs::create_dir_all(destination)?;
+    for entry in fs::read_dir(source)? {
+        let entry = entry?;
+        let src = entry.path();
+        let target = destination.join(entry.file_name());
+        if src.is_dir() {
+            copy_template_directory(&src, &target)?;
+        } else if !target.exists() {
+            fs::copy(&src, &target)?;
+        }
+    }
+    Ok(())
+}
+
 /// `git_init` is a function that initializes a simulated Git repository in the specified directory.
 ///
 /// ## Parameters
 ///
 /// - `directory`: Path to the directory where the repository will be initialized.
 /// - `initial_branch`: Name of the initial branch.
-/// - `template_directory`: Optional path to a template directory to copy files from.
+/// - `template_directory`: Optional path to a template directory to copy files from. Its files
+///   are copied to the repository directory and its directories, such as `hooks`, to the git
+///   directory.
 ///
 /// ## Returns
 ///
@@ -111,7 +142,8 @@ pub fn git_init(
     let index_file = format!("{}/index", &git_dir);
     create_file_if_not_exists(&index_file, "")?;
 
-    // Copy files from the template directory
+    // Copy files from the template directory: files go to the repository directory, while
+    // directories such as 'hooks' go to the git directory
     if let Some(template) = template_directory {
         let template_dir = Path::new(template);
         let repo_dir = Path::new(directory);
@@ -119,8 +151,12 @@ pub fn git_init(
             let entry = entry?;
             let file_name = entry.file_name();
             let src = entry.path();
-            let destination = repo_dir.join(file_name);
-            fs::copy(&src, &destination)?;
+            if src.is_dir() {
+                copy_template_directory(&src, &Path::new(&git_dir).join(file_name))?;
+            } else {
+                let destination = repo_dir.join(file_name);
+                fs::copy(&src, &destination)?;
+            }
         }
     }
 
@@ -382,4 +418,29 @@ mod test {
             panic!("Failed to remove temp directory: {}", err);
         }
     }
+
+    #[cfg(unix)]
+    #[test]
+    /// Test that the directories of a template, such as the hooks, go to the git directory.
+    fn test_git_init_with_template_installs_hooks() {
+        use std::os::unix::fs::PermissionsExt;
+        let temp_dir = create_temp_directory().expect("Failed to create temp directory");
+        let template_dir = create_temp_directory().expect("Failed to create template directory");
+        fs::create_dir_all(format!("{}/hooks", template_dir)).unwrap();
+        let hook_path = format!("{}/hooks/pre-commit", template_dir);
+        fs::write(&hook_path, "#!/bin/sh\nexit 0\n").unwrap();
+        fs::set_permissions(&hook_path, fs::Permissions::from_mode(0o755)).unwrap();
+
+        git_init(&temp_dir, GIT_DIR_FOR_TEST, "main", Some(&template_dir)).unwrap();
+
+        let git_dir_path = format!("{}/{}", temp_dir, GIT_DIR_FOR_TEST);
+        let installed = format!("{}/hooks/pre-commit", git_dir_path);
+        let mode = fs::metadata(&installed).unwrap().permissions().mode();
+        assert_eq!(mode & 0o111, 0o111);
+        assert!(!Path::new(&format!("{}/hooks", temp_dir)).exists());
+        assert!(crate::hooks::find_hook(&git_dir_path, "pre-commit").is_some());
+
+        fs::remove_dir_all(&temp_dir).unwrap();
+        fs::remove_dir_all(&template_dir).unwrap();
+    }
 }
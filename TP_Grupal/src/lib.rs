@@ -1,20 +1,33 @@
 pub mod add;
+pub mod am;
 pub mod api;
+pub mod apply;
+pub mod archive;
+pub mod attributes;
+pub mod bisect;
+pub mod blame;
 pub mod branch;
 pub mod branch_handler;
 pub mod cat_file;
 pub mod check_ignore;
 pub mod checkout;
+pub mod clean;
 pub mod client;
 pub mod clone;
 pub mod commit;
 pub mod config;
 pub mod configuration;
+pub mod describe;
 pub mod diff;
 pub mod fetch;
+pub mod filter;
+pub mod format_patch;
 pub mod git_config;
+pub mod graph;
+pub mod grep;
 pub mod gui;
 pub mod hash_object;
+pub mod hooks;
 pub mod ignorer;
 pub mod index;
 pub mod init;
@@ -23,19 +36,28 @@ pub mod logger;
 pub mod ls_files;
 pub mod ls_tree;
 pub mod merge;
+pub mod mv;
+pub mod notes;
 pub mod packfile;
 pub mod parse_commands;
+pub mod patch;
 pub mod pull;
 pub mod pull_request;
 pub mod push;
 pub mod rebase;
 pub mod remote;
 pub mod remote_handler;
+pub mod restore;
 pub mod rm;
 pub mod server;
 pub mod server_utils;
+pub mod show;
 pub mod show_ref;
+pub mod stash;
 pub mod status;
 pub mod tag;
+#[cfg(test)]
+pub mod test_utils;
 pub mod tree_handler;
 pub mod utils;
+pub mod worktree;
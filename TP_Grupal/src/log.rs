@@ -1,8 +1,17 @@
 use crate::{
-    cat_file, configuration::LOGGER_COMMANDS_FILE, logger::Logger, utils::get_current_time,
+    cat_file, commit,
+    configuration::LOGGER_COMMANDS_FILE,
+    diff::{self, DiffFormat, RenameOptions},
+    graph::Graph,
+    logger::Logger,
+    notes, restore, show_ref, tree_handler, utils,
+    utils::get_current_time,
 };
-use chrono::{TimeZone, Utc};
+use chrono::{FixedOffset, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use regex::Regex;
 use std::{
+    cmp::{Ordering, Reverse},
+    collections::{BTreeSet, BinaryHeap, HashMap, HashSet},
     fmt::Display,
     fs,
     io::{self, Error, Write},
@@ -10,18 +19,150 @@ use std::{
 };
 
 const DATE_ZERO: &str = "Thu Jan 1 00:00:00 1970 +0000";
+const LOG_USAGE: &str = "usage: git log [<options>] [<revision-range>] [[--] <path>...]";
+
+/// A commit waiting to be visited by a `LogIter`.
+///
+/// Commits are ordered by commit date, newest first. Commits with the same date are visited in
+/// the order they were found.
+struct QueuedLog {
+    timestamp: i64,
+    order: usize,
+    log: Log,
+}
+
+impl PartialEq for QueuedLog {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for QueuedLog {}
+
+impl PartialOrd for QueuedLog {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for QueuedLog {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.timestamp
+            .cmp(&other.timestamp)
+            .then(Reverse(self.order).cmp(&Reverse(other.order)))
+    }
+}
+
 /// LogIter is a structure that will help to iterate
 /// through commit logs in the correct way.
 ///
+/// Every parent of a commit is visited, not only the first one, and commits are returned
+/// newest first by commit date. Each commit is returned once, even if it can be reached
+/// through several merges.
+///
 /// Also implements Iterator trait so it has a lot
 /// of flexibility because of that
 pub struct LogIter {
-    log: Option<Log>,
+    git_dir: String,
+    oneline: bool,
+    queue: BinaryHeap<QueuedLog>,
+    seen: HashSet<String>,
+    found: usize,
 }
 
 impl LogIter {
-    fn new(log: Log) -> Self {
-        Self { log: Some(log) }
+    /// Creates an iterator that starts in the given commits and never visits the hidden ones.
+    ///
+    /// # Arguments
+    ///
+    /// * `git_dir` - A string representing the path to the Git directory.
+    /// * `starts` - The hashes of the commits the walk starts from.
+    /// * `hidden` - The hashes of the commits that must not be visited, nor their history through
+    ///   them.
+    /// * `oneline` - Whether the returned logs are displayed in oneline mode.
+    ///
+    fn new(git_dir: &str, starts: &[String], hidden: HashSet<String>, oneline: bool) -> Self {
+        let mut iter = Self {
+            git_dir: git_dir.to_string(),
+            oneline,
+            queue: BinaryHeap::new(),
+            seen: hidden,
+            found: 0,
+        };
+        for start in starts {
+            iter.push(start);
+        }
+        iter
+    }
+
+    /// Creates an iterator for a list of revisions, as given to `git log`.
+    ///
+    /// Every revision is resolved with `utils::resolve_revision` and can take one of these forms:
+    ///
+    /// - `<rev>`: the commits reachable from `<rev>`.
+    /// - `^<rev>`: excludes the commits reachable from `<rev>`.
+    /// - `<a>..<b>`: the commits reachable from `<b>` but not from `<a>`.
+    /// - `<a>...<b>`: the commits reachable from either `<a>` or `<b>`, but not from both.
+    ///
+    /// A missing side of a range means `HEAD`, and so does an empty list of revisions.
+    ///
+    /// # Arguments
+    ///
+    /// * `git_dir` - A string representing the path to the Git directory.
+    /// * `revisions` - The revisions to walk.
+    /// * `oneline` - Whether the returned logs are displayed in oneline mode.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a revision cannot be resolved or a commit cannot be read.
+    ///
+    pub fn from_revisions(git_dir: &str, revisions: &[String], oneline: bool) -> io::Result<Self> {
+        let resolve = |revision: &str| {
+            let revision = if revision.is_empty() {
+                "HEAD"
+            } else {
+                revision
+            };
+            utils::resolve_revision(git_dir, revision)
+        };
+        let mut starts = Vec::new();
+        let mut hidden = HashSet::new();
+        for revision in revisions {
+            if let Some((left, right)) = revision.split_once("...") {
+                let (left, right) = (resolve(left)?, resolve(right)?);
+                let left_ancestors = get_ancestors(git_dir, &left)?;
+                let right_ancestors = get_ancestors(git_dir, &right)?;
+                hidden.extend(left_ancestors.intersection(&right_ancestors).cloned());
+                starts.push(left);
+                starts.push(right);
+            } else if let Some((left, right)) = revision.split_once("..") {
+                hidden.extend(get_ancestors(git_dir, &resolve(left)?)?);
+                starts.push(resolve(right)?);
+            } else if let Some(excluded) = revision.strip_prefix('^') {
+                hidden.extend(get_ancestors(git_dir, &resolve(excluded)?)?);
+            } else {
+                starts.push(resolve(revision)?);
+            }
+        }
+        if starts.is_empty() && revisions.iter().all(|revision| revision.starts_with('^')) {
+            starts.push(resolve("HEAD")?);
+        }
+        Ok(Self::new(git_dir, &starts, hidden, oneline))
+    }
+
+    /// Queues a commit to be visited, unless it was already queued or is hidden.
+    fn push(&mut self, hash: &str) {
+        if !self.seen.insert(hash.to_string()) {
+            return;
+        }
+        if let Ok(log) = Log::load_from_hash(hash, &self.git_dir) {
+            self.queue.push(QueuedLog {
+                timestamp: log.get_commit_timestamp(),
+                order: self.found,
+                log: log.set_oneline(self.oneline),
+            });
+            self.found += 1;
+        }
     }
 }
 
@@ -29,11 +170,150 @@ impl Iterator for LogIter {
     type Item = Log;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let actual = self.log.clone();
-        if let Some(log) = &self.log {
-            self.log = log.get_parent_log();
+        let actual = self.queue.pop()?.log;
+        for parent in actual.parents.clone() {
+            self.push(&parent);
+        }
+        Some(actual)
+    }
+}
+
+/// Returns the hashes of a commit and all of its ancestors.
+///
+/// # Arguments
+///
+/// * `git_dir` - A string representing the path to the Git directory.
+/// * `hash` - The hash of the commit.
+///
+/// # Errors
+///
+/// Returns an error if one of the commits cannot be read.
+///
+pub fn get_ancestors(git_dir: &str, hash: &str) -> io::Result<HashSet<String>> {
+    let mut ancestors = HashSet::new();
+    let mut pending = vec![hash.to_string()];
+    while let Some(hash) = pending.pop() {
+        if ancestors.insert(hash.clone()) {
+            pending.extend(commit::get_commit_parents(&hash, git_dir)?);
+        }
+    }
+    Ok(ancestors)
+}
+
+/// Options that select which commits of a walk are shown by `git log`.
+///
+/// The default options show every commit.
+#[derive(Debug, Default, Clone)]
+pub struct LogOptions {
+    /// Maximum number of commits to show.
+    pub max_count: Option<usize>,
+    /// Number of matching commits to skip before starting to show them.
+    pub skip: usize,
+    /// Only show commits whose author (name and email) matches.
+    pub author: Option<Regex>,
+    /// Only show commits whose message matches.
+    pub grep: Option<Regex>,
+    /// Only show commits committed at or after this Unix timestamp.
+    pub since: Option<i64>,
+    /// Only show commits committed at or before this Unix timestamp.
+    pub until: Option<i64>,
+    /// `Some(true)` only shows merge commits and `Some(false)` hides them.
+    pub merges: Option<bool>,
+    /// Only show commits that changed one of these paths.
+    pub paths: Vec<String>,
+}
+
+/// The layout used to display a `Log`, selected with `--pretty` or `--format`.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub enum LogFormat {
+    /// The hash, author, date and message.
+    #[default]
+    Medium,
+    /// The abbreviated hash and the subject, in a single line.
+    Oneline,
+    /// The hash, author and subject.
+    Short,
+    /// The hash, author, committer and message.
+    Full,
+    /// The hash, author and committer with their dates, and message.
+    Fuller,
+    /// A template with `%` placeholders, see `Log::expand_format`.
+    Format(String),
+}
+
+impl LogFormat {
+    /// Parses the value given to `--pretty` or `--format`.
+    ///
+    /// Accepts the names of the built-in layouts (`oneline`, `short`, `medium`, `full` and
+    /// `fuller`), `format:<template>`, `tformat:<template>` and a bare template containing `%`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidInput` error if the value is not a known layout or a template.
+    ///
+    pub fn parse(value: &str) -> io::Result<Self> {
+        match value {
+            "oneline" => Ok(Self::Oneline),
+            "short" => Ok(Self::Short),
+            "medium" => Ok(Self::Medium),
+            "full" => Ok(Self::Full),
+            "fuller" => Ok(Self::Fuller),
+            _ => match value
+                .strip_prefix("format:")
+                .or_else(|| value.strip_prefix("tformat:"))
+            {
+                Some(template) => Ok(Self::Format(template.to_string())),
+                None if value.contains('%') => Ok(Self::Format(value.to_string())),
+                None => Err(Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("fatal: invalid --pretty format: {}", value),
+                )),
+            },
+        }
+    }
+}
+
+/// How dates are displayed, selected with `--date`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DateMode {
+    /// `Tue Nov 14 19:13:20 2023 -0300`
+    #[default]
+    Default,
+    /// `2023-11-14 19:13:20 -0300`
+    Iso,
+    /// `2023-11-14T19:13:20-03:00`
+    IsoStrict,
+    /// `Tue, 14 Nov 2023 19:13:20 -0300`
+    Rfc,
+    /// `2023-11-14`
+    Short,
+    /// `1700000000`
+    Unix,
+    /// `3 days ago`
+    Relative,
+}
+
+impl DateMode {
+    /// Parses the value given to `--date`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidInput` error if the value is not a known date mode.
+    ///
+    pub fn parse(value: &str) -> io::Result<Self> {
+        match value {
+            "default" => Ok(Self::Default),
+            "iso" | "iso8601" => Ok(Self::Iso),
+            "iso-strict" | "iso8601-strict" => Ok(Self::IsoStrict),
+            "rfc" | "rfc2822" => Ok(Self::Rfc),
+            "short" => Ok(Self::Short),
+            "unix" => Ok(Self::Unix),
+            "relative" => Ok(Self::Relative),
+            _ => Err(Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("fatal: unknown date format {}", value),
+            )),
         }
-        actual
     }
 }
 
@@ -58,12 +338,16 @@ pub struct Log {
     git_dir: String,
     commit_hash: String,
     tree_hash: String,
-    parent_hash: Option<String>,
+    parents: Vec<String>,
     message: String,
     author: String,
     date: String,
     committer: String,
-    oneline: bool,
+    committer_date: String,
+    decorations: Vec<String>,
+    note: Option<String>,
+    format: LogFormat,
+    date_mode: DateMode,
 }
 
 /// Creates a custom `io::Error` with the `InvalidData` kind, representing an error due to
@@ -115,7 +399,7 @@ impl Log {
         let last_commit_ref = head_content.trim().split(": ").last();
         match last_commit_ref {
             Some(refs) => {
-                let heads_path = format!("{}/{}", git_dir, refs);
+                let heads_path = utils::git_path(git_dir, refs);
                 if Path::new(&heads_path).exists() {
                     let hash = fs::read_to_string(heads_path)?;
                     Self::load_from_hash(hash.trim(), git_dir)
@@ -152,7 +436,11 @@ impl Log {
                 for line in commit_content.lines().take(n) {
                     log.parse_commit_header_line(line)?;
                 }
-                log.message = commit_content.lines().skip(n).collect();
+                log.message = commit_content
+                    .lines()
+                    .skip(n + 1)
+                    .collect::<Vec<&str>>()
+                    .join("\n");
                 log.git_dir = git_dir.to_string();
                 log.commit_hash = hash.to_string();
                 Ok(log)
@@ -187,7 +475,7 @@ impl Log {
                 self.tree_hash = hash.to_string();
             }
             Some(("parent", hash)) => {
-                self.parent_hash = Some(hash.to_string());
+                self.parents.push(hash.to_string());
             }
             Some(("author", author)) => {
                 let fields: Vec<&str> = author.split(' ').collect();
@@ -198,7 +486,15 @@ impl Log {
                 self.author = fields[0..len - 2].join(" ");
                 self.date = fields[len - 2..].join(" ")
             }
-            Some(("committer", committer)) => self.committer = committer.to_string(),
+            Some(("committer", committer)) => {
+                let fields: Vec<&str> = committer.split(' ').collect();
+                let len = fields.len();
+                if len < 4 {
+                    return Err(invalid_data_error(line));
+                }
+                self.committer = fields[0..len - 2].join(" ");
+                self.committer_date = fields[len - 2..].join(" ")
+            }
             _ => {}
         }
         Ok(())
@@ -221,71 +517,481 @@ impl Log {
     /// provided boolean value.
     ///
     fn set_oneline(mut self, oneline: bool) -> Self {
-        self.oneline = oneline;
+        self.format = if oneline {
+            LogFormat::Oneline
+        } else {
+            LogFormat::Medium
+        };
         self
     }
 
-    /// Retrieve the parent log of the current commit.
+    /// Set the layout used to display the log, and the way its dates are shown.
+    ///
+    /// # Arguments
     ///
-    /// This method attempts to load the log of the commit's parent, if it exists. If successful,
-    /// it returns an `Option<Log>` containing the parent log with an updated oneline mode, otherwise
-    /// it returns `None`.
+    /// * `format` - The layout, as selected with `--pretty` or `--format`.
+    /// * `date_mode` - The date mode, as selected with `--date`.
     ///
-    /// # Returns
+    fn set_format(mut self, format: LogFormat, date_mode: DateMode) -> Self {
+        self.format = format;
+        self.date_mode = date_mode;
+        self
+    }
+
+    /// Set the references shown next to the commit hash, such as `HEAD -> main` or `tag: v1.0`.
+    ///
+    /// # Arguments
+    ///
+    /// * `decorations` - The names of the references pointing to this commit, in the order they
+    ///   are displayed.
     ///
-    /// Returns an `Option<Log>` containing the parent log with an updated oneline mode if the parent
-    /// commit exists and can be loaded successfully. Returns `None` otherwise.
+    fn set_decorations(mut self, decorations: Vec<String>) -> Self {
+        self.decorations = decorations;
+        self
+    }
+
+    /// Set the note attached to the commit, shown under its message.
+    ///
+    /// # Arguments
+    ///
+    /// * `note` - The text of the note, as stored by `git notes`, or `None` if there is none.
+    ///
+    fn set_note(mut self, note: Option<String>) -> Self {
+        self.note = note;
+        self
+    }
+
+    /// Returns the commit date as a Unix timestamp, or 0 if it cannot be parsed.
+    fn get_commit_timestamp(&self) -> i64 {
+        self.committer_date
+            .split(' ')
+            .next()
+            .and_then(|secs| secs.parse().ok())
+            .unwrap_or(0)
+    }
+
+    /// Checks whether the commit changed any of the given paths.
     ///
-    fn get_parent_log(&self) -> Option<Self> {
-        if let Some(parent) = &self.parent_hash {
-            if let Ok(log) = Log::load_from_hash(parent, &self.git_dir) {
-                return Some(log.set_oneline(self.oneline));
+    /// A root commit is compared against an empty tree. A merge commit only counts as a change if
+    /// it differs from every parent in those paths, since otherwise the change was taken from one
+    /// of the merged branches, which are shown on their own.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the tree of the commit or one of its parents cannot be read.
+    ///
+    fn changes_paths(&self, paths: &[String]) -> io::Result<bool> {
+        let tree = tree_handler::load_tree_from_commit(&self.commit_hash, &self.git_dir)?;
+        let touches = |parent_tree: Option<&tree_handler::Tree>| {
+            diff::get_tree_changes(parent_tree, Some(&tree))
+                .iter()
+                .any(|change| {
+                    paths
+                        .iter()
+                        .any(|path| restore::path_matches_pathspec(&change.path, path))
+                })
+        };
+        if self.parents.is_empty() {
+            return Ok(touches(None));
+        }
+        for parent in &self.parents {
+            let parent_tree = tree_handler::load_tree_from_commit(parent, &self.git_dir)?;
+            if !touches(Some(&parent_tree)) {
+                return Ok(false);
             }
         }
-        None
+        Ok(true)
+    }
+
+    /// Returns the files changed by the commit with respect to its first parent, or every file
+    /// of the commit if it has no parents.
+    ///
+    /// # Arguments
+    ///
+    /// * `renames` - How renamed and copied files are detected, or `None` to list them as
+    ///   additions and deletions.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the tree of the commit or its parent, or the blobs compared while
+    /// detecting renames, cannot be read.
+    ///
+    pub fn get_changes(&self, renames: Option<RenameOptions>) -> io::Result<Vec<diff::FileChange>> {
+        let tree = tree_handler::load_tree_from_commit(&self.commit_hash, &self.git_dir)?;
+        let parent_tree = match self.parents.first() {
+            Some(parent) => Some(tree_handler::load_tree_from_commit(parent, &self.git_dir)?),
+            None => None,
+        };
+        diff::get_tree_changes_with_renames(
+            parent_tree.as_ref(),
+            Some(&tree),
+            &self.git_dir,
+            renames,
+        )
+    }
+
+    /// Returns the path a file had before the commit renamed it, as used by `--follow`.
+    ///
+    /// # Returns
+    ///
+    /// Returns `None` if the commit did not rename the file to `path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the trees or blobs of the commit cannot be read.
+    ///
+    fn get_rename_source(&self, path: &str) -> io::Result<Option<String>> {
+        if self.parents.is_empty() {
+            return Ok(None);
+        }
+        let source = self
+            .get_changes(Some(RenameOptions::default()))?
+            .into_iter()
+            .find(|change| change.path == path)
+            .and_then(|change| change.source)
+            .filter(|source| !source.copied)
+            .map(|source| source.path);
+        Ok(source)
+    }
+
+    /// Describes the changes of the commit as selected by `-p`, `--stat`, `--numstat` and
+    /// `--name-status`.
+    ///
+    /// Merge commits are not described, like `git log` does by default. When the description is
+    /// not a patch, it is followed by a blank line to separate it from the next commit.
+    ///
+    /// # Arguments
+    ///
+    /// * `paths` - If not empty, only the changes to these paths are described.
+    /// * `diff_format` - How the changes are described.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the trees or blobs of the commit cannot be read.
+    ///
+    fn describe_changes(&self, paths: &[String], diff_format: DiffFormat) -> io::Result<String> {
+        if diff_format.is_empty() || self.parents.len() > 1 {
+            return Ok(String::new());
+        }
+        let mut changes = self.get_changes(diff_format.renames)?;
+        if !paths.is_empty() {
+            changes.retain(|change| {
+                paths.iter().any(|path| {
+                    restore::path_matches_pathspec(&change.path, path)
+                        || restore::path_matches_pathspec(change.old_path(), path)
+                })
+            });
+        }
+        let mut description = Vec::new();
+        diff::write_changes(&changes, &self.git_dir, diff_format, &mut description)?;
+        if !description.is_empty() && !diff_format.patch {
+            description.push(b'\n');
+        }
+        Ok(String::from_utf8_lossy(&description).to_string())
+    }
+
+    /// Checks whether the commit is selected by the given options.
+    ///
+    /// The count and skip options are not considered here, since they depend on the position of
+    /// the commit in the walk.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the options limit the paths and the trees cannot be read.
+    ///
+    pub fn matches(&self, options: &LogOptions) -> io::Result<bool> {
+        let timestamp = self.get_commit_timestamp();
+        let selected = options
+            .author
+            .as_ref()
+            .is_none_or(|author| author.is_match(&self.author))
+            && options
+                .grep
+                .as_ref()
+                .is_none_or(|grep| grep.is_match(&self.message))
+            && options.since.is_none_or(|since| timestamp >= since)
+            && options.until.is_none_or(|until| timestamp <= until)
+            && options
+                .merges
+                .is_none_or(|merges| merges == (self.parents.len() > 1));
+        if !selected || options.paths.is_empty() {
+            return Ok(selected);
+        }
+        self.changes_paths(&options.paths)
+    }
+
+    /// Splits an identity such as `name email` into the name and the email.
+    fn split_identity(identity: &str) -> (&str, &str) {
+        match identity.rsplit_once(' ') {
+            Some((name, email)) => (name, email.trim_start_matches('<').trim_end_matches('>')),
+            None => (identity, ""),
+        }
+    }
+
+    /// Returns the references pointing to the commit, in the form used by `%d`.
+    fn get_decorations(&self) -> String {
+        if self.decorations.is_empty() {
+            String::new()
+        } else {
+            format!(" ({})", self.decorations.join(", "))
+        }
     }
 
-    fn get_formatted_date(&self) -> String {
-        let (secs, offset) = &self.date.split_once(' ').unwrap_or(("0", "0"));
-        let secs = secs.parse::<i64>().unwrap_or(0);
-        let offset_int = offset.parse::<i64>().unwrap_or(0) * 36;
-        match Utc.timestamp_opt(secs + offset_int, 0) {
-            chrono::LocalResult::Single(date) => {
-                let date = date.format("%a %b %e %T %Y");
-                format!("{} {}", date, offset)
+    /// Expands the placeholders of a `--format` template.
+    ///
+    /// The supported placeholders are:
+    ///
+    /// - `%H`/`%h`: commit hash, complete and abbreviated.
+    /// - `%T`/`%t`: tree hash, complete and abbreviated.
+    /// - `%P`/`%p`: parent hashes, complete and abbreviated.
+    /// - `%an`, `%ae`, `%ad`: author name, email and date (following `--date`).
+    /// - `%ar`, `%at`, `%ai`, `%aI`, `%aD`, `%as`: author date, relative, as a Unix timestamp,
+    ///   ISO, strict ISO, RFC 2822 and short.
+    /// - `%cn`, `%ce`, `%cd`, `%cr`, `%ct`, `%ci`, `%cI`, `%cD`, `%cs`: the same for the committer.
+    /// - `%s`, `%b`, `%B`: subject, body and raw message.
+    /// - `%d`/`%D`: reference names, with and without the surrounding ` (...)`.
+    /// - `%N`: the note attached to the commit.
+    /// - `%n` and `%%`: a newline and a `%`.
+    ///
+    /// Unknown placeholders are left as they are.
+    ///
+    pub fn expand_format(&self, template: &str) -> String {
+        let abbreviate = |hash: &String| hash.chars().take(7).collect::<String>();
+        let (subject, body) = match self.message.split_once('\n') {
+            Some((subject, body)) => (subject, body.trim_start_matches('\n')),
+            None => (self.message.as_str(), ""),
+        };
+        let mut output = String::new();
+        let mut chars = template.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                output.push(c);
+                continue;
             }
-            _ => DATE_ZERO.to_string(),
+            let placeholder = match chars.next() {
+                Some(first @ ('a' | 'c')) => match chars.peek() {
+                    Some(&second) => {
+                        chars.next();
+                        format!("{}{}", first, second)
+                    }
+                    None => first.to_string(),
+                },
+                Some(other) => other.to_string(),
+                None => String::new(),
+            };
+            let (identity, date) = match placeholder.chars().next() {
+                Some('c') => (self.committer.as_str(), self.committer_date.as_str()),
+                _ => (self.author.as_str(), self.date.as_str()),
+            };
+            let (name, email) = Self::split_identity(identity);
+            let expanded = match placeholder.as_str() {
+                "H" => self.commit_hash.clone(),
+                "h" => abbreviate(&self.commit_hash),
+                "T" => self.tree_hash.clone(),
+                "t" => abbreviate(&self.tree_hash),
+                "P" => self.parents.join(" "),
+                "p" => self
+                    .parents
+                    .iter()
+                    .map(abbreviate)
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                "an" | "cn" => name.to_string(),
+                "ae" | "ce" => email.to_string(),
+                "ad" | "cd" => format_date_with(date, self.date_mode),
+                "ar" | "cr" => format_date_with(date, DateMode::Relative),
+                "at" | "ct" => format_date_with(date, DateMode::Unix),
+                "ai" | "ci" => format_date_with(date, DateMode::Iso),
+                "aI" | "cI" => format_date_with(date, DateMode::IsoStrict),
+                "aD" | "cD" => format_date_with(date, DateMode::Rfc),
+                "as" | "cs" => format_date_with(date, DateMode::Short),
+                "s" => subject.to_string(),
+                "b" => body.to_string(),
+                "B" => self.message.clone(),
+                "d" => self.get_decorations(),
+                "D" => self.decorations.join(", "),
+                "N" => self.note.clone().unwrap_or_default(),
+                "n" => "\n".to_string(),
+                "%" => "%".to_string(),
+                other => format!("%{}", other),
+            };
+            output.push_str(&expanded);
         }
+        output
     }
 
     /// Returns an iterator starting in 'self'
     ///
-    /// When accessing to the next Log, it refers to 'parent log'
+    /// The iterator visits every ancestor of 'self', newest first
     ///
     /// self is consumed
     pub fn iter(self) -> LogIter {
-        LogIter::new(self)
+        let start = [self.commit_hash.clone()];
+        let oneline = self.format == LogFormat::Oneline;
+        LogIter::new(&self.git_dir, &start, HashSet::new(), oneline)
     }
 }
 
 impl Display for Log {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let commit = format!("\x1b[0;33mcommit {}\x1b[0m", &self.commit_hash);
-        let message = self
+        if let LogFormat::Format(template) = &self.format {
+            return write!(f, "{}", self.expand_format(template));
+        }
+        let commit = format!(
+            "\x1b[0;33mcommit {}{}\x1b[0m",
+            &self.commit_hash,
+            self.get_decorations()
+        );
+        let message: Vec<String> = self
             .message
             .lines()
-            .fold(String::new(), |acc, line| format!("{}\t{}", acc, line));
+            .map(|line| format!("\t{}", line))
+            .collect();
+        let subject = message.first().cloned().unwrap_or_default();
+        let note = match &self.note {
+            Some(note) => {
+                let lines: Vec<String> = note.lines().map(|line| format!("\t{}", line)).collect();
+                format!("\n\nNotes:\n{}", lines.join("\n"))
+            }
+            None => String::new(),
+        };
 
-        if self.oneline {
-            let commit = commit.replace("commit ", "");
-            return write!(f, "{} {}", commit, message);
+        let author = format!("Author: {}", &self.author);
+        let committer = format!("Commit: {}", &self.committer);
+        let date = format_date_with(&self.date, self.date_mode);
+        let commit_date = format_date_with(&self.committer_date, self.date_mode);
+        match self.format {
+            LogFormat::Oneline => {
+                let commit = commit.replace("commit ", "");
+                write!(f, "{} {}", commit, subject)
+            }
+            LogFormat::Short => writeln!(f, "{}\n{}\n\n{}", commit, author, subject),
+            LogFormat::Full => writeln!(
+                f,
+                "{}\n{}\n{}\n\n{}{}",
+                commit,
+                author,
+                committer,
+                message.join("\n"),
+                note
+            ),
+            LogFormat::Fuller => writeln!(
+                f,
+                "{}\n{}\nAuthorDate: {}\n{}\nCommitDate: {}\n\n{}{}",
+                commit,
+                author,
+                date,
+                committer,
+                commit_date,
+                message.join("\n"),
+                note
+            ),
+            _ => {
+                let date = format!("Date: {}", date);
+                writeln!(
+                    f,
+                    "{}\n{}\n{}\n\n{}{}",
+                    commit,
+                    author,
+                    date,
+                    message.join("\n"),
+                    note
+                )
+            }
         }
+    }
+}
 
-        let author = format!("Author: {}", &self.author);
-        let date = self.get_formatted_date();
-        let date = format!("Date: {}", date);
-        writeln!(f, "{}\n{}\n{}\n\n{}", commit, author, date, message)
+/// Formats a raw Git date into the human readable form shown by `log` and `show`.
+///
+/// # Arguments
+///
+/// * `date` - The date as stored in commit and tag headers: seconds since the epoch followed by the
+///   timezone offset, e.g. `1700000000 -0300`.
+///
+/// # Returns
+///
+/// Returns the date in the local time of its offset, e.g. `Tue Nov 14 19:13:20 2023 -0300`, or the
+/// epoch if the date cannot be represented.
+///
+pub fn format_date(date: &str) -> String {
+    format_date_with(date, DateMode::Default)
+}
+
+/// Parses a timezone offset such as `-0300` or `+0530` into seconds east of UTC.
+/// Fails if the offset is not a sign followed by four digits.
+fn parse_offset(offset: &str) -> io::Result<i32> {
+    let (sign, digits) = match offset.strip_prefix('-') {
+        Some(digits) => (-1, digits),
+        None => (1, offset.strip_prefix('+').unwrap_or(offset)),
+    };
+    if digits.len() != 4 || !digits.bytes().all(|byte| byte.is_ascii_digit()) {
+        return Err(Error::new(
+            io::ErrorKind::InvalidData,
+            format!("invalid timezone offset: '{}'", offset),
+        ));
+    }
+    let number = |digits: &str| digits.parse::<i32>().unwrap_or(0);
+    Ok(sign * (number(&digits[..2]) * 3600 + number(&digits[2..]) * 60))
+}
+
+/// Describes how long ago a timestamp was, like `--date=relative`.
+fn format_relative_date(secs: i64) -> String {
+    let elapsed = Utc::now().timestamp() - secs;
+    if elapsed < 0 {
+        return "in the future".to_string();
     }
+    let (amount, unit) = match elapsed {
+        0..=89 => (elapsed, "second"),
+        90..=5399 => ((elapsed + 30) / 60, "minute"),
+        5400..=129599 => ((elapsed + 1800) / 3600, "hour"),
+        129600..=1209599 => ((elapsed + 43200) / 86400, "day"),
+        1209600..=5183999 => ((elapsed + 302400) / 604800, "week"),
+        5184000..=31535999 => ((elapsed + 1296000) / 2592000, "month"),
+        _ => ((elapsed + 15768000) / 31536000, "year"),
+    };
+    let plural = if amount == 1 { "" } else { "s" };
+    format!("{} {}{} ago", amount, unit, plural)
+}
+
+/// Formats a raw Git date following a `--date` mode.
+///
+/// The date is shown in the timezone stored with it, not converted to UTC.
+///
+/// # Arguments
+///
+/// * `date` - The date as stored in commit and tag headers: seconds since the epoch followed by the
+///   timezone offset, e.g. `1700000000 -0300`.
+/// * `mode` - How the date is displayed.
+///
+/// # Returns
+///
+/// Returns the formatted date, or the epoch if the date cannot be represented.
+///
+pub fn format_date_with(date: &str, mode: DateMode) -> String {
+    let (secs, offset) = date.split_once(' ').unwrap_or(("0", "+0000"));
+    let secs = secs.parse::<i64>().unwrap_or(0);
+    match mode {
+        DateMode::Unix => return secs.to_string(),
+        DateMode::Relative => return format_relative_date(secs),
+        _ => {}
+    }
+    let timezone = match parse_offset(offset).map(FixedOffset::east_opt) {
+        Ok(Some(timezone)) => timezone,
+        _ => return DATE_ZERO.to_string(),
+    };
+    let date = match timezone.timestamp_opt(secs, 0) {
+        chrono::LocalResult::Single(date) => date,
+        _ => return DATE_ZERO.to_string(),
+    };
+    let format = match mode {
+        DateMode::Iso => "%Y-%m-%d %H:%M:%S %z",
+        DateMode::IsoStrict => "%Y-%m-%dT%H:%M:%S%:z",
+        DateMode::Rfc => "%a, %-d %b %Y %H:%M:%S %z",
+        DateMode::Short => "%Y-%m-%d",
+        _ => "%a %b %e %T %Y %z",
+    };
+    date.format(format).to_string()
 }
 
 /// Logs the 'git log' command with the specified commit and Git directory.
@@ -338,6 +1044,440 @@ pub fn log(
     Ok(log.iter().skip(skip).take(amount))
 }
 
+/// Parses a date limit given to `--since` or `--until` into a Unix timestamp.
+///
+/// Accepts a Unix timestamp, a date (`2023-11-14`), a date and time (`2023-11-14 19:13:20` or
+/// `2023-11-14T19:13:20`, in UTC) and relative dates such as `2 weeks ago` or `3.days.ago`.
+///
+/// # Errors
+///
+/// Returns an `InvalidInput` error if the date cannot be parsed.
+///
+fn parse_date_limit(value: &str) -> io::Result<i64> {
+    let invalid = || {
+        Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("fatal: invalid date '{}'", value),
+        )
+    };
+    let value = value.trim();
+    if let Ok(timestamp) = value.parse::<i64>() {
+        return Ok(timestamp);
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        let date = date.and_hms_opt(0, 0, 0).ok_or_else(invalid)?;
+        return Ok(date.and_utc().timestamp());
+    }
+    for format in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S"] {
+        if let Ok(date) = NaiveDateTime::parse_from_str(value, format) {
+            return Ok(date.and_utc().timestamp());
+        }
+    }
+    let relative = value.replace('.', " ");
+    let words: Vec<&str> = relative.split_whitespace().collect();
+    if let [amount, unit, "ago"] = words.as_slice() {
+        let amount: i64 = amount.parse().map_err(|_| invalid())?;
+        let unit_seconds = match unit.trim_end_matches('s') {
+            "second" => 1,
+            "minute" => 60,
+            "hour" => 3600,
+            "day" => 86400,
+            "week" => 7 * 86400,
+            "month" => 30 * 86400,
+            "year" => 365 * 86400,
+            _ => return Err(invalid()),
+        };
+        return Ok(Utc::now().timestamp() - amount * unit_seconds);
+    }
+    Err(invalid())
+}
+
+/// Compiles a pattern given to `--author` or `--grep`.
+fn parse_pattern(pattern: &str) -> io::Result<Regex> {
+    Regex::new(pattern).map_err(|error| {
+        Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("fatal: invalid regular expression '{}': {}", pattern, error),
+        )
+    })
+}
+
+/// Parses a count given to `-n`, `--max-count` or `--skip`.
+fn parse_count(value: Option<&str>) -> io::Result<usize> {
+    value.and_then(|value| value.parse().ok()).ok_or_else(|| {
+        Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("fatal: '{}' is not a valid count", value.unwrap_or("")),
+        )
+    })
+}
+
+/// Shows the commit history, like `git log`.
+///
+/// The history is walked from the given revisions (`HEAD` by default) following every parent,
+/// newest commits first. Revisions may be ranges such as `main..feature` or `a...b`, or
+/// exclusions such as `^main`; see `LogIter::from_revisions`. Commits with a note attached by
+/// `git notes` show it under their message, except in the `oneline` and `short` layouts.
+///
+/// The supported options are:
+///
+/// - `-n <count>`, `-<count>` and `--max-count=<count>`: limit the number of commits shown.
+/// - `--skip=<count>`: skip that many matching commits.
+/// - `--author=<regex>` and `--grep=<regex>`: match the author or the message.
+/// - `--since=<date>`/`--after=<date>` and `--until=<date>`/`--before=<date>`: limit the
+///   commit date.
+/// - `--merges` and `--no-merges`: only show or hide merge commits.
+/// - `--oneline`: show every commit in a single line.
+/// - `--pretty=<format>` and `--format=<format>`: choose the layout, one of `oneline`, `short`,
+///   `medium`, `full`, `fuller` or a template with placeholders such as `%h %an %ad %s`; see
+///   `Log::expand_format`.
+/// - `--date=<mode>`: show dates as `default`, `iso`, `iso-strict`, `rfc`, `short`, `unix` or
+///   `relative`.
+/// - `--graph`: draw the history as an ASCII graph next to the commits. It implies `--decorate`.
+/// - `--decorate`: show the branches and tags pointing to every commit.
+/// - `--all`: walk the history of every branch, remote branch and tag, besides `HEAD`.
+/// - `--follow`: keep listing the history of a single path beyond the commits that renamed it.
+/// - `-p`, `--stat`, `--numstat` and `--name-status`: describe the changes of every commit
+///   against its first parent; see `diff::DiffFormat`. Merge commits are not described.
+/// - `-- <path>...`: only show commits that changed those paths.
+///
+/// # Arguments
+///
+/// * `git_dir` - A string representing the path to the Git directory.
+/// * `line` - The full command line, starting with `git log`.
+/// * `output` - Where the commits are written.
+///
+/// # Errors
+///
+/// Returns an `InvalidInput` error if an option is unknown or malformed, or an error if a
+/// revision cannot be resolved or a commit cannot be read.
+///
+pub fn git_log(git_dir: &str, line: Vec<String>, output: &mut impl Write) -> io::Result<()> {
+    let mut options = LogOptions::default();
+    let mut revisions = Vec::new();
+    let mut format = LogFormat::Medium;
+    let mut date_mode = DateMode::Default;
+    let mut diff_format = DiffFormat::default();
+    let mut graph = false;
+    let mut decorate = false;
+    let mut all = false;
+    let mut follow = false;
+    let mut args = line.iter().skip(2);
+    while let Some(arg) = args.next() {
+        let (name, inline) = match arg.split_once('=') {
+            Some((name, value)) if name.starts_with("--") => (name, Some(value)),
+            _ => (arg.as_str(), None),
+        };
+        let mut value = || inline.or_else(|| args.next().map(|value| value.as_str()));
+        match name {
+            "-n" | "--max-count" => options.max_count = Some(parse_count(value())?),
+            "--skip" => options.skip = parse_count(value())?,
+            "--author" => options.author = Some(parse_pattern(value().unwrap_or(""))?),
+            "--grep" => options.grep = Some(parse_pattern(value().unwrap_or(""))?),
+            "--since" | "--after" => options.since = Some(parse_date_limit(value().unwrap_or(""))?),
+            "--until" | "--before" => {
+                options.until = Some(parse_date_limit(value().unwrap_or(""))?)
+            }
+            "--merges" => options.merges = Some(true),
+            "--no-merges" => options.merges = Some(false),
+            "--oneline" => format = LogFormat::Oneline,
+            "--pretty" | "--format" => format = LogFormat::parse(value().unwrap_or("medium"))?,
+            "--date" => date_mode = DateMode::parse(value().unwrap_or("default"))?,
+            "--graph" => graph = true,
+            "--decorate" => decorate = true,
+            "--all" => all = true,
+            "--follow" => follow = true,
+            "--" => options.paths.extend(args.by_ref().cloned()),
+            _ if diff_format.parse_option(name) => {}
+            _ => match name.strip_prefix('-') {
+                Some(count) if !count.is_empty() && count.chars().all(|c| c.is_ascii_digit()) => {
+                    options.max_count = Some(parse_count(Some(count))?)
+                }
+                Some(_) => {
+                    writeln!(output, "{}", LOG_USAGE)?;
+                    return Err(Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("error: unknown option '{}'", arg),
+                    ));
+                }
+                None => revisions.push(arg.to_string()),
+            },
+        }
+    }
+    log_log(
+        Path::new(git_dir),
+        revisions.first().map(|revision| revision.as_str()),
+    )?;
+    if all {
+        revisions.push("HEAD".to_string());
+        revisions.extend(
+            show_ref::get_refs(git_dir)?
+                .into_iter()
+                .map(|(hash, _)| hash),
+        );
+    }
+    decorate |= graph;
+    if follow {
+        if options.paths.len() != 1 {
+            return Err(Error::new(
+                io::ErrorKind::InvalidInput,
+                "error: --follow requires exactly one pathspec",
+            ));
+        }
+        diff_format
+            .renames
+            .get_or_insert_with(RenameOptions::default);
+    }
+    let mut followed = options.paths.clone();
+
+    let mut shown = Vec::new();
+    let mut walked = HashMap::new();
+    let mut skipped = 0;
+    for log in LogIter::from_revisions(git_dir, &revisions, false)? {
+        if options
+            .max_count
+            .is_some_and(|max_count| shown.len() >= max_count)
+        {
+            break;
+        }
+        walked.insert(log.commit_hash.clone(), log.parents.clone());
+        if !log.matches(&options)? {
+            continue;
+        }
+        if follow {
+            if let Some(source) = log.get_rename_source(&options.paths[0])? {
+                options.paths = vec![source.clone()];
+                followed.push(source);
+            }
+        }
+        if skipped < options.skip {
+            skipped += 1;
+            continue;
+        }
+        shown.push(log.set_format(format.clone(), date_mode));
+    }
+    if decorate {
+        let decorations = get_decorations(git_dir)?;
+        shown = shown
+            .into_iter()
+            .map(|log| {
+                let names = decorations
+                    .get(&log.commit_hash)
+                    .cloned()
+                    .unwrap_or_default();
+                log.set_decorations(names)
+            })
+            .collect();
+    }
+    let notes = notes::load_notes(git_dir)?;
+    if !notes.is_empty() {
+        let mut with_notes = Vec::new();
+        for log in shown {
+            let note = match notes.get(&log.commit_hash) {
+                Some(blob) => Some(cat_file::cat_file_return_content(blob, git_dir)?),
+                None => None,
+            };
+            with_notes.push(log.set_note(note));
+        }
+        shown = with_notes;
+    }
+    if graph {
+        return write_graph(shown, &walked, &followed, diff_format, output);
+    }
+    for log in shown {
+        writeln!(output, "{}", log)?;
+        let changes = log.describe_changes(&followed, diff_format)?;
+        output.write_all(changes.as_bytes())?;
+    }
+    Ok(())
+}
+
+/// Returns the names of the references pointing to every commit, as shown by `--decorate`.
+///
+/// The references are the ones listed by `git show-ref`. The branch checked out is shown as
+/// `HEAD -> <branch>` and a detached `HEAD` as `HEAD`, followed by the other branches, the remote
+/// branches and the tags (`tag: <name>`). Annotated tags are shown next to the commit they point to.
+///
+/// # Arguments
+///
+/// * `git_dir` - A string representing the path to the Git directory.
+///
+/// # Errors
+///
+/// Returns an error if the references or the tag objects cannot be read.
+///
+fn get_decorations(git_dir: &str) -> io::Result<HashMap<String, Vec<String>>> {
+    let head = fs::read_to_string(format!("{}/HEAD", git_dir))?;
+    let head_ref = head
+        .trim()
+        .strip_prefix("ref: ")
+        .map(|name| name.to_string());
+    let mut decorations: HashMap<String, Vec<String>> = HashMap::new();
+    if head_ref.is_none() {
+        if let Some(hash) = head.split_whitespace().next() {
+            decorations
+                .entry(hash.to_string())
+                .or_default()
+                .push("HEAD".to_string());
+        }
+    }
+    for (hash, name) in show_ref::get_refs(git_dir)? {
+        if let Some(branch) = name.strip_prefix("refs/heads/") {
+            let names = decorations.entry(hash).or_default();
+            if head_ref.as_deref() == Some(name.as_str()) {
+                names.insert(0, format!("HEAD -> {}", branch));
+            } else {
+                names.push(branch.to_string());
+            }
+        } else if let Some(remote_branch) = name.strip_prefix("refs/remotes/") {
+            decorations
+                .entry(hash)
+                .or_default()
+                .push(remote_branch.to_string());
+        } else if let Some(tag) = name.strip_prefix("refs/tags/") {
+            let commit = utils::resolve_revision(git_dir, &name)?;
+            decorations
+                .entry(commit)
+                .or_default()
+                .push(format!("tag: {}", tag));
+        }
+    }
+    Ok(decorations)
+}
+
+/// Returns the parents of a commit among the commits shown, skipping the commits that were
+/// walked but filtered out, the way `git log --graph` rewrites the history it draws.
+///
+/// # Arguments
+///
+/// * `hash` - The hash of the commit.
+/// * `walked` - The parents of every commit visited by the walk.
+/// * `shown` - The hashes of the commits that are shown.
+/// * `rewritten` - The parents already computed for other commits.
+///
+fn rewrite_parents(
+    hash: &str,
+    walked: &HashMap<String, Vec<String>>,
+    shown: &HashSet<String>,
+    rewritten: &mut HashMap<String, Vec<String>>,
+) -> Vec<String> {
+    // Hidden parents are rewritten before the commits that point to them, so a long run of
+    // hidden commits needs no recursion.
+    let mut pending = vec![hash.to_string()];
+    while let Some(current) = pending.last().cloned() {
+        if rewritten.contains_key(&current) {
+            pending.pop();
+            continue;
+        }
+        let walked_parents = walked.get(&current).map(Vec::as_slice).unwrap_or_default();
+        let hidden: Vec<String> = walked_parents
+            .iter()
+            .filter(|parent| !shown.contains(*parent) && !rewritten.contains_key(*parent))
+            .cloned()
+            .collect();
+        if !hidden.is_empty() {
+            pending.extend(hidden);
+            continue;
+        }
+        let mut parents: Vec<String> = Vec::new();
+        for parent in walked_parents {
+            let candidates = if shown.contains(parent) {
+                vec![parent.clone()]
+            } else {
+                rewritten.get(parent).cloned().unwrap_or_default()
+            };
+            for candidate in candidates {
+                if !parents.contains(&candidate) {
+                    parents.push(candidate);
+                }
+            }
+        }
+        rewritten.insert(current, parents);
+        pending.pop();
+    }
+    rewritten.get(hash).cloned().unwrap_or_default()
+}
+
+/// Writes the commits next to an ASCII graph of their history, like `git log --graph`.
+///
+/// The commits are reordered, if needed, so that no commit is drawn before its children.
+///
+/// # Arguments
+///
+/// * `logs` - The commits to show, in the order they were walked.
+/// * `walked` - The parents of every commit visited by the walk, including the ones filtered out.
+/// * `paths` - The paths the history is limited to, which also limit the changes shown.
+/// * `diff_format` - How the changes of every commit are described.
+/// * `output` - Where the graph is written.
+///
+/// # Errors
+///
+/// Returns an error if the changes of a commit cannot be read or the output cannot be written.
+///
+fn write_graph(
+    logs: Vec<Log>,
+    walked: &HashMap<String, Vec<String>>,
+    paths: &[String],
+    diff_format: DiffFormat,
+    output: &mut impl Write,
+) -> io::Result<()> {
+    let shown: HashSet<String> = logs.iter().map(|log| log.commit_hash.clone()).collect();
+    let mut rewritten = HashMap::new();
+    let parents: Vec<Vec<String>> = logs
+        .iter()
+        .map(|log| rewrite_parents(&log.commit_hash, walked, &shown, &mut rewritten))
+        .collect();
+
+    let positions: HashMap<&str, usize> = logs
+        .iter()
+        .enumerate()
+        .map(|(position, log)| (log.commit_hash.as_str(), position))
+        .collect();
+    let mut children = vec![0; logs.len()];
+    for commit_parents in &parents {
+        for parent in commit_parents {
+            children[positions[parent.as_str()]] += 1;
+        }
+    }
+    let mut ready: BTreeSet<usize> = (0..logs.len()).filter(|&i| children[i] == 0).collect();
+    let mut graph = Graph::new();
+    while let Some(position) = ready.pop_first() {
+        for parent in &parents[position] {
+            let parent = positions[parent.as_str()];
+            children[parent] -= 1;
+            if children[parent] == 0 {
+                ready.insert(parent);
+            }
+        }
+        let log = &logs[position];
+        let rows = graph.next_commit(&log.commit_hash, &parents[position]);
+        let text = log.to_string();
+        let mut lines = text.lines();
+        writeln!(output, "{} {}", rows.commit, lines.next().unwrap_or(""))?;
+        for line in lines {
+            writeln!(
+                output,
+                "{}",
+                format!("{} {}", rows.padding, line).trim_end()
+            )?;
+        }
+        if !matches!(log.format, LogFormat::Oneline | LogFormat::Format(_)) {
+            writeln!(output, "{}", rows.padding)?;
+        }
+        for line in log.describe_changes(paths, diff_format)?.lines() {
+            writeln!(
+                output,
+                "{}",
+                format!("{} {}", rows.padding, line).trim_end()
+            )?;
+        }
+        for row in rows.transition {
+            writeln!(output, "{}", row)?;
+        }
+    }
+    Ok(())
+}
+
 /// Print logs from an iterator.
 ///
 /// This function takes an iterator of logs and prints each log to the console. It is a convenient
@@ -380,10 +1520,401 @@ pub fn accumulate_logs(log_iter: impl Iterator<Item = Log>) -> String {
 
 #[cfg(test)]
 mod tests {
-    use crate::{add, commit, configuration::GIT_DIR_FOR_TEST, init};
+    use crate::{add, commit, configuration::GIT_DIR_FOR_TEST, hash_object, init};
 
     use super::*;
 
+    /// Stages the given files and returns the hash of the resulting tree.
+    fn stage_tree(git_dir: &str, files: &[(&str, &str)]) -> String {
+        let index = format!("{}/index", git_dir);
+        for (path, content) in files {
+            fs::write(path, content).unwrap();
+            add::add(path, &index, git_dir, "", None).unwrap();
+        }
+        let tree = tree_handler::build_tree_from_index(&index, git_dir, "").unwrap();
+        tree_handler::write_tree(&tree, git_dir).unwrap().0
+    }
+
+    /// Stores a commit with a fixed author and date, so the walk order is known.
+    fn store_commit(
+        git_dir: &str,
+        tree: &str,
+        parents: &[&str],
+        author: &str,
+        time: i64,
+        message: &str,
+    ) -> String {
+        let mut content = format!("tree {}\n", tree);
+        for parent in parents {
+            content.push_str(&format!("parent {}\n", parent));
+        }
+        content.push_str(&format!(
+            "author {author} {time} -0300\ncommitter {author} {time} -0300\n\n{message}\0",
+            author = author,
+            time = time,
+            message = message
+        ));
+        hash_object::store_string_to_file(&content, git_dir, "commit").unwrap()
+    }
+
+    /// Creates a repository with this history, where the numbers are the commit dates:
+    ///
+    /// ```text
+    /// Initial (1000) -- Add b (2000) ---- Merge feature (3000)   <- master
+    ///        \                         /
+    ///         `------ Add c (1500) ----'                          <- feature
+    /// ```
+    ///
+    /// `main` points to "Add b".
+    fn prepare_history(test_dir: &str) -> String {
+        if Path::new(test_dir).exists() {
+            fs::remove_dir_all(test_dir).unwrap();
+        }
+        fs::create_dir_all(test_dir).unwrap();
+        init::git_init(test_dir, ".mgit", "master", None).unwrap();
+        let git_dir = format!("{}/.mgit", test_dir);
+        let a = format!("{}/a.txt", test_dir);
+        let b = format!("{}/b.txt", test_dir);
+        let c = format!("{}/c.txt", test_dir);
+
+        let tree = stage_tree(&git_dir, &[(&a, "a\n")]);
+        let alice = "alice alice@mail.com";
+        let bob = "bob bob@mail.com";
+        let initial = store_commit(&git_dir, &tree, &[], alice, 1000, "Initial");
+        let tree = stage_tree(&git_dir, &[(&c, "c\n")]);
+        let fs_c = store_commit(&git_dir, &tree, &[&initial], alice, 1500, "Add c");
+        fs::remove_file(&c).unwrap();
+        fs::write(format!("{}/index", git_dir), "").unwrap();
+        let tree = stage_tree(&git_dir, &[(&a, "a\n"), (&b, "b\n")]);
+        let add_b = store_commit(&git_dir, &tree, &[&initial], bob, 2000, "Add b");
+        let tree = stage_tree(&git_dir, &[(&c, "c\n")]);
+        let merge = store_commit(
+            &git_dir,
+            &tree,
+            &[&add_b, &fs_c],
+            alice,
+            3000,
+            "Merge feature",
+        );
+
+        fs::write(format!("{}/refs/heads/master", git_dir), merge).unwrap();
+        fs::write(format!("{}/refs/heads/main", git_dir), add_b).unwrap();
+        fs::write(format!("{}/refs/heads/feature", git_dir), fs_c).unwrap();
+        git_dir
+    }
+
+    /// Runs `git log --oneline` with the given arguments and returns the subjects shown.
+    fn log_subjects(git_dir: &str, args: &[&str]) -> Vec<String> {
+        let mut line = vec![
+            "git".to_string(),
+            "log".to_string(),
+            "--oneline".to_string(),
+        ];
+        line.extend(args.iter().map(|arg| arg.to_string()));
+        let mut output = Vec::new();
+        git_log(git_dir, line, &mut output).unwrap();
+        String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .filter_map(|line| line.split_once('\t'))
+            .map(|(_, subject)| subject.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_log_walks_every_parent_by_date() {
+        let test_dir = "tests/log_walk/all_parents";
+        let git_dir = prepare_history(test_dir);
+        let subjects = log_subjects(&git_dir, &[]);
+        assert_eq!(subjects, ["Merge feature", "Add b", "Add c", "Initial"]);
+        fs::remove_dir_all(test_dir).unwrap();
+    }
+
+    #[test]
+    fn test_log_ranges() {
+        let test_dir = "tests/log_walk/ranges";
+        let git_dir = prepare_history(test_dir);
+        assert_eq!(log_subjects(&git_dir, &["main..feature"]), ["Add c"]);
+        assert_eq!(log_subjects(&git_dir, &["feature..main"]), ["Add b"]);
+        assert_eq!(
+            log_subjects(&git_dir, &["main...feature"]),
+            ["Add b", "Add c"]
+        );
+        assert_eq!(
+            log_subjects(&git_dir, &["main.."]),
+            ["Merge feature", "Add c"]
+        );
+        assert_eq!(
+            log_subjects(&git_dir, &["^main"]),
+            ["Merge feature", "Add c"]
+        );
+        fs::remove_dir_all(test_dir).unwrap();
+    }
+
+    #[test]
+    fn test_log_filters() {
+        let test_dir = "tests/log_walk/filters";
+        let git_dir = prepare_history(test_dir);
+        assert_eq!(log_subjects(&git_dir, &["--author=bob"]), ["Add b"]);
+        assert_eq!(
+            log_subjects(&git_dir, &["--grep", "^Add"]),
+            ["Add b", "Add c"]
+        );
+        assert_eq!(log_subjects(&git_dir, &["--merges"]), ["Merge feature"]);
+        assert_eq!(
+            log_subjects(&git_dir, &["--no-merges", "-n", "2"]),
+            ["Add b", "Add c"]
+        );
+        assert_eq!(
+            log_subjects(&git_dir, &["--since=1600"]),
+            ["Merge feature", "Add b"]
+        );
+        assert_eq!(
+            log_subjects(&git_dir, &["--until=1500"]),
+            ["Add c", "Initial"]
+        );
+        assert_eq!(log_subjects(&git_dir, &["-1", "--skip=1"]), ["Add b"]);
+        fs::remove_dir_all(test_dir).unwrap();
+    }
+
+    #[test]
+    fn test_log_limited_to_paths() {
+        let test_dir = "tests/log_walk/paths";
+        let git_dir = prepare_history(test_dir);
+        let c = format!("{}/c.txt", test_dir);
+        assert_eq!(log_subjects(&git_dir, &["--", &c]), ["Add c"]);
+        assert_eq!(log_subjects(&git_dir, &["--", test_dir]).len(), 4);
+        fs::remove_dir_all(test_dir).unwrap();
+    }
+
+    #[test]
+    fn test_log_describes_the_changes_of_every_commit() {
+        let test_dir = "tests/log_walk/changes";
+        let git_dir = prepare_history(test_dir);
+        let a = format!("{}/a.txt", test_dir);
+        let b = format!("{}/b.txt", test_dir);
+        let c = format!("{}/c.txt", test_dir);
+        let run = |args: &[&str]| {
+            let mut line = vec!["git".to_string(), "log".to_string()];
+            line.extend(args.iter().map(|arg| arg.to_string()));
+            let mut output = Vec::new();
+            git_log(&git_dir, line, &mut output).unwrap();
+            String::from_utf8(output).unwrap()
+        };
+
+        let expected = format!("Add b\nA\t{}\n\nInitial\nA\t{}\n\n", b, a);
+        assert_eq!(run(&["--format=%s", "--name-status", "main"]), expected);
+        let expected = format!("Add b\n1\t0\t{}\n\n", b);
+        assert_eq!(run(&["--format=%s", "--numstat", "-1", "main"]), expected);
+        let expected = format!(
+            "Add c\ndiff --git a/{c} b/{c}\nnew file mode 100644\n--- /dev/null\n+++ b/{c}\n@@ -0,0 +1 @@\n+c\n",
+            c = c
+        );
+        assert_eq!(run(&["--format=%s", "-p", "master", "--", &c]), expected);
+        fs::remove_dir_all(test_dir).unwrap();
+    }
+
+    #[test]
+    fn test_log_follows_renamed_files() {
+        let test_dir = "tests/log_walk/follow";
+        if Path::new(test_dir).exists() {
+            fs::remove_dir_all(test_dir).unwrap();
+        }
+        fs::create_dir_all(test_dir).unwrap();
+        init::git_init(test_dir, ".mgit", "master", None).unwrap();
+        let git_dir = format!("{}/.mgit", test_dir);
+        let old = format!("{}/old.txt", test_dir);
+        let new = format!("{}/new.txt", test_dir);
+        let author = "alice alice@mail.com";
+
+        let tree = stage_tree(&git_dir, &[(&old, "a\nb\nc\n")]);
+        let create = store_commit(&git_dir, &tree, &[], author, 1000, "Create");
+        fs::remove_file(&old).unwrap();
+        fs::write(format!("{}/index", git_dir), "").unwrap();
+        let tree = stage_tree(&git_dir, &[(&new, "a\nb\nc\n")]);
+        let rename = store_commit(&git_dir, &tree, &[&create], author, 2000, "Rename");
+        let tree = stage_tree(&git_dir, &[(&new, "a\nb\nd\n")]);
+        let edit = store_commit(&git_dir, &tree, &[&rename], author, 3000, "Edit");
+        fs::write(format!("{}/refs/heads/master", git_dir), edit).unwrap();
+
+        assert_eq!(log_subjects(&git_dir, &["--", &new]), ["Edit", "Rename"]);
+        assert_eq!(
+            log_subjects(&git_dir, &["--follow", "--", &new]),
+            ["Edit", "Rename", "Create"]
+        );
+        let line = [
+            "git",
+            "log",
+            "--format=%s",
+            "--name-status",
+            "--follow",
+            "--",
+            &new,
+        ]
+        .iter()
+        .map(|arg| arg.to_string())
+        .collect();
+        let mut output = Vec::new();
+        git_log(&git_dir, line, &mut output).unwrap();
+        let expected = format!(
+            "Edit\nM\t{new}\n\nRename\nR100\t{old}\t{new}\n\nCreate\nA\t{old}\n\n",
+            old = old,
+            new = new
+        );
+        assert_eq!(String::from_utf8(output).unwrap(), expected);
+        fs::remove_dir_all(test_dir).unwrap();
+    }
+
+    #[test]
+    fn test_log_graph_with_decorations() {
+        let test_dir = "tests/log_walk/graph";
+        let git_dir = prepare_history(test_dir);
+        let read_ref = |name: &str| fs::read_to_string(format!("{}/refs/heads/{}", git_dir, name));
+        let merge = read_ref("master").unwrap();
+        let add_b = read_ref("main").unwrap();
+        let add_c = read_ref("feature").unwrap();
+        let initial = commit::get_commit_parents(&add_b, &git_dir).unwrap()[0].clone();
+        fs::write(format!("{}/refs/tags/v1.0", git_dir), &initial).unwrap();
+
+        let line = ["git", "log", "--graph", "--oneline"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        let mut output = Vec::new();
+        git_log(&git_dir, line, &mut output).unwrap();
+        let output = String::from_utf8(output)
+            .unwrap()
+            .replace("\x1b[0;33m", "")
+            .replace("\x1b[0m", "");
+        let expected = format!(
+            "* {} (HEAD -> master) \tMerge feature\n|\\\n* | {} (main) \tAdd b\n| * {} (feature) \tAdd c\n|/\n* {} (tag: v1.0) \tInitial\n",
+            merge, add_b, add_c, initial
+        );
+        assert_eq!(output, expected);
+        fs::remove_dir_all(test_dir).unwrap();
+    }
+
+    #[test]
+    fn test_log_custom_format() {
+        let test_dir = "tests/log_walk/format";
+        let git_dir = prepare_history(test_dir);
+        let add_b = fs::read_to_string(format!("{}/refs/heads/main", git_dir)).unwrap();
+        let line = [
+            "git",
+            "log",
+            "-1",
+            "--format=%h|%an|%ae|%ad|%s|%D|%P%n%%",
+            "--date=iso",
+            "--decorate",
+            "main",
+        ]
+        .iter()
+        .map(|arg| arg.to_string())
+        .collect();
+        let mut output = Vec::new();
+        git_log(&git_dir, line, &mut output).unwrap();
+        let parent = commit::get_commit_parents(&add_b, &git_dir).unwrap()[0].clone();
+        let expected = format!(
+            "{}|bob|bob@mail.com|1969-12-31 21:33:20 -0300|Add b|main|{}\n%\n",
+            &add_b[..7],
+            parent
+        );
+        assert_eq!(String::from_utf8(output).unwrap(), expected);
+        fs::remove_dir_all(test_dir).unwrap();
+    }
+
+    #[test]
+    fn test_log_reads_format_and_date_from_the_next_argument() {
+        let test_dir = "tests/log_walk/format_next_argument";
+        let git_dir = prepare_history(test_dir);
+        let add_b = fs::read_to_string(format!("{}/refs/heads/main", git_dir)).unwrap();
+        let line = [
+            "git", "log", "-1", "--format", "%h %ad", "--date", "short", "main",
+        ]
+        .iter()
+        .map(|arg| arg.to_string())
+        .collect();
+        let mut output = Vec::new();
+        git_log(&git_dir, line, &mut output).unwrap();
+        let expected = format!("{} 1969-12-31\n", &add_b[..7]);
+        assert_eq!(String::from_utf8(output).unwrap(), expected);
+        fs::remove_dir_all(test_dir).unwrap();
+    }
+
+    #[test]
+    fn test_format_date_keeps_the_stored_offset() {
+        let date = "1700000000 +0530";
+        assert_eq!(format_date(date), "Wed Nov 15 03:43:20 2023 +0530");
+        assert_eq!(
+            format_date_with(date, DateMode::Iso),
+            "2023-11-15 03:43:20 +0530"
+        );
+        assert_eq!(
+            format_date_with(date, DateMode::IsoStrict),
+            "2023-11-15T03:43:20+05:30"
+        );
+        assert_eq!(
+            format_date_with(date, DateMode::Rfc),
+            "Wed, 15 Nov 2023 03:43:20 +0530"
+        );
+        assert_eq!(format_date_with(date, DateMode::Short), "2023-11-15");
+        assert_eq!(format_date_with(date, DateMode::Unix), "1700000000");
+        let yesterday = format!("{} -0300", Utc::now().timestamp() - 86400);
+        assert_eq!(
+            format_date_with(&yesterday, DateMode::Relative),
+            "24 hours ago"
+        );
+    }
+
+    #[test]
+    fn test_rewrite_parents_skips_a_long_run_of_hidden_commits() {
+        let mut walked = HashMap::new();
+        for i in 0..200_000 {
+            walked.insert(i.to_string(), vec![(i + 1).to_string()]);
+        }
+        let shown: HashSet<String> = ["0".to_string(), "200000".to_string()].into();
+        let mut rewritten = HashMap::new();
+        assert_eq!(
+            rewrite_parents("0", &walked, &shown, &mut rewritten),
+            vec!["200000".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_parse_offset_rejects_anything_but_four_digits() {
+        assert_eq!(parse_offset("-0300").unwrap(), -3 * 3600);
+        assert_eq!(parse_offset("+0530").unwrap(), 5 * 3600 + 30 * 60);
+        assert!(parse_offset("+05").is_err());
+        assert!(parse_offset("+1é3").is_err());
+        assert!(parse_offset("+1a30").is_err());
+        assert_eq!(format_date("1700000000 +1é3"), DATE_ZERO);
+    }
+
+    #[test]
+    fn test_parse_log_format() {
+        assert_eq!(LogFormat::parse("oneline").unwrap(), LogFormat::Oneline);
+        assert_eq!(
+            LogFormat::parse("format:%h").unwrap(),
+            LogFormat::Format("%h".to_string())
+        );
+        assert_eq!(
+            LogFormat::parse("%an").unwrap(),
+            LogFormat::Format("%an".to_string())
+        );
+        assert!(LogFormat::parse("unknown").is_err());
+        assert!(DateMode::parse("sometimes").is_err());
+    }
+
+    #[test]
+    fn test_parse_date_limit() {
+        assert_eq!(parse_date_limit("1700000000").unwrap(), 1700000000);
+        assert_eq!(parse_date_limit("2023-11-14").unwrap(), 1699920000);
+        assert_eq!(parse_date_limit("2023-11-14 22:13:20").unwrap(), 1700000000);
+        let week_ago = Utc::now().timestamp() - 7 * 86400;
+        assert!((parse_date_limit("1.week.ago").unwrap() - week_ago).abs() < 5);
+        assert!(parse_date_limit("yesterday-ish").is_err());
+    }
+
     #[test]
     #[ignore]
     fn test_oneline() {
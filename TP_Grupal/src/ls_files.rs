@@ -7,7 +7,6 @@ use std::{
     io::{self, Write},
     path::Path,
 };
-const BLOB: &str = "blob";
 
 /// Lists files present in the provided index.
 ///
@@ -160,7 +159,7 @@ fn list_modified_files(
         let complete_path_string = working_dir.to_string() + "/" + path;
         let complete_path = Path::new(&complete_path_string);
         if complete_path.is_file() {
-            let new_hash = hash_object::hash_file_content(&complete_path_string, BLOB)?;
+            let new_hash = hash_object::hash_working_file(working_dir, path)?;
             if hash.ne(&new_hash) {
                 let buffer = format!("{}\n", path);
                 output.write_all(buffer.as_bytes())?;
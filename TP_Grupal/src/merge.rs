@@ -1,12 +1,13 @@
+use std::collections::HashMap;
 use std::{fs, io};
 
 use crate::commit::is_merge_commit;
-use crate::configuration::{GIT_DIR, LOGGER_COMMANDS_FILE};
+use crate::configuration::LOGGER_COMMANDS_FILE;
 use crate::logger::Logger;
-use crate::tree_handler::Tree;
+use crate::tree_handler::{BinaryConflict, Tree};
 use crate::utils::get_current_time;
 use crate::{
-    branch, commit, tree_handler,
+    branch, checkout, commit, diff, hooks, tree_handler,
     utils::{self, get_git_ignore_path},
 };
 use std::io::Write;
@@ -59,9 +60,108 @@ pub fn find_common_ancestor(
     Ok(common_ancestor)
 }
 
+/// Returns the files renamed between the common ancestor of a merge and one of its sides, as
+/// pairs of (old path, new path).
+fn find_renames(base_tree: &Tree, tree: &Tree, git_dir: &str) -> io::Result<Vec<(String, String)>> {
+    let changes = diff::get_tree_changes_with_renames(
+        Some(base_tree),
+        Some(tree),
+        git_dir,
+        Some(diff::RenameOptions::default()),
+    )?;
+    Ok(changes
+        .into_iter()
+        .filter_map(|change| match change.source {
+            Some(source) if !source.copied => Some((source.path, change.path)),
+            _ => None,
+        })
+        .collect())
+}
+
+/// Moves the files renamed by one side of a merge on the other side too.
+///
+/// For every rename, the other side's version of the file is moved to the new path. If only one
+/// side changed the content of the file, both sides get that content, so the edits follow the
+/// renamed file; otherwise both versions are left at the new path to be merged there.
+/// Renames to a path the other side also has are skipped.
+fn apply_renames(
+    renames: &[(String, String)],
+    base_tree: &Tree,
+    renamed: &mut HashMap<String, String>,
+    other: &mut HashMap<String, String>,
+) {
+    for (old_path, new_path) in renames {
+        if other.contains_key(new_path) {
+            continue;
+        }
+        let (other_hash, renamed_hash) = match (other.remove(old_path), renamed.get(new_path)) {
+            (Some(other_hash), Some(renamed_hash)) => (other_hash, renamed_hash.clone()),
+            (Some(other_hash), None) => {
+                other.insert(old_path.clone(), other_hash);
+                continue;
+            }
+            _ => continue,
+        };
+        let base_hash = base_tree.get_hash_from_path(old_path);
+        if base_hash.as_ref() == Some(&other_hash) {
+            other.insert(new_path.clone(), renamed_hash);
+        } else if base_hash.as_ref() == Some(&renamed_hash) {
+            renamed.insert(new_path.clone(), other_hash.clone());
+            other.insert(new_path.clone(), other_hash);
+        } else {
+            other.insert(new_path.clone(), other_hash);
+        }
+    }
+}
+
+/// Merges two trees like `tree_handler::merge_trees`, following the files that either side
+/// renamed since their common ancestor.
+///
+/// Without this, a file renamed on one side and edited on the other would end up twice in the
+/// result: at its new path and, with the edits, at its old path. Renames are detected with
+/// `diff::detect_renames` and the default similarity threshold.
+///
+/// # Arguments
+///
+/// * `base_commit` - The hash of the common ancestor of both sides.
+/// * `our_tree` - The tree of the current branch.
+/// * `their_tree` - The tree of the branch being merged.
+/// * `git_dir` - The path to the git directory.
+///
+/// # Returns
+///
+/// Returns the merged tree, the paths with conflicts, and the binary files among them.
+///
+/// # Errors
+///
+/// Returns an error if the trees or blobs involved cannot be read.
+///
+pub fn merge_trees_following_renames(
+    base_commit: &str,
+    our_tree: &Tree,
+    their_tree: &Tree,
+    git_dir: &str,
+) -> io::Result<(Tree, Vec<String>, Vec<BinaryConflict>)> {
+    let base_tree = tree_handler::load_tree_from_commit(base_commit, git_dir)?;
+    let our_renames = find_renames(&base_tree, our_tree, git_dir)?;
+    let their_renames = find_renames(&base_tree, their_tree, git_dir)?;
+    if our_renames.is_empty() && their_renames.is_empty() {
+        return tree_handler::merge_trees(our_tree, their_tree, git_dir);
+    }
+    let mut ours: HashMap<String, String> = our_tree.squash_tree_into_vec("").into_iter().collect();
+    let mut theirs: HashMap<String, String> =
+        their_tree.squash_tree_into_vec("").into_iter().collect();
+    apply_renames(&our_renames, &base_tree, &mut ours, &mut theirs);
+    apply_renames(&their_renames, &base_tree, &mut theirs, &mut ours);
+    let our_tree = tree_handler::build_tree_from_entries(ours.iter())?;
+    let their_tree = tree_handler::build_tree_from_entries(theirs.iter())?;
+    tree_handler::merge_trees(&our_tree, &their_tree, git_dir)
+}
+
 /// Given two branches, fast forwards `our_branch` to `their_branch`.
 /// This means that `our_branch` will point to the same commit as `their_branch`
 /// And the working directory will be updated to match the one of `their_branch`.
+/// Fails without changing anything if that would overwrite local changes.
 fn fast_forward_merge(
     our_branch: &str,
     their_branch: &str,
@@ -72,8 +172,8 @@ fn fast_forward_merge(
     let old_tree = tree_handler::load_tree_from_commit(&our_commit, git_dir)?;
     let their_commit = branch::get_branch_commit_hash(their_branch, git_dir)?;
     let new_tree = tree_handler::load_tree_from_commit(&their_commit, git_dir)?;
-    old_tree.delete_directories(root_dir)?;
-    new_tree.create_directories(root_dir, git_dir)?;
+    checkout::check_overwritten_paths(git_dir, root_dir, Some(&old_tree), &new_tree, "merge")?;
+    checkout::update_working_tree(git_dir, root_dir, Some(&old_tree), &new_tree)?;
     let index_path = utils::get_index_file_path(git_dir);
     let new_index_file_contents =
         new_tree.build_index_file_from_tree(&index_path, git_dir, &get_git_ignore_path(git_dir))?;
@@ -85,25 +185,38 @@ fn fast_forward_merge(
 /// Given two branches, merges `our_branch` with `their_branch`.
 /// `our_branch` will point to a new commit that contains the changes of both branches.
 /// The working directory will be updated to match the one of the new commit.
-/// If there are conflicts, the user will have to resolve them.
+/// Files renamed by either branch since `common_ancestor` are followed; see
+/// `merge_trees_following_renames`.
+/// If there are conflicts, the user will have to resolve them. Both versions of a binary file
+/// with conflicts are written next to it, as `<path>.ours` and `<path>.theirs`.
+/// Fails without changing anything if updating the working directory would overwrite local changes.
+///
+/// Returns the paths with conflicts and the warnings about the binary ones.
 fn two_way_merge(
     our_branch: &str,
     their_branch: &str,
+    common_ancestor: &str,
     git_dir: &str,
     root_dir: &str,
-) -> io::Result<Vec<String>> {
+) -> io::Result<(Vec<String>, Vec<String>)> {
     let our_commit = branch::get_branch_commit_hash(our_branch, git_dir)?;
     let their_commit = branch::get_branch_commit_hash(their_branch, git_dir)?;
     let our_tree = tree_handler::load_tree_from_commit(&our_commit, git_dir)?;
     let their_tree = tree_handler::load_tree_from_commit(&their_commit, git_dir)?;
-    let (new_tree, conflicting_paths) = tree_handler::merge_trees(&our_tree, &their_tree, git_dir)?;
-    our_tree.delete_directories(root_dir)?;
-    new_tree.create_directories(root_dir, git_dir)?;
+    let (new_tree, conflicting_paths, binary_conflicts) =
+        merge_trees_following_renames(common_ancestor, &our_tree, &their_tree, git_dir)?;
+    checkout::check_overwritten_paths(git_dir, root_dir, Some(&our_tree), &new_tree, "merge")?;
+    checkout::update_working_tree(git_dir, root_dir, Some(&our_tree), &new_tree)?;
     let index_path = utils::get_index_file_path(git_dir);
     let new_index_file_contents =
         new_tree.build_index_file_from_tree(&index_path, git_dir, &get_git_ignore_path(git_dir))?;
     new_index_file_contents.write_file()?;
-    Ok(conflicting_paths)
+    let mut warnings = vec![];
+    for conflict in &binary_conflicts {
+        conflict.write_versions(git_dir, root_dir)?;
+        warnings.push(conflict.warning());
+    }
+    Ok((conflicting_paths, warnings))
 }
 
 /// Logs the 'git merge' command with the specified branch names, Git directory, and root directory.
@@ -150,6 +263,9 @@ pub fn log_merge(
 /// The working directory will be updated to match the changes.
 /// If there are conflicts, the user will have to resolve them.
 ///
+/// Returns the hash our branch points to, the paths with conflicts, and the warnings about
+/// binary files that could not be merged.
+///
 /// # Arguments
 /// * `our_branch` - The name of the branch that will be updated.
 /// * `their_branch` - The name of the branch that will be merged with `our_branch`.
@@ -164,28 +280,46 @@ pub fn git_merge(
     their_branch: &str,
     git_dir: &str,
     root_dir: &str,
-) -> io::Result<(String, Vec<String>)> {
+) -> io::Result<(String, Vec<String>, Vec<String>)> {
     let our_commit = branch::get_branch_commit_hash(our_branch, git_dir)?;
     let their_commit = branch::get_branch_commit_hash(their_branch, git_dir)?;
 
     let common_ancestor = find_common_ancestor(&our_commit, &their_commit, git_dir)?;
     if is_fast_forward(&our_commit, &common_ancestor) {
         fast_forward_merge(our_branch, their_branch, git_dir, root_dir)?;
-        let tuple = (our_commit, vec![]);
+        let tuple = (our_commit, vec![], vec![]);
         log_merge(our_branch, their_branch, git_dir, root_dir)?;
+        run_post_merge_hook(git_dir);
         Ok(tuple)
     } else {
-        let conflicting_paths = two_way_merge(our_branch, their_branch, git_dir, root_dir)?;
+        let (conflicting_paths, warnings) = two_way_merge(
+            our_branch,
+            their_branch,
+            &common_ancestor,
+            git_dir,
+            root_dir,
+        )?;
         let commit_message = format!("Merge branch '{}'", their_branch);
         let hash =
             commit::new_merge_commit(git_dir, &commit_message, &our_commit, &their_commit, "")?;
-        let tuple = (hash, conflicting_paths);
+        if conflicting_paths.is_empty() {
+            run_post_merge_hook(git_dir);
+        }
+        let tuple = (hash, conflicting_paths, warnings);
         log_merge(our_branch, their_branch, git_dir, root_dir)?;
 
         Ok(tuple)
     }
 }
 
+/// Runs the `post-merge` hook after a merge that did not stop on conflicts. Its argument tells
+/// whether the merge was a squash, which is never the case here.
+///
+/// Like git, the exit status of the hook does not undo the merge.
+pub fn run_post_merge_hook(git_dir: &str) {
+    let _ = hooks::run_hook(git_dir, "post-merge", &["0"], None);
+}
+
 /// Given two branches, merges `our_branch` with `their_branch`.
 /// This function is used for the UI, where the user can choose to merge or not.
 /// It will try to do a fast forward merge, if it is not possible, it will do a two way merge.
@@ -211,16 +345,22 @@ pub fn git_merge_for_ui(
     if is_fast_forward(&our_commit, &common_ancestor) {
         fast_forward_merge(our_branch, their_branch, git_dir, root_dir)?;
         log_merge(our_branch, their_branch, git_dir, root_dir)?;
+        run_post_merge_hook(git_dir);
         Ok(vec![])
     } else {
-        let conflicting_paths = two_way_merge(our_branch, their_branch, git_dir, root_dir)?;
+        let (conflicting_paths, _warnings) = two_way_merge(
+            our_branch,
+            their_branch,
+            &common_ancestor,
+            git_dir,
+            root_dir,
+        )?;
         // Create a MERGE_HEAD file
-        let mut merge_head_file = fs::File::create(format!("{}/{}/MERGE_HEAD", root_dir, GIT_DIR))?;
+        let mut merge_head_file = fs::File::create(format!("{}/MERGE_HEAD", git_dir))?;
         merge_head_file.write_all(their_commit.as_bytes())?;
 
         // Create a merge_index file where all the conflicts are written
-        let mut merge_index_file =
-            fs::File::create(format!("{}/{}/MERGE_INDEX", root_dir, GIT_DIR))?;
+        let mut merge_index_file = fs::File::create(format!("{}/MERGE_INDEX", git_dir))?;
         for path in conflicting_paths.iter() {
             merge_index_file.write_all(path.as_bytes())?;
             merge_index_file.write_all(b"\n")?;
@@ -264,7 +404,12 @@ pub fn merge_remote_branch(branch: &str, remote_hash: &str, git_dir: &str) -> io
     } else {
         let our_tree = tree_handler::load_tree_from_commit(&our_commit, git_dir)?;
         let remote_tree = tree_handler::load_tree_from_commit(remote_hash, git_dir)?;
-        let (new_tree, _conflicts) = tree_handler::merge_trees(&our_tree, &remote_tree, git_dir)?;
+        let (new_tree, _conflicts, binary_conflicts) =
+            merge_trees_following_renames(&common_ancestor, &our_tree, &remote_tree, git_dir)?;
+        let working_dir = utils::get_working_dir(git_dir)?;
+        for conflict in &binary_conflicts {
+            conflict.write_versions(git_dir, &working_dir)?;
+        }
         let index_path = utils::get_index_file_path(git_dir);
         let new_index_file_contents = new_tree.build_index_file_from_tree(
             &index_path,
@@ -301,7 +446,9 @@ fn merge_pull_request(
 ) -> Result<String, io::Error> {
     let base_tree = tree_handler::load_tree_from_commit(base_commit, git_dir)?;
     let pull_request_tree = tree_handler::load_tree_from_commit(pull_request_commit, git_dir)?;
-    let (new_tree, conflicts) = tree_handler::merge_trees(&base_tree, &pull_request_tree, git_dir)?;
+    let common_ancestor = find_common_ancestor(base_commit, pull_request_commit, git_dir)?;
+    let (new_tree, conflicts, _) =
+        merge_trees_following_renames(&common_ancestor, &base_tree, &pull_request_tree, git_dir)?;
     if conflicts.is_empty() {
         let commit_message = format!("Merge pull request #{}", pull_request_branch);
         let commit_hash = commit::new_pr_merge_commit(
@@ -777,7 +924,9 @@ mod tests {
         let mut file = fs::File::create(&file_6_path).unwrap();
         file.write_all(b"int placeholder() { return 0; }").unwrap();
 
-        for i in 1..6 {
+        // The files of the other branch are written by the merge, while the ones it does not
+        // change are kept as they are in the working tree.
+        for i in [3, 4] {
             let file_path = format!("{}/src/{}.c", root_dir, i);
             fs::remove_file(file_path).unwrap();
         }
@@ -978,7 +1127,9 @@ mod tests {
         let mut file = fs::File::create(&file_6_path).unwrap();
         file.write_all(b"int placeholder() { return 0; }").unwrap();
 
-        for i in 1..6 {
+        // The files of the other branch are written by the merge, while the ones it does not
+        // change are kept as they are in the working tree.
+        for i in [3, 4] {
             let file_path = format!("{}/src/{}.c", root_dir, i);
             fs::remove_file(file_path).unwrap();
         }
@@ -1010,4 +1161,101 @@ mod tests {
         fs::remove_dir_all(NAME_OF_GIT_DIRECTORY_5).unwrap();
         fs::remove_dir_all(root_dir).unwrap();
     }
+
+    fn tree_with(git_dir: &str, files: &[(&str, &str)]) -> Tree {
+        let entries: HashMap<String, String> = files
+            .iter()
+            .map(|(path, content)| {
+                let hash =
+                    crate::hash_object::store_string_to_file(content, git_dir, "blob").unwrap();
+                (path.to_string(), hash)
+            })
+            .collect();
+        tree_handler::build_tree_from_entries(entries.iter()).unwrap()
+    }
+
+    #[test]
+    fn merge_follows_a_file_renamed_by_one_side_and_edited_by_the_other() {
+        let root_dir = "tests/merge/test_rename_merge";
+        if std::path::Path::new(root_dir).exists() {
+            fs::remove_dir_all(root_dir).unwrap();
+        }
+        fs::create_dir_all(root_dir).unwrap();
+        crate::init::git_init(root_dir, crate::configuration::GIT_DIR, "master", None).unwrap();
+        let git_dir = format!("{}/{}", root_dir, crate::configuration::GIT_DIR);
+        let content = "1\n2\n3\n4\n5\n";
+        let edited = "1\n2\n3\n4\nfive\n";
+
+        let base_tree = tree_with(&git_dir, &[("old.txt", content), ("other.txt", "x\n")]);
+        let (base_tree_hash, _) = tree_handler::write_tree(&base_tree, &git_dir).unwrap();
+        let base_commit =
+            commit::new_commit_from_tree(&git_dir, &base_tree_hash, &[], "Base").unwrap();
+        let our_tree = tree_with(&git_dir, &[("new.txt", content), ("other.txt", "x\n")]);
+        let their_tree = tree_with(&git_dir, &[("old.txt", edited), ("other.txt", "x\n")]);
+
+        let (merged, conflicts, _) =
+            merge_trees_following_renames(&base_commit, &our_tree, &their_tree, &git_dir).unwrap();
+        assert!(conflicts.is_empty());
+        let edited_hash =
+            crate::hash_object::store_string_to_file(edited, &git_dir, "blob").unwrap();
+        assert_eq!(merged.get_hash_from_path("new.txt"), Some(edited_hash));
+        assert_eq!(merged.get_hash_from_path("old.txt"), None);
+        assert!(merged.get_hash_from_path("other.txt").is_some());
+
+        let (merged, conflicts, _) =
+            merge_trees_following_renames(&base_commit, &their_tree, &our_tree, &git_dir).unwrap();
+        assert!(conflicts.is_empty());
+        assert!(merged.get_hash_from_path("new.txt").is_some());
+        assert_eq!(merged.get_hash_from_path("old.txt"), None);
+        fs::remove_dir_all(root_dir).unwrap();
+    }
+
+    #[test]
+    fn merge_keeps_both_versions_of_a_binary_file_changed_on_both_sides() {
+        let root_dir = "tests/merge/test_binary_conflict_merge";
+        if std::path::Path::new(root_dir).exists() {
+            fs::remove_dir_all(root_dir).unwrap();
+        }
+        fs::create_dir_all(root_dir).unwrap();
+        crate::init::git_init(root_dir, crate::configuration::GIT_DIR, "master", None).unwrap();
+        let git_dir = format!("{}/{}", root_dir, crate::configuration::GIT_DIR);
+        let commit_tree = |files: &[(&str, &str)], parents: &[String], message: &str| {
+            let tree = tree_with(&git_dir, files);
+            let (tree_hash, _) = tree_handler::write_tree(&tree, &git_dir).unwrap();
+            commit::new_commit_from_tree(&git_dir, &tree_hash, parents, message).unwrap()
+        };
+        let base = commit_tree(&[("data.bin", "ba\0se"), ("a.txt", "a\n")], &[], "Base");
+        let ours = commit_tree(
+            &[("data.bin", "ou\0rs"), ("a.txt", "a\n")],
+            std::slice::from_ref(&base),
+            "Ours",
+        );
+        let theirs = commit_tree(
+            &[("data.bin", "th\0eirs"), ("a.txt", "b\n")],
+            &[base],
+            "Theirs",
+        );
+        branch::update_branch_commit_hash("master", &ours, &git_dir).unwrap();
+        branch::update_branch_commit_hash("other", &theirs, &git_dir).unwrap();
+        let our_tree = tree_handler::load_tree_from_commit(&ours, &git_dir).unwrap();
+        checkout::update_working_tree(&git_dir, root_dir, None, &our_tree).unwrap();
+        let index_path = utils::get_index_file_path(&git_dir);
+        our_tree
+            .build_index_file_from_tree(&index_path, &git_dir, &get_git_ignore_path(&git_dir))
+            .unwrap()
+            .write_file()
+            .unwrap();
+
+        let (_, conflicts, warnings) = git_merge("master", "other", &git_dir, root_dir).unwrap();
+
+        assert!(conflicts.contains(&"data.bin".to_string()));
+        assert_eq!(
+            warnings,
+            vec!["warning: Cannot merge binary files: data.bin".to_string()]
+        );
+        let read = |name: &str| fs::read_to_string(format!("{}/{}", root_dir, name)).unwrap();
+        assert_eq!(read("data.bin.ours"), "ou\0rs");
+        assert_eq!(read("data.bin.theirs"), "th\0eirs");
+        fs::remove_dir_all(root_dir).unwrap();
+    }
 }
@@ -0,0 +1,404 @@
+use crate::checkout;
+use crate::configuration::LOGGER_COMMANDS_FILE;
+use crate::ignorer::is_subpath;
+use crate::index::Index;
+use crate::logger::Logger;
+use crate::utils::get_current_time;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+const MV_USAGE: &str = "usage: git mv [-f] [-k] [-n] [-v] <source>... <destination>";
+
+/// Logs the 'git mv' command with the specified sources and destination.
+///
+/// This function logs the 'git mv' command with the provided sources and destination to a file
+/// named 'logger_commands.txt'.
+///
+/// # Arguments
+///
+/// * `sources` - The paths moved.
+/// * `destination` - Where they were moved to.
+///
+/// # Errors
+///
+/// Returns an `io::Result` indicating whether the operation was successful.
+///
+fn log_mv(sources: &[String], destination: &str) -> io::Result<()> {
+    let log_file_path = LOGGER_COMMANDS_FILE;
+    let mut logger = Logger::new(log_file_path)?;
+
+    let full_message = format!(
+        "Command 'git mv': Sources '{}', Destination '{}', {}",
+        sources.join(" "),
+        destination,
+        get_current_time()
+    );
+    logger.write_all(full_message.as_bytes())?;
+    logger.flush()?;
+    Ok(())
+}
+
+/// The options of `git mv`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MvOptions {
+    /// `-f`: overwrite an existing destination file.
+    pub force: bool,
+    /// `-k`: skip the moves that would fail instead of aborting.
+    pub skip_errors: bool,
+    /// `-n`: only report what would be moved.
+    pub dry_run: bool,
+    /// `-v`: report every move.
+    pub verbose: bool,
+}
+
+/// Returns the index entries under a path: the path itself if it is a tracked file, or the files
+/// tracked inside it if it is a directory.
+fn tracked_entries(index: &Index, path: &str) -> Vec<(String, String)> {
+    let mut entries: Vec<(String, String)> = index
+        .iter()
+        .filter(|(tracked, _)| is_subpath(tracked, path))
+        .map(|(tracked, hash)| (tracked.to_string(), hash.to_string()))
+        .collect();
+    entries.sort();
+    entries
+}
+
+/// Checks that a source can be moved to a destination, returning the reason if it cannot.
+fn check_move(
+    root_dir: &str,
+    index: &Index,
+    source: &str,
+    destination: &str,
+    force: bool,
+) -> Option<&'static str> {
+    let source_path = checkout::working_path(root_dir, source);
+    let destination_path = checkout::working_path(root_dir, destination);
+    if fs::symlink_metadata(&source_path).is_err() {
+        return Some("bad source");
+    }
+    if source == destination {
+        return Some("can not move directory into itself");
+    }
+    if Path::new(&source_path).is_dir() {
+        if is_subpath(destination, source) {
+            return Some("can not move directory into itself");
+        }
+        if tracked_entries(index, source).is_empty() {
+            return Some("source directory is empty");
+        }
+        if Path::new(&destination_path).exists() {
+            return Some("destination already exists");
+        }
+    } else {
+        if !index.contains(source) {
+            return Some("not under version control");
+        }
+        if Path::new(&destination_path).is_dir() {
+            return Some("destination already exists");
+        }
+        if Path::new(&destination_path).exists() && !force {
+            return Some("destination exists");
+        }
+    }
+    None
+}
+
+/// Moves a path of the working tree and renames its entries in the index.
+fn move_path(root_dir: &str, index: &mut Index, source: &str, destination: &str) -> io::Result<()> {
+    let source_path = checkout::working_path(root_dir, source);
+    let destination_path = checkout::working_path(root_dir, destination);
+    if let Some(parent) = Path::new(&destination_path).parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::rename(&source_path, &destination_path)?;
+    for (tracked, hash) in tracked_entries(index, source) {
+        index.remove_file(&tracked)?;
+        let renamed = format!("{}{}", destination, &tracked[source.len()..]);
+        index.add_file(&renamed, &hash)?;
+    }
+    checkout::remove_empty_parents(root_dir, source)
+}
+
+/// Moves or renames tracked files and directories, in the working tree and the index at once.
+///
+/// With a single source, `destination` is its new name, unless it is an existing directory.
+/// With several sources, `destination` must be an existing directory, and every source is moved
+/// inside it. Every move is checked before anything is touched: a source that is missing, is
+/// not tracked, or would overwrite an existing path makes the whole command fail, unless
+/// `options.skip_errors` is set, in which case that source is skipped. Existing files are only
+/// overwritten with `options.force`. The index is written once, after every move, or after the
+/// moves already done if one of them fails.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the Git directory.
+/// * `root_dir` - The root of the working directory.
+/// * `sources` - The paths to move, relative to the root.
+/// * `destination` - The new path, or the directory to move the sources into.
+/// * `options` - The options of the command.
+/// * `output` - Where `Renaming <source> to <destination>` is written for every move, on a dry
+///   run or when `options.verbose` is set.
+///
+/// # Errors
+///
+/// Returns an error if a move is not possible, or if the working tree or the index cannot be
+/// written.
+///
+pub fn mv(
+    git_dir: &str,
+    root_dir: &str,
+    sources: &[String],
+    destination: &str,
+    options: MvOptions,
+    output: &mut impl Write,
+) -> io::Result<()> {
+    let mut index = checkout::load_index(git_dir, root_dir)?;
+    let destination = destination.trim_end_matches('/');
+    let into_directory = Path::new(&checkout::working_path(root_dir, destination)).is_dir();
+    if sources.len() > 1 && !into_directory {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("fatal: destination '{}' is not a directory", destination),
+        ));
+    }
+    let mut moves: Vec<(String, String)> = Vec::new();
+    for source in sources {
+        let source = source.trim_end_matches('/').to_string();
+        let target = match Path::new(&source).file_name() {
+            Some(name) if into_directory => {
+                checkout::working_path(destination, &name.to_string_lossy())
+            }
+            _ => destination.to_string(),
+        };
+        if let Some(reason) = check_move(root_dir, &index, &source, &target, options.force) {
+            if options.skip_errors {
+                continue;
+            }
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "fatal: {}, source={}, destination={}",
+                    reason, source, target
+                ),
+            ));
+        }
+        if moves.iter().any(|(_, other)| other == &target) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "fatal: multiple sources for the same target, source={}, destination={}",
+                    source, target
+                ),
+            ));
+        }
+        moves.push((source, target));
+    }
+    for (source, target) in &moves {
+        if options.dry_run || options.verbose {
+            writeln!(output, "Renaming {} to {}", source, target)?;
+        }
+        if !options.dry_run {
+            if let Err(error) = move_path(root_dir, &mut index, source, target) {
+                // The moves already done stay in the working tree, so the index has to follow
+                index.write_file()?;
+                return Err(error);
+            }
+        }
+    }
+    if !options.dry_run {
+        index.write_file()?;
+    }
+    Ok(())
+}
+
+/// Moves or renames files and directories, like `git mv`.
+///
+/// The command line is `git mv [-f] [-k] [-n] [-v] <source>... <destination>`:
+///
+/// - `-f` or `--force` overwrites existing destination files.
+/// - `-k` skips the moves that would fail instead of aborting.
+/// - `-n` or `--dry-run` only reports what would be moved.
+/// - `-v` or `--verbose` reports every move.
+///
+/// Short options can be combined, as in `-fv`. See [`mv`] for how the paths are moved.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the Git directory.
+/// * `root_dir` - The root of the working directory.
+/// * `line` - The command line, starting with `git mv`.
+/// * `output` - Where the moves are reported.
+///
+/// # Errors
+///
+/// Returns an error if the arguments are invalid or the move fails.
+///
+pub fn git_mv(
+    git_dir: &str,
+    root_dir: &str,
+    line: Vec<String>,
+    output: &mut impl Write,
+) -> io::Result<()> {
+    let mut options = MvOptions::default();
+    let mut paths: Vec<String> = Vec::new();
+    let invalid = |arg: &str| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("error: unknown option '{}'\n{}", arg, MV_USAGE),
+        )
+    };
+    let mut args = line.iter().skip(2);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--" => paths.extend(args.by_ref().cloned()),
+            "--force" => options.force = true,
+            "--dry-run" => options.dry_run = true,
+            "--verbose" => options.verbose = true,
+            _ if arg.starts_with("--") => return Err(invalid(arg)),
+            _ if arg.starts_with('-') && arg.len() > 1 => {
+                for flag in arg.chars().skip(1) {
+                    match flag {
+                        'f' => options.force = true,
+                        'k' => options.skip_errors = true,
+                        'n' => options.dry_run = true,
+                        'v' => options.verbose = true,
+                        _ => return Err(invalid(arg)),
+                    }
+                }
+            }
+            _ => paths.push(arg.to_string()),
+        }
+    }
+    let Some((destination, sources)) = paths.split_last() else {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, MV_USAGE));
+    };
+    if sources.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, MV_USAGE));
+    }
+    mv(git_dir, root_dir, sources, destination, options, output)?;
+    log_mv(sources, destination)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+
+    /// Creates a repository with the tracked files `a.txt`, `b.txt` and `dir/c.txt`, and the
+    /// untracked file `untracked.txt`.
+    fn create_repo(root_dir: &str) -> String {
+        let git_dir = test_utils::create_repo(root_dir);
+        test_utils::stage_files(
+            &git_dir,
+            root_dir,
+            &[
+                ("a.txt", "a.txt\n"),
+                ("b.txt", "b.txt\n"),
+                ("dir/c.txt", "dir/c.txt\n"),
+            ],
+        );
+        fs::write(format!("{}/untracked.txt", root_dir), "untracked\n").unwrap();
+        git_dir
+    }
+
+    fn run_mv(git_dir: &str, root_dir: &str, args: &[&str]) -> io::Result<String> {
+        let mut line = vec!["git".to_string(), "mv".to_string()];
+        line.extend(args.iter().map(|arg| arg.to_string()));
+        let mut output = Vec::new();
+        git_mv(git_dir, root_dir, line, &mut output)?;
+        Ok(String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn test_mv_renames_files_and_directories() {
+        let root_dir = "tests/mv/rename";
+        let git_dir = create_repo(root_dir);
+
+        run_mv(&git_dir, root_dir, &["a.txt", "renamed.txt"]).unwrap();
+        assert_eq!(
+            run_mv(&git_dir, root_dir, &["-v", "dir", "moved/inner"]).unwrap(),
+            "Renaming dir to moved/inner\n"
+        );
+        run_mv(&git_dir, root_dir, &["renamed.txt", "b.txt", "moved"]).unwrap();
+
+        let index = checkout::load_index(&git_dir, root_dir).unwrap();
+        let mut tracked: Vec<&String> = index.iter().map(|(path, _)| path).collect();
+        tracked.sort();
+        assert_eq!(
+            tracked,
+            vec!["moved/b.txt", "moved/inner/c.txt", "moved/renamed.txt"]
+        );
+        assert_eq!(
+            fs::read_to_string(format!("{}/moved/renamed.txt", root_dir)).unwrap(),
+            "a.txt\n"
+        );
+        assert!(!Path::new(&format!("{}/dir", root_dir)).exists());
+        assert!(!Path::new(&format!("{}/index.lock", git_dir)).exists());
+
+        fs::remove_dir_all(root_dir).unwrap();
+    }
+
+    #[test]
+    fn test_mv_refuses_untracked_sources_and_existing_destinations() {
+        let root_dir = "tests/mv/refuse";
+        let git_dir = create_repo(root_dir);
+
+        let error = run_mv(&git_dir, root_dir, &["untracked.txt", "new.txt"]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "fatal: not under version control, source=untracked.txt, destination=new.txt"
+        );
+        let error = run_mv(&git_dir, root_dir, &["a.txt", "b.txt"]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "fatal: destination exists, source=a.txt, destination=b.txt"
+        );
+        assert!(run_mv(&git_dir, root_dir, &["a.txt", "b.txt", "missing"]).is_err());
+        assert_eq!(
+            run_mv(&git_dir, root_dir, &["-n", "a.txt", "dir"]).unwrap(),
+            "Renaming a.txt to dir/a.txt\n"
+        );
+        assert!(Path::new(&format!("{}/a.txt", root_dir)).exists());
+
+        run_mv(&git_dir, root_dir, &["-k", "untracked.txt", "a.txt", "dir"]).unwrap();
+        assert!(Path::new(&format!("{}/untracked.txt", root_dir)).exists());
+        assert!(Path::new(&format!("{}/dir/a.txt", root_dir)).exists());
+
+        run_mv(&git_dir, root_dir, &["-f", "dir/a.txt", "b.txt"]).unwrap();
+        let index = checkout::load_index(&git_dir, root_dir).unwrap();
+        assert!(!index.contains("dir/a.txt"));
+        assert_eq!(
+            fs::read_to_string(format!("{}/b.txt", root_dir)).unwrap(),
+            "a.txt\n"
+        );
+
+        fs::remove_dir_all(root_dir).unwrap();
+    }
+
+    #[test]
+    fn test_mv_writes_the_index_for_the_moves_done_before_a_failure() {
+        let root_dir = "tests/mv/partial";
+        let git_dir = create_repo(root_dir);
+        fs::create_dir_all(format!("{}/other", root_dir)).unwrap();
+
+        // Moving `dir/c.txt` leaves `dir` empty, so it is removed before its own move.
+        let sources = ["dir/c.txt".to_string(), "dir".to_string()];
+        let result = mv(
+            &git_dir,
+            root_dir,
+            &sources,
+            "other",
+            MvOptions::default(),
+            &mut Vec::new(),
+        );
+
+        assert!(result.is_err());
+        assert!(Path::new(&format!("{}/other/c.txt", root_dir)).exists());
+        let index = checkout::load_index(&git_dir, root_dir).unwrap();
+        assert!(index.contains("other/c.txt"));
+        assert!(!index.contains("dir/c.txt"));
+
+        fs::remove_dir_all(root_dir).unwrap();
+    }
+}
@@ -0,0 +1,467 @@
+use crate::cat_file;
+use crate::commit;
+use crate::configuration::LOGGER_COMMANDS_FILE;
+use crate::hash_object;
+use crate::logger::Logger;
+use crate::tree_handler;
+use crate::utils::{self, get_current_time};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// The reference holding the history of the notes attached to commits.
+pub const NOTES_REF: &str = "refs/notes/commits";
+
+const NOTES_USAGE: &str = "usage: git notes [list [<object>]]\n   \
+     or: git notes add [-f] [-m <msg> | -F <file>] [<object>]\n   \
+     or: git notes append [-m <msg> | -F <file>] [<object>]\n   \
+     or: git notes show [<object>]\n   \
+     or: git notes remove [<object>...]";
+
+/// Logs the 'git notes' command with the specified subcommand.
+///
+/// This function logs the 'git notes' command with the provided subcommand to a file named
+/// 'logger_commands.txt'.
+///
+/// # Arguments
+///
+/// * `subcommand` - The subcommand being run, such as `add` or `list`.
+///
+/// # Errors
+///
+/// Returns an `io::Result` indicating whether the operation was successful.
+///
+fn log_notes(subcommand: &str) -> io::Result<()> {
+    let log_file_path = LOGGER_COMMANDS_FILE;
+    let mut logger = Logger::new(log_file_path)?;
+
+    let full_message = format!(
+        "Command 'git notes': Subcommand '{}', {}",
+        subcommand,
+        get_current_time()
+    );
+    logger.write_all(full_message.as_bytes())?;
+    logger.flush()?;
+    Ok(())
+}
+
+/// Returns the commit the notes reference points to, if any note was ever added.
+fn read_notes_ref(git_dir: &str) -> io::Result<Option<String>> {
+    let ref_path = utils::git_path(git_dir, NOTES_REF);
+    if !Path::new(&ref_path).exists() {
+        return Ok(None);
+    }
+    let hash = fs::read_to_string(ref_path)?.trim().to_string();
+    Ok(if hash.is_empty() { None } else { Some(hash) })
+}
+
+/// Loads the notes attached to every object.
+///
+/// The notes are blobs stored in the tree of the commit `refs/notes/commits` points to, named
+/// after the hash of the object they annotate. Trees written by Git that split the names in
+/// directories, such as `ab/cdef...`, are read as well.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the Git directory.
+///
+/// # Returns
+///
+/// Returns a map from the hash of every annotated object to the hash of its note blob. The map
+/// is empty if no note was ever added.
+///
+/// # Errors
+///
+/// Returns an error if the notes reference, its commit or its tree cannot be read.
+///
+pub fn load_notes(git_dir: &str) -> io::Result<HashMap<String, String>> {
+    let tip = match read_notes_ref(git_dir)? {
+        Some(tip) => tip,
+        None => return Ok(HashMap::new()),
+    };
+    let tree = tree_handler::load_tree_from_commit(&tip, git_dir)?;
+    Ok(tree
+        .squash_tree_into_vec("")
+        .into_iter()
+        .map(|(path, blob)| (path.replace('/', ""), blob))
+        .collect())
+}
+
+/// Returns the note attached to an object.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the Git directory.
+/// * `object` - The complete hash of the annotated object.
+///
+/// # Returns
+///
+/// Returns the text of the note, or `None` if the object has no note.
+///
+/// # Errors
+///
+/// Returns an error if the notes or the note blob cannot be read.
+///
+pub fn read_note(git_dir: &str, object: &str) -> io::Result<Option<String>> {
+    match load_notes(git_dir)?.get(object) {
+        Some(blob) => Ok(Some(cat_file::cat_file_return_content(blob, git_dir)?)),
+        None => Ok(None),
+    }
+}
+
+/// Records a new version of the notes, on top of the current one.
+///
+/// A tree with the given notes is written, and a commit pointing to it becomes the new tip of
+/// `refs/notes/commits`, so the history of the notes is kept and can be exchanged with a remote
+/// like any other reference.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the Git directory.
+/// * `notes` - The hash of every annotated object, mapped to the hash of its note blob.
+/// * `message` - The message of the notes commit.
+///
+/// # Errors
+///
+/// Returns an error if the tree, the commit or the reference cannot be written.
+///
+fn write_notes(git_dir: &str, notes: &HashMap<String, String>, message: &str) -> io::Result<()> {
+    let tree = tree_handler::build_tree_from_entries(notes.iter())?;
+    let (tree_hash, _) = tree_handler::write_tree(&tree, git_dir)?;
+    let parents: Vec<String> = read_notes_ref(git_dir)?.into_iter().collect();
+    let commit_hash = commit::new_commit_from_tree(git_dir, &tree_hash, &parents, message)?;
+    let ref_path = PathBuf::from(utils::git_path(git_dir, NOTES_REF));
+    if let Some(parent) = ref_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(ref_path, format!("{}\n", commit_hash))
+}
+
+/// Cleans up the text of a note: trailing spaces and blank lines are removed, and the note ends
+/// with a newline.
+fn normalize_note(text: &str) -> String {
+    let lines: Vec<&str> = text.lines().map(|line| line.trim_end()).collect();
+    let note = lines.join("\n");
+    let note = note.trim_matches('\n');
+    if note.is_empty() {
+        String::new()
+    } else {
+        format!("{}\n", note)
+    }
+}
+
+/// Options shared by `add` and `append`.
+#[derive(Default)]
+struct NoteArguments {
+    /// The paragraphs given with `-m` or read with `-F`, in order.
+    paragraphs: Vec<String>,
+    /// Whether `-f` was given.
+    force: bool,
+    /// The objects named in the command line.
+    objects: Vec<String>,
+}
+
+/// Parses the arguments of a subcommand, after its name.
+fn parse_arguments(args: &[String]) -> io::Result<NoteArguments> {
+    let mut parsed = NoteArguments::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-f" | "--force" => parsed.force = true,
+            "-m" | "--message" | "-F" | "--file" => {
+                let value = args.next().ok_or(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("error: switch '{}' requires a value", arg),
+                ))?;
+                let paragraph = if arg == "-F" || arg == "--file" {
+                    fs::read_to_string(value).map_err(|error| {
+                        io::Error::new(
+                            error.kind(),
+                            format!("fatal: could not open or read '{}': {}", value, error),
+                        )
+                    })?
+                } else {
+                    value.to_string()
+                };
+                parsed.paragraphs.push(paragraph);
+            }
+            _ if arg.starts_with('-') => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("error: unknown option '{}'\n{}", arg, NOTES_USAGE),
+                ))
+            }
+            _ => parsed.objects.push(arg.to_string()),
+        }
+    }
+    Ok(parsed)
+}
+
+/// Resolves the object named in the command line, `HEAD` if none was given.
+fn resolve_single_object(git_dir: &str, objects: &[String]) -> io::Result<String> {
+    match objects {
+        [] => utils::resolve_object(git_dir, "HEAD"),
+        [object] => utils::resolve_object(git_dir, object),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("error: too many arguments\n{}", NOTES_USAGE),
+        )),
+    }
+}
+
+/// Returns the text of the note given with `-m` or `-F`, failing if it is empty.
+fn note_from_paragraphs(paragraphs: &[String]) -> io::Result<String> {
+    let note = normalize_note(
+        &paragraphs
+            .iter()
+            .map(|paragraph| normalize_note(paragraph))
+            .collect::<Vec<String>>()
+            .join("\n"),
+    );
+    if note.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "error: a non-empty note is required; use -m or -F",
+        ));
+    }
+    Ok(note)
+}
+
+/// Error returned when an object has no note to show.
+fn no_note_error(object: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("error: no note found for object {}.", object),
+    )
+}
+
+/// Attaches a note to an object, or adds a paragraph to its note with `append`.
+fn add_note(git_dir: &str, args: &[String], append: bool) -> io::Result<()> {
+    let arguments = parse_arguments(args)?;
+    if append && arguments.force {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("error: unknown option '-f'\n{}", NOTES_USAGE),
+        ));
+    }
+    let object = resolve_single_object(git_dir, &arguments.objects)?;
+    let note = note_from_paragraphs(&arguments.paragraphs)?;
+    let mut notes = load_notes(git_dir)?;
+    let note = match notes.get(&object) {
+        Some(blob) if append => {
+            let previous = cat_file::cat_file_return_content(blob, git_dir)?;
+            format!("{}\n{}", normalize_note(&previous), note)
+        }
+        Some(_) if !arguments.force => {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!(
+                    "error: Cannot add notes. Found existing notes for object {}. \
+                     Use '-f' to overwrite existing notes",
+                    object
+                ),
+            ))
+        }
+        _ => note,
+    };
+    let blob = hash_object::store_string_to_file(&note, git_dir, "blob")?;
+    notes.insert(object, blob);
+    let command = if append { "append" } else { "add" };
+    write_notes(
+        git_dir,
+        &notes,
+        &format!("Notes added by 'git notes {}'", command),
+    )
+}
+
+/// Removes the notes of the given objects, `HEAD` if none is given.
+fn remove_notes(git_dir: &str, objects: &[String], output: &mut impl Write) -> io::Result<()> {
+    let mut notes = load_notes(git_dir)?;
+    let objects = if objects.is_empty() {
+        vec!["HEAD".to_string()]
+    } else {
+        objects.to_vec()
+    };
+    for object in &objects {
+        let hash = utils::resolve_object(git_dir, object)?;
+        if notes.remove(&hash).is_none() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("error: Object {} has no note", hash),
+            ));
+        }
+        writeln!(output, "Removing note for object {}", hash)?;
+    }
+    write_notes(git_dir, &notes, "Notes removed by 'git notes remove'")
+}
+
+/// Lists the notes as `<note blob> <annotated object>`, or the note blob of a single object.
+fn list_notes(git_dir: &str, objects: &[String], output: &mut impl Write) -> io::Result<()> {
+    let notes = load_notes(git_dir)?;
+    if objects.is_empty() {
+        let mut listed: Vec<(&String, &String)> = notes.iter().collect();
+        listed.sort_by(|a, b| a.0.cmp(b.0));
+        for (object, blob) in listed {
+            writeln!(output, "{} {}", blob, object)?;
+        }
+        return Ok(());
+    }
+    let object = resolve_single_object(git_dir, objects)?;
+    match notes.get(&object) {
+        Some(blob) => writeln!(output, "{}", blob),
+        None => Err(no_note_error(&object)),
+    }
+}
+
+/// Attaches notes to commits, or to any other object, without changing them.
+///
+/// The command line is `git notes [<subcommand>]`, where the subcommand is one of:
+///
+/// - `list [<object>]`: lists every note as `<note blob> <annotated object>`, or prints the note
+///   blob of the given object. It is the default subcommand.
+/// - `add [-f] [-m <msg> | -F <file>] [<object>]`: attaches a note to the object. Several `-m`
+///   and `-F` are joined as separate paragraphs. An object that already has a note is only
+///   overwritten with `-f`.
+/// - `append [-m <msg> | -F <file>] [<object>]`: adds paragraphs to the note of the object,
+///   creating it if needed.
+/// - `show [<object>]`: prints the note of the object.
+/// - `remove [<object>...]`: removes the notes of the objects.
+///
+/// The object is `HEAD` by default. Every change is recorded as a new commit on
+/// `refs/notes/commits`, whose tree holds a blob with each note named after the hash of the
+/// object it annotates.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the Git directory.
+/// * `line` - The command line, starting with `git notes`.
+/// * `output` - Where notes, listings and removals are written.
+///
+/// # Errors
+///
+/// Returns an error if the arguments are invalid, an object cannot be resolved, the object has
+/// no note to show or remove, already has one to add, or the notes cannot be read or written.
+///
+pub fn git_notes(git_dir: &str, line: Vec<String>, output: &mut impl Write) -> io::Result<()> {
+    let subcommand = line.get(2).map(|arg| arg.as_str()).unwrap_or("list");
+    let args = line.get(3..).unwrap_or(&[]);
+    log_notes(subcommand)?;
+    match subcommand {
+        "add" => add_note(git_dir, args, false),
+        "append" => add_note(git_dir, args, true),
+        "list" => list_notes(git_dir, args, output),
+        "remove" => remove_notes(git_dir, args, output),
+        "show" => {
+            let object = resolve_single_object(git_dir, args)?;
+            match read_note(git_dir, &object)? {
+                Some(note) => write!(output, "{}", note),
+                None => Err(no_note_error(&object)),
+            }
+        }
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "error: unknown subcommand: '{}'\n{}",
+                subcommand, NOTES_USAGE
+            ),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+    use crate::{log, show};
+
+    /// Creates a repository in `root_dir` with two commits, returning their hashes.
+    fn create_repo(root_dir: &str) -> (String, String, String) {
+        let git_dir = test_utils::create_repo(root_dir);
+        let first = test_utils::commit_files(&git_dir, root_dir, &[("file.txt", "one\n")], "one");
+        let second = test_utils::commit_files(&git_dir, root_dir, &[("file.txt", "two\n")], "two");
+        (git_dir, first, second)
+    }
+
+    fn run_notes(git_dir: &str, args: &[&str]) -> io::Result<String> {
+        let mut line = vec!["git".to_string(), "notes".to_string()];
+        line.extend(args.iter().map(|arg| arg.to_string()));
+        let mut output = Vec::new();
+        git_notes(git_dir, line, &mut output)?;
+        Ok(String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn test_notes_add_show_append_and_remove() {
+        let root_dir = "tests/notes/add";
+        let (git_dir, first, second) = create_repo(root_dir);
+
+        run_notes(&git_dir, &["add", "-m", "QA: ok  "]).unwrap();
+        assert_eq!(run_notes(&git_dir, &["show"]).unwrap(), "QA: ok\n");
+        assert!(run_notes(&git_dir, &["add", "-m", "again"]).is_err());
+        run_notes(
+            &git_dir,
+            &["add", "-f", "-m", "QA", "-m", "passed", &second],
+        )
+        .unwrap();
+        assert_eq!(
+            run_notes(&git_dir, &["show", &second]).unwrap(),
+            "QA\n\npassed\n"
+        );
+
+        run_notes(&git_dir, &["append", "-m", "first", &first]).unwrap();
+        run_notes(&git_dir, &["append", "-m", "signed off", &first]).unwrap();
+        assert_eq!(
+            read_note(&git_dir, &first).unwrap().unwrap(),
+            "first\n\nsigned off\n"
+        );
+
+        let listed = run_notes(&git_dir, &["list"]).unwrap();
+        let mut objects: Vec<&str> = listed
+            .lines()
+            .map(|line| line.split(' ').nth(1).unwrap())
+            .collect();
+        objects.sort();
+        let mut expected = vec![first.as_str(), second.as_str()];
+        expected.sort();
+        assert_eq!(objects, expected);
+
+        assert_eq!(
+            run_notes(&git_dir, &["remove", &second]).unwrap(),
+            format!("Removing note for object {}\n", second)
+        );
+        assert!(read_note(&git_dir, &second).unwrap().is_none());
+        assert!(run_notes(&git_dir, &["show", &second]).is_err());
+        assert!(run_notes(&git_dir, &["remove", &second]).is_err());
+
+        let tip = fs::read_to_string(format!("{}/{}", git_dir, NOTES_REF)).unwrap();
+        assert_eq!(log::get_ancestors(&git_dir, tip.trim()).unwrap().len(), 5);
+
+        fs::remove_dir_all(root_dir).unwrap();
+    }
+
+    #[test]
+    fn test_notes_are_shown_by_log_and_show() {
+        let root_dir = "tests/notes/log";
+        let (git_dir, first, _) = create_repo(root_dir);
+        run_notes(&git_dir, &["add", "-m", "QA: ok", &first]).unwrap();
+
+        let line = ["git", "log"].iter().map(|arg| arg.to_string()).collect();
+        let mut output = Vec::new();
+        log::git_log(&git_dir, line, &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output.matches("Notes:").count(), 1);
+        assert!(output.ends_with("\tone\n\nNotes:\n\tQA: ok\n\n"));
+
+        let line = ["git", "show", &first]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        let mut output = Vec::new();
+        show::git_show(&git_dir, line, &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("    one\n\nNotes:\n    QA: ok\n"));
+
+        fs::remove_dir_all(root_dir).unwrap();
+    }
+}
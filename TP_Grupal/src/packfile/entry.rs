@@ -6,6 +6,8 @@ use std::{
 
 use flate2::bufread::ZlibDecoder;
 
+use crate::utils;
+
 use super::object_type::ObjectType;
 
 /// A packfile entry.
@@ -35,7 +37,7 @@ impl PackfileEntry {
     /// * `hash` - The hash of the object.
     /// * `git_dir` - The path to the .git directory.
     pub fn from_hash(hash: &str, git_dir: &str) -> io::Result<Self> {
-        let file_dir = format!("{}/objects/{}", git_dir, &hash[..2]);
+        let file_dir = utils::git_path(git_dir, &format!("objects/{}", &hash[..2]));
         let file = File::open(format!("{}/{}", file_dir, &hash[2..]))?;
         let mut decompressor = ZlibDecoder::new(BufReader::new(file));
         let mut decompressed_content = Vec::new();
@@ -3,18 +3,18 @@ use crate::cat_file::cat_file;
 use crate::check_ignore::git_check_ignore;
 use crate::checkout::checkout_branch;
 use crate::checkout::checkout_commit_detached;
+use crate::checkout::checkout_paths;
 use crate::checkout::create_and_checkout_branch;
 use crate::checkout::create_or_reset_branch;
 use crate::checkout::force_checkout;
 use crate::clone::git_clone;
-use crate::commit::{get_branch_name, new_commit};
+use crate::commit::{self, get_branch_name};
 use crate::config::Config;
 use crate::configuration::{GIT_DIR, GIT_IGNORE, HOST, INDEX};
 use crate::fetch::git_fetch;
 use crate::hash_object::store_file;
 use crate::index::Index;
 use crate::init::git_init;
-use crate::log::print_logs;
 use crate::ls_files::git_ls_files;
 use crate::merge::git_merge;
 use crate::pull::git_pull;
@@ -23,8 +23,11 @@ use crate::rm::git_rm;
 use crate::show_ref::git_show_ref;
 use crate::status::{changes_to_be_committed, find_unstaged_changes, find_untracked_files};
 use crate::tree_handler::Tree;
-use crate::utils::{find_git_directory, obtain_git_dir};
-use crate::{add, git_config, log, ls_tree, push, rebase, tag, tree_handler};
+use crate::utils::{self, find_git_directory, get_working_dir, obtain_git_dir};
+use crate::{
+    add, am, apply, archive, bisect, blame, clean, describe, diff, format_patch, git_config, grep,
+    log, ls_tree, mv, notes, push, rebase, restore, show, stash, tag, tree_handler, worktree,
+};
 use std::fs::File;
 use std::io::Read;
 use std::path::{Path, PathBuf};
@@ -59,6 +62,22 @@ pub enum GitCommand {
     Rebase,
     Tag,
     Config,
+    Stash,
+    Restore,
+    Show,
+    Diff,
+    Blame,
+    Bisect,
+    FormatPatch,
+    Am,
+    Apply,
+    Archive,
+    Grep,
+    Describe,
+    Notes,
+    Worktree,
+    Clean,
+    Mv,
 }
 
 /// Reads user input from the command line and splits it into a vector of strings.
@@ -140,6 +159,22 @@ pub fn parse_git_command(second_argument: &str) -> Option<GitCommand> {
         "rebase" => Some(GitCommand::Rebase),
         "tag" => Some(GitCommand::Tag),
         "config" => Some(GitCommand::Config),
+        "stash" => Some(GitCommand::Stash),
+        "restore" => Some(GitCommand::Restore),
+        "show" => Some(GitCommand::Show),
+        "diff" => Some(GitCommand::Diff),
+        "blame" => Some(GitCommand::Blame),
+        "bisect" => Some(GitCommand::Bisect),
+        "format-patch" => Some(GitCommand::FormatPatch),
+        "am" => Some(GitCommand::Am),
+        "apply" => Some(GitCommand::Apply),
+        "archive" => Some(GitCommand::Archive),
+        "grep" => Some(GitCommand::Grep),
+        "describe" => Some(GitCommand::Describe),
+        "notes" => Some(GitCommand::Notes),
+        "worktree" => Some(GitCommand::Worktree),
+        "clean" => Some(GitCommand::Clean),
+        "mv" => Some(GitCommand::Mv),
         _ => {
             eprintln!("Not a valid Git option.");
             None
@@ -169,13 +204,13 @@ pub fn handle_git_command(git_command: GitCommand, args: Vec<String>) {
         GitCommand::Rm => handle_rm(args),
         GitCommand::Commit => handle_commit(args),
         GitCommand::Checkout => handle_checkout(args),
-        GitCommand::Log => handle_log(),
+        GitCommand::Log => handle_log(args),
         GitCommand::Clone => handle_clone(args),
         GitCommand::Fetch => handle_fetch(args),
         GitCommand::Merge => handle_merge(args),
         GitCommand::Remote => handle_remote(args),
         GitCommand::Pull => handle_pull(),
-        GitCommand::Push => handle_push(),
+        GitCommand::Push => handle_push(args),
         GitCommand::Branch => handle_branch(args),
         GitCommand::Init => handle_init(args),
         GitCommand::ListFiles => handle_ls_files(args),
@@ -185,6 +220,415 @@ pub fn handle_git_command(git_command: GitCommand, args: Vec<String>) {
         GitCommand::Rebase => handle_rebase(args),
         GitCommand::Tag => handle_tag(args),
         GitCommand::Config => handle_config(args),
+        GitCommand::Stash => handle_stash(args),
+        GitCommand::Restore => handle_restore(args),
+        GitCommand::Show => handle_show(args),
+        GitCommand::Diff => handle_diff(args),
+        GitCommand::Blame => handle_blame(args),
+        GitCommand::Bisect => handle_bisect(args),
+        GitCommand::FormatPatch => handle_format_patch(args),
+        GitCommand::Am => handle_am(args),
+        GitCommand::Apply => handle_apply(args),
+        GitCommand::Archive => handle_archive(args),
+        GitCommand::Grep => handle_grep(args),
+        GitCommand::Describe => handle_describe(args),
+        GitCommand::Notes => handle_notes(args),
+        GitCommand::Worktree => handle_worktree(args),
+        GitCommand::Clean => handle_clean(args),
+        GitCommand::Mv => handle_mv(args),
+    }
+}
+
+/// Handles the 'git stash' command, saving or restoring local changes.
+///
+/// # Arguments
+///
+/// * `args` - A vector of strings containing the command-line arguments. The third element, if
+///   present, is the stash subcommand (push, list, show, apply, pop or drop).
+///
+fn handle_stash(args: Vec<String>) {
+    let git_dir = match obtain_git_dir() {
+        Ok(dir) => dir,
+        Err(err) => {
+            eprintln!("Error al obtener el git dir.\n {:?}", err);
+            return;
+        }
+    };
+    let working_dir = match get_working_directory(&git_dir) {
+        Ok(dir) => dir,
+        Err(err) => {
+            eprintln!("{}", err);
+            return;
+        }
+    };
+    if let Err(error) = stash::git_stash(&git_dir, &working_dir, args, &mut io::stdout()) {
+        eprintln!("{}", error);
+    }
+}
+
+/// Handles the 'git diff' command, showing the changes between commits, the index and the
+/// working tree.
+///
+/// # Arguments
+///
+/// * `args` - A vector of strings containing the command-line arguments: the diff options
+///   (`--cached`, `--stat`, `-M`, ...), up to two revisions and, after `--`, the paths to limit
+///   the diff to.
+///
+fn handle_diff(args: Vec<String>) {
+    let git_dir = match obtain_git_dir() {
+        Ok(dir) => dir,
+        Err(err) => {
+            eprintln!("Error al obtener el git dir.\n {:?}", err);
+            return;
+        }
+    };
+    let working_dir = match get_working_directory(&git_dir) {
+        Ok(dir) => dir,
+        Err(err) => {
+            eprintln!("{}", err);
+            return;
+        }
+    };
+    if let Err(error) = diff::git_diff(&git_dir, &working_dir, args, &mut io::stdout()) {
+        eprintln!("{}", error);
+    }
+}
+
+/// Handles the 'git bisect' command, searching the commit that introduced a bug.
+///
+/// # Arguments
+///
+/// * `args` - A vector of strings containing the command-line arguments. The third element is
+///   the bisect subcommand (start, good, bad, skip, reset, log or run).
+///
+fn handle_bisect(args: Vec<String>) {
+    let git_dir = match obtain_git_dir() {
+        Ok(dir) => dir,
+        Err(err) => {
+            eprintln!("Error al obtener el git dir.\n {:?}", err);
+            return;
+        }
+    };
+    let working_dir = match get_working_directory(&git_dir) {
+        Ok(dir) => dir,
+        Err(err) => {
+            eprintln!("{}", err);
+            return;
+        }
+    };
+    if let Err(error) = bisect::git_bisect(&git_dir, &working_dir, args, &mut io::stdout()) {
+        eprintln!("{}", error);
+    }
+}
+
+/// Handles the 'git format-patch' command, exporting commits as patch emails.
+///
+/// # Arguments
+///
+/// * `args` - A vector of strings containing the command-line arguments: the output options
+///   (`-o <dir>`, `--stdout`, `-n`, `-<n>`) and the commits to export.
+///
+fn handle_format_patch(args: Vec<String>) {
+    let git_dir = match obtain_git_dir() {
+        Ok(dir) => dir,
+        Err(err) => {
+            eprintln!("Error al obtener el git dir.\n {:?}", err);
+            return;
+        }
+    };
+    if let Err(error) = format_patch::git_format_patch(&git_dir, args, &mut io::stdout()) {
+        eprintln!("{}", error);
+    }
+}
+
+/// Handles the 'git am' command, applying patch emails as commits.
+///
+/// # Arguments
+///
+/// * `args` - A vector of strings containing the command-line arguments: the mailboxes to
+///   apply, or one of `--continue`, `--skip` and `--abort`.
+///
+fn handle_am(args: Vec<String>) {
+    let git_dir = match obtain_git_dir() {
+        Ok(dir) => dir,
+        Err(err) => {
+            eprintln!("Error al obtener el git dir.\n {:?}", err);
+            return;
+        }
+    };
+    let working_dir = match get_working_directory(&git_dir) {
+        Ok(dir) => dir,
+        Err(err) => {
+            eprintln!("{}", err);
+            return;
+        }
+    };
+    if let Err(error) = am::git_am(&git_dir, &working_dir, args, &mut io::stdout()) {
+        eprintln!("{}", error);
+    }
+}
+
+/// Handles the 'git apply' command, applying unified diffs to the working tree or the index.
+///
+/// # Arguments
+///
+/// * `args` - A vector of strings containing the command-line arguments: the options
+///   (`--cached`, `--check`, `--reverse`) and the patch files.
+///
+fn handle_apply(args: Vec<String>) {
+    let git_dir = match obtain_git_dir() {
+        Ok(dir) => dir,
+        Err(err) => {
+            eprintln!("Error al obtener el git dir.\n {:?}", err);
+            return;
+        }
+    };
+    let working_dir = match get_working_directory(&git_dir) {
+        Ok(dir) => dir,
+        Err(err) => {
+            eprintln!("{}", err);
+            return;
+        }
+    };
+    if let Err(error) = apply::git_apply(&git_dir, &working_dir, args) {
+        eprintln!("{}", error);
+    }
+}
+
+/// Handles the 'git archive' command, writing the files of a commit as a tar or zip archive.
+///
+/// # Arguments
+///
+/// * `args` - A vector of strings containing the command-line arguments: the options
+///   (`--format`, `--prefix`, `-o`), the tree-ish to archive and the paths to include.
+///
+fn handle_archive(args: Vec<String>) {
+    let git_dir = match obtain_git_dir() {
+        Ok(dir) => dir,
+        Err(err) => {
+            eprintln!("Error al obtener el git dir.\n {:?}", err);
+            return;
+        }
+    };
+    if let Err(error) = archive::git_archive(&git_dir, args, &mut io::stdout()) {
+        eprintln!("{}", error);
+    }
+}
+
+/// Handles the 'git grep' command, searching the tracked files or past revisions for a pattern.
+///
+/// # Arguments
+///
+/// * `args` - A vector of strings containing the command-line arguments: the options
+///   (`-i`, `-n`, `-l`, `-w`), the pattern, the revisions and, after `--`, the paths to search.
+///
+fn handle_grep(args: Vec<String>) {
+    let git_dir = match obtain_git_dir() {
+        Ok(dir) => dir,
+        Err(err) => {
+            eprintln!("Error al obtener el git dir.\n {:?}", err);
+            return;
+        }
+    };
+    let working_dir = match get_working_directory(&git_dir) {
+        Ok(dir) => dir,
+        Err(err) => {
+            eprintln!("{}", err);
+            return;
+        }
+    };
+    if let Err(error) = grep::git_grep(&git_dir, &working_dir, args, &mut io::stdout()) {
+        eprintln!("{}", error);
+    }
+}
+
+/// Handles the 'git describe' command, naming a commit after the nearest tag it can reach.
+///
+/// # Arguments
+///
+/// * `args` - A vector of strings containing the command-line arguments: the options
+///   (`--tags`, `--long`, `--dirty`) and the commit to describe.
+///
+fn handle_describe(args: Vec<String>) {
+    let git_dir = match obtain_git_dir() {
+        Ok(dir) => dir,
+        Err(err) => {
+            eprintln!("Error al obtener el git dir.\n {:?}", err);
+            return;
+        }
+    };
+    let working_dir = match get_working_directory(&git_dir) {
+        Ok(dir) => dir,
+        Err(err) => {
+            eprintln!("{}", err);
+            return;
+        }
+    };
+    if let Err(error) = describe::git_describe(&git_dir, &working_dir, args, &mut io::stdout()) {
+        eprintln!("{}", error);
+    }
+}
+
+/// Handles the 'git notes' command, attaching notes to commits without changing them.
+///
+/// # Arguments
+///
+/// * `args` - A vector of strings containing the command-line arguments: the subcommand
+///   (`add`, `append`, `show`, `list` or `remove`), its options and the objects.
+///
+fn handle_notes(args: Vec<String>) {
+    let git_dir = match obtain_git_dir() {
+        Ok(dir) => dir,
+        Err(err) => {
+            eprintln!("Error al obtener el git dir.\n {:?}", err);
+            return;
+        }
+    };
+    if let Err(error) = notes::git_notes(&git_dir, args, &mut io::stdout()) {
+        eprintln!("{}", error);
+    }
+}
+
+/// Handles the 'git worktree' command, managing the working trees linked to the repository.
+///
+/// # Arguments
+///
+/// * `args` - A vector of strings containing the command-line arguments: the subcommand
+///   (`add`, `list` or `remove`), its options and the paths of the working trees.
+///
+fn handle_worktree(args: Vec<String>) {
+    let git_dir = match obtain_git_dir() {
+        Ok(dir) => dir,
+        Err(err) => {
+            eprintln!("Error al obtener el git dir.\n {:?}", err);
+            return;
+        }
+    };
+    if let Err(error) = worktree::git_worktree(&git_dir, args, &mut io::stdout()) {
+        eprintln!("{}", error);
+    }
+}
+
+/// Handles the 'git clean' command, removing untracked files from the working tree.
+///
+/// # Arguments
+///
+/// * `args` - A vector of strings containing the command-line arguments: the options
+///   (`-n`, `-f`, `-d`, `-x`, `-X`) and the paths to clean.
+///
+fn handle_clean(args: Vec<String>) {
+    let git_dir = match obtain_git_dir() {
+        Ok(dir) => dir,
+        Err(err) => {
+            eprintln!("Error al obtener el git dir.\n {:?}", err);
+            return;
+        }
+    };
+    let working_dir = match get_working_directory(&git_dir) {
+        Ok(dir) => dir,
+        Err(err) => {
+            eprintln!("{}", err);
+            return;
+        }
+    };
+    if let Err(error) = clean::git_clean(&git_dir, &working_dir, args, &mut io::stdout()) {
+        eprintln!("{}", error);
+    }
+}
+
+/// Handles the 'git mv' command, moving files in the working tree and the index together.
+///
+/// # Arguments
+///
+/// * `args` - A vector of strings containing the command-line arguments: the options
+///   (`-f`, `-k`, `-n`, `-v`), the sources and the destination.
+///
+fn handle_mv(args: Vec<String>) {
+    let git_dir = match obtain_git_dir() {
+        Ok(dir) => dir,
+        Err(err) => {
+            eprintln!("Error al obtener el git dir.\n {:?}", err);
+            return;
+        }
+    };
+    let working_dir = match get_working_directory(&git_dir) {
+        Ok(dir) => dir,
+        Err(err) => {
+            eprintln!("{}", err);
+            return;
+        }
+    };
+    if let Err(error) = mv::git_mv(&git_dir, &working_dir, args, &mut io::stdout()) {
+        eprintln!("{}", error);
+    }
+}
+
+/// Handles the 'git blame' command, showing the commit that last modified each line of a file.
+///
+/// # Arguments
+///
+/// * `args` - A vector of strings containing the command-line arguments: the `-L <start>,<end>`
+///   ranges, an optional revision (`HEAD` by default) and the file to blame.
+///
+fn handle_blame(args: Vec<String>) {
+    let git_dir = match obtain_git_dir() {
+        Ok(dir) => dir,
+        Err(err) => {
+            eprintln!("Error al obtener el git dir.\n {:?}", err);
+            return;
+        }
+    };
+    if let Err(error) = blame::git_blame(&git_dir, args, &mut io::stdout()) {
+        eprintln!("{}", error);
+    }
+}
+
+/// Handles the 'git show' command, printing commits, tags, trees and blobs.
+///
+/// # Arguments
+///
+/// * `args` - A vector of strings containing the command-line arguments: the diff options
+///   (`--stat`, `--name-status`, `-s`, ...) and the revisions to show. When no revision is given,
+///   `HEAD` is shown.
+///
+fn handle_show(args: Vec<String>) {
+    let git_dir = match obtain_git_dir() {
+        Ok(dir) => dir,
+        Err(err) => {
+            eprintln!("Error al obtener el git dir.\n {:?}", err);
+            return;
+        }
+    };
+    if let Err(error) = show::git_show(&git_dir, args, &mut io::stdout()) {
+        eprintln!("{}", error);
+    }
+}
+
+/// Handles the 'git restore' command, restoring files of the working tree and/or the index.
+///
+/// # Arguments
+///
+/// * `args` - A vector of strings containing the command-line arguments: the options
+///   (`--source=<rev>`, `--staged`, `--worktree`) followed by the paths to restore.
+///
+fn handle_restore(args: Vec<String>) {
+    let git_dir = match obtain_git_dir() {
+        Ok(dir) => dir,
+        Err(err) => {
+            eprintln!("Error al obtener el git dir.\n {:?}", err);
+            return;
+        }
+    };
+    let working_dir = match get_working_directory(&git_dir) {
+        Ok(dir) => dir,
+        Err(err) => {
+            eprintln!("{}", err);
+            return;
+        }
+    };
+    if let Err(error) = restore::git_restore(&git_dir, &working_dir, args, &mut io::stdout()) {
+        eprintln!("{}", error);
     }
 }
 
@@ -237,9 +681,9 @@ fn handle_ls_files(args: Vec<String>) {
         }
     };
 
-    let working_dir = match Path::new(&git_dir).parent() {
-        Some(dir) => dir.to_string_lossy().to_string(),
-        None => {
+    let working_dir = match get_working_dir(&git_dir) {
+        Ok(dir) => dir,
+        Err(_) => {
             eprintln!("Fatal error on ls files.");
             return;
         }
@@ -349,9 +793,9 @@ fn handle_check_ignore(args: Vec<String>) {
         }
     };
 
-    let working_dir = match Path::new(&git_dir).parent() {
-        Some(dir) => dir.to_string_lossy().to_string(),
-        None => {
+    let working_dir = match get_working_dir(&git_dir) {
+        Ok(dir) => dir,
+        Err(_) => {
             eprintln!("Fatal error on ls files.");
             return;
         }
@@ -541,10 +985,7 @@ fn handle_cat_file(args: Vec<String>) {
 /// otherwise returns an `io::Error` with a description of the encountered issue.
 ///
 fn get_working_directory_status(git_dir: &str) -> io::Result<PathBuf> {
-    let parent = Path::new(git_dir)
-        .parent()
-        .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "Error al obtener el working dir"))?;
-    Ok(parent.to_path_buf())
+    Ok(PathBuf::from(get_working_dir(git_dir)?))
 }
 
 /// Loads the index and commit tree of a Git repository.
@@ -576,7 +1017,7 @@ fn load_index_and_commit_tree(git_dir: &str) -> io::Result<(Index, Tree)> {
     let index = Index::load(&index_path, git_dir, &git_ignore_path)?;
 
     let branch_path = get_current_branch_path(git_dir)?;
-    let current_branch_path = format!("{}/{}", git_dir, branch_path);
+    let current_branch_path = utils::git_path(git_dir, &branch_path);
 
     if let Ok(mut current_commit_file) = File::open(current_branch_path) {
         let mut commit_hash = String::new();
@@ -851,15 +1292,15 @@ fn handle_rm(args: Vec<String>) {
     };
 
     let index_path = format!("{}/{}", git_dir, INDEX);
-    let git_dir_parent = match Path::new(&git_dir).parent() {
-        Some(dir) => dir,
-        None => {
+    let git_dir_parent = match get_working_dir(&git_dir) {
+        Ok(dir) => dir,
+        Err(_) => {
             eprintln!("Fatal error on git rm");
             return;
         }
     };
 
-    let git_ignore_path = format!("{}/{}", git_dir_parent.to_string_lossy(), GIT_IGNORE);
+    let git_ignore_path = format!("{}/{}", git_dir_parent, GIT_IGNORE);
 
     match git_rm(&args[2], &index_path, &git_dir, &git_ignore_path) {
         Ok(_) => {}
@@ -869,27 +1310,32 @@ fn handle_rm(args: Vec<String>) {
 
 /// Handles the 'git commit' command, creating a new commit with the specified message.
 ///
-/// This function uses the 'find_git_directory_and_ignore' function to retrieve the Git directory
-/// and ignore file path. It then calls the 'new_commit' function to create a new commit with the
-/// specified message.
+/// This function retrieves the Git directory and the working directory, and then calls the
+/// 'git_commit' function, which stages the tracked changes with `-a`, replaces the last commit
+/// with `--amend` and reads the message from `-m`, `-F` or the editor.
 ///
 /// # Arguments
 ///
-/// * `args` - A vector of command-line arguments, where the fourth element is the commit message.
+/// * `args` - A vector of command-line arguments: the options and the commit message.
 ///
 fn handle_commit(args: Vec<String>) {
-    let (git_dir, git_ignore_path) =
-        match crate::gui::repository_window::find_git_directory_and_ignore() {
-            Ok((dir, ignore_path)) => (dir, ignore_path),
-            Err(err) => {
-                eprintln!("Error: {:?}", err);
-                return;
-            }
-        };
-    match new_commit(&git_dir, &args[3], &git_ignore_path) {
-        Ok(_) => {}
-        Err(_err) => {}
+    let git_dir = match obtain_git_dir() {
+        Ok(dir) => dir,
+        Err(err) => {
+            eprintln!("Error al obtener el git dir.\n {:?}", err);
+            return;
+        }
     };
+    let working_dir = match get_working_directory(&git_dir) {
+        Ok(dir) => dir,
+        Err(err) => {
+            eprintln!("{}", err);
+            return;
+        }
+    };
+    if let Err(error) = commit::git_commit(&git_dir, &working_dir, args, &mut io::stdout()) {
+        eprintln!("{}", error);
+    }
 }
 
 /// Get the working directory based on the Git directory.
@@ -903,13 +1349,7 @@ fn handle_commit(args: Vec<String>) {
 /// * `Ok(String)` - The working directory if successful.
 /// * `Err(io::Error)` - An error if the working directory cannot be obtained.
 fn get_working_directory(git_dir: &str) -> io::Result<String> {
-    match Path::new(git_dir).parent() {
-        Some(parent) => Ok(parent.to_string_lossy().to_string()),
-        None => Err(io::Error::new(
-            io::ErrorKind::Other,
-            "Error al obtener el working dir",
-        )),
-    }
+    get_working_dir(git_dir)
 }
 
 /// Handle different checkout options.
@@ -931,6 +1371,16 @@ fn handle_checkout_option(
     option: &str,
     args: Vec<String>,
 ) -> io::Result<()> {
+    if let Some(separator) = args.iter().position(|arg| arg == "--") {
+        let revision = if separator > 2 { Some(option) } else { None };
+        return checkout_paths(
+            git_dir,
+            working_dir,
+            revision,
+            &args[separator + 1..],
+            &mut io::stdout(),
+        );
+    }
     match option {
         "-b" => create_and_checkout_branch(git_dir, working_dir, &args[3]),
         "-B" => create_or_reset_branch(git_dir, working_dir, &args[3]),
@@ -951,6 +1401,9 @@ fn handle_checkout_option(
 /// * `args` - A vector of command-line arguments, where the third element is the checkout option
 ///            ('-b', '-B', '--detach', '-f') and the fourth element is the branch or commit to checkout.
 ///
+/// When the arguments contain `[<rev>] -- <paths>`, only the given paths are checked out, from the
+/// revision if one is given or from the index otherwise.
+///
 pub fn handle_checkout(args: Vec<String>) {
     let git_dir = match obtain_git_dir() {
         Ok(dir) => dir,
@@ -982,6 +1435,9 @@ pub fn handle_checkout(args: Vec<String>) {
             std::io::ErrorKind::UnexpectedEof => {
                 eprintln!(" ");
             }
+            std::io::ErrorKind::AlreadyExists | std::io::ErrorKind::NotFound => {
+                eprintln!("{}", err);
+            }
             _ => {
                 eprintln!("Error cambiar de rama : {:?}", err);
             }
@@ -991,35 +1447,27 @@ pub fn handle_checkout(args: Vec<String>) {
 
 /// Handles the 'git log' command, displaying commit history for the repository.
 ///
-/// This function retrieves the current directory, finds the Git directory, and calls the 'git log'
-/// function to obtain an iterator over the commit logs. It then prints the logs using the
-/// 'print_logs' function.
+/// This function finds the Git directory and calls the 'git log' function, which walks the
+/// history from the given revisions and prints the commits selected by the options.
 ///
-fn handle_log() {
-    let mut current_dir = match std::env::current_dir() {
+/// # Arguments
+///
+/// * `args` - A vector of strings containing the command-line arguments: the options
+///   (`-n`, `--author`, `--grep`, `--since`, `--until`, `--merges`, `--graph`, `--all`,
+///   `--follow`, `-p`, `--stat`, ...), the revisions or ranges to walk and, after `--`, the
+///   paths to limit the history to.
+///
+fn handle_log(args: Vec<String>) {
+    let git_dir = match obtain_git_dir() {
         Ok(dir) => dir,
         Err(err) => {
-            eprintln!("Error al obtener el directorio actual: {:?}", err);
-            return;
-        }
-    };
-    let git_dir = match find_git_directory(&mut current_dir, GIT_DIR) {
-        Some(git_dir) => git_dir,
-        None => {
-            eprintln!("Error al obtener el git dir");
-            return;
-        }
-    };
-    println!("Current dir {}", current_dir.to_string_lossy());
-    println!("Git dir {}", git_dir);
-    let log_iter = match log::log(None, &git_dir, 10, 0, false) {
-        Ok(iter) => iter,
-        Err(_e) => {
-            eprintln!("Error en git log.");
+            eprintln!("Error al obtener el git dir.\n {:?}", err);
             return;
         }
     };
-    print_logs(log_iter);
+    if let Err(error) = log::git_log(&git_dir, args, &mut io::stdout()) {
+        eprintln!("{}", error);
+    }
 }
 
 /// Handles the 'clone' command for the custom Git implementation.
@@ -1085,9 +1533,9 @@ fn handle_fetch(_args: Vec<String>) {
         }
     };
 
-    let working_dir = match Path::new(&git_dir).parent() {
-        Some(dir) => dir.to_string_lossy().to_string(),
-        None => {
+    let working_dir = match get_working_dir(&git_dir) {
+        Ok(dir) => dir,
+        Err(_) => {
             eprintln!("No pudimos obtener el working dir");
             return;
         }
@@ -1121,9 +1569,9 @@ fn handle_merge(args: Vec<String>) {
         }
     };
 
-    let working_dir = match Path::new(&git_dir).parent() {
-        Some(parent) => parent.to_string_lossy().to_string(),
-        None => {
+    let working_dir = match get_working_dir(&git_dir) {
+        Ok(dir) => dir,
+        Err(_) => {
             eprintln!("Error al obtener el working dir");
             return;
         }
@@ -1138,7 +1586,11 @@ fn handle_merge(args: Vec<String>) {
     };
 
     match git_merge(&branch_name, &args[2], &git_dir, &working_dir) {
-        Ok(_) => {}
+        Ok((_, _, warnings)) => {
+            for warning in warnings {
+                eprintln!("{}", warning);
+            }
+        }
         Err(_e) => {
             eprintln!("Error en git merge.");
         }
@@ -1198,9 +1650,9 @@ fn handle_pull() {
         }
     };
 
-    let working_dir = match Path::new(&git_dir).parent() {
-        Some(parent) => parent.to_string_lossy().to_string(),
-        None => {
+    let working_dir = match get_working_dir(&git_dir) {
+        Ok(dir) => dir,
+        Err(_) => {
             eprintln!("Error al obtener el working dir");
             return;
         }
@@ -1228,7 +1680,11 @@ fn handle_pull() {
 /// This function retrieves the current directory, finds the Git directory, and gets the current branch name.
 /// It then calls the 'git push' function to push changes to the remote repository associated with the current branch.
 ///
-fn handle_push() {
+/// # Arguments
+///
+/// * `args` - A vector of command-line arguments. `--no-verify` skips the `pre-push` hook.
+///
+fn handle_push(args: Vec<String>) {
     let git_dir = match obtain_git_dir() {
         Ok(dir) => dir,
         Err(err) => {
@@ -1243,7 +1699,8 @@ fn handle_push() {
             return;
         }
     };
-    match push::git_push(&branch_name, &git_dir) {
+    let no_verify = args.iter().skip(2).any(|arg| arg == "--no-verify");
+    match push::git_push(&branch_name, &git_dir, no_verify) {
         Ok(_) => {
             println!("Push ok")
         }
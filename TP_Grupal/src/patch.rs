@@ -0,0 +1,734 @@
+use crate::cat_file;
+use crate::checkout;
+use crate::hash_object;
+use crate::index::Index;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Mode of the executable files, the only one other than the regular `100644` a patch may set.
+const EXECUTABLE_MODE: &str = "100755";
+
+/// Most context lines dropped from each end of a hunk that does not apply as it is.
+const MAX_FUZZ: usize = 2;
+
+/// A line of a hunk. The text keeps its line break, unless the file ends without one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HunkLine {
+    Context(String),
+    Removed(String),
+    Added(String),
+}
+
+/// A `@@ -<old_start>,<old_count> +<new_start>,<new_count> @@` section of a unified diff.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Hunk {
+    pub old_start: usize,
+    pub old_count: usize,
+    pub new_start: usize,
+    pub new_count: usize,
+    pub lines: Vec<HunkLine>,
+}
+
+impl Hunk {
+    /// Returns the lines the hunk expects to find: its context and removed lines.
+    fn old_lines(&self) -> Vec<&str> {
+        self.lines
+            .iter()
+            .filter_map(|line| match line {
+                HunkLine::Context(text) | HunkLine::Removed(text) => Some(text.as_str()),
+                HunkLine::Added(_) => None,
+            })
+            .collect()
+    }
+
+    /// Returns the lines the hunk leaves in place of the old ones: its context and added lines.
+    fn new_lines(&self) -> Vec<&str> {
+        self.lines
+            .iter()
+            .filter_map(|line| match line {
+                HunkLine::Context(text) | HunkLine::Added(text) => Some(text.as_str()),
+                HunkLine::Removed(_) => None,
+            })
+            .collect()
+    }
+
+    /// Returns the number of context lines before the first change and after the last one.
+    fn context_around(&self) -> (usize, usize) {
+        let is_context = |line: &&HunkLine| matches!(line, HunkLine::Context(_));
+        let leading = self.lines.iter().take_while(is_context).count();
+        if leading == self.lines.len() {
+            return (leading, 0);
+        }
+        (
+            leading,
+            self.lines.iter().rev().take_while(is_context).count(),
+        )
+    }
+
+    /// Returns the hunk that undoes this one.
+    fn reversed(&self) -> Hunk {
+        Hunk {
+            old_start: self.new_start,
+            old_count: self.new_count,
+            new_start: self.old_start,
+            new_count: self.old_count,
+            lines: self
+                .lines
+                .iter()
+                .map(|line| match line {
+                    HunkLine::Context(text) => HunkLine::Context(text.clone()),
+                    HunkLine::Removed(text) => HunkLine::Added(text.clone()),
+                    HunkLine::Added(text) => HunkLine::Removed(text.clone()),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// The changes a patch makes to a single file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FilePatch {
+    /// The path before the change, or `None` if the file is created.
+    pub old_path: Option<String>,
+    /// The path after the change, or `None` if the file is deleted.
+    pub new_path: Option<String>,
+    /// The mode of the file before the change, when the patch states it.
+    pub old_mode: Option<String>,
+    /// The mode of the file after the change, when the patch states it.
+    pub new_mode: Option<String>,
+    pub hunks: Vec<Hunk>,
+}
+
+impl FilePatch {
+    /// Returns the path the patch is reported with: the new path, or the old one for deletions.
+    pub fn path(&self) -> &str {
+        self.new_path
+            .as_deref()
+            .or(self.old_path.as_deref())
+            .unwrap_or_default()
+    }
+
+    /// Returns the patch that undoes this one, as applied by `git apply --reverse`.
+    pub fn reversed(&self) -> FilePatch {
+        FilePatch {
+            old_path: self.new_path.clone(),
+            new_path: self.old_path.clone(),
+            old_mode: self.new_mode.clone(),
+            new_mode: self.old_mode.clone(),
+            hunks: self.hunks.iter().map(Hunk::reversed).collect(),
+        }
+    }
+}
+
+/// Returns an error for a patch that cannot be parsed.
+fn corrupt_patch(line_number: usize) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("error: corrupt patch at line {}", line_number),
+    )
+}
+
+/// Reads the path of a `---` or `+++` line.
+///
+/// Anything after a tab (usually a timestamp) is dropped, `/dev/null` means there is no file
+/// and the leading `a/` or `b/` directory is removed.
+fn parse_header_path(value: &str) -> Option<String> {
+    let path = value.split('\t').next().unwrap_or_default().trim_end();
+    if path == "/dev/null" {
+        return None;
+    }
+    let path = path
+        .strip_prefix("a/")
+        .or_else(|| path.strip_prefix("b/"))
+        .unwrap_or(path);
+    Some(path.to_string())
+}
+
+/// Parses the paths of a `diff --git a/<old> b/<new>` line.
+fn parse_git_header(value: &str) -> (Option<String>, Option<String>) {
+    match value.split_once(" b/") {
+        Some((old, new)) => (
+            Some(old.strip_prefix("a/").unwrap_or(old).to_string()),
+            Some(new.to_string()),
+        ),
+        None => (None, None),
+    }
+}
+
+/// Parses a hunk range such as `12,3` or `12`, where a missing count means 1.
+fn parse_range(range: &str) -> Option<(usize, usize)> {
+    match range.split_once(',') {
+        Some((start, count)) => Some((start.parse().ok()?, count.parse().ok()?)),
+        None => Some((range.parse().ok()?, 1)),
+    }
+}
+
+/// Parses a `@@ -<old> +<new> @@` line into an empty hunk.
+fn parse_hunk_header(line: &str) -> Option<Hunk> {
+    let ranges = line.strip_prefix("@@ -")?.split(" @@").next()?;
+    let (old, new) = ranges.split_once(" +")?;
+    let (old_start, old_count) = parse_range(old)?;
+    let (new_start, new_count) = parse_range(new)?;
+    Some(Hunk {
+        old_start,
+        old_count,
+        new_start,
+        new_count,
+        lines: Vec::new(),
+    })
+}
+
+/// Parses a patch with the changes of one or more files.
+///
+/// Both git patches (starting each file with `diff --git`, and possibly announcing new,
+/// deleted and renamed files and mode changes) and plain `diff -u` output are understood. Any
+/// text that is not part of a file patch, such as the message of an email, is ignored.
+///
+/// # Arguments
+///
+/// * `text` - The content of the patch.
+///
+/// # Returns
+///
+/// The patch of every file, in the order they appear.
+///
+/// # Errors
+///
+/// Returns an `InvalidData` error if a hunk is malformed or shorter than its header says.
+///
+pub fn parse_patch(text: &str) -> io::Result<Vec<FilePatch>> {
+    let lines: Vec<&str> = text.split_inclusive('\n').collect();
+    let mut patches: Vec<FilePatch> = Vec::new();
+    let mut in_git_header = false;
+    let mut index = 0;
+    while index < lines.len() {
+        let line = lines[index].trim_end_matches('\n');
+        index += 1;
+        if let Some(value) = line.strip_prefix("diff --git ") {
+            let (old_path, new_path) = parse_git_header(value);
+            patches.push(FilePatch {
+                old_path,
+                new_path,
+                ..FilePatch::default()
+            });
+            in_git_header = true;
+            continue;
+        }
+        if let (Some(value), Some(next)) = (line.strip_prefix("--- "), lines.get(index)) {
+            if let Some(new_value) = next.trim_end_matches('\n').strip_prefix("+++ ") {
+                if !in_git_header {
+                    patches.push(FilePatch::default());
+                }
+                if let Some(patch) = patches.last_mut() {
+                    patch.old_path = parse_header_path(value);
+                    patch.new_path = parse_header_path(new_value);
+                }
+                in_git_header = false;
+                index += 1;
+                continue;
+            }
+        }
+        if in_git_header {
+            if let Some(patch) = patches.last_mut() {
+                if let Some(mode) = line.strip_prefix("new file mode ") {
+                    patch.old_path = None;
+                    patch.new_mode = Some(mode.to_string());
+                } else if let Some(mode) = line.strip_prefix("deleted file mode ") {
+                    patch.new_path = None;
+                    patch.old_mode = Some(mode.to_string());
+                } else if let Some(mode) = line.strip_prefix("old mode ") {
+                    patch.old_mode = Some(mode.to_string());
+                } else if let Some(mode) = line.strip_prefix("new mode ") {
+                    patch.new_mode = Some(mode.to_string());
+                } else if let Some(path) = line
+                    .strip_prefix("rename from ")
+                    .or_else(|| line.strip_prefix("copy from "))
+                {
+                    patch.old_path = Some(path.to_string());
+                } else if let Some(path) = line
+                    .strip_prefix("rename to ")
+                    .or_else(|| line.strip_prefix("copy to "))
+                {
+                    patch.new_path = Some(path.to_string());
+                }
+            }
+        }
+        if !line.starts_with("@@ -") {
+            continue;
+        }
+        let mut hunk = parse_hunk_header(line).ok_or_else(|| corrupt_patch(index))?;
+        let patch = patches.last_mut().ok_or_else(|| corrupt_patch(index))?;
+        in_git_header = false;
+        let (mut old_left, mut new_left) = (hunk.old_count, hunk.new_count);
+        while old_left > 0 || new_left > 0 {
+            let raw = lines.get(index).ok_or_else(|| corrupt_patch(index + 1))?;
+            index += 1;
+            let text = match raw.get(1..) {
+                Some("") | None => "\n".to_string(),
+                Some(text) => text.to_string(),
+            };
+            let hunk_line = match raw.chars().next() {
+                Some(' ') | Some('\n') if old_left > 0 && new_left > 0 => {
+                    old_left -= 1;
+                    new_left -= 1;
+                    HunkLine::Context(text)
+                }
+                Some('-') if old_left > 0 => {
+                    old_left -= 1;
+                    HunkLine::Removed(text)
+                }
+                Some('+') if new_left > 0 => {
+                    new_left -= 1;
+                    HunkLine::Added(text)
+                }
+                Some('\\') => {
+                    remove_last_line_break(&mut hunk.lines);
+                    continue;
+                }
+                _ => return Err(corrupt_patch(index)),
+            };
+            hunk.lines.push(hunk_line);
+        }
+        while lines.get(index).is_some_and(|next| next.starts_with('\\')) {
+            remove_last_line_break(&mut hunk.lines);
+            index += 1;
+        }
+        patch.hunks.push(hunk);
+    }
+    Ok(patches)
+}
+
+/// Handles a `\ No newline at end of file` marker, which applies to the previous line.
+fn remove_last_line_break(lines: &mut [HunkLine]) {
+    if let Some(HunkLine::Context(text) | HunkLine::Removed(text) | HunkLine::Added(text)) =
+        lines.last_mut()
+    {
+        if text.ends_with('\n') {
+            text.pop();
+        }
+    }
+}
+
+/// Applies the hunks of a file patch to its content.
+///
+/// Every hunk is looked for at the position its header says, shifted by the offset of the
+/// previous hunks. When the lines there do not match, the nearest position where they do is
+/// used instead, so a patch still applies after unrelated lines were added or removed. A hunk
+/// that is not found anywhere is tried again ignoring up to two context lines at each end.
+///
+/// # Arguments
+///
+/// * `content` - The content of the file before the patch.
+/// * `hunks` - The hunks to apply, in order.
+///
+/// # Returns
+///
+/// The content after the patch, or the index of the first hunk that does not apply.
+///
+pub fn apply_hunks(content: &str, hunks: &[Hunk]) -> Result<String, usize> {
+    let (result, rejected) = apply_hunks_with_rejects(content, hunks);
+    match rejected.first() {
+        Some(hunk_index) => Err(*hunk_index),
+        None => Ok(result),
+    }
+}
+
+/// Applies the hunks of a file patch to its content, leaving out the ones that do not apply.
+///
+/// Hunks are looked for as explained in [`apply_hunks`]. A rejected hunk does not change the
+/// content, and the hunks after it are still applied.
+///
+/// # Arguments
+///
+/// * `content` - The content of the file before the patch.
+/// * `hunks` - The hunks to apply, in order.
+///
+/// # Returns
+///
+/// The content with the hunks that applied, and the indexes of the rejected ones.
+///
+pub fn apply_hunks_with_rejects(content: &str, hunks: &[Hunk]) -> (String, Vec<usize>) {
+    let lines: Vec<&str> = content.split_inclusive('\n').collect();
+    let mut result = String::new();
+    let mut rejected = Vec::new();
+    let mut cursor = 0;
+    let mut offset: isize = 0;
+    for (hunk_index, hunk) in hunks.iter().enumerate() {
+        let stated = if hunk.old_count == 0 {
+            hunk.old_start
+        } else {
+            hunk.old_start.saturating_sub(1)
+        };
+        let old_lines = hunk.old_lines();
+        let new_lines = hunk.new_lines();
+        let (leading, trailing) = hunk.context_around();
+        let mut found = None;
+        for fuzz in 0..=MAX_FUZZ {
+            let (skip_start, skip_end) = (fuzz.min(leading), fuzz.min(trailing));
+            if fuzz > 0
+                && skip_start + skip_end == (fuzz - 1).min(leading) + (fuzz - 1).min(trailing)
+            {
+                continue;
+            }
+            if !old_lines.is_empty() && skip_start + skip_end >= old_lines.len() {
+                break;
+            }
+            let wanted = &old_lines[skip_start..old_lines.len() - skip_end];
+            let expected = (stated as isize + skip_start as isize + offset).max(cursor as isize);
+            if let Some(position) = find_lines(&lines, wanted, cursor, expected as usize) {
+                let replacement = &new_lines[skip_start..new_lines.len() - skip_end];
+                found = Some((position, wanted.len(), replacement, skip_start));
+                break;
+            }
+        }
+        let Some((position, matched, replacement, skip_start)) = found else {
+            rejected.push(hunk_index);
+            continue;
+        };
+        for line in &lines[cursor..position] {
+            result.push_str(line);
+        }
+        for line in replacement {
+            result.push_str(line);
+        }
+        cursor = position + matched;
+        offset = position as isize - (stated + skip_start) as isize;
+    }
+    for line in &lines[cursor..] {
+        result.push_str(line);
+    }
+    (result, rejected)
+}
+
+/// Finds where `wanted` appears in `lines`, at or after `from`, as near as possible to
+/// `expected`.
+fn find_lines(lines: &[&str], wanted: &[&str], from: usize, expected: usize) -> Option<usize> {
+    if lines.len() < wanted.len() {
+        return None;
+    }
+    let last = lines.len() - wanted.len();
+    let matches_at = |position: usize| {
+        position >= from && position <= last && lines[position..].starts_with(wanted)
+    };
+    for distance in 0..=lines.len() {
+        if matches_at(expected + distance) {
+            return Some(expected + distance);
+        }
+        if distance > 0 && distance <= expected && matches_at(expected - distance) {
+            return Some(expected - distance);
+        }
+    }
+    None
+}
+
+/// Where [`apply_patches`] reads the files to patch from and writes the patched ones to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApplyTarget {
+    /// The files of the working tree, leaving the index as it is.
+    WorkingTree,
+    /// The files staged in the index, leaving the working tree as it is.
+    Index,
+    /// The files staged in the index, writing the result to both the index and the working tree.
+    IndexAndWorkingTree,
+}
+
+impl ApplyTarget {
+    fn uses_index(self) -> bool {
+        self != ApplyTarget::WorkingTree
+    }
+
+    fn uses_working_tree(self) -> bool {
+        self != ApplyTarget::Index
+    }
+}
+
+/// Reads the content of a file to patch from the index or the working tree.
+fn read_original(
+    git_dir: &str,
+    root_dir: &str,
+    index: &Index,
+    path: &str,
+    target: ApplyTarget,
+) -> io::Result<Option<String>> {
+    if target.uses_index() {
+        return match index.get_hash(path) {
+            Some(hash) => Ok(Some(cat_file::cat_file_return_content(hash, git_dir)?)),
+            None => Ok(None),
+        };
+    }
+    let working_path = checkout::working_path(root_dir, path);
+    if !Path::new(&working_path).is_file() {
+        return Ok(None);
+    }
+    Ok(Some(fs::read_to_string(working_path)?))
+}
+
+/// Patches a file in memory, returning its new content or the errors that explain why the
+/// patch does not apply.
+fn patch_file(
+    git_dir: &str,
+    root_dir: &str,
+    index: &Index,
+    patch: &FilePatch,
+    target: ApplyTarget,
+) -> io::Result<Result<String, Vec<String>>> {
+    let location = if target.uses_index() {
+        "index"
+    } else {
+        "working directory"
+    };
+    let old_content = match &patch.old_path {
+        Some(path) => match read_original(git_dir, root_dir, index, path, target)? {
+            Some(content) => content,
+            None => {
+                return Ok(Err(vec![format!(
+                    "error: {}: does not exist in {}",
+                    path, location
+                )]))
+            }
+        },
+        None => {
+            if let Some(path) = &patch.new_path {
+                if read_original(git_dir, root_dir, index, path, target)?.is_some() {
+                    return Ok(Err(vec![format!(
+                        "error: {}: already exists in {}",
+                        path, location
+                    )]));
+                }
+            }
+            String::new()
+        }
+    };
+
+    let path = patch.old_path.as_deref().unwrap_or(patch.path());
+    let (new_content, rejected) = apply_hunks_with_rejects(&old_content, &patch.hunks);
+    let mut errors: Vec<String> = rejected
+        .iter()
+        .map(|hunk| {
+            format!(
+                "error: patch failed: {}:{}",
+                path, patch.hunks[*hunk].old_start
+            )
+        })
+        .collect();
+    if errors.is_empty() && patch.new_path.is_none() && !new_content.is_empty() {
+        errors.push(format!(
+            "error: removal patch leaves file contents: {}",
+            path
+        ));
+    }
+    if errors.is_empty() {
+        return Ok(Ok(new_content));
+    }
+    errors.push(format!("error: {}: patch does not apply", path));
+    Ok(Err(errors))
+}
+
+/// Gives a file of the working tree the mode a patch sets, making it executable or not.
+#[cfg(unix)]
+fn set_working_mode(working_path: &str, mode: &str) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    let permissions = if mode == EXECUTABLE_MODE {
+        0o755
+    } else {
+        0o644
+    };
+    fs::set_permissions(working_path, fs::Permissions::from_mode(permissions))
+}
+
+#[cfg(not(unix))]
+fn set_working_mode(_working_path: &str, _mode: &str) -> io::Result<()> {
+    Ok(())
+}
+
+/// Applies file patches to the index, the working tree or both.
+///
+/// Every file is patched in memory first, so when a hunk does not apply nothing is written at
+/// all. Hunks are matched as explained in [`apply_hunks`]. Since the index does not keep the
+/// mode of its files, mode changes only make the files of the working tree executable or not.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the Git directory.
+/// * `root_dir` - The root of the working directory. Paths in the index are relative to it.
+/// * `patches` - The patches to apply.
+/// * `target` - Where the files are read from and written to.
+/// * `check` - Whether to only check that the patches apply, writing nothing.
+///
+/// # Errors
+///
+/// Returns an `InvalidData` error listing, for every file that cannot be patched, the line of
+/// each rejected hunk, or whether the file is missing or already exists. Returns an error as
+/// well if the index or the working tree cannot be read or written.
+///
+pub fn apply_patches(
+    git_dir: &str,
+    root_dir: &str,
+    patches: &[FilePatch],
+    target: ApplyTarget,
+    check: bool,
+) -> io::Result<()> {
+    let mut index = checkout::load_index(git_dir, root_dir)?;
+    let mut results: Vec<(&FilePatch, String)> = Vec::new();
+    let mut errors: Vec<String> = Vec::new();
+    for patch in patches {
+        match patch_file(git_dir, root_dir, &index, patch, target)? {
+            Ok(content) => results.push((patch, content)),
+            Err(file_errors) => errors.extend(file_errors),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            errors.join("\n"),
+        ));
+    }
+    if check {
+        return Ok(());
+    }
+
+    for (patch, content) in results {
+        if let Some(old_path) = &patch.old_path {
+            if patch.new_path.as_ref() != Some(old_path) {
+                if target.uses_index() {
+                    index.remove_file(old_path)?;
+                }
+                if target.uses_working_tree() {
+                    let working_path = checkout::working_path(root_dir, old_path);
+                    if Path::new(&working_path).exists() {
+                        fs::remove_file(&working_path)?;
+                    }
+                    checkout::remove_empty_parents(root_dir, old_path)?;
+                }
+            }
+        }
+        if let Some(new_path) = &patch.new_path {
+            if target.uses_working_tree() {
+                let working_path = checkout::working_path(root_dir, new_path);
+                if let Some(parent) = Path::new(&working_path).parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::write(&working_path, &content)?;
+                if let Some(mode) = &patch.new_mode {
+                    set_working_mode(&working_path, mode)?;
+                }
+            }
+            if target.uses_index() {
+                let hash = hash_object::store_string_to_file(&content, git_dir, "blob")?;
+                index.add_file(new_path, &hash)?;
+            }
+        }
+    }
+    if target.uses_index() {
+        index.write_file()?;
+    }
+    Ok(())
+}
+
+/// Applies file patches to the index and the working tree.
+///
+/// The contents the patches start from are read from the index, as done by `git am`. See
+/// [`apply_patches`].
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the Git directory.
+/// * `root_dir` - The root of the working directory. Paths in the index are relative to it.
+/// * `patches` - The patches to apply.
+///
+/// # Errors
+///
+/// Returns an `InvalidData` error naming the files and lines of the hunks that do not apply,
+/// or an error if the index or the working tree cannot be written.
+///
+pub fn apply_to_index_and_working_tree(
+    git_dir: &str,
+    root_dir: &str,
+    patches: &[FilePatch],
+) -> io::Result<()> {
+    apply_patches(
+        git_dir,
+        root_dir,
+        patches,
+        ApplyTarget::IndexAndWorkingTree,
+        false,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff;
+
+    #[test]
+    fn test_parse_patch_reads_git_and_plain_diffs() {
+        let text = "From: someone\n\ndiff --git a/a.txt b/a.txt\nnew file mode 100644\n--- /dev/null\n+++ b/a.txt\n@@ -0,0 +1,2 @@\n+one\n+two\n\\ No newline at end of file\n--- old/b.txt\t2024-01-01\n+++ new/b.txt\t2024-01-02\n@@ -1 +1 @@\n-x\n+y\n";
+        let patches = parse_patch(text).unwrap();
+        assert_eq!(patches.len(), 2);
+        assert_eq!(patches[0].old_path, None);
+        assert_eq!(patches[0].new_path.as_deref(), Some("a.txt"));
+        assert_eq!(
+            patches[0].hunks[0].lines,
+            [
+                HunkLine::Added("one\n".to_string()),
+                HunkLine::Added("two".to_string())
+            ]
+        );
+        assert_eq!(patches[1].old_path.as_deref(), Some("old/b.txt"));
+        assert_eq!(patches[1].hunks[0].old_count, 1);
+        assert!(parse_patch("--- a/x\n+++ b/x\n@@ -1,2 +1,2 @@\n-x\n").is_err());
+    }
+
+    #[test]
+    fn test_apply_hunks_reverses_the_diff_engine() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\n";
+        let new = "a\nB\nc\nd\ne\nf\ng\nh\ni\nj\nk";
+        let patch = diff::file_patch("f.txt", Some(old), Some(new));
+        let patches = parse_patch(&patch).unwrap();
+        assert_eq!(apply_hunks(old, &patches[0].hunks).unwrap(), new);
+
+        let shifted = format!("new first line\n{}", old);
+        let expected = format!("new first line\n{}", new);
+        assert_eq!(apply_hunks(&shifted, &patches[0].hunks).unwrap(), expected);
+        assert_eq!(apply_hunks("z\n", &patches[0].hunks), Err(0));
+    }
+
+    #[test]
+    fn test_apply_hunks_with_fuzz_rejects_and_reverse() {
+        let old: String = (1..=20).map(|n| format!("{}\n", n)).collect();
+        let new = old
+            .replace("\n5\n", "\nfive\n")
+            .replace("\n15\n", "\nfifteen\n");
+        let patch = diff::file_patch("f.txt", Some(&old), Some(&new));
+        let hunks = &parse_patch(&patch).unwrap()[0].hunks;
+        assert_eq!(hunks.len(), 2);
+
+        let drifted = old.replace("\n2\n", "\ntwo\n").replace("\n15\n", "\nx\n");
+        let (result, rejected) = apply_hunks_with_rejects(&drifted, hunks);
+        assert_eq!(rejected, [1]);
+        assert_eq!(result, drifted.replace("\n5\n", "\nfive\n"));
+
+        let reversed = FilePatch {
+            hunks: hunks.clone(),
+            ..FilePatch::default()
+        }
+        .reversed();
+        assert_eq!(apply_hunks(&new, &reversed.hunks).unwrap(), old);
+        assert_eq!(apply_hunks(&old, &reversed.hunks), Err(0));
+    }
+
+    #[test]
+    fn test_parse_patch_reads_mode_changes() {
+        let text = "diff --git a/run.sh b/run.sh\nold mode 100644\nnew mode 100755\ndiff --git a/gone.txt b/gone.txt\ndeleted file mode 100644\n--- a/gone.txt\n+++ /dev/null\n@@ -1 +0,0 @@\n-bye\n";
+        let patches = parse_patch(text).unwrap();
+        assert_eq!(patches.len(), 2);
+        assert_eq!(patches[0].path(), "run.sh");
+        assert_eq!(patches[0].old_mode.as_deref(), Some("100644"));
+        assert_eq!(patches[0].new_mode.as_deref(), Some(EXECUTABLE_MODE));
+        assert!(patches[0].hunks.is_empty());
+        assert_eq!(patches[1].new_path, None);
+        assert_eq!(patches[1].reversed().old_path, None);
+        assert_eq!(patches[1].reversed().new_mode.as_deref(), Some("100644"));
+    }
+}
@@ -2,10 +2,10 @@ use std::fs;
 use std::io::{self, Write};
 use std::path::Path;
 
-use crate::configuration::{GIT_DIR, LOGGER_COMMANDS_FILE};
+use crate::configuration::LOGGER_COMMANDS_FILE;
 use crate::logger::Logger;
-use crate::utils::get_current_time;
-use crate::{fetch, merge, tree_handler};
+use crate::utils::{self, get_current_time, get_git_dir_of};
+use crate::{branch, checkout, fetch, merge, tree_handler};
 
 /// Logs the 'git push' command with the specified branch, local directory, and remote repository name.
 ///
@@ -45,7 +45,8 @@ pub fn log_push(branch: &str, local_dir: &str, remote_repo_name: Option<&str>) -
 /// from the remote repository and merging the changes into a local branch. It updates the specified `branch` in
 /// the local Git repository located in `local_dir` by synchronizing it with the remote repository. The `remote_repo_name`
 /// can be optionally provided to specify the name of the remote repository to pull from, and the `host` identifies
-/// the host of the remote repository.
+/// the host of the remote repository. The pull is aborted before the branch is updated if merging the
+/// changes into the working directory would overwrite local modifications or untracked files.
 ///
 /// # Arguments
 ///
@@ -65,7 +66,7 @@ pub fn git_pull(
     host: &str,
 ) -> io::Result<()> {
     let result = fetch::git_fetch(remote_repo_name, host, local_dir);
-    let git_dir = local_dir.to_string() + "/" + GIT_DIR;
+    let git_dir = get_git_dir_of(local_dir);
 
     if result.is_err() {
         return Err(io::Error::new(
@@ -88,15 +89,28 @@ pub fn git_pull(
             ));
         }
     };
-    let head_ref = git_dir.to_string() + "/refs/heads/" + branch;
+    let head_ref = utils::git_path(&git_dir, &format!("refs/heads/{}", branch));
+    let remote_tree = tree_handler::load_tree_from_commit(&hash, &git_dir)?;
     if Path::new(&head_ref).exists() {
+        let our_commit = branch::get_branch_commit_hash(branch, &git_dir)?;
+        let our_tree = tree_handler::load_tree_from_commit(&our_commit, &git_dir)?;
+        checkout::check_overwritten_paths(
+            &git_dir,
+            local_dir,
+            Some(&our_tree),
+            &remote_tree,
+            "merge",
+        )?;
+        let index = checkout::load_index(&git_dir, local_dir)?;
         let tree = merge::merge_remote_branch(branch, &hash, &git_dir)?;
-        tree.create_directories(local_dir, &git_dir)?;
+        index.write_file()?;
+        checkout::update_working_tree(&git_dir, local_dir, Some(&our_tree), &tree)?;
     } else {
-        let commit_tree = tree_handler::load_tree_from_commit(&hash, &git_dir)?;
-        commit_tree.create_directories(local_dir, &git_dir)?;
+        checkout::check_overwritten_paths(&git_dir, local_dir, None, &remote_tree, "merge")?;
+        checkout::update_working_tree(&git_dir, local_dir, None, &remote_tree)?;
     }
     update_heads_from_fetch_head(&git_dir)?;
+    merge::run_post_merge_hook(&git_dir);
     log_push(branch, local_dir, remote_repo_name)?;
     Ok(())
 }
@@ -105,7 +119,8 @@ fn update_heads_from_fetch_head(git_dir: &str) -> io::Result<()> {
     let fetch_head_path = git_dir.to_string() + "/FETCH_HEAD";
     let fetch_head = fetch::FetchHead::load_file(&fetch_head_path)?;
     for entry in fetch_head.get_entries() {
-        let branch_file_path = git_dir.to_string() + "/refs/heads/" + &entry.branch_name;
+        let branch_file_path =
+            utils::git_path(git_dir, &format!("refs/heads/{}", entry.branch_name));
         let mut branch_file = std::fs::File::create(branch_file_path)?;
         branch_file.write_all(entry.commit_hash.as_bytes())?;
     }
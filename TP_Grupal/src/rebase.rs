@@ -7,7 +7,6 @@ use std::{
     collections::HashMap,
     fs::File,
     io::{self, Write},
-    path::Path,
     rc::Rc,
 };
 
@@ -153,25 +152,16 @@ fn load_file_diffs(
 ///
 /// # Errors
 ///
-/// Returns an error if the parent directory of the specified Git directory cannot be obtained.
+/// Returns an error if the working directory of the specified Git directory cannot be obtained.
 ///
 fn get_root_dir(git_dir: &str) -> io::Result<String> {
-    let root_dir = match Path::new(&git_dir).parent() {
-        Some(dir) => dir.to_string_lossy().to_string(),
-        None => {
-            return Err(io::Error::new(
-                io::ErrorKind::NotFound,
-                "No se pudo encontrar el working dir",
-            ));
-        }
-    };
-    Ok(root_dir)
+    utils::get_working_dir(git_dir)
 }
 
 // Write the given hash into the refs/heads/branch_name file pointed by the HEAD file
 fn write_hash_into_branch_file(hash: &str, git_dir: &str) -> io::Result<()> {
     let branch_path = get_current_branch_path(git_dir)?;
-    let branch_path = format!("{}/{}", git_dir, branch_path);
+    let branch_path = utils::git_path(git_dir, &branch_path);
     let mut file = match File::create(branch_path) {
         Ok(file) => file,
         Err(_error) => {
@@ -299,8 +289,7 @@ fn abort_rebase_button_on_click(
             println!("Error writing to branch file");
         }
     }
-    let git_dir_path = Path::new(&git_dir);
-    match checkout::checkout_branch(git_dir_path, &root_dir, &branch_name) {
+    match checkout::reset_working_tree(&git_dir, &root_dir, &original_our_branch_hash) {
         Ok(_) => {
             println!("Checkout to branch {} completed", branch_name);
         }
@@ -645,17 +634,9 @@ fn finalize_rebase(builder: &gtk::Builder, git_dir: &str) -> io::Result<()> {
     match text_view.get_buffer() {
         Some(buffer) => {
             let branch_name = get_branch_name(git_dir)?;
-            let root_dir = match Path::new(&git_dir).parent() {
-                Some(dir) => dir.to_string_lossy().to_string(),
-                None => {
-                    return Err(io::Error::new(
-                        io::ErrorKind::NotFound,
-                        "No se pudo encontrar el working dir",
-                    ));
-                }
-            };
-            let git_dir_path = Path::new(&git_dir);
-            checkout::checkout_branch(git_dir_path, &root_dir, &branch_name)?;
+            let root_dir = utils::get_working_dir(git_dir)?;
+            let branch_commit = branch::get_branch_commit_hash(&branch_name, git_dir)?;
+            checkout::reset_working_tree(git_dir, &root_dir, &branch_commit)?;
             buffer.set_text("Rebase finalizado");
         }
         None => {
@@ -779,19 +760,9 @@ fn fast_forward_rebase(
             fast_forward_rebase_commit(&commit, &our_new_branch_hash, &common_ancestor, git_dir)?;
     }
 
-    let branch_name = get_branch_name(git_dir)?;
-    let root_dir = match Path::new(&git_dir).parent() {
-        Some(dir) => dir.to_string_lossy().to_string(),
-        None => {
-            return Err(io::Error::new(
-                io::ErrorKind::NotFound,
-                "No se pudo encontrar el working dir",
-            ));
-        }
-    };
+    let root_dir = utils::get_working_dir(git_dir)?;
     write_hash_into_branch_file(&our_new_branch_hash, git_dir)?;
-    let git_dir_path = Path::new(&git_dir);
-    checkout::checkout_branch(git_dir_path, &root_dir, &branch_name)?;
+    checkout::reset_working_tree(git_dir, &root_dir, &our_new_branch_hash)?;
 
     Ok(())
 }
@@ -899,6 +870,7 @@ mod tests {
     use super::*;
     use crate::add;
     use std::fs;
+    use std::path::Path;
 
     const NAME_OF_GIT_DIRECTORY_1: &str = "tests/rebase_tests/test1/.mgit";
 
@@ -0,0 +1,384 @@
+use crate::branch;
+use crate::checkout;
+use crate::configuration::LOGGER_COMMANDS_FILE;
+use crate::logger::Logger;
+use crate::tree_handler;
+use crate::utils::{self, get_current_time};
+use std::collections::{BTreeSet, HashMap};
+use std::io::{self, Write};
+
+const RESTORE_USAGE: &str =
+    "usage: git restore [--source=<tree>] [--staged] [--worktree] <pathspec>...";
+
+/// Logs the 'git restore' command with the specified source and paths.
+///
+/// This function logs the 'git restore' command with the provided source and paths to a file
+/// named 'logger_commands.txt'.
+///
+/// # Arguments
+///
+/// * `source` - The revision the files were restored from, or "index".
+/// * `pathspecs` - The paths given to the command.
+///
+/// # Errors
+///
+/// Returns an `io::Result` indicating whether the operation was successful.
+///
+fn log_restore(source: &str, pathspecs: &[String]) -> io::Result<()> {
+    let log_file_path = LOGGER_COMMANDS_FILE;
+    let mut logger = Logger::new(log_file_path)?;
+
+    let full_message = format!(
+        "Command 'git restore': Source '{}', Paths '{}', {}",
+        source,
+        pathspecs.join(" "),
+        get_current_time()
+    );
+    logger.write_all(full_message.as_bytes())?;
+    logger.flush()?;
+    Ok(())
+}
+
+/// Checks whether a path is matched by a pathspec.
+///
+/// A pathspec matches the path itself and, when it names a directory, every path inside it.
+/// The pathspec "." matches every path.
+///
+/// # Arguments
+///
+/// * `path` - The path as it is written in the index.
+/// * `pathspec` - The pathspec given by the user.
+///
+/// # Returns
+///
+/// Returns `true` if `pathspec` matches `path`.
+///
+pub fn path_matches_pathspec(path: &str, pathspec: &str) -> bool {
+    let pathspec = pathspec.trim_start_matches("./").trim_end_matches('/');
+    if pathspec.is_empty() || pathspec == "." {
+        return true;
+    }
+    path == pathspec || (path.starts_with(pathspec) && path[pathspec.len()..].starts_with('/'))
+}
+
+/// Selects, among `paths`, the ones matched by any of the pathspecs.
+///
+/// # Arguments
+///
+/// * `paths` - The candidate paths.
+/// * `pathspecs` - The pathspecs given by the user.
+///
+/// # Returns
+///
+/// Returns the matched paths, sorted.
+///
+/// # Errors
+///
+/// Returns a `NotFound` error naming the first pathspec that does not match any path.
+///
+pub fn select_paths<'a>(
+    paths: impl Iterator<Item = &'a String> + Clone,
+    pathspecs: &[String],
+) -> io::Result<BTreeSet<String>> {
+    let mut selected = BTreeSet::new();
+    for pathspec in pathspecs {
+        let matches: Vec<&String> = paths
+            .clone()
+            .filter(|path| path_matches_pathspec(path, pathspec))
+            .collect();
+        if matches.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!(
+                    "error: pathspec '{}' did not match any file(s) known to git",
+                    pathspec
+                ),
+            ));
+        }
+        selected.extend(matches.into_iter().cloned());
+    }
+    Ok(selected)
+}
+
+/// Reads the files of the tree of a revision.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the Git directory.
+/// * `revision` - The revision whose tree is read.
+///
+/// # Returns
+///
+/// Returns a map from every file path of the tree to the hash of its blob.
+///
+/// # Errors
+///
+/// Returns an error if the revision cannot be resolved or its tree cannot be loaded.
+///
+pub fn get_revision_files(git_dir: &str, revision: &str) -> io::Result<HashMap<String, String>> {
+    let commit = utils::resolve_revision(git_dir, revision)?;
+    let tree = tree_handler::load_tree_from_commit(&commit, git_dir)?;
+    Ok(tree.squash_tree_into_vec("").into_iter().collect())
+}
+
+/// Restores the given paths in the index and/or the working tree from a source.
+///
+/// When `source` is `None`, the working tree is restored from the index and the index from HEAD.
+/// Tracked paths matched by the pathspecs that do not exist in the source are removed from the
+/// restored locations.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the Git directory.
+/// * `root_dir` - The root directory of the working tree.
+/// * `source` - The revision to restore from, if any.
+/// * `pathspecs` - The paths to restore.
+/// * `staged` - Whether the index entries must be restored.
+/// * `worktree` - Whether the working tree files must be restored.
+///
+/// # Errors
+///
+/// Returns an error if a pathspec does not match any file, if the source cannot be read, or if
+/// the index or the working tree cannot be written.
+///
+pub fn restore_paths(
+    git_dir: &str,
+    root_dir: &str,
+    source: Option<&str>,
+    pathspecs: &[String],
+    staged: bool,
+    worktree: bool,
+) -> io::Result<()> {
+    let mut index = checkout::load_index(git_dir, root_dir)?;
+    let index_files: HashMap<String, String> = index
+        .iter()
+        .map(|(path, hash)| (path.to_string(), hash.to_string()))
+        .collect();
+    let source_files = match source {
+        Some(revision) => get_revision_files(git_dir, revision)?,
+        None if staged => match branch::get_current_branch_commit(git_dir) {
+            Ok(_) => get_revision_files(git_dir, "HEAD")?,
+            Err(_) => HashMap::new(),
+        },
+        None => index_files.clone(),
+    };
+    let candidates: BTreeSet<&String> = source_files.keys().chain(index_files.keys()).collect();
+    let selected = select_paths(candidates.iter().copied(), pathspecs)?;
+
+    for path in &selected {
+        let hash = source_files.get(path);
+        if staged {
+            match hash {
+                Some(hash) => index.add_file(path, hash)?,
+                None => {
+                    if index.contains(path) {
+                        index.remove_file(path)?;
+                    }
+                }
+            }
+        }
+        if worktree {
+            checkout::restore_path(root_dir, git_dir, path, hash)?;
+        }
+    }
+    if staged {
+        index.write_file()?;
+    }
+    log_restore(source.unwrap_or("index"), pathspecs)?;
+    Ok(())
+}
+
+/// Parses and executes the 'git restore' command.
+///
+/// Supported options are `--source=<rev>` (or `-s <rev>`), `--staged` (`-S`) and `--worktree`
+/// (`-W`). When neither `--staged` nor `--worktree` is given, only the working tree is restored.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the Git directory.
+/// * `root_dir` - The root directory of the working tree.
+/// * `line` - The command line, starting with the program name and "restore".
+/// * `output` - The writer where the usage is printed when the command is malformed.
+///
+/// # Errors
+///
+/// Returns an `InvalidInput` error if the command is malformed, or any error from `restore_paths`.
+///
+pub fn git_restore(
+    git_dir: &str,
+    root_dir: &str,
+    line: Vec<String>,
+    output: &mut impl Write,
+) -> io::Result<()> {
+    let mut source = None;
+    let mut staged = false;
+    let mut worktree = false;
+    let mut pathspecs = Vec::new();
+    let mut args = line.iter().skip(2);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--staged" | "-S" => staged = true,
+            "--worktree" | "-W" => worktree = true,
+            "--source" | "-s" => source = args.next().cloned(),
+            "--" => pathspecs.extend(args.by_ref().cloned()),
+            _ => match arg.strip_prefix("--source=") {
+                Some(revision) => source = Some(revision.to_string()),
+                None if arg.starts_with('-') => {
+                    writeln!(output, "{}", RESTORE_USAGE)?;
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("error: unknown option '{}'", arg),
+                    ));
+                }
+                None => pathspecs.push(arg.to_string()),
+            },
+        }
+    }
+    if pathspecs.is_empty() {
+        writeln!(output, "{}", RESTORE_USAGE)?;
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "fatal: you must specify path(s) to restore",
+        ));
+    }
+    if !staged {
+        worktree = true;
+    }
+    restore_paths(
+        git_dir,
+        root_dir,
+        source.as_deref(),
+        &pathspecs,
+        staged,
+        worktree,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commit;
+    use crate::test_utils;
+    use std::fs;
+    use std::path::Path;
+
+    fn prepare_repo(root_dir: &str) -> String {
+        let git_dir = test_utils::create_repo(root_dir);
+        test_utils::commit_files(
+            &git_dir,
+            root_dir,
+            &[("a.txt", "first a\n"), ("src/b.txt", "first b\n")],
+            "first",
+        );
+        git_dir
+    }
+
+    fn args(line: &[&str]) -> Vec<String> {
+        line.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_path_matches_pathspec() {
+        assert!(path_matches_pathspec("src/b.txt", "src"));
+        assert!(path_matches_pathspec("src/b.txt", "src/"));
+        assert!(path_matches_pathspec("src/b.txt", "."));
+        assert!(path_matches_pathspec("a.txt", "a.txt"));
+        assert!(!path_matches_pathspec("src2/b.txt", "src"));
+    }
+
+    #[test]
+    fn test_restore_discards_working_tree_changes() {
+        let root_dir = "tests/restore/worktree";
+        let git_dir = prepare_repo(root_dir);
+        fs::write(format!("{}/a.txt", root_dir), "edited\n").unwrap();
+        fs::write(format!("{}/src/b.txt", root_dir), "edited\n").unwrap();
+
+        git_restore(
+            &git_dir,
+            root_dir,
+            args(&["git", "restore", "a.txt"]),
+            &mut vec![],
+        )
+        .unwrap();
+
+        let a = fs::read_to_string(format!("{}/a.txt", root_dir)).unwrap();
+        let b = fs::read_to_string(format!("{}/src/b.txt", root_dir)).unwrap();
+        assert_eq!(a, "first a\n");
+        assert_eq!(b, "edited\n");
+        fs::remove_dir_all(root_dir).unwrap();
+    }
+
+    #[test]
+    fn test_restore_staged_unstages_without_touching_the_working_tree() {
+        let root_dir = "tests/restore/staged";
+        let git_dir = prepare_repo(root_dir);
+        test_utils::stage_files(
+            &git_dir,
+            root_dir,
+            &[("src/b.txt", "second b\n"), ("src/c.txt", "new c\n")],
+        );
+        let head_files = get_revision_files(&git_dir, "HEAD").unwrap();
+
+        git_restore(
+            &git_dir,
+            root_dir,
+            args(&["git", "restore", "--staged", "src"]),
+            &mut vec![],
+        )
+        .unwrap();
+
+        let index = checkout::load_index(&git_dir, root_dir).unwrap();
+        assert_eq!(index.get_hash("src/b.txt"), head_files.get("src/b.txt"));
+        assert!(!index.contains("src/c.txt"));
+        let b = fs::read_to_string(format!("{}/src/b.txt", root_dir)).unwrap();
+        assert_eq!(b, "second b\n");
+        assert!(Path::new(&format!("{}/src/c.txt", root_dir)).exists());
+        fs::remove_dir_all(root_dir).unwrap();
+    }
+
+    #[test]
+    fn test_restore_from_source_revision() {
+        let root_dir = "tests/restore/source";
+        let git_dir = prepare_repo(root_dir);
+        test_utils::stage_files(&git_dir, root_dir, &[("a.txt", "second a\n")]);
+        commit::new_commit(&git_dir, "second", "").unwrap();
+
+        git_restore(
+            &git_dir,
+            root_dir,
+            args(&[
+                "git",
+                "restore",
+                "--source=HEAD~1",
+                "--staged",
+                "--worktree",
+                "a.txt",
+            ]),
+            &mut vec![],
+        )
+        .unwrap();
+
+        let a = fs::read_to_string(format!("{}/a.txt", root_dir)).unwrap();
+        assert_eq!(a, "first a\n");
+        let index = checkout::load_index(&git_dir, root_dir).unwrap();
+        let first_files = get_revision_files(&git_dir, "main^").unwrap();
+        assert_eq!(index.get_hash("a.txt"), first_files.get("a.txt"));
+        fs::remove_dir_all(root_dir).unwrap();
+    }
+
+    #[test]
+    fn test_restore_unknown_path_fails() {
+        let root_dir = "tests/restore/unknown";
+        let git_dir = prepare_repo(root_dir);
+
+        let result = git_restore(
+            &git_dir,
+            root_dir,
+            args(&["git", "restore", "missing.txt"]),
+            &mut vec![],
+        );
+
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::NotFound);
+        fs::remove_dir_all(root_dir).unwrap();
+    }
+}
@@ -1,3 +1,4 @@
+use crate::hooks;
 use crate::packfile::handler::{create_packfile, unpack_packfile};
 use crate::server_utils::*;
 
@@ -11,6 +12,7 @@ use std::{fs, thread};
 
 const CAPABILITIES: &str = "multi_ack side-band-64k ofs-delta";
 const ZERO_HASH: &str = "0000000000000000000000000000000000000000";
+const SIDE_BAND_MESSAGE: char = '\u{2}';
 
 enum Command {
     UploadPack,
@@ -119,7 +121,9 @@ impl ServerInstace {
 
     // Receives the packfile from the client
     // After receiving it, it is unpacked and stored in the git_dir
-    // Then, the refs are updated
+    // Then, the refs accepted by the hooks are updated one by one
+    // Finally, the client is sent 'ok <ref>' or 'ng <ref> <reason>' for every ref it pushed,
+    // whether the hooks declined it or updating it failed
     fn receive_pack(&mut self) -> io::Result<()> {
         self.send_refs()?;
         let new_refs = self.wait_changes()?;
@@ -132,7 +136,67 @@ impl ServerInstace {
         if wait_for_packfile {
             self.wait_and_unpack_packfile()?;
         };
-        self.make_refs_changes(new_refs)
+
+        let mut updates: Vec<(String, (String, String))> = new_refs.into_iter().collect();
+        updates.sort();
+        if !self.run_receive_hook("pre-receive", &[], Some(&hook_input(&updates)))? {
+            for (ref_name, _) in &updates {
+                self.send(&pkt_line(&format!(
+                    "ng {} pre-receive hook declined\n",
+                    ref_name
+                )))?;
+            }
+            return self.flush();
+        }
+
+        log("Updating refs...")?;
+        let mut updated = vec![];
+        let mut statuses = vec![];
+        for (ref_name, (old, new)) in updates {
+            let result = match self.run_receive_hook("update", &[&ref_name, &old, &new], None) {
+                Ok(true) => self.make_ref_change(&ref_name, &old, &new),
+                Ok(false) => Err(io::Error::other("hook declined")),
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(()) => {
+                    statuses.push(format!("ok {}\n", ref_name));
+                    updated.push((ref_name, (old, new)));
+                }
+                Err(e) => {
+                    log(&format!("Ref {} not updated: {}", ref_name, e))?;
+                    statuses.push(format!("ng {} {}\n", ref_name, e));
+                }
+            }
+        }
+        // The refs are already updated, so a failing post-receive hook changes nothing
+        if !updated.is_empty() {
+            let _ = self.run_receive_hook("post-receive", &[], Some(&hook_input(&updated)));
+        }
+
+        for status in statuses {
+            self.send(&pkt_line(&status))?;
+        }
+        self.flush()
+    }
+
+    // Runs one of the hooks invoked while receiving a push, if it is installed
+    // Whatever the hook prints is sent to the client, one side-band message per line
+    // Returns whether the hook succeeded, which is the case when it is not installed
+    fn run_receive_hook(
+        &mut self,
+        name: &str,
+        args: &[&str],
+        input: Option<&str>,
+    ) -> io::Result<bool> {
+        let git_dir = self.git_dir_path.clone();
+        let Some(result) = hooks::run_receive_hook(&git_dir, name, args, input)? else {
+            return Ok(true);
+        };
+        for line in result.output.lines() {
+            self.send(&pkt_line(&format!("{}{}\n", SIDE_BAND_MESSAGE, line)))?;
+        }
+        Ok(result.success)
     }
 
     // Sends the server refs to the client
@@ -157,6 +221,11 @@ impl ServerInstace {
                 .iter()
                 .map(|(k, v)| format!("{} refs/heads/{}", v, k)),
         );
+        refs.extend(
+            get_notes_refs(&self.git_dir_path)?
+                .iter()
+                .map(|(k, v)| format!("{} {}", v, k)),
+        );
 
         if refs.is_empty() {
             let empty = format!("{} {}\0{}", ZERO_HASH, "capabilities^{}", CAPABILITIES);
@@ -221,17 +290,14 @@ impl ServerInstace {
         ))
     }
 
-    // Updates the refs with the new ones received from the client
-    fn make_refs_changes(&mut self, new_refs: HashMap<String, (String, String)>) -> io::Result<()> {
-        log("Updating refs...")?;
-        for (ref_name, (old, new)) in &new_refs {
-            match (old, new) {
-                (old, new) if old == ZERO_HASH => self.create_ref(ref_name, new)?,
-                (_old, new) if new == ZERO_HASH => self.delete_ref(ref_name)?,
-                (old, new) => self.update_ref(ref_name, old, new)?,
-            }
+    // Updates a ref with the new hash received from the client
+    // The ref is created when the old hash is the zero hash, and deleted when the new one is
+    fn make_ref_change(&mut self, ref_name: &str, old: &str, new: &str) -> io::Result<()> {
+        match (old, new) {
+            (old, new) if old == ZERO_HASH => self.create_ref(ref_name, new),
+            (_old, new) if new == ZERO_HASH => self.delete_ref(ref_name),
+            (old, new) => self.update_ref(ref_name, old, new),
         }
-        Ok(())
     }
 
     // Creates a new ref with the given name and hash
@@ -245,6 +311,9 @@ impl ServerInstace {
                 format!("Ref already exists: {}. Use update", ref_name),
             ));
         }
+        if let Some(parent) = ref_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
         let content = [new.as_bytes(), b"\n"].concat();
         write_file_with_lock(ref_path, content)?;
         Ok(())
@@ -291,25 +360,7 @@ impl ServerInstace {
             if size < 4 {
                 break;
             }
-            let (old, new, ref_name) = {
-                let (old, line) = line.split_once(' ').ok_or(io::Error::new(
-                    io::ErrorKind::InvalidData,
-                    format!("Invalid line: {}", line),
-                ))?;
-                let (new, line) = line.split_once(' ').ok_or(io::Error::new(
-                    io::ErrorKind::InvalidData,
-                    format!("Invalid line: {}", line),
-                ))?;
-                let (ref_name, _) = line.split_once('\0').ok_or(io::Error::new(
-                    io::ErrorKind::InvalidData,
-                    format!("Invalid line: {}", line),
-                ))?;
-                (
-                    old.to_string(),
-                    new.to_string(),
-                    ref_name.trim().to_string(),
-                )
-            };
+            let (old, new, ref_name) = parse_ref_update(&line)?;
 
             let head_ref = match get_head_from_branch(&self.git_dir_path, "HEAD") {
                 Ok(head) => head,
@@ -346,6 +397,33 @@ impl ServerInstace {
     }
 }
 
+// Parses a '<old> <new> <ref>' line sent by the client when pushing, returning the three values
+// Only the first line carries the capabilities, after a '\0'
+fn parse_ref_update(line: &str) -> io::Result<(String, String, String)> {
+    let invalid = || {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Invalid line: {}", line),
+        )
+    };
+    let (old, rest) = line.split_once(' ').ok_or_else(invalid)?;
+    let (new, rest) = rest.split_once(' ').ok_or_else(invalid)?;
+    let ref_name = rest.split_once('\0').map_or(rest, |(ref_name, _)| ref_name);
+    Ok((
+        old.to_string(),
+        new.to_string(),
+        ref_name.trim().to_string(),
+    ))
+}
+
+// Returns the lines given to the 'pre-receive' and 'post-receive' hooks, one '<old> <new> <ref>' per ref
+fn hook_input(updates: &[(String, (String, String))]) -> String {
+    updates
+        .iter()
+        .map(|(ref_name, (old, new))| format!("{} {} {}\n", old, new, ref_name))
+        .collect()
+}
+
 /// Runs a git server
 ///
 /// # Arguments
@@ -496,3 +574,143 @@ fn is_repo_bare(git_dir: &str) -> io::Result<bool> {
     let bare = bare.trim();
     Ok(bare == "true")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::Client;
+    use crate::configuration::GIT_DIR_FOR_TEST;
+    use crate::{notes, test_utils};
+
+    const NOTES_REF: &str = "refs/notes/commits";
+
+    /// Creates a local repository with a commit on `main` and a note, and an empty repository
+    /// named `server` next to it, served from `root_dir`, with the hook `hook_name` installed
+    /// and `other` checked out.
+    /// Then pushes `main`, which also pushes the notes, to a server handling that single push.
+    ///
+    /// Returns the result of the push, the messages the client received from the server, and
+    /// the git directory of the server repository.
+    #[cfg(unix)]
+    fn push_with_hook(
+        root_dir: &str,
+        hook_name: &str,
+        hook: &str,
+    ) -> (io::Result<()>, Vec<String>, String) {
+        use std::os::unix::fs::PermissionsExt;
+        let _ = fs::remove_dir_all(root_dir);
+        let local_dir = format!("{}/local", root_dir);
+        let local_git_dir = test_utils::create_repo(&local_dir);
+        test_utils::commit_files(&local_git_dir, &local_dir, &[("file.txt", "one\n")], "one");
+        let line = ["git", "notes", "add", "-m", "note"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        notes::git_notes(&local_git_dir, line, &mut Vec::new()).unwrap();
+
+        let server_git_dir = test_utils::create_repo(&format!("{}/server", root_dir));
+        // The server refuses to update the branch it has checked out
+        fs::write(
+            format!("{}/HEAD", server_git_dir),
+            "ref: refs/heads/other\n",
+        )
+        .unwrap();
+        let hook_path = format!("{}/hooks/{}", server_git_dir, hook_name);
+        fs::create_dir_all(format!("{}/hooks", server_git_dir)).unwrap();
+        fs::write(&hook_path, hook).unwrap();
+        fs::set_permissions(&hook_path, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let listener = TcpListener::bind("localhost:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let path = root_dir.to_string();
+        let server = thread::spawn(move || {
+            let (socket, _) = listener.accept().unwrap();
+            let mut server = ServerInstace {
+                socket,
+                path,
+                git_dir: GIT_DIR_FOR_TEST.to_string(),
+                git_dir_path: String::default(),
+            };
+            server.handle_client()
+        });
+
+        let mut client = Client::new(&address, "server", "localhost");
+        let result = client.receive_pack("main", &local_git_dir);
+        server.join().unwrap().unwrap();
+        (result, client.remote_messages().to_vec(), server_git_dir)
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_push_declined_by_the_pre_receive_hook_updates_no_ref() {
+        let root_dir = "tests/server/pre_receive_declined";
+        let hook = "#!/bin/sh\necho \"pushes are closed\"\nexit 1\n";
+        let (result, messages, server_git_dir) = push_with_hook(root_dir, "pre-receive", hook);
+
+        let error = result.unwrap_err().to_string();
+        assert!(error.contains("refs/heads/main (pre-receive hook declined)"));
+        assert!(error.contains("refs/notes/commits (pre-receive hook declined)"));
+        assert_eq!(messages, vec!["remote: pushes are closed".to_string()]);
+        assert!(!Path::new(&format!("{}/refs/heads/main", server_git_dir)).exists());
+        assert!(!Path::new(&format!("{}/{}", server_git_dir, NOTES_REF)).exists());
+        let _ = fs::remove_dir_all(root_dir);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_push_partly_declined_by_the_update_hook_updates_the_accepted_refs() {
+        let root_dir = "tests/server/update_declined";
+        let hook = "#!/bin/sh\n\
+            if [ \"$1\" = refs/notes/commits ]; then\n\
+            echo \"notes are not accepted\"\n\
+            exit 1\n\
+            fi\n";
+        let (result, messages, server_git_dir) = push_with_hook(root_dir, "update", hook);
+
+        let error = result.unwrap_err().to_string();
+        assert!(error.contains("refs/notes/commits (hook declined)"));
+        assert!(!error.contains("refs/heads/main"));
+        assert_eq!(messages, vec!["remote: notes are not accepted".to_string()]);
+        assert!(Path::new(&format!("{}/refs/heads/main", server_git_dir)).exists());
+        assert!(!Path::new(&format!("{}/{}", server_git_dir, NOTES_REF)).exists());
+        let _ = fs::remove_dir_all(root_dir);
+    }
+
+    #[test]
+    fn test_parse_ref_update_with_and_without_capabilities() {
+        let old = "1".repeat(40);
+        let new = "2".repeat(40);
+        let first = format!("{} {} refs/heads/main\0{}\n", old, new, CAPABILITIES);
+        assert_eq!(
+            parse_ref_update(&first).unwrap(),
+            (old.clone(), new.clone(), "refs/heads/main".to_string())
+        );
+        let second = format!("{} {} refs/notes/commits\n", ZERO_HASH, new);
+        assert_eq!(
+            parse_ref_update(&second).unwrap(),
+            (
+                ZERO_HASH.to_string(),
+                new.clone(),
+                "refs/notes/commits".to_string()
+            )
+        );
+        let error = parse_ref_update("refs/heads/main").unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_push_reports_a_ref_that_cannot_be_updated_and_updates_the_others() {
+        let root_dir = "tests/server/ref_update_failed";
+        // The hook runs from the git directory, creating the branch before the push does
+        let hook = "#!/bin/sh\necho 0000000000000000000000000000000000000001 > refs/heads/main\n";
+        let (result, messages, server_git_dir) = push_with_hook(root_dir, "pre-receive", hook);
+
+        let error = result.unwrap_err().to_string();
+        assert!(error.contains("refs/heads/main (Ref already exists: refs/heads/main. Use update)"));
+        assert!(!error.contains("refs/notes/commits"));
+        assert!(messages.is_empty());
+        assert!(Path::new(&format!("{}/{}", server_git_dir, NOTES_REF)).exists());
+        let _ = fs::remove_dir_all(root_dir);
+    }
+}
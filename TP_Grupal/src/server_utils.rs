@@ -7,7 +7,13 @@ use std::{
     str::from_utf8,
 };
 
-use crate::{cat_file, logger, utils::get_current_time};
+use crate::{
+    cat_file, logger,
+    utils::{self, get_current_time},
+};
+
+/// Prefix of the refs holding the notes attached to objects
+pub const NOTES_REFS_PREFIX: &str = "refs/notes/";
 
 pub fn log(message: &str) -> io::Result<()> {
     let mut logger = logger::Logger::new("logs/log.log")?;
@@ -106,10 +112,7 @@ pub fn get_head_from_branch(git_dir: &str, branch: &str) -> io::Result<String> {
         ))?;
         return Ok(head.trim().to_string());
     }
-    let pathbuf = PathBuf::from(git_dir)
-        .join("refs")
-        .join("tags")
-        .join(branch);
+    let pathbuf = PathBuf::from(utils::git_path(git_dir, &format!("refs/tags/{}", branch)));
     if pathbuf.exists() {
         Ok(format!("refs/tags/{}", branch))
     } else {
@@ -119,9 +122,9 @@ pub fn get_head_from_branch(git_dir: &str, branch: &str) -> io::Result<String> {
 
 /// Auxiliar function which get refs under refs/heads
 pub fn get_head_tags_refs(git_dir: &str) -> io::Result<HashMap<String, String>> {
-    let pathbuf = PathBuf::from(git_dir);
-    let heads = pathbuf.join("refs").join("heads");
-    let tags = pathbuf.join("refs").join("tags");
+    let pathbuf = PathBuf::from(utils::git_path(git_dir, "refs"));
+    let heads = pathbuf.join("heads");
+    let tags = pathbuf.join("tags");
     let mut refs = get_refs(heads)?;
     let tags = get_refs(tags)?;
     refs.extend(tags);
@@ -130,18 +133,33 @@ pub fn get_head_tags_refs(git_dir: &str) -> io::Result<HashMap<String, String>>
 
 /// Auxiliar function which get refs under refs/heads
 pub fn get_client_refs(git_dir: &str, remote: &str) -> io::Result<HashMap<String, String>> {
-    let pathbuf = PathBuf::from(git_dir);
-    let remotes = pathbuf.join("refs").join("remotes").join(remote);
+    let pathbuf = PathBuf::from(utils::git_path(git_dir, "refs"));
+    let remotes = pathbuf.join("remotes").join(remote);
     if !remotes.exists() {
         fs::create_dir_all(&remotes)?;
     }
-    let tags = pathbuf.join("refs").join("tags");
+    let tags = pathbuf.join("tags");
     let mut refs = get_refs(remotes)?;
     let tags = get_refs(tags)?;
     refs.extend(tags);
     Ok(refs)
 }
 
+/// Auxiliar function which get refs under refs/notes
+/// Unlike the other refs, the keys are the complete ref names. e.g. refs/notes/commits
+/// If there are no notes, the hashmap is empty
+pub fn get_notes_refs(git_dir: &str) -> io::Result<HashMap<String, String>> {
+    let notes = PathBuf::from(utils::git_path(git_dir, "refs/notes"));
+    if !notes.exists() {
+        return Ok(HashMap::new());
+    }
+    let refs = get_refs(notes)?
+        .into_iter()
+        .map(|(name, hash)| (format!("{}{}", NOTES_REFS_PREFIX, name), hash))
+        .collect();
+    Ok(refs)
+}
+
 // Auxiliar function which get refs under refs_path
 fn get_refs(refs_path: PathBuf) -> io::Result<HashMap<String, String>> {
     let mut refs = HashMap::new();
@@ -0,0 +1,467 @@
+use crate::cat_file;
+use crate::commit;
+use crate::configuration::LOGGER_COMMANDS_FILE;
+use crate::diff::{self, DiffFormat, RenameOptions};
+use crate::log::format_date;
+use crate::logger::Logger;
+use crate::notes;
+use crate::tree_handler;
+use crate::utils::{self, get_current_time};
+use std::io::{self, Write};
+
+/// Logs the 'git show' command with the specified objects.
+///
+/// This function logs the 'git show' command with the provided revisions to a file named
+/// 'logger_commands.txt'.
+///
+/// # Arguments
+///
+/// * `revisions` - The revisions given to the command.
+///
+/// # Errors
+///
+/// Returns an `io::Result` indicating whether the operation was successful.
+///
+fn log_show(revisions: &[String]) -> io::Result<()> {
+    let log_file_path = LOGGER_COMMANDS_FILE;
+    let mut logger = Logger::new(log_file_path)?;
+
+    let full_message = format!(
+        "Command 'git show': Objects '{}', {}",
+        revisions.join(" "),
+        get_current_time()
+    );
+    logger.write_all(full_message.as_bytes())?;
+    logger.flush()?;
+    Ok(())
+}
+
+/// Splits the content of a commit or tag object into its header lines and its message.
+fn split_header_and_message(content: &str) -> (Vec<&str>, &str) {
+    match content.split_once("\n\n") {
+        Some((header, message)) => (header.lines().collect(), message),
+        None => (content.lines().collect(), ""),
+    }
+}
+
+/// Returns the value of the first header line starting with the given key.
+fn header_value<'a>(header: &[&'a str], key: &str) -> Option<&'a str> {
+    header
+        .iter()
+        .find_map(|line| line.strip_prefix(key)?.strip_prefix(' '))
+}
+
+/// Splits an identity such as `name email 1700000000 -0300` into the person and the date.
+fn split_identity(identity: &str) -> (String, String) {
+    let fields: Vec<&str> = identity.split(' ').collect();
+    if fields.len() < 3 {
+        return (identity.to_string(), String::new());
+    }
+    let len = fields.len();
+    (fields[..len - 2].join(" "), fields[len - 2..].join(" "))
+}
+
+/// Writes a message indented by four spaces, the way `git show` and `git log` print it.
+fn write_indented_message(message: &str, output: &mut impl Write) -> io::Result<()> {
+    for line in message.trim_end_matches('\n').lines() {
+        if line.is_empty() {
+            writeln!(output)?;
+        } else {
+            writeln!(output, "    {}", line)?;
+        }
+    }
+    Ok(())
+}
+
+/// Shows a commit: its header, its message, its note if it has one, and the changes it
+/// introduced.
+///
+/// A regular commit is diffed against its first parent (or an empty tree for the root commit).
+/// A merge commit is shown with a combined diff against both parents, which only includes the
+/// files that differ from every parent. Descriptions other than the patch compare a merge
+/// commit with its first parent.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the git directory.
+/// * `hash` - The hash of the commit.
+/// * `diff_format` - How the changes of the commit are described.
+/// * `output` - Where the commit is written.
+///
+/// # Errors
+///
+/// Returns an error if the commit, its trees or its blobs cannot be read.
+///
+fn show_commit(
+    git_dir: &str,
+    hash: &str,
+    diff_format: DiffFormat,
+    output: &mut impl Write,
+) -> io::Result<()> {
+    let content = cat_file::cat_file_return_content(hash, git_dir)?;
+    let (header, message) = split_header_and_message(&content);
+    let is_merge = commit::is_merge_commit(hash, git_dir)?;
+
+    writeln!(output, "commit {}", hash)?;
+    let parents = if is_merge {
+        let parents = commit::get_merge_parents(hash, git_dir)?;
+        let abbreviated: Vec<&str> = parents.iter().map(|parent| &parent[..7]).collect();
+        writeln!(output, "Merge: {}", abbreviated.join(" "))?;
+        parents
+    } else {
+        commit::get_commit_parents(hash, git_dir)?
+    };
+    if let Some(author) = header_value(&header, "author") {
+        let (author, date) = split_identity(author);
+        writeln!(output, "Author: {}", author)?;
+        writeln!(output, "Date:   {}", format_date(&date))?;
+    }
+    writeln!(output)?;
+    write_indented_message(message, output)?;
+    if let Some(note) = notes::read_note(git_dir, hash)? {
+        writeln!(output, "\nNotes:")?;
+        write_indented_message(&note, output)?;
+    }
+
+    let tree = tree_handler::load_tree_from_commit(hash, git_dir)?;
+    let parent_tree = match parents.first() {
+        Some(parent) => Some(tree_handler::load_tree_from_commit(parent, git_dir)?),
+        None => None,
+    };
+    let changes = diff::get_tree_changes_with_renames(
+        parent_tree.as_ref(),
+        Some(&tree),
+        git_dir,
+        diff_format.renames,
+    )?;
+    let mut description = Vec::new();
+    if is_merge {
+        let summary = DiffFormat {
+            patch: false,
+            ..diff_format
+        };
+        diff::write_changes(&changes, git_dir, summary, &mut description)?;
+        if diff_format.patch {
+            let mut parent_trees = Vec::new();
+            for parent in &parents {
+                parent_trees.push(tree_handler::load_tree_from_commit(parent, git_dir)?);
+            }
+            let mut patch = Vec::new();
+            diff::write_combined_patch(&parent_trees, &tree, git_dir, &mut patch)?;
+            if !patch.is_empty() && !description.is_empty() {
+                writeln!(description)?;
+            }
+            description.extend(patch);
+        }
+    } else {
+        diff::write_changes(&changes, git_dir, diff_format, &mut description)?;
+    }
+    if !description.is_empty() {
+        writeln!(output)?;
+        output.write_all(&description)?;
+    }
+    Ok(())
+}
+
+/// Shows an annotated tag: its header and message, followed by the object it points to.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the git directory.
+/// * `hash` - The hash of the tag object.
+/// * `diff_format` - How the changes are described if the tag points to a commit.
+/// * `output` - Where the tag is written.
+///
+/// # Errors
+///
+/// Returns an error if the tag or its target cannot be read.
+///
+fn show_tag(
+    git_dir: &str,
+    hash: &str,
+    diff_format: DiffFormat,
+    output: &mut impl Write,
+) -> io::Result<()> {
+    let content = cat_file::cat_file_return_content(hash, git_dir)?;
+    let (header, message) = split_header_and_message(&content);
+    let target = header_value(&header, "object").ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("error: tag {} has no target object", hash),
+        )
+    })?;
+
+    writeln!(
+        output,
+        "tag {}",
+        header_value(&header, "tag").unwrap_or(hash)
+    )?;
+    if let Some(tagger) = header_value(&header, "tagger") {
+        let (tagger, date) = split_identity(tagger);
+        writeln!(output, "Tagger: {}", tagger)?;
+        writeln!(output, "Date:   {}", format_date(&date))?;
+    }
+    writeln!(output)?;
+    for line in message.trim_end_matches('\n').lines() {
+        writeln!(output, "{}", line)?;
+    }
+    writeln!(output)?;
+    show_object(git_dir, target.trim(), target.trim(), diff_format, output)
+}
+
+/// Shows a tree as a listing of its entries, marking directories with a trailing `/`.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the git directory.
+/// * `revision` - The revision as the user wrote it, used in the `tree` header line.
+/// * `hash` - The hash of the tree object.
+/// * `output` - Where the listing is written.
+///
+/// # Errors
+///
+/// Returns an error if the tree cannot be read.
+///
+fn show_tree(git_dir: &str, revision: &str, hash: &str, output: &mut impl Write) -> io::Result<()> {
+    writeln!(output, "tree {}\n", revision)?;
+    for (mode, name, _) in cat_file::cat_tree(hash, git_dir)? {
+        if mode.starts_with('4') {
+            writeln!(output, "{}/", name)?;
+        } else {
+            writeln!(output, "{}", name)?;
+        }
+    }
+    Ok(())
+}
+
+/// Shows a single object according to its type.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the git directory.
+/// * `revision` - The revision as the user wrote it.
+/// * `hash` - The hash of the object named by `revision`.
+/// * `diff_format` - How the changes are described if the object is a commit.
+/// * `output` - Where the object is written.
+///
+/// # Errors
+///
+/// Returns an error if the object cannot be read or has an unknown type.
+///
+fn show_object(
+    git_dir: &str,
+    revision: &str,
+    hash: &str,
+    diff_format: DiffFormat,
+    output: &mut impl Write,
+) -> io::Result<()> {
+    match cat_file::get_object_type(hash, git_dir)?.as_str() {
+        "commit" => show_commit(git_dir, hash, diff_format, output),
+        "tag" => show_tag(git_dir, hash, diff_format, output),
+        "tree" => show_tree(git_dir, revision, hash, output),
+        "blob" => {
+            let content = cat_file::cat_file_return_content(hash, git_dir)?;
+            output.write_all(content.as_bytes())
+        }
+        object_type => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("error: object {} has unknown type '{}'", hash, object_type),
+        )),
+    }
+}
+
+/// Shows one or more objects, like `git show`.
+///
+/// Every revision is resolved with `utils::resolve_object`, so it can be a branch, a tag, a
+/// (possibly abbreviated) hash or a revision with `~`/`^` suffixes. Commits are shown with their
+/// header, message and diff, annotated tags with their header followed by the tagged object,
+/// trees as a listing of their entries and blobs as their raw content. Without revisions, `HEAD`
+/// is shown.
+///
+/// The diff of a commit is a patch by default. It can be replaced with `--stat`, `--numstat` or
+/// `--name-status`, combined with `-p`, or hidden with `-s`; see `diff::DiffFormat`.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the git directory.
+/// * `line` - The full command line, starting with `git show`.
+/// * `output` - Where the objects are written.
+///
+/// # Errors
+///
+/// Returns an error if an option is unknown, a revision cannot be resolved or an object cannot
+/// be read. Objects before the failing revision have already been written to `output`.
+///
+pub fn git_show(git_dir: &str, line: Vec<String>, output: &mut impl Write) -> io::Result<()> {
+    let mut diff_format = DiffFormat::default();
+    let mut explicit_format = false;
+    let mut revisions: Vec<String> = Vec::new();
+    for arg in line.iter().skip(2) {
+        if !arg.starts_with('-') {
+            revisions.push(arg.clone());
+        } else if diff_format.parse_option(arg) {
+            explicit_format |= RenameOptions::parse(arg).is_none();
+        } else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("error: unknown option '{}'", arg),
+            ));
+        }
+    }
+    if !explicit_format {
+        diff_format.patch = true;
+    }
+    if revisions.is_empty() {
+        revisions.push("HEAD".to_string());
+    }
+    log_show(&revisions)?;
+    for (index, revision) in revisions.iter().enumerate() {
+        if index > 0 {
+            writeln!(output)?;
+        }
+        let hash = utils::resolve_object(git_dir, revision)?;
+        show_object(git_dir, revision, &hash, diff_format, output)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{add, hash_object, init, tag};
+    use std::{fs, path::Path};
+
+    fn prepare_repo(test_dir: &str) -> String {
+        if Path::new(test_dir).exists() {
+            fs::remove_dir_all(test_dir).expect("Failed to delete directory");
+        }
+        fs::create_dir_all(test_dir).expect("Failed to create test directory");
+        init::git_init(test_dir, ".mgit", "master", None).expect("Failed to init repo");
+        test_dir.to_string() + "/.mgit"
+    }
+
+    fn commit_file(git_dir: &str, path: &str, content: &str, message: &str) -> String {
+        fs::write(path, content).expect("Failed to write file");
+        let index = git_dir.to_string() + "/index";
+        add::add(path, &index, git_dir, "", None).expect("Failed to add file");
+        commit::new_commit(git_dir, message, "").expect("Failed to commit")
+    }
+
+    fn show(git_dir: &str, revisions: &[&str]) -> String {
+        let mut line = vec!["git".to_string(), "show".to_string()];
+        line.extend(revisions.iter().map(|revision| revision.to_string()));
+        let mut output = Vec::new();
+        git_show(git_dir, line, &mut output).expect("Failed to show");
+        String::from_utf8(output).expect("Invalid UTF-8")
+    }
+
+    #[test]
+    fn test_show_commit_prints_header_and_patch() {
+        let test_dir = "tests/show/commit";
+        let git_dir = prepare_repo(test_dir);
+        let path = test_dir.to_string() + "/file.txt";
+        commit_file(&git_dir, &path, "a\nb\n", "First");
+        let hash = commit_file(&git_dir, &path, "a\nc\n", "Second");
+
+        let shown = show(&git_dir, &[]);
+        let expected_start = format!("commit {}\nAuthor: ", hash);
+        assert!(shown.starts_with(&expected_start));
+        let expected_end = format!(
+            "\n    Second\n\ndiff --git a/{path} b/{path}\n--- a/{path}\n+++ b/{path}\n@@ -1,2 +1,2 @@\n a\n-b\n+c\n",
+            path = path
+        );
+        assert!(shown.ends_with(&expected_end));
+
+        let first = show(&git_dir, &["HEAD~1"]);
+        assert!(first.contains("new file mode 100644"));
+        fs::remove_dir_all(test_dir).unwrap();
+    }
+
+    #[test]
+    fn test_show_commit_with_stat_and_name_status() {
+        let test_dir = "tests/show/stat";
+        let git_dir = prepare_repo(test_dir);
+        let path = test_dir.to_string() + "/file.txt";
+        commit_file(&git_dir, &path, "a\nb\n", "First");
+        commit_file(&git_dir, &path, "a\nc\n", "Second");
+
+        let shown = show(&git_dir, &["--name-status"]);
+        assert!(shown.ends_with(&format!("\n    Second\n\nM\t{}\n", path)));
+        let shown = show(&git_dir, &["--numstat", "-p"]);
+        let expected = format!(
+            "\n    Second\n\n1\t1\t{path}\n\ndiff --git a/{path} b/{path}\n",
+            path = path
+        );
+        assert!(shown.contains(&expected));
+        let shown = show(&git_dir, &["-s", "HEAD~1"]);
+        assert!(shown.ends_with("\n    First\n"));
+        fs::remove_dir_all(test_dir).unwrap();
+    }
+
+    #[test]
+    fn test_show_merge_commit_prints_combined_diff() {
+        let test_dir = "tests/show/merge";
+        let git_dir = prepare_repo(test_dir);
+        let path = test_dir.to_string() + "/file.txt";
+        let ours = commit_file(&git_dir, &path, "a\nours\nc\n", "Ours");
+        let theirs = commit_file(&git_dir, &path, "a\ntheirs\nc\n", "Theirs");
+        fs::write(&path, "a\nresolved\nc\n").unwrap();
+        let index = git_dir.to_string() + "/index";
+        add::add(&path, &index, &git_dir, "", None).unwrap();
+        commit::new_merge_commit(&git_dir, "Merge", &ours, &theirs, "").unwrap();
+
+        let shown = show(&git_dir, &["HEAD"]);
+        assert!(shown.contains(&format!("Merge: {} {}\n", &ours[..7], &theirs[..7])));
+        let expected_end = format!(
+            "diff --cc {path}\n--- a/{path}\n+++ b/{path}\n@@@ -1,3 -1,3 +1,3 @@@\n  a\n- ours\n -theirs\n++resolved\n  c\n",
+            path = path
+        );
+        assert!(shown.ends_with(&expected_end));
+        fs::remove_dir_all(test_dir).unwrap();
+    }
+
+    #[test]
+    fn test_show_annotated_tag_prints_tag_and_target() {
+        let test_dir = "tests/show/tag";
+        let git_dir = prepare_repo(test_dir);
+        let path = test_dir.to_string() + "/file.txt";
+        let hash = commit_file(&git_dir, &path, "a\n", "First");
+        let config_path = git_dir.to_string() + "/config";
+        let config = fs::read_to_string(&config_path).unwrap();
+        let config = config + "[user]\n\tname = Claris\n\temail = crfrugoli@unmail.com.ar\n";
+        fs::write(&config_path, config).unwrap();
+        let line: Vec<String> = ["git", "tag", "-a", "v1", "-m", "Release"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        tag::git_tag(&git_dir, line, &mut Vec::new()).unwrap();
+
+        let shown = show(&git_dir, &["v1"]);
+        assert!(shown.starts_with("tag v1\nTagger: "));
+        assert!(shown.contains(&format!("\n\nRelease\n\ncommit {}\n", hash)));
+        fs::remove_dir_all(test_dir).unwrap();
+    }
+
+    #[test]
+    fn test_show_tree_and_blob() {
+        let test_dir = "tests/show/tree";
+        let git_dir = prepare_repo(test_dir);
+        let path = test_dir.to_string() + "/file.txt";
+        commit_file(&git_dir, &path, "contenido\n", "First");
+        let blob = hash_object::store_string_to_file("contenido\n", &git_dir, "blob").unwrap();
+        let content = cat_file::cat_file_return_content(&blob, &git_dir).unwrap();
+        let tree = tree_handler::load_tree_from_commit(
+            &utils::resolve_revision(&git_dir, "HEAD").unwrap(),
+            &git_dir,
+        )
+        .unwrap();
+        let (tree_hash, _) = tree_handler::write_tree(&tree, &git_dir).unwrap();
+
+        assert_eq!(show(&git_dir, &[&blob]), content);
+        assert_eq!(
+            show(&git_dir, &[&tree_hash]),
+            format!("tree {}\n\ntests/\n", tree_hash)
+        );
+        fs::remove_dir_all(test_dir).unwrap();
+    }
+}
@@ -4,7 +4,7 @@ use std::{
     path::Path,
 };
 
-use crate::utils::get_current_time;
+use crate::utils::{self, get_current_time};
 use crate::{configuration::LOGGER_COMMANDS_FILE, logger::Logger};
 use std::fs::File;
 use std::io::prelude::*;
@@ -129,7 +129,7 @@ pub fn git_show_ref(git_dir: &str, line: Vec<String>, output: &mut impl Write) -
 ///
 fn verify_ref(git_dir: &str, line: Vec<String>, output: &mut impl Write) -> io::Result<()> {
     for line_path in line.iter().skip(3) {
-        let path_to_verify = format!("{}/{}", git_dir, &line_path);
+        let path_to_verify = utils::git_path(git_dir, line_path);
         let path = Path::new(&path_to_verify);
 
         if !path.exists() {
@@ -200,9 +200,9 @@ fn show_refs_in_remotes_folder(
 /// writer is correctly implemented.
 ///
 fn show_ref(git_dir: &str, output: &mut impl Write) -> io::Result<()> {
-    let heads_path = format!("{}/{}", git_dir, "refs/heads");
-    let tags_path = format!("{}/{}", git_dir, "refs/tags");
-    let remotes_path = format!("{}/{}", git_dir, "refs/remotes");
+    let heads_path = utils::git_path(git_dir, "refs/heads");
+    let tags_path = utils::git_path(git_dir, "refs/tags");
+    let remotes_path = utils::git_path(git_dir, "refs/remotes");
     process_files_in_directory(&heads_path, "heads", false, output)?;
     process_files_in_directory(&tags_path, "tags", false, output)?;
     show_refs_in_remotes_folder(&remotes_path, false, output)?;
@@ -249,21 +249,21 @@ fn show_ref_with_options(
     output: &mut impl Write,
 ) -> io::Result<()> {
     if line[2].eq("--heads") {
-        let heads_path = format!("{}/{}", git_dir, "refs/heads");
+        let heads_path = utils::git_path(git_dir, "refs/heads");
         let path = Path::new(&heads_path);
         if path.exists() {
             process_files_in_directory(&heads_path, "heads", false, output)?;
         }
     } else if line[2].eq("--tags") {
-        let tags_path: String = format!("{}/{}", git_dir, "refs/tags");
+        let tags_path: String = utils::git_path(git_dir, "refs/tags");
         let path = Path::new(&tags_path);
         if path.exists() {
             process_files_in_directory(&tags_path, "tags", false, output)?;
         }
     } else if line[2].eq("--hash") {
-        let heads_path = format!("{}/{}", git_dir, "refs/heads");
-        let tags_path = format!("{}/{}", git_dir, "refs/tags");
-        let remotes_path = format!("{}/{}", git_dir, "refs/remotes");
+        let heads_path = utils::git_path(git_dir, "refs/heads");
+        let tags_path = utils::git_path(git_dir, "refs/tags");
+        let remotes_path = utils::git_path(git_dir, "refs/remotes");
         let path = Path::new(&heads_path);
         if path.exists() {
             process_files_in_directory(&heads_path, "heads", true, output)?;
@@ -328,6 +328,34 @@ fn process_files_in_directory(
     is_hash: bool,
     output: &mut impl Write,
 ) -> io::Result<()> {
+    for (contents, ref_name) in read_refs_in_directory(path, type_)? {
+        if is_hash {
+            writeln!(output, "{}\n", contents)?;
+        } else {
+            writeln!(output, "{}\t{}\n", contents, ref_name)?;
+        }
+    }
+    Ok(())
+}
+
+/// Reads the references stored as files in the specified directory.
+///
+/// # Arguments
+///
+/// * `path` - A string slice representing the path to the directory containing the references.
+/// * `type_` - A string slice specifying the type associated with the references, such as `heads`.
+///
+/// # Returns
+///
+/// Returns a vector of `(hash, name)` pairs, where the name is the full reference name such as
+/// `refs/heads/main`.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if the directory or one of its files cannot be read.
+///
+fn read_refs_in_directory(path: &str, type_: &str) -> io::Result<Vec<(String, String)>> {
+    let mut refs = vec![];
     for entry in fs::read_dir(path)? {
         let entry = entry?;
         let file_path = entry.path();
@@ -344,20 +372,53 @@ fn process_files_in_directory(
 
             let mut contents = String::new();
             file.read_to_string(&mut contents)?;
-            if is_hash {
-                writeln!(output, "{}\n", contents.trim())?;
-            } else {
-                writeln!(
-                    output,
-                    "{}\trefs/{}/{}\n",
-                    contents.trim(),
-                    type_,
-                    file_name
-                )?;
+            refs.push((
+                contents.trim().to_string(),
+                format!("refs/{}/{}", type_, file_name),
+            ));
+        }
+    }
+    Ok(refs)
+}
+
+/// Returns every branch, tag and remote branch of the repository, the same references listed by
+/// `git show-ref`.
+///
+/// # Arguments
+///
+/// * `git_dir` - A string slice representing the path to the Git directory.
+///
+/// # Returns
+///
+/// Returns a vector of `(hash, name)` pairs sorted by name, where the name is the full reference
+/// name such as `refs/heads/main`, `refs/tags/v1.0` or `refs/remotes/origin/main`. Tags are not
+/// peeled, so annotated tags point to the tag object.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if one of the references cannot be read.
+///
+pub fn get_refs(git_dir: &str) -> io::Result<Vec<(String, String)>> {
+    let mut refs = vec![];
+    for type_ in ["heads", "tags"] {
+        let path = utils::git_path(git_dir, &format!("refs/{}", type_));
+        if Path::new(&path).exists() {
+            refs.extend(read_refs_in_directory(&path, type_)?);
+        }
+    }
+    let remotes_path = utils::git_path(git_dir, "refs/remotes");
+    if Path::new(&remotes_path).exists() {
+        for entry in fs::read_dir(&remotes_path)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                let remote = path.file_name().unwrap_or_default().to_string_lossy();
+                let type_ = format!("remotes/{}", remote);
+                refs.extend(read_refs_in_directory(&path.to_string_lossy(), &type_)?);
             }
         }
     }
-    Ok(())
+    refs.sort_by(|a, b| a.1.cmp(&b.1));
+    Ok(refs)
 }
 
 #[cfg(test)]
@@ -0,0 +1,772 @@
+use crate::cat_file;
+use crate::checkout;
+use crate::commit;
+use crate::configuration::{GIT_IGNORE, LOGGER_COMMANDS_FILE};
+use crate::diff;
+use crate::hash_object;
+use crate::index::Index;
+use crate::logger::Logger;
+use crate::tree_handler::{self, Tree};
+use crate::utils::{self, get_current_time};
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+const STASH_REF: &str = "refs/stash";
+const STASH_LOG: &str = "logs/refs/stash";
+const NULL_HASH: &str = "0000000000000000000000000000000000000000";
+const STASH_USAGE: &str = "usage: git stash [push [-m <message>] [-u | --include-untracked]] | list | show [-p] [<stash>] | apply [<stash>] | pop [<stash>] | drop [<stash>]";
+
+/// Logs the 'git stash' command with the specified subcommand.
+///
+/// This function logs the 'git stash' command with the provided Git directory and subcommand
+/// to a file named 'logger_commands.txt'.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the Git directory.
+/// * `subcommand` - The stash subcommand that was executed (push, list, show, apply, pop or drop).
+///
+/// # Errors
+///
+/// Returns an `io::Result` indicating whether the operation was successful.
+///
+fn log_stash(git_dir: &str, subcommand: &str) -> io::Result<()> {
+    let log_file_path = LOGGER_COMMANDS_FILE;
+    let mut logger = Logger::new(log_file_path)?;
+
+    let full_message = format!(
+        "Command 'git stash': Git Directory '{}', Subcommand '{}', {}",
+        git_dir,
+        subcommand,
+        get_current_time()
+    );
+    logger.write_all(full_message.as_bytes())?;
+    logger.flush()?;
+    Ok(())
+}
+
+/// Stashes and restores local changes based on command line arguments.
+///
+/// The supported forms are:
+/// - `git stash [push] [-m <message>] [-u | --include-untracked]`
+/// - `git stash list`
+/// - `git stash show [-p] [<stash>]`
+/// - `git stash apply [<stash>]`
+/// - `git stash pop [<stash>]`
+/// - `git stash drop [<stash>]`
+///
+/// A stash is referenced as `stash@{n}` (or just `n`), where `stash@{0}` is the most recent one.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the Git directory.
+/// * `root_dir` - The root of the working directory. Paths in the index are relative to it.
+/// * `line` - A vector of strings representing the command line arguments.
+/// * `output` - Where the results of the command are written.
+///
+/// # Errors
+///
+/// Returns an error if the arguments are not valid, if the stash does not exist or if
+/// applying it would overwrite local changes.
+///
+pub fn git_stash(
+    git_dir: &str,
+    root_dir: &str,
+    line: Vec<String>,
+    output: &mut impl Write,
+) -> io::Result<()> {
+    let (subcommand, args) = match line.get(2).map(|arg| arg.as_str()) {
+        Some("push") => ("push", &line[3..]),
+        Some(arg) if arg.starts_with('-') => ("push", &line[2..]),
+        Some(arg) => (arg, &line[3..]),
+        None => ("push", &line[2..]),
+    };
+    log_stash(git_dir, subcommand)?;
+
+    match subcommand {
+        "push" => {
+            let (message, include_untracked) = parse_push_options(args)?;
+            stash_push(
+                git_dir,
+                root_dir,
+                message.as_deref(),
+                include_untracked,
+                output,
+            )
+        }
+        "list" => stash_list(git_dir, output),
+        "show" => {
+            let patch = args.iter().any(|arg| arg == "-p" || arg == "--patch");
+            let stash = args.iter().find(|arg| !arg.starts_with('-'));
+            stash_show(git_dir, parse_stash_index(stash)?, patch, output)
+        }
+        "apply" => stash_apply(git_dir, root_dir, parse_stash_index(args.first())?),
+        "pop" => {
+            let index = parse_stash_index(args.first())?;
+            stash_apply(git_dir, root_dir, index)?;
+            stash_drop(git_dir, index, output)
+        }
+        "drop" => stash_drop(git_dir, parse_stash_index(args.first())?, output),
+        _ => Err(io::Error::new(io::ErrorKind::InvalidInput, STASH_USAGE)),
+    }
+}
+
+/// Parses the options of `git stash push`.
+///
+/// # Returns
+///
+/// A tuple with the message given with `-m` (if any) and whether untracked files should be stashed.
+///
+fn parse_push_options(args: &[String]) -> io::Result<(Option<String>, bool)> {
+    let mut message = None;
+    let mut include_untracked = false;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-m" | "--message" => match args.next() {
+                Some(text) => message = Some(text.to_string()),
+                None => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "switch 'm' requires a value",
+                    ))
+                }
+            },
+            "-u" | "--include-untracked" => include_untracked = true,
+            _ => return Err(io::Error::new(io::ErrorKind::InvalidInput, STASH_USAGE)),
+        }
+    }
+    Ok((message, include_untracked))
+}
+
+/// Parses a stash reference (`stash@{n}` or `n`). If no reference is given, `stash@{0}` is used.
+fn parse_stash_index(stash: Option<&String>) -> io::Result<usize> {
+    let stash = match stash {
+        Some(stash) => stash,
+        None => return Ok(0),
+    };
+    let number = stash
+        .strip_prefix("stash@{")
+        .and_then(|rest| rest.strip_suffix('}'))
+        .unwrap_or(stash);
+    number.parse::<usize>().map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("'{}' is not a stash reference", stash),
+        )
+    })
+}
+
+/// Reads the commit pointed by HEAD, either through a branch or in detached mode.
+fn read_head_commit(git_dir: &str) -> io::Result<String> {
+    let head = fs::read_to_string(format!("{}/HEAD", git_dir))?;
+    let head = head.trim();
+    let hash = match head.strip_prefix("ref: ") {
+        Some(reference) => {
+            fs::read_to_string(utils::git_path(git_dir, reference)).unwrap_or_default()
+        }
+        None => head.split(' ').next().unwrap_or_default().to_string(),
+    };
+    let hash = hash.trim();
+    if hash.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "You do not have the initial commit yet",
+        ));
+    }
+    Ok(hash.to_string())
+}
+
+/// Returns the name of the current branch, or "(no branch)" when HEAD is detached.
+fn read_head_branch_name(git_dir: &str) -> io::Result<String> {
+    let head = fs::read_to_string(format!("{}/HEAD", git_dir))?;
+    match head.trim().strip_prefix("ref: refs/heads/") {
+        Some(branch) => Ok(branch.to_string()),
+        None => Ok("(no branch)".to_string()),
+    }
+}
+
+/// Returns the first line of the message of a commit.
+fn get_commit_subject(commit_hash: &str, git_dir: &str) -> io::Result<String> {
+    let content = cat_file::cat_file_return_content(commit_hash, git_dir)?;
+    let message = content
+        .split_once("\n\n")
+        .map(|(_, message)| message)
+        .unwrap_or("");
+    Ok(message
+        .trim_end_matches('\0')
+        .lines()
+        .next()
+        .unwrap_or("")
+        .to_string())
+}
+
+/// Stores the current content of every tracked file and returns the resulting (path, hash) entries.
+/// Tracked files that were deleted from the working directory are left out.
+pub fn snapshot_tracked_files(
+    index: &Index,
+    root_dir: &str,
+    git_dir: &str,
+) -> io::Result<HashMap<String, String>> {
+    let mut entries = HashMap::new();
+    for (path, staged_hash) in index.iter() {
+        let file_path = checkout::working_path(root_dir, path);
+        if !Path::new(&file_path).is_file() {
+            continue;
+        }
+        let hash = hash_object::hash_working_file(root_dir, path)?;
+        if &hash != staged_hash {
+            hash_object::store_file(&file_path, git_dir)?;
+        }
+        entries.insert(path.to_string(), hash);
+    }
+    Ok(entries)
+}
+
+/// Recursively looks for files that are neither tracked nor ignored, storing them as blobs.
+fn collect_untracked_files(
+    root_dir: &str,
+    relative_dir: &str,
+    git_dir: &Path,
+    index: &Index,
+    untracked: &mut HashMap<String, String>,
+) -> io::Result<()> {
+    let dir = match (root_dir.is_empty(), relative_dir.is_empty()) {
+        (true, true) => ".".to_string(),
+        (_, true) => root_dir.to_string(),
+        _ => checkout::working_path(root_dir, relative_dir),
+    };
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        let path = if relative_dir.is_empty() {
+            name
+        } else {
+            format!("{}/{}", relative_dir, name)
+        };
+        if index.path_should_be_ignored(&path) {
+            continue;
+        }
+        let entry_path = entry.path();
+        if entry_path.is_dir() {
+            if fs::canonicalize(&entry_path)? == git_dir {
+                continue;
+            }
+            collect_untracked_files(root_dir, &path, git_dir, index, untracked)?;
+        } else if !index.contains(&path) {
+            let hash =
+                hash_object::store_file(&entry_path.to_string_lossy(), &git_dir.to_string_lossy())?;
+            untracked.insert(path, hash);
+        }
+    }
+    Ok(())
+}
+
+/// Reads the stash reflog. The entries are returned from the oldest to the newest one.
+fn read_stash_log(git_dir: &str) -> io::Result<Vec<String>> {
+    match fs::read_to_string(utils::git_path(git_dir, STASH_LOG)) {
+        Ok(content) => Ok(content.lines().map(|line| line.to_string()).collect()),
+        Err(_) => Ok(Vec::new()),
+    }
+}
+
+/// Rewrites the stash reflog and makes `refs/stash` point to its newest entry.
+/// If there are no entries left, both the reference and the reflog are removed.
+fn write_stash_log(git_dir: &str, entries: &[String]) -> io::Result<()> {
+    let ref_path = utils::git_path(git_dir, STASH_REF);
+    let log_path = utils::git_path(git_dir, STASH_LOG);
+    match entries.last() {
+        Some(newest) => {
+            let mut content = entries.join("\n");
+            content.push('\n');
+            fs::write(&log_path, content)?;
+            fs::write(&ref_path, get_reflog_entry_hash(newest)?)
+        }
+        None => {
+            if Path::new(&ref_path).exists() {
+                fs::remove_file(&ref_path)?;
+            }
+            if Path::new(&log_path).exists() {
+                fs::remove_file(&log_path)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Returns the new hash stored in a reflog entry.
+fn get_reflog_entry_hash(entry: &str) -> io::Result<String> {
+    match entry.split(' ').nth(1) {
+        Some(hash) => Ok(hash.to_string()),
+        None => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Corrupted stash reflog",
+        )),
+    }
+}
+
+/// Returns the message stored in a reflog entry.
+fn get_reflog_entry_message(entry: &str) -> String {
+    match entry.split_once('\t') {
+        Some((_, message)) => message.to_string(),
+        None => String::new(),
+    }
+}
+
+/// Returns the hash of the commit of `stash@{index}`.
+fn get_stash_commit(git_dir: &str, index: usize) -> io::Result<String> {
+    let entries = read_stash_log(git_dir)?;
+    if index >= entries.len() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("stash@{{{}}} is not a valid reference", index),
+        ));
+    }
+    get_reflog_entry_hash(&entries[entries.len() - 1 - index])
+}
+
+/// Makes `refs/stash` point to the new stash commit and appends it to the stash reflog.
+fn push_stash_entry(git_dir: &str, stash_commit: &str, message: &str) -> io::Result<()> {
+    let mut entries = read_stash_log(git_dir)?;
+    let old_hash = match entries.last() {
+        Some(entry) => get_reflog_entry_hash(entry)?,
+        None => NULL_HASH.to_string(),
+    };
+    let (user, email) = commit::get_user_and_email(git_dir);
+    let (timestamp, offset) = utils::get_timestamp()?;
+    entries.push(format!(
+        "{} {} {} {} {} {}\t{}",
+        old_hash, stash_commit, user, email, timestamp, offset, message
+    ));
+    fs::create_dir_all(utils::git_path(git_dir, "logs/refs"))?;
+    write_stash_log(git_dir, &entries)
+}
+
+/// Saves the local changes as a new stash and resets the working directory and the index to HEAD.
+///
+/// The stash is a commit whose tree is the state of the working directory and whose parents are
+/// HEAD, a commit with the state of the index and, if `include_untracked` is set, a commit with
+/// the untracked files.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the Git directory.
+/// * `root_dir` - The root of the working directory.
+/// * `message` - An optional description of the stash.
+/// * `include_untracked` - Whether untracked files are also stashed (and removed).
+/// * `output` - Where the result is written.
+///
+/// # Errors
+///
+/// Returns an error if there is no commit yet or if any object cannot be read or stored.
+///
+pub fn stash_push(
+    git_dir: &str,
+    root_dir: &str,
+    message: Option<&str>,
+    include_untracked: bool,
+    output: &mut impl Write,
+) -> io::Result<()> {
+    let head = read_head_commit(git_dir)?;
+    let branch = read_head_branch_name(git_dir)?;
+    let head_tree = tree_handler::load_tree_from_commit(&head, git_dir)?;
+    let index = checkout::load_index(git_dir, root_dir)?;
+    let index_tree = tree_handler::build_tree_from_entries(index.iter())?;
+    let worktree_entries = snapshot_tracked_files(&index, root_dir, git_dir)?;
+    let worktree_tree = tree_handler::build_tree_from_entries(worktree_entries.iter())?;
+    let mut untracked = HashMap::new();
+    if include_untracked {
+        let canonical_git_dir = fs::canonicalize(git_dir)?;
+        collect_untracked_files(root_dir, "", &canonical_git_dir, &index, &mut untracked)?;
+    }
+
+    let index_changes = diff::get_tree_changes(Some(&head_tree), Some(&index_tree));
+    let worktree_changes = diff::get_tree_changes(Some(&head_tree), Some(&worktree_tree));
+    if index_changes.is_empty() && worktree_changes.is_empty() && untracked.is_empty() {
+        writeln!(output, "No local changes to save")?;
+        return Ok(());
+    }
+
+    let short_head = &head[..7.min(head.len())];
+    let description = format!(
+        "{}: {} {}",
+        branch,
+        short_head,
+        get_commit_subject(&head, git_dir)?
+    );
+    let (index_tree_hash, _) = tree_handler::write_tree(&index_tree, git_dir)?;
+    let index_commit = commit::new_commit_from_tree(
+        git_dir,
+        &index_tree_hash,
+        std::slice::from_ref(&head),
+        &format!("index on {}", description),
+    )?;
+    let mut parents = vec![head, index_commit];
+    if !untracked.is_empty() {
+        let untracked_tree = tree_handler::build_tree_from_entries(untracked.iter())?;
+        let (untracked_tree_hash, _) = tree_handler::write_tree(&untracked_tree, git_dir)?;
+        parents.push(commit::new_commit_from_tree(
+            git_dir,
+            &untracked_tree_hash,
+            &[],
+            &format!("untracked files on {}", description),
+        )?);
+    }
+    let stash_message = match message {
+        Some(message) => format!("On {}: {}", branch, message),
+        None => format!("WIP on {}", description),
+    };
+    let (worktree_tree_hash, _) = tree_handler::write_tree(&worktree_tree, git_dir)?;
+    let stash_commit =
+        commit::new_commit_from_tree(git_dir, &worktree_tree_hash, &parents, &stash_message)?;
+    push_stash_entry(git_dir, &stash_commit, &stash_message)?;
+
+    let changed_paths: BTreeSet<&String> = index_changes
+        .iter()
+        .chain(worktree_changes.iter())
+        .map(|change| &change.path)
+        .collect();
+    for path in changed_paths {
+        checkout::restore_path(
+            root_dir,
+            git_dir,
+            path,
+            head_tree.get_hash_from_path(path).as_ref(),
+        )?;
+    }
+    for path in untracked.keys() {
+        checkout::restore_path(root_dir, git_dir, path, None)?;
+    }
+    let index_path = utils::get_index_file_path(git_dir);
+    let gitignore_path = checkout::working_path(root_dir, GIT_IGNORE);
+    head_tree
+        .build_index_file_from_tree(&index_path, git_dir, &gitignore_path)?
+        .write_file()?;
+
+    writeln!(
+        output,
+        "Saved working directory and index state {}",
+        stash_message
+    )
+}
+
+/// Lists the stashes, from the newest to the oldest one.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the Git directory.
+/// * `output` - Where the list is written, one `stash@{n}: <message>` per line.
+///
+pub fn stash_list(git_dir: &str, output: &mut impl Write) -> io::Result<()> {
+    for (index, entry) in read_stash_log(git_dir)?.iter().rev().enumerate() {
+        writeln!(
+            output,
+            "stash@{{{}}}: {}",
+            index,
+            get_reflog_entry_message(entry)
+        )?;
+    }
+    Ok(())
+}
+
+/// Shows the changes recorded in a stash, compared to the commit it was created from.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the Git directory.
+/// * `index` - The position of the stash, 0 being the newest one.
+/// * `patch` - If true, the full patch is shown. Otherwise a diffstat is shown.
+/// * `output` - Where the changes are written.
+///
+/// # Errors
+///
+/// Returns an error if the stash does not exist.
+///
+pub fn stash_show(
+    git_dir: &str,
+    index: usize,
+    patch: bool,
+    output: &mut impl Write,
+) -> io::Result<()> {
+    let stash_commit = get_stash_commit(git_dir, index)?;
+    let parents = commit::get_commit_parents(&stash_commit, git_dir)?;
+    let base_tree = match parents.first() {
+        Some(base) => Some(tree_handler::load_tree_from_commit(base, git_dir)?),
+        None => None,
+    };
+    let stash_tree = tree_handler::load_tree_from_commit(&stash_commit, git_dir)?;
+    let changes = diff::get_tree_changes(base_tree.as_ref(), Some(&stash_tree));
+    if patch {
+        diff::write_patch(&changes, git_dir, output)
+    } else {
+        diff::write_stat(&changes, git_dir, output)
+    }
+}
+
+/// Applies a stash on top of the current working directory and index, keeping the stash.
+///
+/// Every file changed by the stash is restored both in the working directory and in the index.
+/// Untracked files saved with `--include-untracked` are restored as untracked files. Nothing is
+/// modified if a file touched by the stash has local changes or was modified differently since
+/// the stash was created.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the Git directory.
+/// * `root_dir` - The root of the working directory.
+/// * `index` - The position of the stash, 0 being the newest one.
+///
+/// # Errors
+///
+/// Returns an error if the stash does not exist or if it cannot be applied without losing changes.
+///
+pub fn stash_apply(git_dir: &str, root_dir: &str, index: usize) -> io::Result<()> {
+    let stash_commit = get_stash_commit(git_dir, index)?;
+    let parents = commit::get_commit_parents(&stash_commit, git_dir)?;
+    if parents.len() < 2 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} is not a stash-like commit", stash_commit),
+        ));
+    }
+    let base_tree = tree_handler::load_tree_from_commit(&parents[0], git_dir)?;
+    let stash_index_tree = tree_handler::load_tree_from_commit(&parents[1], git_dir)?;
+    let stash_tree = tree_handler::load_tree_from_commit(&stash_commit, git_dir)?;
+    let untracked_tree: Option<Tree> = match parents.get(2) {
+        Some(untracked) => Some(tree_handler::load_tree_from_commit(untracked, git_dir)?),
+        None => None,
+    };
+    let head_tree = tree_handler::load_tree_from_commit(&read_head_commit(git_dir)?, git_dir)?;
+    let mut index = checkout::load_index(git_dir, root_dir)?;
+
+    let worktree_changes = diff::get_tree_changes(Some(&base_tree), Some(&stash_tree));
+    let index_changes = diff::get_tree_changes(Some(&base_tree), Some(&stash_index_tree));
+    let untracked_files = match &untracked_tree {
+        Some(tree) => tree.squash_tree_into_vec(""),
+        None => Vec::new(),
+    };
+
+    let mut conflicts = BTreeSet::new();
+    for change in worktree_changes.iter().chain(index_changes.iter()) {
+        let head_hash = head_tree.get_hash_from_path(&change.path);
+        if head_hash != change.old_hash && head_hash != change.new_hash {
+            conflicts.insert(change.path.clone());
+        }
+        if index.get_hash(&change.path) != head_hash.as_ref()
+            || checkout::hash_working_file(root_dir, &change.path)? != head_hash
+        {
+            conflicts.insert(change.path.clone());
+        }
+    }
+    for (path, _) in &untracked_files {
+        if Path::new(&checkout::working_path(root_dir, path)).exists() {
+            conflicts.insert(path.clone());
+        }
+    }
+    if !conflicts.is_empty() {
+        let files: Vec<String> = conflicts.iter().map(|path| format!("\t{}", path)).collect();
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!(
+                "Your local changes to the following files would be overwritten:\n{}\nPlease commit or stash them before applying the stash.",
+                files.join("\n")
+            ),
+        ));
+    }
+
+    for change in &worktree_changes {
+        checkout::restore_path(root_dir, git_dir, &change.path, change.new_hash.as_ref())?;
+    }
+    for change in &index_changes {
+        match &change.new_hash {
+            Some(hash) => index.add_file(&change.path, hash)?,
+            None => {
+                let _ = index.remove_file(&change.path);
+            }
+        }
+    }
+    for (path, hash) in &untracked_files {
+        checkout::restore_path(root_dir, git_dir, path, Some(hash))?;
+    }
+    index.write_file()
+}
+
+/// Removes a stash from the stash list.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the Git directory.
+/// * `index` - The position of the stash, 0 being the newest one.
+/// * `output` - Where the dropped stash is reported.
+///
+/// # Errors
+///
+/// Returns an error if the stash does not exist.
+///
+pub fn stash_drop(git_dir: &str, index: usize, output: &mut impl Write) -> io::Result<()> {
+    let mut entries = read_stash_log(git_dir)?;
+    if index >= entries.len() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("stash@{{{}}} is not a valid reference", index),
+        ));
+    }
+    let removed = entries.remove(entries.len() - 1 - index);
+    write_stash_log(git_dir, &entries)?;
+    writeln!(
+        output,
+        "Dropped stash@{{{}}} ({})",
+        index,
+        get_reflog_entry_hash(&removed)?
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+
+    /// Creates a repository in `root_dir` with a single commit containing `a.txt` and `dir/b.txt`.
+    fn create_repo(root_dir: &str) -> String {
+        let git_dir = test_utils::create_repo(root_dir);
+        test_utils::commit_files(
+            &git_dir,
+            root_dir,
+            &[("a.txt", "hola\n"), ("dir/b.txt", "chau\n")],
+            "first commit",
+        );
+        git_dir
+    }
+
+    fn run(git_dir: &str, root_dir: &str, args: &[&str]) -> io::Result<String> {
+        let mut line = vec!["git".to_string(), "stash".to_string()];
+        line.extend(args.iter().map(|arg| arg.to_string()));
+        let mut output: Vec<u8> = vec![];
+        git_stash(git_dir, root_dir, line, &mut output)?;
+        Ok(String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn test_push_saves_changes_and_resets_working_tree() {
+        let root_dir = "tests/stash/push";
+        let git_dir = create_repo(root_dir);
+        test_utils::stage_files(&git_dir, root_dir, &[("a.txt", "hola mundo\n")]);
+        fs::write(format!("{}/dir/b.txt", root_dir), "adios\n").unwrap();
+
+        let output = run(&git_dir, root_dir, &["push", "-m", "cambios"]).unwrap();
+
+        assert_eq!(
+            output,
+            "Saved working directory and index state On main: cambios\n"
+        );
+        let a = fs::read_to_string(format!("{}/a.txt", root_dir)).unwrap();
+        let b = fs::read_to_string(format!("{}/dir/b.txt", root_dir)).unwrap();
+        assert_eq!(a, "hola\n");
+        assert_eq!(b, "chau\n");
+        assert!(Path::new(&format!("{}/refs/stash", git_dir)).exists());
+        let list = run(&git_dir, root_dir, &["list"]).unwrap();
+        assert_eq!(list, "stash@{0}: On main: cambios\n");
+
+        fs::remove_dir_all(root_dir).unwrap();
+    }
+
+    #[test]
+    fn test_pop_restores_changes_and_drops_stash() {
+        let root_dir = "tests/stash/pop";
+        let git_dir = create_repo(root_dir);
+        test_utils::stage_files(&git_dir, root_dir, &[("a.txt", "hola mundo\n")]);
+        fs::write(format!("{}/dir/b.txt", root_dir), "adios\n").unwrap();
+        run(&git_dir, root_dir, &[]).unwrap();
+
+        let output = run(&git_dir, root_dir, &["pop"]).unwrap();
+
+        assert!(output.starts_with("Dropped stash@{0} ("));
+        let a = fs::read_to_string(format!("{}/a.txt", root_dir)).unwrap();
+        let b = fs::read_to_string(format!("{}/dir/b.txt", root_dir)).unwrap();
+        assert_eq!(a, "hola mundo\n");
+        assert_eq!(b, "adios\n");
+        let index = checkout::load_index(&git_dir, root_dir).unwrap();
+        let staged_hash = hash_object::hash_file_content(&format!("{}/a.txt", root_dir), "blob");
+        assert_eq!(index.get_hash("a.txt"), staged_hash.ok().as_ref());
+        assert!(!Path::new(&format!("{}/refs/stash", git_dir)).exists());
+        assert_eq!(run(&git_dir, root_dir, &["list"]).unwrap(), "");
+
+        fs::remove_dir_all(root_dir).unwrap();
+    }
+
+    #[test]
+    fn test_push_include_untracked_and_apply() {
+        let root_dir = "tests/stash/untracked";
+        let git_dir = create_repo(root_dir);
+        fs::create_dir_all(format!("{}/nuevo", root_dir)).unwrap();
+        fs::write(format!("{}/nuevo/c.txt", root_dir), "nuevo\n").unwrap();
+
+        run(&git_dir, root_dir, &["-u"]).unwrap();
+        assert!(!Path::new(&format!("{}/nuevo", root_dir)).exists());
+
+        run(&git_dir, root_dir, &["apply", "stash@{0}"]).unwrap();
+        let c = fs::read_to_string(format!("{}/nuevo/c.txt", root_dir)).unwrap();
+        assert_eq!(c, "nuevo\n");
+        assert!(!checkout::load_index(&git_dir, root_dir)
+            .unwrap()
+            .contains("nuevo/c.txt"));
+        assert_eq!(
+            run(&git_dir, root_dir, &["list"]).unwrap().lines().count(),
+            1
+        );
+
+        fs::remove_dir_all(root_dir).unwrap();
+    }
+
+    #[test]
+    fn test_apply_refuses_to_overwrite_local_changes() {
+        let root_dir = "tests/stash/conflict";
+        let git_dir = create_repo(root_dir);
+        fs::write(format!("{}/a.txt", root_dir), "stashed\n").unwrap();
+        run(&git_dir, root_dir, &[]).unwrap();
+        fs::write(format!("{}/a.txt", root_dir), "local\n").unwrap();
+
+        assert!(run(&git_dir, root_dir, &["apply"]).is_err());
+        let a = fs::read_to_string(format!("{}/a.txt", root_dir)).unwrap();
+        assert_eq!(a, "local\n");
+
+        fs::remove_dir_all(root_dir).unwrap();
+    }
+
+    #[test]
+    fn test_show_patch() {
+        let root_dir = "tests/stash/show";
+        let git_dir = create_repo(root_dir);
+        fs::write(format!("{}/a.txt", root_dir), "hola mundo\n").unwrap();
+        run(&git_dir, root_dir, &[]).unwrap();
+
+        let patch = run(&git_dir, root_dir, &["show", "-p"]).unwrap();
+        let stat = run(&git_dir, root_dir, &["show"]).unwrap();
+
+        assert_eq!(
+            patch,
+            "diff --git a/a.txt b/a.txt\n--- a/a.txt\n+++ b/a.txt\n@@ -1 +1 @@\n-hola\n+hola mundo\n"
+        );
+        assert_eq!(
+            stat,
+            " a.txt | 2 +-\n 1 file changed, 1 insertion(+), 1 deletion(-)\n"
+        );
+
+        fs::remove_dir_all(root_dir).unwrap();
+    }
+
+    #[test]
+    fn test_push_without_changes_and_drop_without_stash() {
+        let root_dir = "tests/stash/empty";
+        let git_dir = create_repo(root_dir);
+
+        let output = run(&git_dir, root_dir, &[]).unwrap();
+
+        assert_eq!(output, "No local changes to save\n");
+        assert!(run(&git_dir, root_dir, &["drop"]).is_err());
+
+        fs::remove_dir_all(root_dir).unwrap();
+    }
+}
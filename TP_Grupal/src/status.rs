@@ -1,10 +1,11 @@
-const BLOB: &str = "blob";
 use crate::configuration::LOGGER_COMMANDS_FILE;
+use crate::diff;
 use crate::hash_object;
 use crate::index::Index;
 use crate::logger::Logger;
-use crate::tree_handler::Tree;
+use crate::tree_handler::{self, Tree};
 use crate::utils::get_current_time;
+use std::collections::HashMap;
 use std::fs;
 use std::io::{self, Write};
 use std::path::Path;
@@ -130,7 +131,14 @@ pub fn changes_to_be_committed(
     output: &mut impl Write,
 ) -> io::Result<()> {
     let mut count = 0;
+    let renames = find_staged_renames(index, commit_tree)?;
     for (path, hash) in index.iter() {
+        if let Some(source) = renames.get(path) {
+            count += 1;
+            let buffer = format!("\x1b[31m\t\trenamed:\t {} -> {}\x1b[0m\n", source, path);
+            output.write_all(buffer.as_bytes())?;
+            continue;
+        }
         match commit_tree.get_hash_from_path(path) {
             Some(new_hash) => {
                 if hash.ne(&new_hash) {
@@ -150,6 +158,41 @@ pub fn changes_to_be_committed(
     Ok(())
 }
 
+/// Finds the files of the last commit that were renamed in the index.
+///
+/// A file counts as renamed when it was removed from the index and a new file at least
+/// `diff::DEFAULT_RENAME_THRESHOLD` percent similar to it was added.
+///
+/// # Arguments
+///
+/// * `index` - A reference to the Git index containing file information.
+/// * `commit_tree` - The tree of the last commit.
+///
+/// # Returns
+///
+/// A map from the new path of every renamed file to its path in the last commit.
+///
+/// # Errors
+///
+/// Returns an error if the blobs compared cannot be read.
+///
+fn find_staged_renames(index: &Index, commit_tree: &Tree) -> io::Result<HashMap<String, String>> {
+    let index_tree = tree_handler::build_tree_from_entries(index.iter())?;
+    let changes = diff::get_tree_changes_with_renames(
+        Some(commit_tree),
+        Some(&index_tree),
+        index.get_git_dir(),
+        Some(diff::RenameOptions::default()),
+    )?;
+    Ok(changes
+        .into_iter()
+        .filter_map(|change| {
+            let source = change.source?;
+            Some((change.path, source.path))
+        })
+        .collect())
+}
+
 /// Return a string containing all staged changes in a Git repository's index.
 pub fn get_staged_changes(index: &Index, commit_tree: Option<Tree>) -> Result<String, io::Error> {
     let output = match commit_tree {
@@ -174,6 +217,7 @@ pub fn get_staged_changes(index: &Index, commit_tree: Option<Tree>) -> Result<St
     if let Ok(result) = String::from_utf8(output) {
         let mut resultado = result;
         resultado = resultado.replace("\x1b[31m\t\tmodified:\t ", "");
+        resultado = resultado.replace("\x1b[31m\t\trenamed:\t ", "");
         resultado = resultado.replace("\x1b[0m\n", "\n");
         Ok(resultado)
     } else {
@@ -211,7 +255,7 @@ pub fn find_unstaged_changes(
         let complete_path_string = git_dir.to_string() + "/" + path;
         let complete_path = Path::new(&complete_path_string);
         if complete_path.is_file() {
-            let new_hash = hash_object::hash_file_content(&complete_path_string, BLOB)?;
+            let new_hash = hash_object::hash_working_file(git_dir, path)?;
 
             if hash.ne(&new_hash) {
                 count += 1;
@@ -248,7 +292,7 @@ mod tests {
         io::Read,
     };
 
-    use crate::{commit, configuration::GIT_DIR_FOR_TEST, init, tree_handler};
+    use crate::{add, commit, configuration::GIT_DIR_FOR_TEST, init, tree_handler};
 
     use super::*;
 
@@ -359,6 +403,41 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_changes_to_be_committed_shows_renamed_files() -> Result<(), io::Error> {
+        let test_dir = "tests/status_renamed_repo";
+        if Path::new(test_dir).exists() {
+            fs::remove_dir_all(test_dir)?;
+        }
+        fs::create_dir_all(test_dir)?;
+        init::git_init(test_dir, GIT_DIR_FOR_TEST, "current_branch", None)?;
+        let git_dir = test_dir.to_string() + "/.mgit";
+        let index_path = git_dir.clone() + "/index";
+        let old = test_dir.to_string() + "/old.txt";
+        let new = test_dir.to_string() + "/new.txt";
+        fs::write(&old, "1\n2\n3\n4\n")?;
+        add::add(&old, &index_path, &git_dir, "", None)?;
+        let commit_hash = commit::new_commit(&git_dir, "message", "")?;
+        fs::rename(&old, &new)?;
+        fs::write(&new, "1\n2\n3\nfour\n")?;
+        let mut staged = Index::load(&index_path, &git_dir, "")?;
+        staged.remove_file(&old)?;
+        staged.write_file()?;
+        add::add(&new, &index_path, &git_dir, "", None)?;
+
+        let index = Index::load(&index_path, &git_dir, "")?;
+        let tree = tree_handler::load_tree_from_commit(&commit_hash, &git_dir)?;
+        let mut output: Vec<u8> = vec![];
+        changes_to_be_committed(&index, &tree, &mut output)?;
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(
+            output,
+            format!("\x1b[31m\t\trenamed:\t {} -> {}\x1b[0m\n", old, new)
+        );
+        fs::remove_dir_all(test_dir)?;
+        Ok(())
+    }
+
     #[test]
     fn test_unstaged_changes_detected_when_changes_not_added() -> Result<(), io::Error> {
         create_repo("tests/status_repo")?;
@@ -375,7 +375,7 @@ pub fn log_tag(git_dir: &str, tag: &str) -> io::Result<()> {
 /// This function does not panic under normal circumstances. Panics may occur in case of unexpected errors
 /// while writing to the output or when calling tag-related functions.
 pub fn git_tag(git_dir: &str, line: Vec<String>, output: &mut impl Write) -> io::Result<()> {
-    let tags_path = format!("{}/{}", git_dir, "refs/tags");
+    let tags_path = utils::git_path(git_dir, "refs/tags");
     if line.len() == 2 {
         list_tags(&tags_path, output)?;
     } else if line.len() == 3 {
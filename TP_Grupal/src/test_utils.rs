@@ -0,0 +1,55 @@
+//! Helpers shared by the unit tests of the crate.
+
+use crate::configuration::GIT_DIR_FOR_TEST;
+use crate::{checkout, commit, hash_object, init};
+use std::fs;
+use std::path::Path;
+
+/// Creates an empty repository in `root_dir` with `git init`, removing whatever was there
+/// before. The initial branch is `main`.
+///
+/// # Returns
+///
+/// The path to the git directory of the repository.
+///
+pub fn create_repo(root_dir: &str) -> String {
+    create_repo_with_git_dir(root_dir, GIT_DIR_FOR_TEST)
+}
+
+/// Like `create_repo`, but naming the git directory `git_dir_name`.
+pub fn create_repo_with_git_dir(root_dir: &str, git_dir_name: &str) -> String {
+    let _ = fs::remove_dir_all(root_dir);
+    init::git_init(root_dir, git_dir_name, "main", None).unwrap();
+    format!("{}/{}", root_dir, git_dir_name)
+}
+
+/// Writes `files` into the working tree, creating their directories, and stages them.
+pub fn stage_files(git_dir: &str, root_dir: &str, files: &[(&str, &str)]) {
+    let mut index = checkout::load_index(git_dir, root_dir).unwrap();
+    for (path, content) in files {
+        let working_path = checkout::working_path(root_dir, path);
+        if let Some(parent) = Path::new(&working_path).parent() {
+            fs::create_dir_all(parent).unwrap();
+        }
+        fs::write(&working_path, content).unwrap();
+        let hash = hash_object::store_file(&working_path, git_dir).unwrap();
+        index.add_file(path, &hash).unwrap();
+    }
+    index.write_file().unwrap();
+}
+
+/// Writes and stages `files`, and commits them on the current branch.
+///
+/// # Returns
+///
+/// The hash of the new commit.
+///
+pub fn commit_files(
+    git_dir: &str,
+    root_dir: &str,
+    files: &[(&str, &str)],
+    message: &str,
+) -> String {
+    stage_files(git_dir, root_dir, files);
+    commit::new_commit(git_dir, message, "").unwrap()
+}
@@ -5,9 +5,11 @@ use std::{
 };
 
 use crate::{
+    attributes::{Attributes, ATTRIBUTES_FILE},
     cat_file::{self, cat_tree},
-    diff, hash_object,
+    checkout, diff, filter, hash_object,
     index::{self},
+    utils,
 };
 
 const BLOB_NORMAL_MODE: &str = "100644";
@@ -197,10 +199,16 @@ impl Tree {
             fs::create_dir_all(&dir_path)?;
         }
 
-        for file in &self.files {
+        // The attributes of the directory are written first, as they apply to the other files.
+        let mut files: Vec<&(String, String)> = self.files.iter().collect();
+        files.sort_by_key(|(name, _)| name != ATTRIBUTES_FILE);
+        for file in files {
             let path = dir_path.to_string() + "/" + &file.0;
-            let mut new_file = fs::File::create(path)?;
-            cat_file::cat_file(&file.1, git_dir_path, &mut new_file)?;
+            let content = cat_file::cat_file_return_content(&file.1, git_dir_path)?;
+            let attributes = Attributes::for_file(git_dir_path, &path)?;
+            // The file is only written once the filters succeed, so a failing one leaves it as is
+            let content = filter::convert_to_working_tree(git_dir_path, &attributes, content)?;
+            fs::write(&path, content)?;
         }
 
         for subdirs in &self.directories {
@@ -244,7 +252,7 @@ impl Tree {
     }
 
     /// Squash the tree into a vector of tuples (file_name, hash). So a file that is in a subtree will have its complete path from the root tree.
-    fn squash_tree_into_vec(&self, parent_dir: &str) -> Vec<(String, String)> {
+    pub fn squash_tree_into_vec(&self, parent_dir: &str) -> Vec<(String, String)> {
         let mut result = Vec::new();
         let dir_path = if parent_dir.is_empty() {
             parent_dir.to_string() + &self.name
@@ -434,13 +442,23 @@ pub fn build_tree_from_index(
     git_ignore_path: &str,
 ) -> io::Result<Tree> {
     let index = index::Index::load(index_path, git_dir_path, git_ignore_path)?;
+    build_tree_from_entries(index.iter())
+}
+
+/// Builds a tree from pairs of (path, hash), using the same path format as the index file.
+///
+/// This is useful to create trees that do not correspond to the index, for example the
+/// state of the working directory.
+pub fn build_tree_from_entries<'a>(
+    entries: impl Iterator<Item = (&'a String, &'a String)>,
+) -> io::Result<Tree> {
     let mut tree = Tree::new("");
 
-    //Iterates over the index struct, adding each file to the tree.
+    //Iterates over the entries, adding each file to the tree.
     //It grabs a path, gets the filename (the last part of the path).
     //Then, for every other part of the path, it gets or creates a directory with that name.
     //Starting from the root directory of the tree, it goes down the tree until it reaches the directory where the file should be.
-    for (path, hash) in index.iter() {
+    for (path, hash) in entries {
         let mut path = path.split('/').collect::<Vec<&str>>();
         let file_name = match path.pop() {
             Some(file_name) => file_name,
@@ -679,7 +697,63 @@ fn merge_their_tree_into_ours(our_tree: &Tree, their_tree: &Tree, mut new_tree:
     new_tree
 }
 
+/// Checks whether two versions of a file cannot be merged line by line, because the attributes
+/// of the file say so or because one of them is binary.
+fn is_binary_for_merge(
+    path: &str,
+    our_hash: &str,
+    their_hash: &str,
+    git_dir: &str,
+) -> io::Result<bool> {
+    let (Ok(ours), Ok(theirs)) = (
+        cat_file::cat_file_return_bytes(our_hash, git_dir),
+        cat_file::cat_file_return_bytes(their_hash, git_dir),
+    ) else {
+        return Ok(false);
+    };
+    let root_dir = utils::get_working_dir(git_dir)?;
+    Ok(Attributes::load(&root_dir, path)?.is_binary_for_merge(&[&ours, &theirs]))
+}
+
+/// A file changed on both sides of a merge that cannot be merged line by line.
+///
+/// There is no way to mark the conflict inside the file, so the merged tree keeps our version.
+/// Both versions can be written next to the file with `write_versions`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BinaryConflict {
+    /// The path of the file, relative to the root of the working tree.
+    pub path: String,
+    /// The hash of our version of the file.
+    pub ours: String,
+    /// The hash of their version of the file.
+    pub theirs: String,
+}
+
+impl BinaryConflict {
+    /// Returns the warning shown to the user for this conflict.
+    pub fn warning(&self) -> String {
+        format!("warning: Cannot merge binary files: {}", self.path)
+    }
+
+    /// Writes both versions of the file in the working tree, as `<path>.ours` and
+    /// `<path>.theirs`, so the user can pick one of them.
+    ///
+    /// # Arguments
+    ///
+    /// * `git_dir` - The path to the git directory.
+    /// * `root_dir` - The root of the working tree.
+    pub fn write_versions(&self, git_dir: &str, root_dir: &str) -> io::Result<()> {
+        for (suffix, hash) in [("ours", &self.ours), ("theirs", &self.theirs)] {
+            let content = cat_file::cat_file_return_bytes(hash, git_dir)?;
+            let path = format!("{}.{}", self.path, suffix);
+            fs::write(checkout::working_path(root_dir, &path), content)?;
+        }
+        Ok(())
+    }
+}
+
 /// Merges a file from the current branch with the same file on the other branch if it exists.
+/// A conflict on a binary file is added to `binary_conflicts`.
 fn merge_file(
     path: &str,
     hash: &str,
@@ -687,6 +761,7 @@ fn merge_file(
     current_tree: &mut Tree,
     filename: &str,
     git_dir: &str,
+    binary_conflicts: &mut Vec<BinaryConflict>,
 ) -> io::Result<String> {
     let their_hash = their_tree.get_hash_from_path(path);
     match their_hash {
@@ -694,6 +769,14 @@ fn merge_file(
             if their_hash == hash {
                 current_tree.add_file(filename, hash);
                 Ok("".to_string())
+            } else if is_binary_for_merge(path, hash, &their_hash, git_dir)? {
+                current_tree.add_file(filename, hash);
+                binary_conflicts.push(BinaryConflict {
+                    path: path.to_string(),
+                    ours: hash.to_string(),
+                    theirs: their_hash,
+                });
+                Ok(path.to_string())
             } else {
                 let mut new_file = fs::File::create(path)?;
                 let diff = diff::return_object_diff_string(&their_hash, hash, git_dir);
@@ -726,6 +809,9 @@ fn merge_file(
 /// * If a file is in both trees and has different hashes, the diff between the two files will be calculated and added to the new tree.
 /// * If a file is in one tree but not in the other, it will be added to the new tree.
 ///
+/// A binary file changed in both trees keeps our version, and is returned as a `BinaryConflict`
+/// besides being one of the conflicting paths.
+///
 /// ## Arguments
 /// * `our_tree`: The tree of the current branch.
 /// * `their_tree`: The tree of the branch we want to merge.
@@ -738,10 +824,11 @@ pub fn merge_trees(
     our_tree: &Tree,
     their_tree: &Tree,
     git_dir: &str,
-) -> io::Result<(Tree, Vec<String>)> {
+) -> io::Result<(Tree, Vec<String>, Vec<BinaryConflict>)> {
     let our_tree_vec = our_tree.squash_tree_into_vec("");
     let mut new_tree = Tree::new("");
     let mut conflicting_paths: Vec<String> = Vec::new();
+    let mut binary_conflicts = Vec::new();
 
     for (path, hash) in our_tree_vec {
         let mut path_vec = path.split('/').collect::<Vec<&str>>();
@@ -758,14 +845,22 @@ pub fn merge_trees(
         for dir in path_vec {
             current_tree = current_tree.get_or_create_dir(dir);
         }
-        let result = merge_file(&path, &hash, their_tree, current_tree, filename, git_dir)?;
+        let result = merge_file(
+            &path,
+            &hash,
+            their_tree,
+            current_tree,
+            filename,
+            git_dir,
+            &mut binary_conflicts,
+        )?;
         if !result.is_empty() {
             conflicting_paths.push(result);
         }
     }
 
     let new_tree = merge_their_tree_into_ours(our_tree, their_tree, new_tree);
-    let tuple = (new_tree, conflicting_paths);
+    let tuple = (new_tree, conflicting_paths, binary_conflicts);
     Ok(tuple)
 }
 
@@ -1,8 +1,15 @@
-use std::{collections::HashSet, io, path::PathBuf};
+use std::{
+    collections::HashSet,
+    fs, io,
+    path::{Path, PathBuf},
+};
 
 use chrono::{DateTime, FixedOffset, Offset, Utc};
 
-use crate::{commit, configuration::GIT_DIR};
+use crate::{cat_file, commit, configuration::GIT_DIR};
+
+/// Entries of a git directory shared by every working tree of the repository.
+const SHARED_ENTRIES: [&str; 4] = ["objects", "refs", "logs/refs", "config"];
 
 /// Obtains the path to the Git directory of the current project.
 ///
@@ -55,6 +62,9 @@ pub fn obtain_git_dir() -> Result<String, io::Error> {
 ///
 /// This function returns an `Option<String>` containing the path to the found directory as a string if it is found.
 /// If the directory is not found, it returns `None`.
+///
+/// In a linked working tree, created by `git worktree add`, the entry found is a file pointing to
+/// the git directory of the working tree instead, and that directory is returned.
 pub fn find_git_directory(
     current_dir: &mut PathBuf,
     name_of_git_directory: &str,
@@ -64,6 +74,9 @@ pub fn find_git_directory(
         if git_dir.exists() && git_dir.is_dir() {
             return Some(git_dir.display().to_string());
         }
+        if git_dir.is_file() {
+            return read_gitdir_file(&git_dir);
+        }
 
         if !current_dir.pop() {
             break;
@@ -72,6 +85,123 @@ pub fn find_git_directory(
     None
 }
 
+/// Reads the file left as git directory in a linked working tree.
+///
+/// The file holds a single line `gitdir: <path>`, pointing to the git directory of the working tree.
+/// A relative path is relative to the directory containing the file.
+///
+/// # Arguments
+///
+/// * `path`: The path to the file.
+///
+/// # Returns
+///
+/// Returns the git directory the file points to, or `None` if the file cannot be read or does not
+/// have that format.
+pub fn read_gitdir_file(path: &Path) -> Option<String> {
+    let content = fs::read_to_string(path).ok()?;
+    let git_dir = content.trim_end().strip_prefix("gitdir: ")?;
+    let git_dir = match path.parent() {
+        Some(parent) if Path::new(git_dir).is_relative() => parent.join(git_dir),
+        _ => PathBuf::from(git_dir),
+    };
+    Some(git_dir.display().to_string())
+}
+
+/// Returns the root of the working tree of a git directory.
+///
+/// The git directory usually lives in the root of its working tree. The git directory of a linked
+/// working tree lives in `worktrees/<name>` of the main git directory instead, and its `gitdir`
+/// file records the path to the `.mgit` file in the root of the working tree.
+///
+/// # Arguments
+///
+/// * `git_dir`: The path to the git directory.
+///
+/// # Errors
+///
+/// Returns an error if the `gitdir` file cannot be read, or if the working tree has no parent
+/// directory.
+pub fn get_working_dir(git_dir: &str) -> io::Result<String> {
+    let gitdir_file = Path::new(git_dir).join("gitdir");
+    let git_file = if gitdir_file.is_file() {
+        PathBuf::from(fs::read_to_string(gitdir_file)?.trim_end())
+    } else {
+        PathBuf::from(git_dir)
+    };
+    match git_file.parent() {
+        Some(parent) => Ok(parent.to_string_lossy().to_string()),
+        None => Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "Error al obtener el working dir",
+        )),
+    }
+}
+
+/// Returns the git directory shared by every working tree of a repository.
+///
+/// It is the git directory itself, unless it belongs to a linked working tree: then its
+/// `commondir` file points to the main git directory, relative to the linked one.
+///
+/// # Arguments
+///
+/// * `git_dir`: The path to the git directory.
+pub fn get_common_dir(git_dir: &str) -> String {
+    match fs::read_to_string(Path::new(git_dir).join("commondir")) {
+        Ok(common_dir) => {
+            let common_dir = Path::new(git_dir).join(common_dir.trim_end());
+            fs::canonicalize(&common_dir)
+                .unwrap_or(common_dir)
+                .display()
+                .to_string()
+        }
+        Err(_) => git_dir.to_string(),
+    }
+}
+
+/// Returns the path to an entry of a git directory, such as `refs/heads/main`, `objects` or
+/// `HEAD`.
+///
+/// The refs, the objects and the config are shared by every working tree of a repository, so
+/// they are looked up in the common git directory (see `get_common_dir`). HEAD, the index and the
+/// state of merges, rebases and bisections stay in the git directory of each working tree.
+///
+/// # Arguments
+///
+/// * `git_dir`: The path to the git directory.
+/// * `entry`: The path to the entry, relative to the git directory.
+pub fn git_path(git_dir: &str, entry: &str) -> String {
+    let shared = SHARED_ENTRIES.iter().any(|shared| {
+        entry == *shared
+            || entry
+                .strip_prefix(shared)
+                .is_some_and(|rest| rest.starts_with('/'))
+    });
+    if shared {
+        format!("{}/{}", get_common_dir(git_dir), entry)
+    } else {
+        format!("{}/{}", git_dir, entry)
+    }
+}
+
+/// Returns the git directory of the working tree rooted at `root_dir`.
+///
+/// It is `<root_dir>/.mgit`, unless that is a file left by `git worktree add`, in which case the
+/// directory it points to is returned.
+///
+/// # Arguments
+///
+/// * `root_dir`: The root of the working tree.
+pub fn get_git_dir_of(root_dir: &str) -> String {
+    let git_dir = Path::new(root_dir).join(GIT_DIR);
+    if git_dir.is_file() {
+        if let Some(linked) = read_gitdir_file(&git_dir) {
+            return linked;
+        }
+    }
+    format!("{}/{}", root_dir, GIT_DIR)
+}
+
 pub fn get_current_time() -> String {
     use chrono::Local;
     Local::now().to_string()
@@ -286,11 +416,229 @@ pub fn get_timestamp() -> io::Result<(i64, String)> {
     Ok((timestamp, offset_formatted_for_timestamp))
 }
 
+/// Reads the commit hash HEAD points to, following the branch reference when HEAD is not detached.
+///
+/// # Arguments
+///
+/// * `git_dir`: A string representing the path to the Git repository directory.
+///
+/// # Errors
+///
+/// Returns an error if HEAD or the branch it points to cannot be read.
+///
+fn read_head_hash(git_dir: &str) -> io::Result<String> {
+    let head = fs::read_to_string(format!("{}/HEAD", git_dir))?;
+    match head.trim().strip_prefix("ref: ") {
+        Some(reference) => Ok(fs::read_to_string(git_path(git_dir, reference))?
+            .trim()
+            .to_string()),
+        None => Ok(head
+            .split_whitespace()
+            .next()
+            .unwrap_or_default()
+            .to_string()),
+    }
+}
+
+/// Looks for the objects whose hash starts with `prefix`.
+///
+/// # Arguments
+///
+/// * `git_dir`: A string representing the path to the Git repository directory.
+/// * `prefix`: The beginning of the hash, of at least two hexadecimal characters.
+///
+/// # Returns
+///
+/// Returns every complete hash stored in the objects folder that starts with `prefix`.
+///
+fn find_objects_by_prefix(git_dir: &str, prefix: &str) -> Vec<String> {
+    let dir = git_path(git_dir, &format!("objects/{}", &prefix[..2]));
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return vec![],
+    };
+    entries
+        .flatten()
+        .map(|entry| format!("{}{}", &prefix[..2], entry.file_name().to_string_lossy()))
+        .filter(|hash| hash.starts_with(prefix))
+        .collect()
+}
+
+/// Follows annotated tags until a non-tag object is found.
+///
+/// # Arguments
+///
+/// * `git_dir`: A string representing the path to the Git repository directory.
+/// * `hash`: The hash of the object to peel.
+///
+/// # Errors
+///
+/// Returns an error if one of the objects cannot be read.
+///
+fn peel_tag(git_dir: &str, hash: &str) -> io::Result<String> {
+    if cat_file::get_object_type(hash, git_dir)? != "tag" {
+        return Ok(hash.to_string());
+    }
+    let content = cat_file::cat_file_return_content(hash, git_dir)?;
+    match content
+        .lines()
+        .next()
+        .and_then(|line| line.strip_prefix("object "))
+    {
+        Some(object) if content.contains("\ntype ") => peel_tag(git_dir, object.trim()),
+        _ => Ok(hash.to_string()),
+    }
+}
+
+/// Resolves a name without `~` or `^` suffixes into an object hash.
+///
+/// The name is looked up as `HEAD`, as a reference (`refs/...`, a tag, a branch or a remote
+/// branch, in that order) and finally as a complete or abbreviated object hash.
+///
+/// # Arguments
+///
+/// * `git_dir`: A string representing the path to the Git repository directory.
+/// * `name`: The name to resolve.
+///
+/// # Errors
+///
+/// Returns a `NotFound` error if the name matches nothing, or an `InvalidInput` error if it is an
+/// abbreviated hash shared by more than one object.
+///
+fn resolve_name(git_dir: &str, name: &str) -> io::Result<String> {
+    if name == "HEAD" || name == "@" {
+        return read_head_hash(git_dir);
+    }
+    let candidates = [
+        name.to_string(),
+        format!("refs/{}", name),
+        format!("refs/tags/{}", name),
+        format!("refs/heads/{}", name),
+        format!("refs/remotes/{}", name),
+    ];
+    for candidate in candidates.iter() {
+        let path = git_path(git_dir, candidate);
+        if Path::new(&path).is_file() {
+            return Ok(fs::read_to_string(path)?.trim().to_string());
+        }
+    }
+    if name.len() >= 4 && name.len() <= 40 && name.chars().all(|c| c.is_ascii_hexdigit()) {
+        let matches = find_objects_by_prefix(git_dir, &name.to_lowercase());
+        match matches.len() {
+            0 => {}
+            1 => return Ok(matches[0].to_string()),
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("error: short SHA1 {} is ambiguous", name),
+                ))
+            }
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::NotFound,
+        format!(
+            "fatal: ambiguous argument '{}': unknown revision or path not in the working tree.",
+            name
+        ),
+    ))
+}
+
+/// Resolves a revision into the hash of the object it names, without peeling annotated tags.
+///
+/// Accepts `HEAD`, branch, tag and remote branch names, full references such as `refs/heads/main`,
+/// complete or abbreviated hashes, and any sequence of the `~<n>` (n-th first-parent ancestor) and
+/// `^<n>` (n-th parent) suffixes, e.g. `main~2` or `HEAD^2`. A name with suffixes is peeled before
+/// walking the parents, but a plain tag name resolves to the tag object itself.
+///
+/// # Arguments
+///
+/// * `git_dir`: A string representing the path to the Git repository directory.
+/// * `revision`: The revision to resolve.
+///
+/// # Returns
+///
+/// Returns the hash of the object named by `revision`.
+///
+/// # Errors
+///
+/// Returns a `NotFound` error if the revision does not name an existing object or asks for a
+/// parent that does not exist, or an `InvalidInput` error if the revision is malformed.
+///
+pub fn resolve_object(git_dir: &str, revision: &str) -> io::Result<String> {
+    let name_end = revision.find(['~', '^']).unwrap_or(revision.len());
+    let mut hash = resolve_name(git_dir, &revision[..name_end])?;
+    let mut suffix = &revision[name_end..];
+    if !suffix.is_empty() {
+        hash = peel_tag(git_dir, &hash)?;
+    }
+    while let Some(operator) = suffix.chars().next() {
+        let digits: String = suffix[1..]
+            .chars()
+            .take_while(|c| c.is_ascii_digit())
+            .collect();
+        suffix = &suffix[1 + digits.len()..];
+        let number: usize = if digits.is_empty() {
+            1
+        } else {
+            digits.parse().map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("fatal: invalid revision '{}'", revision),
+                )
+            })?
+        };
+        let (steps, parent_index) = match operator {
+            '~' => (number, 1),
+            '^' if number == 0 => (0, 1),
+            '^' => (1, number),
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("fatal: invalid revision '{}'", revision),
+                ))
+            }
+        };
+        for _ in 0..steps {
+            let parents = commit::get_commit_parents(&hash, git_dir)?;
+            hash = match parents.get(parent_index - 1) {
+                Some(parent) => parent.to_string(),
+                None => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::NotFound,
+                        format!("fatal: revision '{}' does not exist", revision),
+                    ))
+                }
+            };
+        }
+    }
+    Ok(hash)
+}
+
+/// Resolves a revision into the hash of the object it names, peeling annotated tags.
+///
+/// This is the same as `resolve_object`, except that an annotated tag resolves to the object it
+/// points to, which is what commands that expect a commit need.
+///
+/// # Arguments
+///
+/// * `git_dir`: A string representing the path to the Git repository directory.
+/// * `revision`: The revision to resolve.
+///
+/// # Errors
+///
+/// Returns the same errors as `resolve_object`, or an error if a tag object cannot be read.
+///
+pub fn resolve_revision(git_dir: &str, revision: &str) -> io::Result<String> {
+    let hash = resolve_object(git_dir, revision)?;
+    peel_tag(git_dir, &hash)
+}
+
 #[cfg(test)]
 mod tests {
     use std::{fs, io::Write};
 
-    use crate::commit;
+    use crate::{commit, hash_object};
 
     use super::*;
     const NAME_OF_GIT_DIRECTORY: &str = ".test_git";
@@ -434,4 +782,61 @@ mod tests {
         assert_eq!(result.1, expected_offset_formatted);
         Ok(())
     }
+
+    #[test]
+    fn test_resolve_revision() {
+        let git_dir = "tests/utils/resolve_revision";
+        let _ = fs::remove_dir_all(git_dir);
+        fs::create_dir_all(format!("{}/objects", git_dir)).unwrap();
+        fs::create_dir_all(format!("{}/refs/heads", git_dir)).unwrap();
+        fs::create_dir_all(format!("{}/refs/tags", git_dir)).unwrap();
+        fs::write(format!("{}/HEAD", git_dir), "ref: refs/heads/main\n").unwrap();
+        for content in ["first", "second"] {
+            let blob = hash_object::store_string_to_file(content, git_dir, "blob").unwrap();
+            fs::write(format!("{}/index", git_dir), format!("{} file.txt\n", blob)).unwrap();
+            commit::new_commit(git_dir, content, "").unwrap();
+        }
+        let second = fs::read_to_string(format!("{}/refs/heads/main", git_dir)).unwrap();
+        let first = commit::get_parent_hash(&second, git_dir).unwrap();
+        fs::write(format!("{}/refs/tags/v1", git_dir), &first).unwrap();
+
+        assert_eq!(resolve_revision(git_dir, "HEAD").unwrap(), second);
+        assert_eq!(resolve_revision(git_dir, "main~1").unwrap(), first);
+        assert_eq!(resolve_revision(git_dir, "HEAD^").unwrap(), first);
+        assert_eq!(resolve_revision(git_dir, "v1").unwrap(), first);
+        assert_eq!(resolve_revision(git_dir, &second[..7]).unwrap(), second);
+        assert!(resolve_revision(git_dir, "HEAD~2").is_err());
+        assert!(resolve_revision(git_dir, "unknown").is_err());
+        fs::remove_dir_all(git_dir).unwrap();
+    }
+
+    #[test]
+    fn test_git_path_finds_shared_entries_in_the_common_dir() {
+        let main = "tests/utils/git_path/.mgit";
+        let linked = format!("{}/worktrees/linked", main);
+        let _ = fs::remove_dir_all("tests/utils/git_path");
+        fs::create_dir_all(&linked).unwrap();
+        assert_eq!(
+            git_path(main, "refs/heads/main"),
+            format!("{}/refs/heads/main", main)
+        );
+
+        fs::write(format!("{}/commondir", linked), "../..\n").unwrap();
+        let common = fs::canonicalize(main).unwrap().display().to_string();
+        assert_eq!(
+            git_path(&linked, "refs/heads/main"),
+            format!("{}/refs/heads/main", common)
+        );
+        assert_eq!(git_path(&linked, "objects"), format!("{}/objects", common));
+        assert_eq!(git_path(&linked, "config"), format!("{}/config", common));
+        assert_eq!(
+            git_path(&linked, "logs/refs/stash"),
+            format!("{}/logs/refs/stash", common)
+        );
+        assert_eq!(git_path(&linked, "HEAD"), format!("{}/HEAD", linked));
+        assert_eq!(git_path(&linked, "refsx"), format!("{}/refsx", linked));
+        assert_eq!(git_path(&linked, "index"), format!("{}/index", linked));
+
+        fs::remove_dir_all("tests/utils/git_path").unwrap();
+    }
 }
@@ -0,0 +1,618 @@
+use crate::checkout;
+use crate::configuration::{GIT_DIR, LOGGER_COMMANDS_FILE};
+use crate::describe;
+use crate::log::Log;
+use crate::logger::Logger;
+use crate::status;
+use crate::tree_handler;
+use crate::utils::{self, get_current_time};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+const WORKTREE_USAGE: &str =
+    "usage: git worktree add [-f] [--detach] [-b <new-branch>] <path> [<commit-ish>]\n   \
+     or: git worktree list\n   \
+     or: git worktree remove [-f] <worktree>";
+
+/// Directory of the main git directory holding the git directories of the linked working trees.
+const WORKTREES_DIR: &str = "worktrees";
+/// Number of characters of the abbreviated hashes shown by the command.
+const ABBREV_LENGTH: usize = 7;
+
+/// Logs the 'git worktree' command with the specified subcommand.
+///
+/// This function logs the 'git worktree' command with the provided subcommand to a file named
+/// 'logger_commands.txt'.
+///
+/// # Arguments
+///
+/// * `subcommand` - The subcommand being run, such as `add` or `list`.
+///
+/// # Errors
+///
+/// Returns an `io::Result` indicating whether the operation was successful.
+///
+fn log_worktree(subcommand: &str) -> io::Result<()> {
+    let log_file_path = LOGGER_COMMANDS_FILE;
+    let mut logger = Logger::new(log_file_path)?;
+
+    let full_message = format!(
+        "Command 'git worktree': Subcommand '{}', {}",
+        subcommand,
+        get_current_time()
+    );
+    logger.write_all(full_message.as_bytes())?;
+    logger.flush()?;
+    Ok(())
+}
+
+/// A working tree of the repository, either the main one or a linked one.
+struct Worktree {
+    /// The root of the working tree.
+    path: String,
+    /// The git directory of the working tree.
+    git_dir: String,
+    /// The content of its HEAD: `ref: refs/heads/<branch>`, or `<hash> (commit)` when detached.
+    head: String,
+    /// Whether it is the main working tree.
+    main: bool,
+}
+
+impl Worktree {
+    /// Loads the working tree whose git directory is `git_dir`.
+    fn load(git_dir: &str, main: bool) -> io::Result<Self> {
+        let head = fs::read_to_string(Path::new(git_dir).join("HEAD"))?;
+        Ok(Self {
+            path: utils::get_working_dir(git_dir)?,
+            git_dir: git_dir.to_string(),
+            head: head.trim().to_string(),
+            main,
+        })
+    }
+
+    /// Returns the reference checked out, such as `refs/heads/main`, or `None` if HEAD is
+    /// detached.
+    fn branch_ref(&self) -> Option<&str> {
+        self.head.strip_prefix("ref: ")
+    }
+}
+
+/// Returns every working tree of the repository: the main one first, followed by the linked ones
+/// sorted by name.
+fn list_worktrees(common_dir: &str) -> io::Result<Vec<Worktree>> {
+    let mut worktrees = vec![Worktree::load(common_dir, true)?];
+    let worktrees_dir = Path::new(common_dir).join(WORKTREES_DIR);
+    if worktrees_dir.is_dir() {
+        let mut names: Vec<String> = fs::read_dir(&worktrees_dir)?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        for name in names {
+            let git_dir = worktrees_dir.join(name).display().to_string();
+            worktrees.push(Worktree::load(&git_dir, false)?);
+        }
+    }
+    Ok(worktrees)
+}
+
+/// Returns the canonical form of a path, or the path itself if it does not exist.
+fn canonical(path: &str) -> String {
+    fs::canonicalize(path)
+        .map(|path| path.display().to_string())
+        .unwrap_or_else(|_| path.to_string())
+}
+
+/// Returns the working tree that has a branch checked out, if any.
+fn find_checked_out<'a>(worktrees: &'a [Worktree], branch_ref: &str) -> Option<&'a Worktree> {
+    worktrees
+        .iter()
+        .find(|worktree| worktree.branch_ref() == Some(branch_ref))
+}
+
+/// Returns the root of another working tree of the repository that has a branch checked out.
+///
+/// Checking out or deleting a branch checked out in another working tree would leave that one
+/// with a HEAD that no longer matches its files, so both are refused.
+///
+/// # Arguments
+///
+/// * `git_dir` - The git directory of the current working tree, which is not looked at.
+/// * `branch_name` - The name of the branch, such as `main`.
+///
+/// # Returns
+///
+/// The root of the working tree that has the branch checked out, or `None` if there is none.
+///
+/// # Errors
+///
+/// Returns an error if the HEAD of a working tree cannot be read.
+///
+pub fn checked_out_elsewhere(git_dir: &str, branch_name: &str) -> io::Result<Option<String>> {
+    let branch_ref = format!("refs/heads/{}", branch_name);
+    let own_git_dir = canonical(git_dir);
+    let worktrees = list_worktrees(&utils::get_common_dir(git_dir))?;
+    Ok(worktrees
+        .iter()
+        .filter(|worktree| canonical(&worktree.git_dir) != own_git_dir)
+        .find(|worktree| worktree.branch_ref() == Some(branch_ref.as_str()))
+        .map(|worktree| canonical(&worktree.path)))
+}
+
+/// Creates the git directory of a new linked working tree, under `worktrees/<name>` of the main
+/// git directory, and the `.mgit` file in the root of the working tree that points to it.
+///
+/// Only HEAD and the index live in the new git directory. Its `commondir` file points back to the
+/// main one, where the refs, the objects and the config are found (see `utils::git_path`).
+///
+/// # Returns
+///
+/// Returns the path to the new git directory.
+fn create_linked_git_dir(common_dir: &str, root_dir: &str, head: &str) -> io::Result<String> {
+    let common_dir = PathBuf::from(canonical(common_dir));
+    let base_name = Path::new(root_dir)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| "worktree".to_string());
+    let worktrees_dir = common_dir.join(WORKTREES_DIR);
+    let mut git_dir = worktrees_dir.join(&base_name);
+    let mut suffix = 1;
+    while git_dir.exists() {
+        git_dir = worktrees_dir.join(format!("{}{}", base_name, suffix));
+        suffix += 1;
+    }
+    fs::create_dir_all(&git_dir)?;
+    let git_file = Path::new(root_dir).join(GIT_DIR);
+    fs::write(git_dir.join("commondir"), "../..\n")?;
+    fs::write(git_dir.join("gitdir"), format!("{}\n", git_file.display()))?;
+    fs::write(git_dir.join("HEAD"), format!("{}\n", head))?;
+    fs::write(git_dir.join("index"), "")?;
+    fs::write(git_file, format!("gitdir: {}\n", git_dir.display()))?;
+    Ok(git_dir.display().to_string())
+}
+
+/// Creates a linked working tree, as `git worktree add`.
+fn add_worktree(git_dir: &str, args: &[String], output: &mut impl Write) -> io::Result<()> {
+    let mut force = false;
+    let mut detach = false;
+    let mut new_branch: Option<String> = None;
+    let mut positional: Vec<String> = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-f" | "--force" => force = true,
+            "--detach" => detach = true,
+            "-b" => {
+                let name = args.next().ok_or(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "error: switch 'b' requires a value",
+                ))?;
+                new_branch = Some(name.to_string());
+            }
+            _ if arg.starts_with('-') => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("error: unknown option '{}'\n{}", arg, WORKTREE_USAGE),
+                ))
+            }
+            _ => positional.push(arg.to_string()),
+        }
+    }
+    let (path, commit_ish) = match positional.as_slice() {
+        [path] => (path.as_str(), None),
+        [path, commit_ish] => (path.as_str(), Some(commit_ish.as_str())),
+        _ => return Err(io::Error::new(io::ErrorKind::InvalidInput, WORKTREE_USAGE)),
+    };
+    if new_branch.is_some() && detach {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "fatal: options '-b' and '--detach' cannot be used together",
+        ));
+    }
+    let root = Path::new(path);
+    if root.exists() && (!root.is_dir() || fs::read_dir(root)?.next().is_some()) {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("fatal: '{}' already exists", path),
+        ));
+    }
+
+    let common_dir = utils::get_common_dir(git_dir);
+    let heads_dir = Path::new(&common_dir).join("refs").join("heads");
+    // Without a commit-ish, a branch named after the working tree is checked out, or created
+    // from HEAD if it does not exist.
+    let (branch, create) = match (&new_branch, commit_ish) {
+        (Some(name), _) => (Some(name.clone()), true),
+        (None, _) if detach => (None, false),
+        (None, Some(commit_ish)) => match heads_dir.join(commit_ish).is_file() {
+            true => (Some(commit_ish.to_string()), false),
+            false => (None, false),
+        },
+        (None, None) => {
+            let name = root
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let exists = heads_dir.join(&name).is_file();
+            (Some(name), !exists)
+        }
+    };
+    let commit = utils::resolve_revision(git_dir, commit_ish.unwrap_or("HEAD"))?;
+
+    let (head, preparing) = match &branch {
+        Some(name) if create => {
+            if heads_dir.join(name).exists() {
+                return Err(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    format!("fatal: a branch named '{}' already exists", name),
+                ));
+            }
+            fs::create_dir_all(&heads_dir)?;
+            fs::write(heads_dir.join(name), &commit)?;
+            (
+                format!("ref: refs/heads/{}", name),
+                format!("new branch '{}'", name),
+            )
+        }
+        Some(name) => {
+            let branch_ref = format!("refs/heads/{}", name);
+            let worktrees = list_worktrees(&common_dir)?;
+            if let Some(worktree) = find_checked_out(&worktrees, &branch_ref) {
+                if !force {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!(
+                            "fatal: '{}' is already checked out at '{}'",
+                            name, worktree.path
+                        ),
+                    ));
+                }
+            }
+            (
+                format!("ref: {}", branch_ref),
+                format!("checking out '{}'", name),
+            )
+        }
+        None => (
+            format!("{} (commit)", commit),
+            format!("detached HEAD {}", &commit[..ABBREV_LENGTH]),
+        ),
+    };
+    log_worktree("add")?;
+    writeln!(output, "Preparing worktree ({})", preparing)?;
+
+    fs::create_dir_all(root)?;
+    let root_dir = canonical(path);
+    let linked_git_dir = create_linked_git_dir(&common_dir, &root_dir, &head)?;
+    let tree = tree_handler::load_tree_from_commit(&commit, &linked_git_dir)?;
+    checkout::update_working_tree(&linked_git_dir, &root_dir, None, &tree)?;
+    let subject = Log::load(Some(&commit), &linked_git_dir)?.expand_format("%s");
+    writeln!(
+        output,
+        "HEAD is now at {} {}",
+        &commit[..ABBREV_LENGTH],
+        subject
+    )
+}
+
+/// Lists the working trees, as `git worktree list`.
+fn write_worktrees(git_dir: &str, output: &mut impl Write) -> io::Result<()> {
+    let worktrees = list_worktrees(&utils::get_common_dir(git_dir))?;
+    let paths: Vec<String> = worktrees
+        .iter()
+        .map(|worktree| canonical(&worktree.path))
+        .collect();
+    let width = paths.iter().map(|path| path.len()).max().unwrap_or(0) + 1;
+    for (worktree, path) in worktrees.iter().zip(&paths) {
+        let hash = utils::resolve_revision(&worktree.git_dir, "HEAD")
+            .map(|hash| hash[..ABBREV_LENGTH].to_string())
+            .unwrap_or_else(|_| "0".repeat(ABBREV_LENGTH));
+        let head = match worktree.branch_ref() {
+            Some(branch_ref) => format!(
+                "[{}]",
+                branch_ref.strip_prefix("refs/heads/").unwrap_or(branch_ref)
+            ),
+            None => "(detached HEAD)".to_string(),
+        };
+        let prunable = if Path::new(&worktree.path).exists() {
+            ""
+        } else {
+            " prunable"
+        };
+        writeln!(output, "{:<width$} {} {}{}", path, hash, head, prunable)?;
+    }
+    Ok(())
+}
+
+/// Checks whether a working tree has changes that removing it would lose: differences between
+/// HEAD, the index and the tracked files, or untracked files.
+fn has_local_changes(worktree: &Worktree) -> io::Result<bool> {
+    if describe::is_dirty(&worktree.git_dir, &worktree.path)? {
+        return Ok(true);
+    }
+    let index = checkout::load_index(&worktree.git_dir, &worktree.path)?;
+    let mut untracked = Vec::new();
+    let root = Path::new(&worktree.path);
+    status::find_untracked_files(root, root, &index, &mut untracked)?;
+    Ok(!untracked.is_empty())
+}
+
+/// Removes a linked working tree and its git directory, as `git worktree remove`.
+fn remove_worktree(git_dir: &str, args: &[String], output: &mut impl Write) -> io::Result<()> {
+    let force = args.iter().any(|arg| arg == "-f" || arg == "--force");
+    let targets: Vec<&String> = args
+        .iter()
+        .filter(|arg| arg.as_str() != "-f" && arg.as_str() != "--force")
+        .collect();
+    let target = match targets.as_slice() {
+        [target] if !target.starts_with('-') => target.as_str(),
+        _ => return Err(io::Error::new(io::ErrorKind::InvalidInput, WORKTREE_USAGE)),
+    };
+    let worktrees = list_worktrees(&utils::get_common_dir(git_dir))?;
+    let target_path = canonical(target);
+    let worktree = worktrees
+        .iter()
+        .find(|worktree| {
+            canonical(&worktree.path) == target_path
+                || (!worktree.main
+                    && Path::new(&worktree.git_dir).file_name()
+                        == Some(std::ffi::OsStr::new(target)))
+        })
+        .ok_or(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("fatal: '{}' is not a working tree", target),
+        ))?;
+    if worktree.main {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("fatal: '{}' is a main working tree", target),
+        ));
+    }
+    log_worktree("remove")?;
+    if Path::new(&worktree.path).exists() {
+        if !force && has_local_changes(worktree)? {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "fatal: '{}' contains modified or untracked files, use --force to delete it",
+                    target
+                ),
+            ));
+        }
+        fs::remove_dir_all(&worktree.path)?;
+    }
+    fs::remove_dir_all(&worktree.git_dir)?;
+    if let Some(worktrees_dir) = Path::new(&worktree.git_dir).parent() {
+        if fs::read_dir(worktrees_dir)?.next().is_none() {
+            fs::remove_dir(worktrees_dir)?;
+        }
+    }
+    writeln!(output, "Removed worktree '{}'", target)
+}
+
+/// Manages several working trees attached to the same repository.
+///
+/// The command line is `git worktree <subcommand>`, where the subcommand is one of:
+///
+/// - `add [-f] [--detach] [-b <new-branch>] <path> [<commit-ish>]`: creates a working tree in
+///   `path` and checks out a branch in it. The branch is `<commit-ish>` when it names one, a new
+///   branch created with `-b`, or a branch named after the last component of `path`, created
+///   from HEAD if needed. Other commit-ishes and `--detach` leave HEAD detached. A branch that is
+///   already checked out in another working tree is refused, unless `-f` is given.
+/// - `list`: lists the working trees, with the commit and the branch checked out in each.
+/// - `remove [-f] <worktree>`: removes a linked working tree, given by its path or its name. A
+///   working tree with modified or untracked files is only removed with `-f`.
+///
+/// Every linked working tree has its own HEAD and index, stored in `worktrees/<name>` of the main
+/// git directory, while objects, refs and the configuration are shared. The root of the working
+/// tree holds a `.mgit` file pointing to that directory, so commands run inside it find it.
+///
+/// # Arguments
+///
+/// * `git_dir` - The path to the git directory of the current working tree.
+/// * `line` - The command line, starting with `git worktree`.
+/// * `output` - Where the progress and the listings are written.
+///
+/// # Errors
+///
+/// Returns an error if the arguments are invalid, the path already exists, the branch is checked
+/// out elsewhere, the working tree to remove has local changes, or the repository cannot be read
+/// or written.
+///
+pub fn git_worktree(git_dir: &str, line: Vec<String>, output: &mut impl Write) -> io::Result<()> {
+    let args = line.get(3..).unwrap_or(&[]);
+    match line.get(2).map(|arg| arg.as_str()) {
+        Some("add") => add_worktree(git_dir, args, output),
+        Some("list") => {
+            log_worktree("list")?;
+            write_worktrees(git_dir, output)
+        }
+        Some("remove") => remove_worktree(git_dir, args, output),
+        Some(other) => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("error: unknown subcommand: '{}'\n{}", other, WORKTREE_USAGE),
+        )),
+        None => Err(io::Error::new(io::ErrorKind::InvalidInput, WORKTREE_USAGE)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+    use crate::{commit, hash_object};
+
+    /// Creates a repository in `root_dir` with a commit on `main`, returning its git directory.
+    fn create_repo(root_dir: &str) -> String {
+        let git_dir = test_utils::create_repo(root_dir);
+        test_utils::commit_files(
+            &git_dir,
+            root_dir,
+            &[("dir/file.txt", "content\n")],
+            "first commit",
+        );
+        git_dir
+    }
+
+    fn run_worktree(git_dir: &str, args: &[&str]) -> io::Result<String> {
+        let mut line = vec!["git".to_string(), "worktree".to_string()];
+        line.extend(args.iter().map(|arg| arg.to_string()));
+        let mut output = Vec::new();
+        git_worktree(git_dir, line, &mut output)?;
+        Ok(String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn test_worktree_add_shares_refs_and_keeps_its_own_head() {
+        let base = "tests/worktree/add";
+        let _ = fs::remove_dir_all(base);
+        let git_dir = create_repo(&format!("{}/main", base));
+        let hotfix = format!("{}/hotfix", base);
+
+        let output = run_worktree(&git_dir, &["add", &hotfix]).unwrap();
+        assert!(output.starts_with("Preparing worktree (new branch 'hotfix')\nHEAD is now at"));
+        assert_eq!(
+            fs::read_to_string(format!("{}/dir/file.txt", hotfix)).unwrap(),
+            "content\n"
+        );
+        let linked_git_dir =
+            utils::find_git_directory(&mut PathBuf::from(format!("{}/dir", hotfix)), GIT_DIR)
+                .unwrap();
+        assert!(linked_git_dir.ends_with("main/.mgit/worktrees/hotfix"));
+        assert_eq!(
+            utils::get_working_dir(&linked_git_dir).unwrap(),
+            canonical(&hotfix)
+        );
+        assert_eq!(
+            canonical(&utils::get_common_dir(&linked_git_dir)),
+            canonical(&git_dir)
+        );
+        // The refs, the objects and the config are found through the `commondir` file.
+        for shared in ["refs", "objects", "config"] {
+            assert!(!Path::new(&linked_git_dir).join(shared).exists());
+        }
+        assert!(checkout::load_index(&linked_git_dir, &hotfix)
+            .unwrap()
+            .contains("dir/file.txt"));
+
+        // The branch is shared, but only the linked working tree moves with the new commit.
+        fs::write(format!("{}/new.txt", hotfix), "new\n").unwrap();
+        let hash =
+            hash_object::store_file(&format!("{}/new.txt", hotfix), &linked_git_dir).unwrap();
+        let mut index = checkout::load_index(&linked_git_dir, &hotfix).unwrap();
+        index.add_file("new.txt", &hash).unwrap();
+        index.write_file().unwrap();
+        let fix = commit::new_commit(&linked_git_dir, "fix", "").unwrap();
+        assert_eq!(
+            fs::read_to_string(format!("{}/refs/heads/hotfix", git_dir)).unwrap(),
+            fix
+        );
+        assert_eq!(
+            fs::read_to_string(format!("{}/HEAD", git_dir)).unwrap(),
+            "ref: refs/heads/main\n"
+        );
+
+        let error =
+            run_worktree(&git_dir, &["add", &format!("{}/other", base), "hotfix"]).unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("fatal: 'hotfix' is already checked out at"));
+        assert!(run_worktree(
+            &linked_git_dir,
+            &["add", &format!("{}/again", base), "main"]
+        )
+        .is_err());
+
+        let listed = run_worktree(&linked_git_dir, &["list"]).unwrap();
+        let lines: Vec<&str> = listed.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with(&canonical(&format!("{}/main", base))));
+        assert!(lines[0].ends_with(" [main]"));
+        assert!(lines[1].ends_with(&format!(" {} [hotfix]", &fix[..ABBREV_LENGTH])));
+
+        fs::remove_dir_all(base).unwrap();
+    }
+
+    #[test]
+    fn test_worktree_remove_refuses_local_changes() {
+        let base = "tests/worktree/remove";
+        let _ = fs::remove_dir_all(base);
+        let git_dir = create_repo(&format!("{}/main", base));
+        let detached = format!("{}/detached", base);
+        let output = run_worktree(&git_dir, &["add", "--detach", &detached]).unwrap();
+        assert!(output.starts_with("Preparing worktree (detached HEAD "));
+        assert!(!Path::new(&format!("{}/refs/heads/detached", git_dir)).exists());
+        let commit = utils::resolve_revision(&git_dir, "HEAD").unwrap();
+        assert_eq!(
+            fs::read_to_string(format!("{}/worktrees/detached/HEAD", git_dir)).unwrap(),
+            format!("{} (commit)\n", commit)
+        );
+        let listed = run_worktree(&git_dir, &["list"]).unwrap();
+        assert!(listed
+            .lines()
+            .nth(1)
+            .unwrap()
+            .ends_with(&format!(" {} (detached HEAD)", &commit[..ABBREV_LENGTH])));
+
+        assert!(run_worktree(&git_dir, &["remove", &format!("{}/main", base)]).is_err());
+        fs::write(format!("{}/untracked.txt", detached), "new\n").unwrap();
+        let error = run_worktree(&git_dir, &["remove", &detached]).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("contains modified or untracked files"));
+
+        run_worktree(&git_dir, &["remove", "-f", &detached]).unwrap();
+        assert!(!Path::new(&detached).exists());
+        assert!(!Path::new(&format!("{}/worktrees", git_dir)).exists());
+        assert_eq!(
+            run_worktree(&git_dir, &["list"]).unwrap().lines().count(),
+            1
+        );
+
+        fs::remove_dir_all(base).unwrap();
+    }
+
+    #[test]
+    fn test_branch_checked_out_elsewhere_cannot_be_checked_out_or_deleted() {
+        let base = "tests/worktree/checked_out";
+        let _ = fs::remove_dir_all(base);
+        let main = format!("{}/main", base);
+        let git_dir = create_repo(&main);
+        let hotfix = format!("{}/hotfix", base);
+        run_worktree(&git_dir, &["add", &hotfix]).unwrap();
+        let linked_git_dir =
+            utils::find_git_directory(&mut PathBuf::from(&hotfix), GIT_DIR).unwrap();
+
+        assert_eq!(
+            checked_out_elsewhere(&git_dir, "hotfix").unwrap(),
+            Some(canonical(&hotfix))
+        );
+        assert_eq!(
+            checked_out_elsewhere(&linked_git_dir, "hotfix").unwrap(),
+            None
+        );
+        let error = checkout::checkout_branch(Path::new(&git_dir), &main, "hotfix").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "fatal: 'hotfix' is already checked out at '{}'",
+                canonical(&hotfix)
+            )
+        );
+        assert!(checkout::checkout_branch(Path::new(&linked_git_dir), &hotfix, "main").is_err());
+        assert_eq!(
+            fs::read_to_string(format!("{}/HEAD", git_dir)).unwrap(),
+            "ref: refs/heads/main\n"
+        );
+
+        let mut output = Vec::new();
+        crate::branch::delete_branch(&git_dir, "hotfix", &mut output).unwrap();
+        assert!(String::from_utf8(output)
+            .unwrap()
+            .starts_with("error: Cannot delete branch hotfix checked out at"));
+        assert!(Path::new(&format!("{}/refs/heads/hotfix", git_dir)).exists());
+
+        fs::remove_dir_all(base).unwrap();
+    }
+}